@@ -0,0 +1,220 @@
+use nscq::NscqHandler;
+use nvml_wrapper::Nvml;
+use remote_attestation_verifier::{
+    collect_gpu_evidence, collect_nvswitch_evidence, errors::NscqError, AttestError,
+    DeviceEvidence, EvidenceBundle, HostMetadata, NvSwitchEvidence, Result as AttestResult,
+};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use tokio::sync::{mpsc, oneshot};
+use tracing::{error, info};
+
+use crate::{
+    config::AgentConfig,
+    error::{AgentError, Result},
+};
+
+/// Size of the nonce supplied by verifiers, in bytes.
+pub const NONCE_SIZE: usize = 32;
+
+/// Path of the hostname on Linux.
+const HOSTNAME_PATH: &str = "/proc/sys/kernel/hostname";
+
+/// A source of attestation evidence, owned by the collector thread.
+///
+/// Sources do not need to be `Send`, as they are created and used on the collector
+/// thread only. This lets [`LocalDevices`] keep its NVML and NSCQ sessions open for
+/// the lifetime of the agent.
+pub trait EvidenceSource {
+    /// Returns metadata about the host, included in every bundle.
+    fn host_metadata(&self) -> HostMetadata;
+
+    /// Collects the GPU and `NVSwitch` evidence of the host for `nonce`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the evidence of any device cannot be collected.
+    fn collect(
+        &mut self,
+        nonce: &[u8; NONCE_SIZE],
+    ) -> AttestResult<(Vec<DeviceEvidence>, Vec<NvSwitchEvidence>)>;
+}
+
+/// The GPUs and `NVSwitch` devices of the local host, reached through NVML and NSCQ.
+pub struct LocalDevices {
+    nvml: Option<Nvml>,
+    nscq: Option<NscqHandler>,
+    host: HostMetadata,
+}
+
+impl LocalDevices {
+    /// Initializes NVML and opens an NSCQ session, for the device kinds enabled in
+    /// `config`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::NvmlError` - If NVML cannot be initialized
+    /// * `AttestError::NscqError` - If the NSCQ session cannot be created
+    pub fn open(config: &AgentConfig) -> AttestResult<Self> {
+        let nvml = config.gpus.then(Nvml::init).transpose()?;
+        let nscq = config
+            .switches
+            .then(NscqHandler::new)
+            .transpose()
+            .map_err(|rc| AttestError::from(NscqError::from(rc)))?;
+        let host = HostMetadata {
+            hostname: std::fs::read_to_string(HOSTNAME_PATH)
+                .ok()
+                .map(|hostname| hostname.trim().to_string()),
+            node_id: config.node_id.clone(),
+            driver_version: nvml
+                .as_ref()
+                .and_then(|nvml| nvml.sys_driver_version().ok()),
+            ..HostMetadata::default()
+        };
+        Ok(Self { nvml, nscq, host })
+    }
+}
+
+impl EvidenceSource for LocalDevices {
+    fn host_metadata(&self) -> HostMetadata {
+        self.host.clone()
+    }
+
+    fn collect(
+        &mut self,
+        nonce: &[u8; NONCE_SIZE],
+    ) -> AttestResult<(Vec<DeviceEvidence>, Vec<NvSwitchEvidence>)> {
+        let gpu_evidence = match &self.nvml {
+            Some(nvml) => collect_gpu_evidence(nvml, nonce)?,
+            None => Vec::new(),
+        };
+        let switch_evidence = match &self.nscq {
+            Some(nscq) => collect_nvswitch_evidence(nscq, nonce)?,
+            None => Vec::new(),
+        };
+        Ok((gpu_evidence, switch_evidence))
+    }
+}
+
+/// An evidence request queued for the collector thread.
+struct CollectRequest {
+    nonce: [u8; NONCE_SIZE],
+    reply: oneshot::Sender<AttestResult<EvidenceBundle>>,
+}
+
+/// Handle to the collector thread, which owns the evidence source and serializes
+/// evidence collection.
+///
+/// Devices answer one attestation request at a time, so requests are queued, up to
+/// `max_pending_requests`, and further requests are rejected with [`AgentError::Busy`].
+/// The thread stops, closing the device sessions, when the handle is dropped.
+pub struct Collector {
+    requests: mpsc::Sender<CollectRequest>,
+    public_key: Vec<u8>,
+}
+
+impl Collector {
+    /// Starts the collector thread, opening the evidence source on it.
+    ///
+    /// # Arguments
+    ///
+    /// * `open` - Opens the evidence source, on the collector thread
+    /// * `signing_key` - The key signing every bundle
+    /// * `max_pending_requests` - The maximum number of requests waiting for the collector
+    ///
+    /// # Errors
+    ///
+    /// * `AgentError::Attest` - If the evidence source cannot be opened
+    /// * `AgentError::CollectorStopped` - If the collector thread cannot be started
+    pub fn spawn<S, F>(
+        open: F,
+        signing_key: Ed25519KeyPair,
+        max_pending_requests: usize,
+    ) -> Result<Self>
+    where
+        S: EvidenceSource,
+        F: FnOnce() -> AttestResult<S> + Send + 'static,
+    {
+        let public_key = signing_key.public_key().as_ref().to_vec();
+        let (requests, receiver) = mpsc::channel(max_pending_requests.max(1));
+        let (ready, started) = std::sync::mpsc::channel();
+        std::thread::Builder::new()
+            .name("nvagent-collector".to_string())
+            .spawn(move || match open() {
+                Ok(source) => {
+                    ready.send(Ok(())).ok();
+                    run_collector(source, &signing_key, receiver);
+                }
+                Err(e) => {
+                    ready.send(Err(e)).ok();
+                }
+            })
+            .map_err(|_| AgentError::CollectorStopped)?;
+        started.recv().map_err(|_| AgentError::CollectorStopped)??;
+        Ok(Self {
+            requests,
+            public_key,
+        })
+    }
+
+    /// Returns the public key verifying the bundles signed by the agent.
+    #[must_use]
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Collects a fresh, signed evidence bundle for `nonce`.
+    ///
+    /// # Errors
+    ///
+    /// * `AgentError::Busy` - If too many requests are already waiting for the collector
+    /// * `AgentError::CollectorStopped` - If the collector thread stopped
+    /// * `AgentError::Attest` - If the evidence cannot be collected
+    pub async fn collect(&self, nonce: [u8; NONCE_SIZE]) -> Result<EvidenceBundle> {
+        let (reply, response) = oneshot::channel();
+        self.requests
+            .try_send(CollectRequest { nonce, reply })
+            .map_err(|e| match e {
+                mpsc::error::TrySendError::Full(_) => AgentError::Busy,
+                mpsc::error::TrySendError::Closed(_) => AgentError::CollectorStopped,
+            })?;
+        Ok(response.await.map_err(|_| AgentError::CollectorStopped)??)
+    }
+}
+
+/// Serves evidence requests until every [`Collector`] handle is dropped.
+fn run_collector(
+    mut source: impl EvidenceSource,
+    signing_key: &Ed25519KeyPair,
+    mut receiver: mpsc::Receiver<CollectRequest>,
+) {
+    let host = source.host_metadata();
+    while let Some(CollectRequest { nonce, reply }) = receiver.blocking_recv() {
+        let result = source
+            .collect(&nonce)
+            .map(|(gpu_evidence, switch_evidence)| {
+                let mut bundle =
+                    EvidenceBundle::new(&nonce, gpu_evidence, switch_evidence, host.clone());
+                bundle.sign(signing_key);
+                bundle
+            });
+        match &result {
+            Ok(bundle) => info!(
+                level = "nvagent_collector",
+                nonce = %bundle.nonce,
+                digest = %bundle.digest,
+                gpus = bundle.gpu_evidence.len(),
+                switches = bundle.switch_evidence.len(),
+                "Collected evidence bundle"
+            ),
+            Err(e) => error!(
+                level = "nvagent_collector",
+                nonce = %hex::encode(nonce),
+                error = %e,
+                "Failed to collect evidence"
+            ),
+        }
+        // The requester may have gone away, in which case the bundle is discarded
+        reply.send(result).ok();
+    }
+}
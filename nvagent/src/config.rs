@@ -0,0 +1,319 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use remote_attestation_verifier::{options::read_config_file, AttestError};
+use ring::{rand::SystemRandom, signature::Ed25519KeyPair};
+use serde::Deserialize;
+
+use crate::{
+    error::{AgentError, Result},
+    scheduler::Schedule,
+};
+
+/// Permissions of the Unix socket: read and write for its owner and group only.
+pub const UNIX_SOCKET_MODE: u32 = 0o660;
+
+/// Default maximum number of evidence requests waiting for the collector.
+pub const DEFAULT_MAX_PENDING_REQUESTS: usize = 4;
+
+/// Default time between two re-attestations, in seconds.
+pub const DEFAULT_REATTEST_INTERVAL_SECS: u64 = 300;
+
+/// Default upper bound of the random delay added to the re-attestation interval, in
+/// seconds.
+pub const DEFAULT_REATTEST_JITTER_SECS: u64 = 30;
+
+/// Configuration of the agent, as it appears in a TOML or JSON config file.
+///
+/// ```toml
+/// listen = "0.0.0.0:8090"
+/// unix_socket = "/run/nvagent/nvagent.sock"
+/// signing_key = "/var/lib/nvagent/signing-key.pk8"
+/// node_id = "atoma-node-0"
+/// gpus = true
+/// switches = true
+/// max_pending_requests = 4
+///
+/// [reattest]
+/// interval_secs = 300
+/// jitter_secs = 30
+/// gpu_config = "/etc/nvagent/nras-gpu.toml"
+/// switch_config = "/etc/nvagent/nras-switch.toml"
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AgentConfig {
+    /// TCP address to serve on
+    pub listen: Option<SocketAddr>,
+    /// Path of a Unix socket to serve on, created with [`UNIX_SOCKET_MODE`] permissions
+    pub unix_socket: Option<PathBuf>,
+    /// Path of the PKCS#8 Ed25519 key signing the bundles, generated if missing.
+    /// If unset, an ephemeral key is generated at startup.
+    pub signing_key: Option<PathBuf>,
+    /// Identifier of the node, included in the host metadata of every bundle
+    pub node_id: Option<String>,
+    /// Whether GPU evidence is collected through NVML
+    #[serde(default = "default_true")]
+    pub gpus: bool,
+    /// Whether `NVSwitch` evidence is collected through NSCQ
+    #[serde(default)]
+    pub switches: bool,
+    /// Maximum number of evidence requests waiting for the collector, beyond which
+    /// requests are rejected
+    #[serde(default = "default_max_pending_requests")]
+    pub max_pending_requests: usize,
+    /// Periodic re-attestation of the local devices, disabled if unset
+    #[serde(default)]
+    pub reattest: Option<ReattestConfig>,
+}
+
+/// Configuration of the periodic re-attestation of the local devices.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ReattestConfig {
+    /// Time between two re-attestations, in seconds
+    #[serde(default = "default_reattest_interval_secs")]
+    pub interval_secs: u64,
+    /// Upper bound of the random delay added to every interval, in seconds
+    #[serde(default = "default_reattest_jitter_secs")]
+    pub jitter_secs: u64,
+    /// Remote attestation config file used to verify the GPU evidence, on top of the
+    /// `NV_*` environment variables
+    pub gpu_config: Option<PathBuf>,
+    /// Remote attestation config file used to verify the `NVSwitch` evidence, on top of
+    /// the `NV_*` environment variables
+    pub switch_config: Option<PathBuf>,
+    /// Whether the `PPCIe` topology is checked, when both GPU and `NVSwitch` evidence is
+    /// collected
+    #[serde(default = "default_true")]
+    pub topology: bool,
+}
+
+impl Default for ReattestConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: DEFAULT_REATTEST_INTERVAL_SECS,
+            jitter_secs: DEFAULT_REATTEST_JITTER_SECS,
+            gpu_config: None,
+            switch_config: None,
+            topology: true,
+        }
+    }
+}
+
+impl ReattestConfig {
+    /// Returns the schedule of the re-attestations.
+    #[must_use]
+    pub const fn schedule(&self) -> Schedule {
+        Schedule {
+            interval: Duration::from_secs(self.interval_secs),
+            jitter: Duration::from_secs(self.jitter_secs),
+        }
+    }
+}
+
+impl Default for AgentConfig {
+    fn default() -> Self {
+        Self {
+            listen: None,
+            unix_socket: None,
+            signing_key: None,
+            node_id: None,
+            gpus: true,
+            switches: false,
+            max_pending_requests: DEFAULT_MAX_PENDING_REQUESTS,
+            reattest: None,
+        }
+    }
+}
+
+const fn default_true() -> bool {
+    true
+}
+
+const fn default_max_pending_requests() -> usize {
+    DEFAULT_MAX_PENDING_REQUESTS
+}
+
+const fn default_reattest_interval_secs() -> u64 {
+    DEFAULT_REATTEST_INTERVAL_SECS
+}
+
+const fn default_reattest_jitter_secs() -> u64 {
+    DEFAULT_REATTEST_JITTER_SECS
+}
+
+impl AgentConfig {
+    /// Loads a config file, choosing the format from the file extension
+    /// (`.toml` or `.json`).
+    ///
+    /// # Errors
+    ///
+    /// * `AgentError::Config` - If the file cannot be read, has an unknown extension,
+    ///   or cannot be parsed
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_config_file(path).map_err(|e| match e {
+            AttestError::ConfigError(message) => AgentError::Config(message),
+            e => e.into(),
+        })
+    }
+
+    /// Checks that the agent collects some evidence and serves it somewhere.
+    ///
+    /// # Errors
+    ///
+    /// * `AgentError::Config` - If the configuration is unusable
+    pub fn validate(&self) -> Result<()> {
+        if !self.gpus && !self.switches {
+            return Err(AgentError::Config(
+                "At least one of `gpus` and `switches` must be enabled".to_string(),
+            ));
+        }
+        if self.listen.is_none() && self.unix_socket.is_none() {
+            return Err(AgentError::Config(
+                "At least one of `listen` and `unix_socket` must be set".to_string(),
+            ));
+        }
+        if self.max_pending_requests == 0 {
+            return Err(AgentError::Config(
+                "`max_pending_requests` must be greater than zero".to_string(),
+            ));
+        }
+        if self
+            .reattest
+            .as_ref()
+            .is_some_and(|reattest| reattest.interval_secs == 0)
+        {
+            return Err(AgentError::Config(
+                "`reattest.interval_secs` must be greater than zero".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Loads the signing key of the agent, generating and storing it if `path` does not
+/// exist, or generating an ephemeral key if `path` is `None`.
+///
+/// # Errors
+///
+/// * `AgentError::SigningKey` - If the key cannot be read, generated, stored or parsed
+pub fn load_signing_key(path: Option<&Path>) -> Result<Ed25519KeyPair> {
+    let signing_key_error = |message: String| AgentError::SigningKey(message);
+    let pkcs8 = match path {
+        Some(path) if path.exists() => std::fs::read(path)
+            .map_err(|e| signing_key_error(format!("Failed to read {}: {e}", path.display())))?,
+        _ => {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                .map_err(|_| signing_key_error("Failed to generate a key".to_string()))?;
+            if let Some(path) = path {
+                write_private_file(path, pkcs8.as_ref()).map_err(|e| {
+                    signing_key_error(format!("Failed to write {}: {e}", path.display()))
+                })?;
+            }
+            pkcs8.as_ref().to_vec()
+        }
+    };
+    Ed25519KeyPair::from_pkcs8(&pkcs8)
+        .map_err(|e| signing_key_error(format!("Invalid PKCS#8 Ed25519 key: {e}")))
+}
+
+/// Writes a file readable by its owner only.
+fn write_private_file(path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    use std::{io::Write, os::unix::fs::OpenOptionsExt};
+
+    std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)?
+        .write_all(contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::signature::KeyPair;
+
+    use super::*;
+
+    #[test]
+    fn test_agent_config() {
+        let config: AgentConfig = toml::from_str(
+            r#"
+unix_socket = "/run/nvagent.sock"
+node_id = "node-0"
+switches = true
+"#,
+        )
+        .unwrap();
+        assert!(config.gpus);
+        assert!(config.switches);
+        assert_eq!(config.max_pending_requests, DEFAULT_MAX_PENDING_REQUESTS);
+        assert!(config.reattest.is_none());
+        config.validate().unwrap();
+
+        let config: AgentConfig = toml::from_str(
+            r#"
+listen = "127.0.0.1:8090"
+
+[reattest]
+interval_secs = 90
+gpu_config = "/etc/nvagent/nras-gpu.toml"
+"#,
+        )
+        .unwrap();
+        let reattest = config.reattest.as_ref().unwrap();
+        assert_eq!(
+            reattest.schedule(),
+            Schedule {
+                interval: Duration::from_secs(90),
+                jitter: Duration::from_secs(DEFAULT_REATTEST_JITTER_SECS),
+            }
+        );
+        assert!(reattest.topology);
+        config.validate().unwrap();
+        let config = AgentConfig {
+            reattest: Some(ReattestConfig {
+                interval_secs: 0,
+                ..ReattestConfig::default()
+            }),
+            ..config
+        };
+        assert!(config.validate().is_err());
+
+        assert!(AgentConfig::default().validate().is_err());
+        let config = AgentConfig {
+            listen: Some("127.0.0.1:0".parse().unwrap()),
+            gpus: false,
+            ..AgentConfig::default()
+        };
+        assert!(config.validate().is_err());
+        assert!(toml::from_str::<AgentConfig>("unknown = 1").is_err());
+    }
+
+    #[test]
+    fn test_signing_key_is_persisted() {
+        let path = std::env::temp_dir().join(format!("nvagent-key-{}.pk8", std::process::id()));
+        let generated = load_signing_key(Some(&path)).unwrap();
+        let loaded = load_signing_key(Some(&path)).unwrap();
+        assert_eq!(
+            generated.public_key().as_ref(),
+            loaded.public_key().as_ref()
+        );
+        let ephemeral = load_signing_key(None).unwrap();
+        assert_ne!(
+            generated.public_key().as_ref(),
+            ephemeral.public_key().as_ref()
+        );
+
+        std::fs::write(&path, b"not a key").unwrap();
+        assert!(matches!(
+            load_signing_key(Some(&path)),
+            Err(AgentError::SigningKey(_))
+        ));
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,68 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use remote_attestation_verifier::AttestError;
+use serde_json::json;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, AgentError>;
+
+#[derive(Debug, Error)]
+pub enum AgentError {
+    #[error("Configuration error: {0}")]
+    Config(String),
+    #[error("Signing key error: {0}")]
+    SigningKey(String),
+    #[error("Invalid nonce: {0}")]
+    InvalidNonce(String),
+    #[error("Too many pending evidence requests")]
+    Busy,
+    #[error("Evidence collector is not running")]
+    CollectorStopped,
+    #[error(transparent)]
+    Attest(Box<AttestError>),
+}
+
+impl From<AttestError> for AgentError {
+    fn from(error: AttestError) -> Self {
+        Self::Attest(Box::new(error))
+    }
+}
+
+impl AgentError {
+    /// Returns a stable `snake_case` category of the error.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Config(_) => "configuration",
+            Self::SigningKey(_) => "signing_key",
+            Self::InvalidNonce(_) => "invalid_nonce",
+            Self::Busy => "busy",
+            Self::CollectorStopped => "collector_stopped",
+            Self::Attest(error) => error.kind().as_str(),
+        }
+    }
+
+    /// Returns the HTTP status answered for the error.
+    #[must_use]
+    pub const fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidNonce(_) => StatusCode::BAD_REQUEST,
+            Self::Busy => StatusCode::SERVICE_UNAVAILABLE,
+            _ => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+impl IntoResponse for AgentError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": self.to_string(),
+            "kind": self.kind(),
+            "retryable": matches!(self, Self::Busy),
+        });
+        (self.status(), Json(body)).into_response()
+    }
+}
@@ -0,0 +1,35 @@
+//! `nvagent`: node agent serving fresh NVIDIA attestation evidence on challenge.
+//!
+//! The agent runs on GPU hosts, and serves the following endpoints over TCP, a Unix
+//! socket, or both:
+//!
+//! * `POST /v1/evidence` - collects the GPU (NVML) and `NVSwitch` (NSCQ) evidence of
+//!   the host for the nonce of the request, `{"nonce": "<64 hex digits>"}`, and returns
+//!   it as an [`EvidenceBundle`](remote_attestation_verifier::EvidenceBundle) signed by
+//!   the agent, in CBOR if the request accepts `application/cbor`, and JSON otherwise.
+//!   Instead of a nonce, the request can hold a `binding` with a hex encoded `challenge`
+//!   and `session_public_key` and a `timestamp`, from which the agent derives the nonce
+//!   with [`NonceBinding`](remote_attestation_verifier::NonceBinding)
+//! * `GET /v1/identity` - the Ed25519 public key signing the bundles
+//! * `GET /healthz` - liveness check
+//!
+//! The NVML and NSCQ sessions are owned by a single [`Collector`] thread, opened once
+//! and reused for every request. Requests are queued for the collector up to a
+//! configured limit, and rejected with `503 Service Unavailable` beyond it.
+//!
+//! If configured, a [`Scheduler`] also re-attests the local devices against NRAS on an
+//! interval with jitter, and keeps their current [`AttestationState`].
+
+pub mod collector;
+pub mod config;
+pub mod error;
+pub mod scheduler;
+pub mod service;
+#[cfg(test)]
+mod tests;
+
+pub use collector::{Collector, EvidenceSource, LocalDevices};
+pub use config::{load_signing_key, AgentConfig, ReattestConfig};
+pub use error::{AgentError, Result};
+pub use scheduler::{AttestationState, Attester, LocalAttester, Schedule, Scheduler};
+pub use service::router;
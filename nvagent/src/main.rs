@@ -0,0 +1,161 @@
+//! `nvagent` daemon: serves fresh, signed evidence bundles on the configured TCP
+//! address and Unix socket.
+
+use std::{
+    fs::Permissions, net::SocketAddr, os::unix::fs::PermissionsExt, path::PathBuf,
+    process::ExitCode, sync::Arc,
+};
+
+use clap::Parser;
+use nvagent::{
+    config::UNIX_SOCKET_MODE, load_signing_key, router, AgentConfig, Collector, LocalAttester,
+    LocalDevices, Scheduler,
+};
+use tokio::net::{TcpListener, UnixListener};
+use tracing::{error, info, warn};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+};
+
+/// Node agent serving fresh, signed NVIDIA attestation evidence bundles on challenge
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// TOML or JSON configuration file
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+    /// TCP address to listen on, overriding the configuration file
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+    /// Unix socket to listen on, overriding the configuration file
+    #[arg(long)]
+    unix_socket: Option<PathBuf>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    init_tracing();
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!(level = "nvagent", "{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Installs a `fmt` subscriber, filtered by the `RUST_LOG` directives, or at the `info`
+/// level if `RUST_LOG` is unset or invalid.
+fn init_tracing() {
+    let directives = std::env::var("RUST_LOG").ok();
+    let filter = directives.as_deref().map(str::parse::<Targets>);
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(match &filter {
+            Some(Ok(targets)) => targets.clone(),
+            _ => Targets::new().with_default(LevelFilter::INFO),
+        })
+        .init();
+    if let Some(Err(e)) = filter {
+        warn!(
+            level = "nvagent",
+            "Ignoring invalid RUST_LOG directives: {e}"
+        );
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = match &args.config {
+        Some(path) => AgentConfig::from_file(path)?,
+        None => AgentConfig::default(),
+    };
+    if args.listen.is_some() {
+        config.listen = args.listen;
+    }
+    if args.unix_socket.is_some() {
+        config.unix_socket = args.unix_socket;
+    }
+    config.validate()?;
+
+    let signing_key = load_signing_key(config.signing_key.as_deref())?;
+    let devices_config = config.clone();
+    let collector = Collector::spawn(
+        move || LocalDevices::open(&devices_config),
+        signing_key,
+        config.max_pending_requests,
+    )?;
+    info!(
+        level = "nvagent",
+        "Signing evidence bundles with Ed25519 key {}",
+        hex::encode(collector.public_key())
+    );
+    let collector = Arc::new(collector);
+    // Kept alive until the servers stop, as dropping the scheduler stops it
+    let _scheduler = match &config.reattest {
+        Some(reattest) => {
+            let attester = LocalAttester::new(Arc::clone(&collector), reattest)?;
+            // The scheduler logs every change of the attestation state
+            let scheduler = Scheduler::spawn(attester, reattest.schedule());
+            info!(
+                level = "nvagent",
+                "Re-attesting every {}s, with up to {}s of jitter",
+                reattest.interval_secs,
+                reattest.jitter_secs
+            );
+            Some(scheduler)
+        }
+        None => None,
+    };
+    let router = router(collector);
+
+    let tcp = match config.listen {
+        Some(address) => {
+            let listener = TcpListener::bind(address).await?;
+            info!(
+                level = "nvagent",
+                "Listening on http://{}",
+                listener.local_addr()?
+            );
+            let router = router.clone();
+            Some(tokio::spawn(async move {
+                axum::serve(listener, router)
+                    .with_graceful_shutdown(shutdown_signal())
+                    .await
+            }))
+        }
+        None => None,
+    };
+    let unix = match &config.unix_socket {
+        Some(path) => {
+            // A socket left behind by a previous run would make binding fail
+            if path.exists() {
+                std::fs::remove_file(path)?;
+            }
+            let listener = UnixListener::bind(path)?;
+            // Only the owner and group of the agent may request evidence
+            std::fs::set_permissions(path, Permissions::from_mode(UNIX_SOCKET_MODE))?;
+            info!(level = "nvagent", "Listening on unix:{}", path.display());
+            let router = router.clone();
+            Some(tokio::spawn(async move {
+                axum::serve(listener, router)
+                    .with_graceful_shutdown(shutdown_signal())
+                    .await
+            }))
+        }
+        None => None,
+    };
+    for server in [tcp, unix].into_iter().flatten() {
+        server.await??;
+    }
+    if let Some(path) = &config.unix_socket {
+        std::fs::remove_file(path).ok();
+    }
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    tokio::signal::ctrl_c().await.ok();
+}
@@ -0,0 +1,329 @@
+use std::{
+    fmt,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use remote_attestation_verifier::{
+    check_bundle_topology, verify_gpu_attestation, verify_nvswitch_attestation, AttestError,
+    AttestErrorKind, AttestRemoteOptions, DeviceKind, EvidenceBundle, Result as AttestResult,
+};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::Serialize;
+use tokio::{sync::watch, task::JoinHandle};
+use tracing::{info, warn};
+
+use crate::{
+    collector::{Collector, NONCE_SIZE},
+    config::ReattestConfig,
+    error::AgentError,
+};
+
+/// Attestation state of the local devices, as last established by the [`Scheduler`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AttestationState {
+    /// No attestation has completed yet
+    Unknown,
+    /// The devices, and their topology if checked, were verified
+    Trusted,
+    /// The devices or their topology failed verification
+    Untrusted { reason: String },
+    /// The attestation could not be completed, for example because the devices or
+    /// NRAS could not be reached, so the devices are neither verified nor refuted
+    Degraded { reason: String },
+}
+
+impl AttestationState {
+    /// Returns whether the devices were verified by the last attestation.
+    #[must_use]
+    pub const fn is_trusted(&self) -> bool {
+        matches!(self, Self::Trusted)
+    }
+}
+
+impl fmt::Display for AttestationState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unknown => write!(f, "unknown"),
+            Self::Trusted => write!(f, "trusted"),
+            Self::Untrusted { reason } => write!(f, "untrusted: {reason}"),
+            Self::Degraded { reason } => write!(f, "degraded: {reason}"),
+        }
+    }
+}
+
+/// Performs one attestation of the local devices for the [`Scheduler`].
+pub trait Attester: Send + Sync + 'static {
+    /// Attests the devices, and returns the resulting state.
+    fn attest(&self) -> impl Future<Output = AttestationState> + Send;
+}
+
+/// When the [`Scheduler`] re-attests the devices.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Schedule {
+    /// Time between two attestations
+    pub interval: Duration,
+    /// Upper bound of a random delay added to every interval, so that the nodes of a
+    /// cluster do not all query NRAS at once
+    pub jitter: Duration,
+}
+
+impl Schedule {
+    /// Returns the delay before the next attestation, between `interval` and
+    /// `interval + jitter`.
+    fn next_delay(&self, random: &SystemRandom) -> Duration {
+        let jitter_millis = u64::try_from(self.jitter.as_millis()).unwrap_or(u64::MAX);
+        if jitter_millis == 0 {
+            return self.interval;
+        }
+        let mut bytes = [0u8; 8];
+        // Without randomness, the delay falls back to the plain interval
+        if random.fill(&mut bytes).is_err() {
+            return self.interval;
+        }
+        self.interval + Duration::from_millis(u64::from_le_bytes(bytes) % (jitter_millis + 1))
+    }
+}
+
+type StateCallback = Box<dyn Fn(&AttestationState, &AttestationState) + Send + Sync>;
+
+/// Re-attests the local devices on a [`Schedule`], keeping their current
+/// [`AttestationState`] and notifying subscribers when it changes.
+///
+/// Subscribers either watch the state through [`Scheduler::subscribe`], or register a
+/// callback with [`Scheduler::on_change`]. The first attestation starts immediately,
+/// and the scheduler stops when dropped.
+pub struct Scheduler {
+    state: watch::Receiver<AttestationState>,
+    callbacks: Arc<Mutex<Vec<StateCallback>>>,
+    task: JoinHandle<()>,
+}
+
+impl Scheduler {
+    /// Starts re-attesting the devices with `attester`, on the current Tokio runtime.
+    #[must_use]
+    pub fn spawn(attester: impl Attester, schedule: Schedule) -> Self {
+        let (sender, state) = watch::channel(AttestationState::Unknown);
+        let callbacks = Arc::new(Mutex::new(Vec::<StateCallback>::new()));
+        let task = tokio::spawn({
+            let callbacks = Arc::clone(&callbacks);
+            async move {
+                let random = SystemRandom::new();
+                loop {
+                    let current = attester.attest().await;
+                    let previous = sender.borrow().clone();
+                    if current != previous {
+                        log_state_change(&previous, &current);
+                        // Callbacks run before watchers are notified, and under the lock,
+                        // so every callback sees the changes in order
+                        for callback in callbacks.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+                            callback(&previous, &current);
+                        }
+                        sender.send_replace(current);
+                    }
+                    tokio::time::sleep(schedule.next_delay(&random)).await;
+                }
+            }
+        });
+        Self {
+            state,
+            callbacks,
+            task,
+        }
+    }
+
+    /// Returns the current attestation state.
+    #[must_use]
+    pub fn state(&self) -> AttestationState {
+        self.state.borrow().clone()
+    }
+
+    /// Returns a receiver notified whenever the attestation state changes.
+    #[must_use]
+    pub fn subscribe(&self) -> watch::Receiver<AttestationState> {
+        self.state.clone()
+    }
+
+    /// Registers a callback, called with the previous and the new state whenever the
+    /// attestation state changes.
+    ///
+    /// Callbacks run on the scheduler task, and must not block. A callback is only
+    /// called for the changes that happen after it is registered.
+    pub fn on_change(
+        &self,
+        callback: impl Fn(&AttestationState, &AttestationState) + Send + Sync + 'static,
+    ) {
+        self.callbacks
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(Box::new(callback));
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+fn log_state_change(previous: &AttestationState, current: &AttestationState) {
+    if current.is_trusted() {
+        info!(
+            level = "nvagent_scheduler",
+            previous = %previous,
+            current = %current,
+            "Attestation state changed"
+        );
+    } else {
+        warn!(
+            level = "nvagent_scheduler",
+            previous = %previous,
+            current = %current,
+            "Attestation state changed"
+        );
+    }
+}
+
+/// Attests the local devices through the [`Collector`] and NRAS.
+///
+/// Every attestation collects a bundle for a fresh nonce, checks the `PPCIe` topology
+/// when both GPU and `NVSwitch` evidence is collected, then verifies the evidence
+/// against NRAS.
+pub struct LocalAttester {
+    collector: Arc<Collector>,
+    gpu_options: AttestRemoteOptions,
+    switch_options: AttestRemoteOptions,
+    check_topology: bool,
+    random: SystemRandom,
+}
+
+impl LocalAttester {
+    /// Creates an attester collecting evidence through `collector`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::ConfigError` - If a remote attestation config file cannot be read
+    /// * `AttestError::InvalidOption` - If a remote attestation option fails validation
+    pub fn new(collector: Arc<Collector>, config: &ReattestConfig) -> AttestResult<Self> {
+        let options = |config_file: Option<&std::path::Path>, kind| {
+            let builder = AttestRemoteOptions::builder().with_env(kind);
+            match config_file {
+                Some(path) => builder.config_file(path, kind).build(),
+                None => builder.build(),
+            }
+        };
+        Ok(Self::with_options(
+            collector,
+            options(config.gpu_config.as_deref(), DeviceKind::Gpu)?,
+            options(config.switch_config.as_deref(), DeviceKind::Switch)?,
+            config.topology,
+        ))
+    }
+
+    /// Creates an attester verifying the evidence with the given options.
+    #[must_use]
+    pub fn with_options(
+        collector: Arc<Collector>,
+        gpu_options: AttestRemoteOptions,
+        switch_options: AttestRemoteOptions,
+        check_topology: bool,
+    ) -> Self {
+        Self {
+            collector,
+            gpu_options,
+            switch_options,
+            check_topology,
+            random: SystemRandom::new(),
+        }
+    }
+
+    async fn attest_bundle(&self, bundle: &EvidenceBundle) -> AttestationState {
+        let check_topology = self.check_topology
+            && !bundle.gpu_evidence.is_empty()
+            && !bundle.switch_evidence.is_empty();
+        if check_topology {
+            match check_bundle_topology(bundle) {
+                Ok(report) => {
+                    if let Some(reason) = report.failure_reason() {
+                        return AttestationState::Untrusted { reason };
+                    }
+                }
+                Err(e) => return attest_error_state(&e),
+            }
+        }
+        if !bundle.gpu_evidence.is_empty() {
+            match verify_gpu_attestation(
+                &bundle.gpu_evidence,
+                &bundle.nonce,
+                self.gpu_options.clone(),
+            )
+            .await
+            {
+                Ok((true, _)) => {}
+                Ok((false, _)) => {
+                    return AttestationState::Untrusted {
+                        reason: "GPU attestation failed".to_string(),
+                    }
+                }
+                Err(e) => return attest_error_state(&e),
+            }
+        }
+        if !bundle.switch_evidence.is_empty() {
+            match verify_nvswitch_attestation(
+                &bundle.switch_evidence,
+                &bundle.nonce,
+                self.switch_options.clone(),
+            )
+            .await
+            {
+                Ok((true, _)) => {}
+                Ok((false, _)) => {
+                    return AttestationState::Untrusted {
+                        reason: "NVSwitch attestation failed".to_string(),
+                    }
+                }
+                Err(e) => return attest_error_state(&e),
+            }
+        }
+        AttestationState::Trusted
+    }
+}
+
+impl Attester for LocalAttester {
+    async fn attest(&self) -> AttestationState {
+        let mut nonce = [0u8; NONCE_SIZE];
+        if self.random.fill(&mut nonce).is_err() {
+            return AttestationState::Degraded {
+                reason: "Failed to generate a nonce".to_string(),
+            };
+        }
+        match self.collector.collect(nonce).await {
+            Ok(bundle) => self.attest_bundle(&bundle).await,
+            Err(AgentError::Attest(e)) => attest_error_state(&e),
+            Err(e) => AttestationState::Degraded {
+                reason: e.to_string(),
+            },
+        }
+    }
+}
+
+/// Maps an attestation error to a state by its kind: errors that may resolve on a later
+/// attempt, such as an unreachable NRAS or a device that cannot be queried, degrade the
+/// state, while evidence or a token (JWT or COSE) that fails verification makes the
+/// devices untrusted.
+fn attest_error_state(error: &AttestError) -> AttestationState {
+    let reason = error.to_string();
+    let refutes_evidence = matches!(
+        error.kind(),
+        AttestErrorKind::TokenSignature
+            | AttestErrorKind::ClaimValidation
+            | AttestErrorKind::EvidenceDecode
+    );
+    if refutes_evidence {
+        AttestationState::Untrusted { reason }
+    } else {
+        AttestationState::Degraded { reason }
+    }
+}
@@ -0,0 +1,142 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap, HeaderValue,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use remote_attestation_verifier::{
+    bundle::BUNDLE_SIGNATURE_ALGORITHM, utils::accepts_media_type, BundleEncoding, NonceBinding,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    collector::{Collector, NONCE_SIZE},
+    error::{AgentError, Result},
+};
+
+/// Path of the evidence endpoint.
+pub const EVIDENCE_PATH: &str = "/v1/evidence";
+
+/// Path of the identity endpoint, publishing the public key of the agent.
+pub const IDENTITY_PATH: &str = "/v1/identity";
+
+/// Path of the health check endpoint.
+pub const HEALTHZ_PATH: &str = "/healthz";
+
+/// Body of an evidence request, holding either a nonce or the inputs to derive it from.
+#[derive(Debug, Deserialize)]
+pub struct EvidenceRequest {
+    /// Hex encoded 32-byte nonce supplied by the verifier
+    #[serde(default)]
+    pub nonce: Option<String>,
+    /// Inputs of a nonce bound to a session, see [`NonceBinding`]
+    #[serde(default)]
+    pub binding: Option<BindingRequest>,
+}
+
+/// Inputs of a nonce bound to a session key, hex encoded.
+#[derive(Debug, Deserialize)]
+pub struct BindingRequest {
+    /// Challenge issued by the verifier
+    pub challenge: String,
+    /// Public key of the session the evidence is bound to
+    pub session_public_key: String,
+    /// Derivation time, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl EvidenceRequest {
+    /// Returns the nonce to collect evidence with: the nonce of the request, or the
+    /// nonce derived from its binding.
+    ///
+    /// # Errors
+    ///
+    /// * `AgentError::InvalidNonce` - If the request holds both or neither of a nonce
+    ///   and a binding, or if they are not valid hex
+    pub fn nonce(&self) -> Result<[u8; NONCE_SIZE]> {
+        match (&self.nonce, &self.binding) {
+            (Some(nonce), None) => parse_nonce(nonce),
+            (None, Some(binding)) => {
+                let decode = |value: &str, field: &str| {
+                    hex::decode(value)
+                        .map_err(|e| AgentError::InvalidNonce(format!("`{field}`: {e}")))
+                };
+                let binding = NonceBinding::new(
+                    decode(&binding.challenge, "challenge")?,
+                    decode(&binding.session_public_key, "session_public_key")?,
+                    binding.timestamp,
+                );
+                Ok(binding.derive())
+            }
+            _ => Err(AgentError::InvalidNonce(
+                "expected exactly one of `nonce` and `binding`".to_string(),
+            )),
+        }
+    }
+}
+
+/// Creates the router of the agent.
+pub fn router(collector: Arc<Collector>) -> Router {
+    Router::new()
+        .route(EVIDENCE_PATH, post(evidence))
+        .route(IDENTITY_PATH, get(identity))
+        .route(HEALTHZ_PATH, get(healthz))
+        .with_state(collector)
+}
+
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn identity(State(collector): State<Arc<Collector>>) -> Json<Value> {
+    Json(json!({
+        "algorithm": BUNDLE_SIGNATURE_ALGORITHM,
+        "public_key": hex::encode(collector.public_key()),
+    }))
+}
+
+/// Collects a signed evidence bundle for the (possibly derived) nonce of the request,
+/// encoded as CBOR if the request accepts `application/cbor`, and as JSON otherwise.
+async fn evidence(
+    State(collector): State<Arc<Collector>>,
+    headers: HeaderMap,
+    Json(request): Json<EvidenceRequest>,
+) -> Result<Response> {
+    let nonce = request.nonce()?;
+    let bundle = collector.collect(nonce).await?;
+    let accepts_cbor = accepts_media_type(
+        headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok()),
+        "application/cbor",
+    );
+    let (encoding, content_type) = if accepts_cbor {
+        (BundleEncoding::Cbor, "application/cbor")
+    } else {
+        (BundleEncoding::Json, "application/json")
+    };
+    let body = bundle.encode(encoding)?;
+    Ok((
+        [(CONTENT_TYPE, HeaderValue::from_static(content_type))],
+        body,
+    )
+        .into_response())
+}
+
+/// Decodes a hex encoded 32-byte nonce.
+fn parse_nonce(nonce: &str) -> Result<[u8; NONCE_SIZE]> {
+    hex::decode(nonce)
+        .map_err(|e| AgentError::InvalidNonce(e.to_string()))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| {
+            AgentError::InvalidNonce(format!("expected {NONCE_SIZE} bytes, got {}", bytes.len()))
+        })
+}
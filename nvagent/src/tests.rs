@@ -0,0 +1,434 @@
+use std::{
+    collections::VecDeque,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc as std_mpsc, Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use remote_attestation_verifier::{
+    cose::TokenFormat,
+    test_support::{synthetic_evidence, MockFailure, MockNras},
+    AttestError, DeviceEvidence, EvidenceBundle, HostMetadata, NonceBinding, NvSwitchEvidence,
+    Result as AttestResult,
+};
+use reqwest::{header::ACCEPT, StatusCode};
+use ring::signature::Ed25519KeyPair;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, UnixListener, UnixStream},
+    sync::mpsc,
+};
+use topology::spdm::SpdmMeasurementReport;
+
+use crate::{
+    collector::NONCE_SIZE,
+    config::load_signing_key,
+    router,
+    service::{EVIDENCE_PATH, HEALTHZ_PATH, IDENTITY_PATH},
+    AgentError, AttestationState, Attester, Collector, EvidenceSource, LocalAttester, Schedule,
+    Scheduler,
+};
+
+/// Fake HGX host with 8 GPUs and 4 switches, signing reports for the requested nonce.
+struct FakeHost {
+    collections: Arc<AtomicUsize>,
+    /// If set, every collection notifies `started` and waits for `gate` before returning
+    gate: Option<(mpsc::UnboundedSender<()>, std_mpsc::Receiver<()>)>,
+}
+
+impl FakeHost {
+    const fn new(collections: Arc<AtomicUsize>) -> Self {
+        Self {
+            collections,
+            gate: None,
+        }
+    }
+}
+
+impl EvidenceSource for FakeHost {
+    fn host_metadata(&self) -> HostMetadata {
+        HostMetadata {
+            node_id: Some("fake-node".to_string()),
+            ..HostMetadata::default()
+        }
+    }
+
+    fn collect(
+        &mut self,
+        nonce: &[u8; NONCE_SIZE],
+    ) -> AttestResult<(Vec<DeviceEvidence>, Vec<NvSwitchEvidence>)> {
+        self.collections.fetch_add(1, Ordering::SeqCst);
+        if let Some((started, gate)) = &self.gate {
+            started.send(()).unwrap();
+            gate.recv().unwrap();
+        }
+        Ok(synthetic_evidence(*nonce))
+    }
+}
+
+fn signing_key() -> Ed25519KeyPair {
+    load_signing_key(None).unwrap()
+}
+
+/// Sends a raw HTTP/1.1 request over a Unix socket, and returns the raw response.
+async fn unix_request(path: &PathBuf, request: &str) -> String {
+    let mut stream = UnixStream::connect(path).await.unwrap();
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_evidence_over_tcp_and_unix_socket() {
+    let collections = Arc::new(AtomicUsize::new(0));
+    let opened = Arc::clone(&collections);
+    let collector = Collector::spawn(move || Ok(FakeHost::new(opened)), signing_key(), 4).unwrap();
+    let public_key = collector.public_key().to_vec();
+    let router = router(Arc::new(collector));
+
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let tcp = tokio::spawn({
+        let router = router.clone();
+        async move { axum::serve(listener, router).await }
+    });
+    let socket = std::env::temp_dir().join(format!("nvagent-{}.sock", std::process::id()));
+    std::fs::remove_file(&socket).ok();
+    let unix_listener = UnixListener::bind(&socket).unwrap();
+    let unix = tokio::spawn(async move { axum::serve(unix_listener, router).await });
+
+    let client = reqwest::Client::new();
+    let identity: Value = client
+        .get(format!("{url}{IDENTITY_PATH}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(identity["algorithm"], "ed25519");
+    assert_eq!(identity["public_key"], hex::encode(&public_key));
+
+    let nonce = [0x5au8; NONCE_SIZE];
+    let response = client
+        .post(format!("{url}{EVIDENCE_PATH}"))
+        .json(&json!({ "nonce": hex::encode(nonce) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bundle = EvidenceBundle::decode_detect(&response.bytes().await.unwrap()).unwrap();
+    bundle.verify_signer(&public_key).unwrap();
+    assert_eq!(bundle.nonce_bytes().unwrap(), nonce);
+    assert_eq!(bundle.gpu_evidence.len(), 8);
+    assert_eq!(bundle.switch_evidence.len(), 4);
+    assert_eq!(bundle.host.node_id.as_deref(), Some("fake-node"));
+    for report in bundle
+        .gpu_evidence
+        .iter()
+        .map(DeviceEvidence::evidence_bytes)
+    {
+        let report = SpdmMeasurementReport::parse(&report.unwrap()).unwrap();
+        assert_eq!(report.request.nonce, nonce);
+    }
+
+    let response = client
+        .post(format!("{url}{EVIDENCE_PATH}"))
+        .header(ACCEPT, "application/cbor")
+        .json(&json!({ "nonce": hex::encode([1u8; NONCE_SIZE]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["content-type"], "application/cbor");
+    let bundle = EvidenceBundle::decode_detect(&response.bytes().await.unwrap()).unwrap();
+    bundle.verify_signer(&public_key).unwrap();
+
+    // Other media types, and CBOR with a zero quality, are answered with JSON
+    let response = client
+        .post(format!("{url}{EVIDENCE_PATH}"))
+        .header(ACCEPT, "application/cbor-seq, application/cbor;q=0")
+        .json(&json!({ "nonce": hex::encode([1u8; NONCE_SIZE]) }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["content-type"], "application/json");
+
+    let response = client
+        .post(format!("{url}{EVIDENCE_PATH}"))
+        .json(&json!({ "nonce": "abcd" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let error: Value = response.json().await.unwrap();
+    assert_eq!(error["kind"], "invalid_nonce");
+
+    let response = unix_request(
+        &socket,
+        &format!("GET {HEALTHZ_PATH} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n"),
+    )
+    .await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    let body = json!({ "nonce": hex::encode([2u8; NONCE_SIZE]) }).to_string();
+    let response = unix_request(
+        &socket,
+        &format!(
+            "POST {EVIDENCE_PATH} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+            body.len()
+        ),
+    )
+    .await;
+    assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    let (_, body) = response.split_once("\r\n\r\n").unwrap();
+    let bundle = EvidenceBundle::decode_detect(body.as_bytes()).unwrap();
+    bundle.verify_signer(&public_key).unwrap();
+
+    // Every bundle was collected by the same, once opened, evidence source
+    assert_eq!(collections.load(Ordering::SeqCst), 3);
+
+    tcp.abort();
+    unix.abort();
+    std::fs::remove_file(socket).unwrap();
+}
+
+#[tokio::test]
+async fn test_evidence_for_bound_nonce() {
+    let collector = Collector::spawn(
+        || Ok(FakeHost::new(Arc::new(AtomicUsize::new(0)))),
+        signing_key(),
+        4,
+    )
+    .unwrap();
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}{EVIDENCE_PATH}", listener.local_addr().unwrap());
+    let server =
+        tokio::spawn(async move { axum::serve(listener, router(Arc::new(collector))).await });
+    let client = reqwest::Client::new();
+
+    // The verifier recomputes the nonce the agent derived from the challenge and session key
+    let binding = NonceBinding::now([0xc4; 32], [0x9b; 32]);
+    let response = client
+        .post(&url)
+        .json(&json!({
+            "binding": {
+                "challenge": hex::encode(&binding.challenge),
+                "session_public_key": hex::encode(&binding.session_public_key),
+                "timestamp": binding.timestamp,
+            }
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let bundle = EvidenceBundle::decode_detect(&response.bytes().await.unwrap()).unwrap();
+    let nonce = bundle.nonce_bytes().unwrap();
+    binding.verify(&nonce, Duration::from_secs(60)).unwrap();
+    let mut other_session = binding.clone();
+    other_session.session_public_key = vec![0x9c; 32];
+    assert!(other_session
+        .verify(&nonce, Duration::from_secs(60))
+        .is_err());
+
+    for body in [
+        json!({}),
+        json!({ "nonce": hex::encode([1u8; NONCE_SIZE]), "binding": { "challenge": "00", "session_public_key": "00", "timestamp": 0 } }),
+        json!({ "binding": { "challenge": "zz", "session_public_key": "00", "timestamp": 0 } }),
+    ] {
+        let response = client.post(&url).json(&body).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+        let error: Value = response.json().await.unwrap();
+        assert_eq!(error["kind"], "invalid_nonce");
+    }
+
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_collector_limits_pending_requests() {
+    let (started, mut started_receiver) = mpsc::unbounded_channel();
+    let (gate, gate_receiver) = std_mpsc::channel();
+    let collections = Arc::new(AtomicUsize::new(0));
+    let opened = Arc::clone(&collections);
+    let collector = Collector::spawn(
+        move || {
+            let mut host = FakeHost::new(opened);
+            host.gate = Some((started, gate_receiver));
+            Ok(host)
+        },
+        signing_key(),
+        1,
+    )
+    .unwrap();
+    let collector = Arc::new(collector);
+
+    // The first request is being collected, the second waits in the queue
+    let first = tokio::spawn({
+        let collector = Arc::clone(&collector);
+        async move { collector.collect([1; NONCE_SIZE]).await }
+    });
+    started_receiver.recv().await.unwrap();
+    let second = tokio::spawn({
+        let collector = Arc::clone(&collector);
+        async move { collector.collect([2; NONCE_SIZE]).await }
+    });
+    // Let the second request reach the queue, on the single-threaded test runtime
+    tokio::task::yield_now().await;
+    assert!(matches!(
+        collector.collect([3; NONCE_SIZE]).await,
+        Err(AgentError::Busy)
+    ));
+
+    gate.send(()).unwrap();
+    gate.send(()).unwrap();
+    assert_eq!(
+        first.await.unwrap().unwrap().nonce,
+        hex::encode([1; NONCE_SIZE])
+    );
+    assert_eq!(
+        second.await.unwrap().unwrap().nonce,
+        hex::encode([2; NONCE_SIZE])
+    );
+    assert_eq!(collections.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_collector_reports_open_failures() {
+    let result = Collector::spawn(
+        || -> AttestResult<FakeHost> { Err(AttestError::ConfigError("no devices".to_string())) },
+        signing_key(),
+        1,
+    );
+    assert!(matches!(result, Err(AgentError::Attest(_))));
+}
+
+/// Attester returning scripted states, then the last one forever.
+struct ScriptedAttester {
+    states: Mutex<VecDeque<AttestationState>>,
+    attestations: Arc<AtomicUsize>,
+}
+
+impl Attester for ScriptedAttester {
+    async fn attest(&self) -> AttestationState {
+        self.attestations.fetch_add(1, Ordering::SeqCst);
+        let mut states = self.states.lock().unwrap();
+        if states.len() > 1 {
+            states.pop_front().unwrap()
+        } else {
+            states.front().cloned().unwrap()
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_scheduler_notifies_state_changes() {
+    let degraded = AttestationState::Degraded {
+        reason: "NRAS unreachable".to_string(),
+    };
+    let attestations = Arc::new(AtomicUsize::new(0));
+    let attester = ScriptedAttester {
+        states: Mutex::new(VecDeque::from([
+            AttestationState::Trusted,
+            AttestationState::Trusted,
+            degraded.clone(),
+            AttestationState::Trusted,
+        ])),
+        attestations: Arc::clone(&attestations),
+    };
+    let (changes, mut changes_receiver) = mpsc::unbounded_channel();
+    let scheduler = Scheduler::spawn(
+        attester,
+        Schedule {
+            interval: Duration::from_millis(10),
+            jitter: Duration::from_millis(5),
+        },
+    );
+    let mut state = scheduler.subscribe();
+    scheduler.on_change(move |previous, current| {
+        changes.send((previous.clone(), current.clone())).unwrap();
+    });
+
+    // The first attestation may complete before the callback is registered
+    state
+        .wait_for(AttestationState::is_trusted)
+        .await
+        .expect("Scheduler stopped");
+    let mut received = Vec::new();
+    while received.len() < 2 {
+        let change = changes_receiver.recv().await.unwrap();
+        if change.0 != AttestationState::Unknown {
+            received.push(change);
+        }
+    }
+    assert_eq!(
+        received,
+        [
+            (AttestationState::Trusted, degraded.clone()),
+            (degraded, AttestationState::Trusted),
+        ]
+    );
+    assert_eq!(scheduler.state(), AttestationState::Trusted);
+
+    // Unchanged states are not notified, and the scheduler stops when dropped
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(changes_receiver.try_recv().is_err());
+    drop(scheduler);
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    let stopped_at = attestations.load(Ordering::SeqCst);
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert_eq!(attestations.load(Ordering::SeqCst), stopped_at);
+}
+
+#[tokio::test]
+async fn test_local_attester_with_mock_nras() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let collections = Arc::new(AtomicUsize::new(0));
+    let collector =
+        Collector::spawn(move || Ok(FakeHost::new(collections)), signing_key(), 4).unwrap();
+    let attester = LocalAttester::with_options(
+        Arc::new(collector),
+        nras.gpu_options(),
+        nras.switch_options(),
+        true,
+    );
+
+    assert_eq!(attester.attest().await, AttestationState::Trusted);
+    // Every attestation collects evidence for a fresh nonce
+    let requests = nras.requests();
+    assert_eq!(requests.len(), 2);
+    let first_nonce = requests[0].body["nonce"].clone();
+    assert_eq!(attester.attest().await, AttestationState::Trusted);
+    assert_ne!(nras.requests()[2].body["nonce"], first_nonce);
+
+    nras.set_attestation_result(false);
+    assert!(matches!(
+        attester.attest().await,
+        AttestationState::Untrusted { .. }
+    ));
+    nras.set_attestation_result(true);
+
+    nras.push_failure(MockFailure::WrongKey);
+    assert!(matches!(
+        attester.attest().await,
+        AttestationState::Untrusted { .. }
+    ));
+
+    // A COSE token failing its signature check refutes the evidence too
+    nras.set_token_format(TokenFormat::Cwt);
+    assert_eq!(attester.attest().await, AttestationState::Trusted);
+    nras.push_failure(MockFailure::WrongKey);
+    assert!(matches!(
+        attester.attest().await,
+        AttestationState::Untrusted { .. }
+    ));
+
+    drop(nras);
+    assert!(matches!(
+        attester.attest().await,
+        AttestationState::Degraded { .. }
+    ));
+}
@@ -0,0 +1,191 @@
+use std::{path::PathBuf, time::Duration};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use remote_attestation_verifier::{
+    constants::REMOTE_GPU_VERIFIER_SERVICE_URL, AttestRemoteOptions, BundleEncoding,
+};
+
+use crate::{error::Result, output::OutputFormat};
+
+/// Collect and verify NVIDIA GPU and `NVSwitch` attestation evidence.
+#[derive(Debug, Parser)]
+#[command(name = "nvattest", version, about)]
+pub struct Cli {
+    /// Output format
+    #[arg(long, global = true, value_enum, default_value_t = OutputFormat::Human)]
+    pub format: OutputFormat,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Collect GPU and `NVSwitch` evidence into an evidence bundle
+    Collect(CollectArgs),
+    /// Verify the evidence of an evidence bundle against NRAS
+    Verify(VerifyArgs),
+    /// Decode and verify a saved NRAS token
+    Token(TokenArgs),
+    /// Run the GPU and `NVSwitch` topology checks on the reports of an evidence bundle
+    Topology(TopologyArgs),
+    /// Pretty-print a parsed SPDM attestation report
+    Report(ReportArgs),
+}
+
+/// Kind of attested device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DeviceKind {
+    /// GPUs, through NVML
+    Gpu,
+    /// `NVSwitch` devices, through NSCQ
+    Switch,
+}
+
+impl From<DeviceKind> for remote_attestation_verifier::DeviceKind {
+    fn from(kind: DeviceKind) -> Self {
+        match kind {
+            DeviceKind::Gpu => Self::Gpu,
+            DeviceKind::Switch => Self::Switch,
+        }
+    }
+}
+
+/// Encoding of an evidence bundle file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Encoding {
+    Json,
+    Cbor,
+}
+
+impl From<Encoding> for BundleEncoding {
+    fn from(encoding: Encoding) -> Self {
+        match encoding {
+            Encoding::Json => Self::Json,
+            Encoding::Cbor => Self::Cbor,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct CollectArgs {
+    /// Path of the evidence bundle to write
+    #[arg(short, long)]
+    pub output: PathBuf,
+
+    /// Devices to collect evidence from, comma separated
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [DeviceKind::Gpu])]
+    pub devices: Vec<DeviceKind>,
+
+    /// Hex encoded 32-byte nonce; a random nonce is used if omitted
+    #[arg(long)]
+    pub nonce: Option<String>,
+
+    /// Encoding of the evidence bundle
+    #[arg(long, value_enum, default_value_t = Encoding::Json)]
+    pub encoding: Encoding,
+
+    /// Identifier of the node within the fleet, recorded in the bundle
+    #[arg(long)]
+    pub node_id: Option<String>,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyArgs {
+    /// Path of the evidence bundle, in JSON or CBOR
+    pub bundle: PathBuf,
+
+    #[command(flatten)]
+    pub nras: NrasArgs,
+}
+
+/// Remote attestation options, layered over the `NV_*` environment variables.
+#[derive(Debug, Default, Args)]
+pub struct NrasArgs {
+    /// URL of the NRAS GPU attestation endpoint
+    #[arg(long)]
+    pub gpu_verifier_url: Option<String>,
+
+    /// URL of the NRAS `NVSwitch` attestation endpoint
+    #[arg(long)]
+    pub switch_verifier_url: Option<String>,
+
+    /// Remote attestation config file, in TOML or JSON
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+
+    /// File holding the NRAS service key
+    #[arg(long)]
+    pub service_key_file: Option<PathBuf>,
+
+    /// Request timeout, in seconds
+    #[arg(long)]
+    pub timeout_secs: Option<u64>,
+
+    /// Accept device certificates whose revocation status is on hold
+    #[arg(long)]
+    pub allow_hold_cert: bool,
+}
+
+impl NrasArgs {
+    /// Returns the remote attestation options for the given kind of device.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the config file, the environment or the service key file is invalid.
+    pub fn options(&self, kind: DeviceKind) -> Result<AttestRemoteOptions> {
+        let mut builder =
+            AttestRemoteOptions::builder().layered(self.config.as_deref(), true, kind.into());
+        let verifier_url = match kind {
+            DeviceKind::Gpu => &self.gpu_verifier_url,
+            DeviceKind::Switch => &self.switch_verifier_url,
+        };
+        if let Some(verifier_url) = verifier_url {
+            builder = builder.verifier_url(verifier_url);
+        }
+        if let Some(service_key_file) = &self.service_key_file {
+            builder = builder.service_key_file(service_key_file);
+        }
+        if let Some(timeout_secs) = self.timeout_secs {
+            builder = builder.timeout(Duration::from_secs(timeout_secs));
+        }
+        if self.allow_hold_cert {
+            builder = builder.allow_hold_cert(true);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+#[derive(Debug, Args)]
+pub struct TokenArgs {
+    /// Path of a raw NRAS JWT or of a full NRAS response, or `-` for standard input
+    pub token: PathBuf,
+
+    /// URL of the NRAS instance that issued the token, used to fetch its JWKS
+    #[arg(long, default_value = REMOTE_GPU_VERIFIER_SERVICE_URL)]
+    pub verifier_url: String,
+}
+
+#[derive(Debug, Args)]
+pub struct TopologyArgs {
+    /// Path of the evidence bundle, holding the reports of all GPUs and switches
+    pub bundle: PathBuf,
+}
+
+#[derive(Debug, Args)]
+pub struct ReportArgs {
+    /// Path of an evidence bundle, or of a binary report with `--raw`
+    pub input: PathBuf,
+
+    /// Read the input as a binary SPDM report instead of an evidence bundle
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Kind of device whose report is printed
+    #[arg(long, value_enum, default_value_t = DeviceKind::Gpu, conflicts_with = "raw")]
+    pub device: DeviceKind,
+
+    /// Index of the device within the evidence bundle
+    #[arg(long, default_value_t = 0, conflicts_with = "raw")]
+    pub index: usize,
+}
@@ -0,0 +1,91 @@
+use std::{fmt, path::PathBuf};
+
+use nscq::NscqHandler;
+use nvml_wrapper::Nvml;
+use rand::Rng;
+use remote_attestation_verifier::{
+    collect_gpu_evidence, collect_nvswitch_evidence, errors::NscqError, AttestError,
+    BundleEncoding, EvidenceBundle, HostMetadata,
+};
+use serde::Serialize;
+
+use super::parse_nonce;
+use crate::{
+    cli::{CollectArgs, DeviceKind},
+    error::{CliError, Result},
+    output::Outcome,
+};
+
+/// Path of the hostname on Linux.
+const HOSTNAME_PATH: &str = "/proc/sys/kernel/hostname";
+
+#[derive(Debug, Serialize)]
+pub struct CollectOutcome {
+    pub path: PathBuf,
+    pub encoding: BundleEncoding,
+    pub nonce: String,
+    pub gpus: usize,
+    pub switches: usize,
+    pub digest: String,
+}
+
+impl Outcome for CollectOutcome {}
+
+impl fmt::Display for CollectOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Evidence bundle: {}", self.path.display())?;
+        writeln!(f, "Nonce:           {}", self.nonce)?;
+        writeln!(f, "GPUs:            {}", self.gpus)?;
+        writeln!(f, "Switches:        {}", self.switches)?;
+        writeln!(f, "Digest:          {}", self.digest)
+    }
+}
+
+/// Collects evidence from the local devices, and writes it to an evidence bundle.
+///
+/// # Errors
+///
+/// Returns an error if the nonce is invalid, if NVML or NSCQ fail, or if the bundle
+/// cannot be written.
+pub fn run(args: &CollectArgs) -> Result<CollectOutcome> {
+    let nonce = match &args.nonce {
+        Some(nonce) => parse_nonce(nonce)?,
+        None => rand::thread_rng().gen(),
+    };
+    let mut host = HostMetadata {
+        hostname: std::fs::read_to_string(HOSTNAME_PATH)
+            .ok()
+            .map(|hostname| hostname.trim().to_string()),
+        node_id: args.node_id.clone(),
+        ..HostMetadata::default()
+    };
+
+    let gpu_evidence = if args.devices.contains(&DeviceKind::Gpu) {
+        let nvml = Nvml::init().map_err(AttestError::from)?;
+        host.driver_version = nvml.sys_driver_version().ok();
+        collect_gpu_evidence(&nvml, &nonce)?
+    } else {
+        Vec::new()
+    };
+    let switch_evidence = if args.devices.contains(&DeviceKind::Switch) {
+        let nscq = NscqHandler::new().map_err(|rc| AttestError::from(NscqError::from(rc)))?;
+        collect_nvswitch_evidence(&nscq, &nonce)?
+    } else {
+        Vec::new()
+    };
+
+    let bundle = EvidenceBundle::new(&nonce, gpu_evidence, switch_evidence, host);
+    let encoding = args.encoding.into();
+    std::fs::write(&args.output, bundle.encode(encoding)?).map_err(|source| CliError::Write {
+        path: args.output.clone(),
+        source,
+    })?;
+    Ok(CollectOutcome {
+        path: args.output.clone(),
+        encoding,
+        nonce: bundle.nonce,
+        gpus: bundle.gpu_evidence.len(),
+        switches: bundle.switch_evidence.len(),
+        digest: bundle.digest,
+    })
+}
@@ -0,0 +1,50 @@
+//! Implementations of the `nvattest` subcommands.
+//!
+//! Each subcommand returns an [`Outcome`](crate::output::Outcome), printed by `main`
+//! in the requested output format.
+
+use std::{io::Read, path::Path};
+
+use remote_attestation_verifier::EvidenceBundle;
+
+use crate::error::{CliError, Result};
+
+pub mod collect;
+pub mod report;
+pub mod token;
+pub mod topology;
+pub mod verify;
+
+/// Reads a file, or standard input if `path` is `-`.
+fn read_input(path: &Path) -> Result<Vec<u8>> {
+    let read_error = |source| CliError::Read {
+        path: path.to_path_buf(),
+        source,
+    };
+    if path == Path::new("-") {
+        let mut bytes = Vec::new();
+        std::io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(read_error)?;
+        Ok(bytes)
+    } else {
+        std::fs::read(path).map_err(read_error)
+    }
+}
+
+/// Reads an evidence bundle, in JSON or CBOR, and checks its version and digest.
+fn read_bundle(path: &Path) -> Result<EvidenceBundle> {
+    Ok(EvidenceBundle::decode_detect(&read_input(path)?)?)
+}
+
+/// Decodes a hex encoded 32-byte nonce.
+fn parse_nonce(nonce: &str) -> Result<[u8; 32]> {
+    let invalid_nonce = |message: String| CliError::InvalidArgument {
+        name: "nonce",
+        message,
+    };
+    hex::decode(nonce)
+        .map_err(|e| invalid_nonce(e.to_string()))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| invalid_nonce(format!("expected 32 bytes, got {}", bytes.len())))
+}
@@ -0,0 +1,206 @@
+use std::fmt;
+
+use serde::Serialize;
+use topology::spdm::{opaque_field_name, SpdmMeasurementReport};
+
+use super::{read_bundle, read_input};
+use crate::{
+    cli::{DeviceKind, ReportArgs},
+    error::{CliError, Result},
+    output::Outcome,
+};
+
+#[derive(Debug, Serialize)]
+pub struct RequestView {
+    pub spdm_version: String,
+    pub request_code: u8,
+    pub param1: u8,
+    pub param2: u8,
+    pub nonce: String,
+    pub slot_id: u8,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MeasurementBlockView {
+    pub index: u8,
+    pub measurement_specification: u8,
+    pub value_type: u8,
+    pub value: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct OpaqueFieldView {
+    pub data_type: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<&'static str>,
+    pub value: String,
+    /// The value as text, if it is printable ASCII
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ResponseView {
+    pub spdm_version: String,
+    pub response_code: u8,
+    pub param1: u8,
+    pub param2: u8,
+    pub measurement_blocks: Vec<MeasurementBlockView>,
+    pub nonce: String,
+    pub opaque_fields: Vec<OpaqueFieldView>,
+    pub signature: String,
+}
+
+/// A parsed SPDM report, with byte strings hex encoded.
+#[derive(Debug, Serialize)]
+pub struct ReportOutcome {
+    pub length: usize,
+    pub request: RequestView,
+    pub response: ResponseView,
+}
+
+impl Outcome for ReportOutcome {}
+
+impl ReportOutcome {
+    /// Creates the outcome of a parsed report of `length` bytes.
+    #[must_use]
+    pub fn new(length: usize, report: SpdmMeasurementReport) -> Self {
+        let SpdmMeasurementReport { request, response } = report;
+        Self {
+            length,
+            request: RequestView {
+                spdm_version: spdm_version(request.spdm_version),
+                request_code: request.request_code,
+                param1: request.param1,
+                param2: request.param2,
+                nonce: hex::encode(request.nonce),
+                slot_id: request.slot_id,
+            },
+            response: ResponseView {
+                spdm_version: spdm_version(response.spdm_version),
+                response_code: response.response_code,
+                param1: response.param1,
+                param2: response.param2,
+                measurement_blocks: response
+                    .measurement_blocks
+                    .into_iter()
+                    .map(|block| MeasurementBlockView {
+                        index: block.index,
+                        measurement_specification: block.measurement_specification,
+                        value_type: block.value_type,
+                        value: hex::encode(block.value),
+                    })
+                    .collect(),
+                nonce: hex::encode(response.nonce),
+                opaque_fields: response
+                    .opaque_fields
+                    .into_iter()
+                    .map(|field| OpaqueFieldView {
+                        data_type: field.data_type,
+                        name: opaque_field_name(field.data_type),
+                        text: printable_text(&field.value),
+                        value: hex::encode(field.value),
+                    })
+                    .collect(),
+                signature: hex::encode(response.signature),
+            },
+        }
+    }
+}
+
+impl fmt::Display for ReportOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self {
+            length,
+            request,
+            response,
+        } = self;
+        writeln!(f, "Report length: {length} bytes")?;
+        writeln!(f, "GET_MEASUREMENTS request:")?;
+        writeln!(f, "  SPDM version:  {}", request.spdm_version)?;
+        writeln!(f, "  Request code:  {:#04x}", request.request_code)?;
+        writeln!(f, "  Param1:        {:#04x}", request.param1)?;
+        writeln!(f, "  Param2:        {:#04x}", request.param2)?;
+        writeln!(f, "  Nonce:         {}", request.nonce)?;
+        writeln!(f, "  Slot ID:       {}", request.slot_id)?;
+        writeln!(f, "MEASUREMENTS response:")?;
+        writeln!(f, "  SPDM version:  {}", response.spdm_version)?;
+        writeln!(f, "  Response code: {:#04x}", response.response_code)?;
+        writeln!(f, "  Param1:        {:#04x}", response.param1)?;
+        writeln!(f, "  Param2:        {:#04x}", response.param2)?;
+        writeln!(
+            f,
+            "  Measurement blocks: {}",
+            response.measurement_blocks.len()
+        )?;
+        for block in &response.measurement_blocks {
+            writeln!(
+                f,
+                "    [{:>3}] type {:#04x}: {}",
+                block.index, block.value_type, block.value
+            )?;
+        }
+        writeln!(f, "  Nonce:         {}", response.nonce)?;
+        writeln!(f, "  Opaque data fields: {}", response.opaque_fields.len())?;
+        for field in &response.opaque_fields {
+            let name = field.name.unwrap_or("UNKNOWN");
+            match &field.text {
+                Some(text) => writeln!(f, "    {name} ({}): {text:?}", field.data_type)?,
+                None => writeln!(f, "    {name} ({}): {}", field.data_type, field.value)?,
+            }
+        }
+        writeln!(f, "  Signature:     {}", response.signature)
+    }
+}
+
+/// Parses and returns the SPDM report of a device of an evidence bundle, or of a
+/// binary report file.
+///
+/// # Errors
+///
+/// Returns an error if the input cannot be read, if the bundle has no such device, or
+/// if the report cannot be parsed.
+pub fn run(args: &ReportArgs) -> Result<ReportOutcome> {
+    let report = if args.raw {
+        read_input(&args.input)?
+    } else {
+        let bundle = read_bundle(&args.input)?;
+        let no_such_device = |count: usize| CliError::InvalidArgument {
+            name: "index",
+            message: format!(
+                "the bundle holds {count} {:?} reports, index {} is out of range",
+                args.device, args.index
+            ),
+        };
+        match args.device {
+            DeviceKind::Gpu => bundle
+                .gpu_evidence
+                .get(args.index)
+                .ok_or_else(|| no_such_device(bundle.gpu_evidence.len()))?
+                .evidence_bytes()?,
+            DeviceKind::Switch => bundle
+                .switch_evidence
+                .get(args.index)
+                .ok_or_else(|| no_such_device(bundle.switch_evidence.len()))?
+                .evidence_bytes()?,
+        }
+    };
+    Ok(ReportOutcome::new(
+        report.len(),
+        SpdmMeasurementReport::parse(&report)?,
+    ))
+}
+
+/// Formats an SPDM version byte, e.g. `0x11` as `1.1`.
+fn spdm_version(version: u8) -> String {
+    format!("{}.{}", version >> 4, version & 0x0F)
+}
+
+/// Returns the value as text if it is printable ASCII, ignoring trailing NUL bytes.
+fn printable_text(value: &[u8]) -> Option<String> {
+    let end = value.iter().rposition(|byte| *byte != 0)? + 1;
+    let text = &value[..end];
+    text.iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+        .then(|| String::from_utf8_lossy(text).into_owned())
+}
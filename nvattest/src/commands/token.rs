@@ -0,0 +1,76 @@
+use std::{collections::BTreeMap, fmt};
+
+use remote_attestation_verifier::{blocking::decode_nras_token, utils::get_overall_claims_token};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::read_input;
+use crate::{
+    cli::TokenArgs,
+    error::{CliError, Result},
+    output::{verdict, Outcome},
+};
+
+#[derive(Debug, Serialize)]
+pub struct TokenOutcome {
+    pub verifier_url: String,
+    pub overall_attestation_result: bool,
+    /// All claims of the token, including the overall attestation result
+    pub claims: BTreeMap<String, Value>,
+}
+
+impl Outcome for TokenOutcome {
+    fn succeeded(&self) -> bool {
+        self.overall_attestation_result
+    }
+}
+
+impl fmt::Display for TokenOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Token signature: valid ({})", self.verifier_url)?;
+        writeln!(
+            f,
+            "Overall attestation result: {}",
+            verdict(self.overall_attestation_result)
+        )?;
+        writeln!(f, "Claims:")?;
+        for (name, value) in &self.claims {
+            writeln!(f, "  {name}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Decodes a saved NRAS token, and verifies it against the JWKS of the issuing NRAS.
+///
+/// The input is either the overall JWT itself, or the full JSON response of NRAS, from
+/// which the overall JWT is extracted.
+///
+/// # Errors
+///
+/// Returns an error if the input cannot be read or holds no token, if the JWKS cannot be
+/// fetched, or if the token signature or claims are invalid.
+pub fn run(args: &TokenArgs) -> Result<TokenOutcome> {
+    let input = read_input(&args.token)?;
+    let input = String::from_utf8(input).map_err(|e| CliError::InvalidArgument {
+        name: "token",
+        message: e.to_string(),
+    })?;
+    let input = input.trim();
+    let token = if input.starts_with('[') {
+        get_overall_claims_token(&serde_json::from_str(input)?)?
+    } else {
+        input.to_string()
+    };
+
+    let claims = decode_nras_token(&args.verifier_url, &token)?;
+    let overall_attestation_result = claims.overall_attestation_result;
+    let Value::Object(claims) = serde_json::to_value(claims)? else {
+        unreachable!("NRAS claims serialize to a JSON object");
+    };
+    Ok(TokenOutcome {
+        verifier_url: args.verifier_url.clone(),
+        overall_attestation_result,
+        claims: claims.into_iter().collect(),
+    })
+}
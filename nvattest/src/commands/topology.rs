@@ -0,0 +1,87 @@
+use std::{fmt, path::PathBuf};
+
+use remote_attestation_verifier::check_bundle_topology;
+use serde::Serialize;
+
+use super::read_bundle;
+use crate::{
+    cli::TopologyArgs,
+    error::Result,
+    output::{verdict, Outcome},
+};
+
+#[derive(Debug, Serialize)]
+pub struct TopologyOutcome {
+    pub bundle: PathBuf,
+    pub gpus: usize,
+    pub switches: usize,
+    /// Hex encoded PDIs of the switches connected to every GPU, as reported by the GPUs
+    pub switch_pdis: Vec<String>,
+    pub gpu_topology_passed: bool,
+    /// `None` if the bundle holds no switch evidence
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub switch_topology_passed: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Outcome for TopologyOutcome {
+    fn succeeded(&self) -> bool {
+        self.gpu_topology_passed && self.switch_topology_passed != Some(false)
+    }
+}
+
+impl fmt::Display for TopologyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Evidence bundle: {}", self.bundle.display())?;
+        writeln!(
+            f,
+            "GPU topology check: {} ({} reports)",
+            verdict(self.gpu_topology_passed),
+            self.gpus
+        )?;
+        for pdi in &self.switch_pdis {
+            writeln!(f, "  Switch PDI: {pdi}")?;
+        }
+        match self.switch_topology_passed {
+            Some(passed) => writeln!(
+                f,
+                "Switch topology check: {} ({} reports)",
+                verdict(passed),
+                self.switches
+            )?,
+            None => writeln!(f, "Switch topology check: skipped (no switch evidence)")?,
+        }
+        if let Some(error) = &self.error {
+            writeln!(f, "Error: {error}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Runs the GPU topology check on the GPU reports of an evidence bundle, and the switch
+/// topology check on its switch reports, if any.
+///
+/// A failed check is reported in the outcome rather than as an error.
+///
+/// # Errors
+///
+/// Returns an error if the bundle cannot be read, or if a report is not valid base64.
+pub fn run(args: &TopologyArgs) -> Result<TopologyOutcome> {
+    let bundle = read_bundle(&args.bundle)?;
+    let report = check_bundle_topology(&bundle)?;
+    let has_switches = !bundle.switch_evidence.is_empty();
+    Ok(TopologyOutcome {
+        bundle: args.bundle.clone(),
+        gpus: bundle.gpu_evidence.len(),
+        switches: bundle.switch_evidence.len(),
+        switch_topology_passed: has_switches.then_some(report.switch_topology_passed),
+        // Without switch evidence, only a failed GPU check is an error
+        error: report
+            .error
+            .filter(|_| has_switches || !report.gpu_topology_passed)
+            .map(|e| e.to_string()),
+        gpu_topology_passed: report.gpu_topology_passed,
+        switch_pdis: report.switch_pdis,
+    })
+}
@@ -0,0 +1,105 @@
+use std::{fmt, path::PathBuf};
+
+use remote_attestation_verifier::blocking::{verify_gpu_attestation, verify_nvswitch_attestation};
+use serde::Serialize;
+use serde_json::Value;
+
+use super::read_bundle;
+use crate::{
+    cli::{DeviceKind, VerifyArgs},
+    error::Result,
+    output::{verdict, Outcome},
+};
+
+/// The NRAS verdict for the devices of one kind.
+#[derive(Debug, Serialize)]
+pub struct DeviceVerification {
+    pub attestation_passed: bool,
+    pub devices: usize,
+    /// The full NRAS response
+    pub response: Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct VerifyOutcome {
+    pub bundle: PathBuf,
+    pub nonce: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<DeviceVerification>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub switch: Option<DeviceVerification>,
+}
+
+impl Outcome for VerifyOutcome {
+    fn succeeded(&self) -> bool {
+        let verifications = [&self.gpu, &self.switch];
+        verifications
+            .iter()
+            .any(|verification| verification.is_some())
+            && verifications
+                .iter()
+                .filter_map(|verification| verification.as_ref())
+                .all(|verification| verification.attestation_passed)
+    }
+}
+
+impl fmt::Display for VerifyOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Evidence bundle: {}", self.bundle.display())?;
+        writeln!(f, "Nonce:           {}", self.nonce)?;
+        for (name, verification) in [("GPU", &self.gpu), ("Switch", &self.switch)] {
+            if let Some(verification) = verification {
+                writeln!(
+                    f,
+                    "{name} attestation: {} ({} devices)",
+                    verdict(verification.attestation_passed),
+                    verification.devices
+                )?;
+            }
+        }
+        if self.gpu.is_none() && self.switch.is_none() {
+            writeln!(f, "No evidence to verify")?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies the GPU and switch evidence of an evidence bundle against NRAS.
+///
+/// # Errors
+///
+/// Returns an error if the bundle cannot be read or is invalid, if the remote attestation
+/// options are invalid, or if NRAS cannot be reached or its response cannot be verified.
+pub fn run(args: &VerifyArgs) -> Result<VerifyOutcome> {
+    let bundle = read_bundle(&args.bundle)?;
+    let gpu = if bundle.gpu_evidence.is_empty() {
+        None
+    } else {
+        let options = args.nras.options(DeviceKind::Gpu)?;
+        let (attestation_passed, response) =
+            verify_gpu_attestation(&bundle.gpu_evidence, &bundle.nonce, options)?;
+        Some(DeviceVerification {
+            attestation_passed,
+            devices: bundle.gpu_evidence.len(),
+            response,
+        })
+    };
+    let switch = if bundle.switch_evidence.is_empty() {
+        None
+    } else {
+        let options = args.nras.options(DeviceKind::Switch)?;
+        let (attestation_passed, response) =
+            verify_nvswitch_attestation(&bundle.switch_evidence, &bundle.nonce, options)?;
+        Some(DeviceVerification {
+            attestation_passed,
+            devices: bundle.switch_evidence.len(),
+            response,
+        })
+    };
+    Ok(VerifyOutcome {
+        bundle: args.bundle.clone(),
+        nonce: bundle.nonce,
+        gpu,
+        switch,
+    })
+}
@@ -0,0 +1,57 @@
+use std::path::PathBuf;
+
+use remote_attestation_verifier::AttestError;
+use thiserror::Error;
+use topology::error::NvidiaRemoteAttestationError;
+
+pub type Result<T> = std::result::Result<T, CliError>;
+
+#[derive(Debug, Error)]
+pub enum CliError {
+    #[error("Failed to read `{path}`: {source}")]
+    Read {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Failed to write `{path}`: {source}")]
+    Write {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    #[error("Invalid argument `{name}`: {message}")]
+    InvalidArgument { name: &'static str, message: String },
+    #[error(transparent)]
+    Attest(Box<AttestError>),
+    #[error(transparent)]
+    Topology(Box<NvidiaRemoteAttestationError>),
+    #[error("Failed to serialize output: {0}")]
+    Output(#[from] serde_json::Error),
+}
+
+impl From<AttestError> for CliError {
+    fn from(error: AttestError) -> Self {
+        Self::Attest(Box::new(error))
+    }
+}
+
+impl From<NvidiaRemoteAttestationError> for CliError {
+    fn from(error: NvidiaRemoteAttestationError) -> Self {
+        Self::Topology(Box::new(error))
+    }
+}
+
+impl CliError {
+    /// Returns a stable `snake_case` category of the error, for JSON output.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Read { .. } | Self::Write { .. } => "io",
+            Self::InvalidArgument { .. } => "invalid_argument",
+            Self::Attest(error) => error.kind().as_str(),
+            Self::Topology(_) => "topology",
+            Self::Output(_) => "output",
+        }
+    }
+}
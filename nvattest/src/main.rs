@@ -0,0 +1,52 @@
+//! `nvattest`: command-line tool to collect, verify and inspect NVIDIA GPU and
+//! `NVSwitch` attestation evidence.
+
+use std::process::ExitCode;
+
+use clap::Parser;
+
+use crate::{
+    cli::{Cli, Command},
+    error::Result,
+    output::{print_error, print_outcome, Outcome, OutputFormat},
+};
+
+mod cli;
+mod commands;
+mod error;
+mod output;
+#[cfg(test)]
+mod tests;
+
+/// Exit code of a command whose checked property does not hold.
+const EXIT_FAILED: u8 = 1;
+
+/// Exit code of a command that could not run.
+const EXIT_ERROR: u8 = 2;
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match &cli.command {
+        Command::Collect(args) => commands::collect::run(args).and_then(|o| report(&o, cli.format)),
+        Command::Verify(args) => commands::verify::run(args).and_then(|o| report(&o, cli.format)),
+        Command::Token(args) => commands::token::run(args).and_then(|o| report(&o, cli.format)),
+        Command::Topology(args) => {
+            commands::topology::run(args).and_then(|o| report(&o, cli.format))
+        }
+        Command::Report(args) => commands::report::run(args).and_then(|o| report(&o, cli.format)),
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::from(EXIT_FAILED),
+        Err(e) => {
+            print_error(&e, cli.format);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// Prints the outcome, and returns whether the command succeeded.
+fn report(outcome: &impl Outcome, format: OutputFormat) -> Result<bool> {
+    print_outcome(outcome, format)?;
+    Ok(outcome.succeeded())
+}
@@ -0,0 +1,58 @@
+use std::fmt::Display;
+
+use clap::ValueEnum;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::error::{CliError, Result};
+
+/// Format of the command output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human-readable text
+    Human,
+    /// Pretty-printed JSON
+    Json,
+}
+
+/// The result of a command, printable as text or JSON.
+pub trait Outcome: Serialize + Display {
+    /// Returns `false` if the command ran, but the checked property does not hold,
+    /// e.g. a failed attestation.
+    fn succeeded(&self) -> bool {
+        true
+    }
+}
+
+/// Prints the outcome of a command to standard output.
+///
+/// # Errors
+///
+/// Returns an error if the outcome cannot be serialized to JSON.
+pub fn print_outcome(outcome: &impl Outcome, format: OutputFormat) -> Result<()> {
+    match format {
+        OutputFormat::Human => print!("{outcome}"),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(outcome)?),
+    }
+    Ok(())
+}
+
+/// Prints an error to standard error.
+pub fn print_error(error: &CliError, format: OutputFormat) {
+    match format {
+        OutputFormat::Human => eprintln!("Error: {error}"),
+        OutputFormat::Json => eprintln!(
+            "{:#}",
+            json!({ "error": error.to_string(), "kind": error.kind() })
+        ),
+    }
+}
+
+/// Returns "passed" or "failed".
+pub const fn verdict(passed: bool) -> &'static str {
+    if passed {
+        "passed"
+    } else {
+        "failed"
+    }
+}
@@ -0,0 +1,127 @@
+use std::path::PathBuf;
+
+use clap::CommandFactory;
+use remote_attestation_verifier::{
+    test_support::{synthetic_evidence, MockNras},
+    BundleEncoding, DeviceEvidence, EvidenceBundle, HostMetadata, NvSwitchEvidence,
+};
+
+use crate::{
+    cli::{Cli, DeviceKind, NrasArgs, ReportArgs, TokenArgs, TopologyArgs, VerifyArgs},
+    commands,
+    error::CliError,
+    output::Outcome,
+};
+
+/// Nonce of the bundles of the tests.
+const NONCE: [u8; 32] = [0x3c; 32];
+
+/// Writes an evidence bundle to a temporary file.
+fn write_bundle(
+    name: &str,
+    gpu_evidence: Vec<DeviceEvidence>,
+    switch_evidence: Vec<NvSwitchEvidence>,
+    nonce: &[u8; 32],
+) -> PathBuf {
+    let bundle = EvidenceBundle::new(
+        nonce,
+        gpu_evidence,
+        switch_evidence,
+        HostMetadata::default(),
+    );
+    let path = std::env::temp_dir().join(format!("nvattest-{name}-{}.cbor", std::process::id()));
+    std::fs::write(&path, bundle.encode(BundleEncoding::Cbor).unwrap()).unwrap();
+    path
+}
+
+#[test]
+fn test_cli_definition() {
+    Cli::command().debug_assert();
+}
+
+#[test]
+fn test_topology_and_report_commands() {
+    let (gpu_evidence, switch_evidence) = synthetic_evidence(NONCE);
+    let path = write_bundle("topology", gpu_evidence.clone(), switch_evidence, &NONCE);
+
+    let outcome = commands::topology::run(&TopologyArgs {
+        bundle: path.clone(),
+    })
+    .unwrap();
+    assert!(outcome.succeeded(), "{outcome}");
+    assert_eq!(outcome.switch_pdis.len(), 4);
+    assert_eq!(outcome.switch_topology_passed, Some(true));
+
+    let report_args = |device, index| ReportArgs {
+        input: path.clone(),
+        raw: false,
+        device,
+        index,
+    };
+    let outcome = commands::report::run(&report_args(DeviceKind::Switch, 2)).unwrap();
+    assert_eq!(outcome.request.nonce, hex::encode(NONCE));
+    assert_eq!(outcome.response.measurement_blocks.len(), 2);
+    assert_eq!(outcome.response.opaque_fields[0].name, Some("SWITCH_PDI"));
+    assert_eq!(outcome.response.signature.len(), 2 * 96);
+    assert!(outcome.to_string().contains("GPU_LINK_CONN (26)"));
+    assert!(matches!(
+        commands::report::run(&report_args(DeviceKind::Switch, 4)),
+        Err(CliError::InvalidArgument { name: "index", .. })
+    ));
+
+    // A bundle missing a GPU fails the topology check, without erroring
+    let incomplete = write_bundle("incomplete", gpu_evidence[1..].to_vec(), Vec::new(), &NONCE);
+    let outcome = commands::topology::run(&TopologyArgs {
+        bundle: incomplete.clone(),
+    })
+    .unwrap();
+    assert!(!outcome.succeeded());
+    assert!(outcome.error.is_some());
+    assert_eq!(outcome.switch_topology_passed, None);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(incomplete).unwrap();
+}
+
+#[test]
+fn test_verify_and_token_commands() {
+    // The mock NRAS runs on a runtime of its own, as the commands block the test thread
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+    let nras = runtime
+        .block_on(MockNras::start())
+        .expect("Failed to start mock NRAS");
+    let (gpu_evidence, switch_evidence) = synthetic_evidence(NONCE);
+    let path = write_bundle("verify", gpu_evidence, switch_evidence, &NONCE);
+    let args = VerifyArgs {
+        bundle: path.clone(),
+        nras: NrasArgs {
+            gpu_verifier_url: Some(nras.gpu_verifier_url()),
+            switch_verifier_url: Some(nras.switch_verifier_url()),
+            ..NrasArgs::default()
+        },
+    };
+
+    let outcome = commands::verify::run(&args).unwrap();
+    assert!(outcome.succeeded(), "{outcome}");
+    assert_eq!(outcome.gpu.as_ref().unwrap().devices, 8);
+    assert_eq!(outcome.switch.as_ref().unwrap().devices, 4);
+
+    // The saved NRAS response holds the overall token
+    let token_path = path.with_extension("response.json");
+    let response = &outcome.gpu.as_ref().unwrap().response;
+    std::fs::write(&token_path, response.to_string()).unwrap();
+    let outcome = commands::token::run(&TokenArgs {
+        token: token_path.clone(),
+        verifier_url: nras.gpu_verifier_url(),
+    })
+    .unwrap();
+    assert!(outcome.succeeded());
+    assert_eq!(outcome.claims["x-nvidia-overall-att-result"], true);
+
+    nras.set_attestation_result(false);
+    let outcome = commands::verify::run(&args).unwrap();
+    assert!(!outcome.succeeded());
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(token_path).unwrap();
+}
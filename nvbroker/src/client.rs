@@ -0,0 +1,140 @@
+use remote_attestation_verifier::{EvidenceBundle, NonceBinding};
+use reqwest::Response;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    error::{BrokerError, Result},
+    service::{Challenge, ReleaseRequest, CHALLENGE_PATH, SECRETS_PATH},
+    store::check_secret_name,
+    wrap::{UnwrapKey, WrappedSecret},
+};
+
+/// Client of the key broker, run by the node.
+#[derive(Debug, Clone)]
+pub struct BrokerClient {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl BrokerClient {
+    /// Creates a client of the broker at `url`, e.g. `https://broker.example:8443`.
+    pub fn new(url: impl Into<String>) -> Self {
+        Self::with_client(reqwest::Client::new(), url)
+    }
+
+    /// Creates a client of the broker at `url`, sending requests with `client`.
+    pub fn with_client(client: reqwest::Client, url: impl Into<String>) -> Self {
+        Self {
+            client,
+            url: url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// Requests a challenge from the broker, and generates the ephemeral key the secret
+    /// will be wrapped to.
+    ///
+    /// The evidence of the node must then be collected with
+    /// [`PendingRelease::nonce`], and sent with [`PendingRelease::fetch_secret`].
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::Transport` - If the broker cannot be reached
+    /// * `BrokerError::Rejected` - If the broker answers with an error
+    /// * `BrokerError::InvalidChallenge` - If the challenge is not 32 hex encoded bytes
+    /// * `BrokerError::Crypto` - If no ephemeral key can be generated
+    pub async fn start_release(&self) -> Result<PendingRelease<'_>> {
+        let response = self
+            .client
+            .post(format!("{}{CHALLENGE_PATH}", self.url))
+            .send()
+            .await?;
+        let challenge: Challenge = parse_response(response).await?;
+        let challenge_bytes = hex::decode(&challenge.challenge)
+            .map_err(|e| BrokerError::InvalidChallenge(e.to_string()))?;
+        let key = UnwrapKey::generate()?;
+        let binding = NonceBinding::now(challenge_bytes, key.public_key().as_slice());
+        Ok(PendingRelease {
+            client: self,
+            challenge,
+            binding,
+            key,
+        })
+    }
+}
+
+/// A secret release in progress: a challenge of the broker, and the ephemeral key the
+/// secret will be wrapped to.
+#[derive(Debug)]
+pub struct PendingRelease<'a> {
+    client: &'a BrokerClient,
+    challenge: Challenge,
+    binding: NonceBinding,
+    key: UnwrapKey,
+}
+
+impl PendingRelease<'_> {
+    /// Returns the challenge issued by the broker.
+    #[must_use]
+    pub const fn challenge(&self) -> &Challenge {
+        &self.challenge
+    }
+
+    /// Returns the nonce to collect the evidence of the node with, bound to the
+    /// challenge and to the ephemeral key.
+    #[must_use]
+    pub fn nonce(&self) -> [u8; 32] {
+        self.binding.derive()
+    }
+
+    /// Sends `bundle`, collected with [`PendingRelease::nonce`], to the broker, and
+    /// returns the secret `name` it releases.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::InvalidSecretName` - If `name` is not a valid secret name
+    /// * `BrokerError::Transport` - If the broker cannot be reached
+    /// * `BrokerError::Rejected` - If the broker rejects the request, e.g. with the
+    ///   `attestation_failed` kind if the evidence failed the appraisal
+    /// * `BrokerError::Crypto` - If the secret cannot be unwrapped
+    pub async fn fetch_secret(self, name: &str, bundle: &EvidenceBundle) -> Result<Vec<u8>> {
+        check_secret_name(name)?;
+        let request = ReleaseRequest {
+            challenge: self.challenge.challenge,
+            public_key: hex::encode(self.key.public_key()),
+            timestamp: self.binding.timestamp,
+            bundle: bundle.clone(),
+        };
+        let response = self
+            .client
+            .client
+            .post(format!("{}{SECRETS_PATH}/{name}", self.client.url))
+            .json(&request)
+            .send()
+            .await?;
+        let wrapped: WrappedSecret = parse_response(response).await?;
+        if wrapped.name != name {
+            return Err(BrokerError::Crypto(format!(
+                "Broker released secret `{}` instead of `{name}`",
+                wrapped.name
+            )));
+        }
+        self.key.unwrap(&wrapped, &self.binding.derive())
+    }
+}
+
+/// Parses a successful response, or the error body of the broker into
+/// `BrokerError::Rejected`.
+async fn parse_response<T: DeserializeOwned>(response: Response) -> Result<T> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response.json().await?);
+    }
+    let body: Value = response.json().await.unwrap_or_default();
+    Err(BrokerError::Rejected {
+        status: status.as_u16(),
+        kind: body["kind"].as_str().unwrap_or("unknown").to_string(),
+        message: body["error"].as_str().unwrap_or_default().to_string(),
+        retryable: body["retryable"].as_bool().unwrap_or(false),
+    })
+}
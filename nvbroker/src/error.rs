@@ -0,0 +1,118 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use nvverifier::{ServiceError, Verdict};
+use remote_attestation_verifier::AttestError;
+use serde_json::json;
+use thiserror::Error;
+
+pub type Result<T> = std::result::Result<T, BrokerError>;
+
+/// A secret release that failed, on the broker or on the client side.
+///
+/// Evidence that is verified and rejected is reported as `AttestationFailed`, with the
+/// verdict of the broker.
+#[derive(Debug, Error)]
+pub enum BrokerError {
+    #[error("Invalid challenge: {0}")]
+    InvalidChallenge(String),
+    #[error("Invalid public key: {0}")]
+    InvalidPublicKey(String),
+    #[error("Invalid secret name `{0}`")]
+    InvalidSecretName(String),
+    #[error("Attestation failed")]
+    AttestationFailed(Box<Verdict>),
+    #[error("Secret `{0}` not found")]
+    SecretNotFound(String),
+    #[error("Secret store error: {0}")]
+    Store(String),
+    #[error("Key wrapping error: {0}")]
+    Crypto(String),
+    #[error("Failed to reach the key broker: {0}")]
+    Transport(#[from] reqwest::Error),
+    #[error("Key broker answered HTTP status {status} ({kind}): {message}")]
+    Rejected {
+        status: u16,
+        kind: String,
+        message: String,
+        retryable: bool,
+    },
+    #[error(transparent)]
+    Service(Box<ServiceError>),
+}
+
+impl From<ServiceError> for BrokerError {
+    fn from(error: ServiceError) -> Self {
+        Self::Service(Box::new(error))
+    }
+}
+
+impl From<AttestError> for BrokerError {
+    fn from(error: AttestError) -> Self {
+        Self::Service(Box::new(error.into()))
+    }
+}
+
+impl BrokerError {
+    /// Returns a stable `snake_case` category of the error.
+    #[must_use]
+    pub fn kind(&self) -> &str {
+        match self {
+            Self::InvalidChallenge(_) => "invalid_challenge",
+            Self::InvalidPublicKey(_) => "invalid_public_key",
+            Self::InvalidSecretName(_) => "invalid_secret_name",
+            Self::AttestationFailed(_) => "attestation_failed",
+            Self::SecretNotFound(_) => "secret_not_found",
+            Self::Store(_) => "secret_store",
+            Self::Crypto(_) => "crypto",
+            Self::Transport(_) => "transport",
+            Self::Rejected { kind, .. } => kind,
+            Self::Service(error) => error.kind(),
+        }
+    }
+
+    /// Returns the HTTP status answered for the error.
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::InvalidChallenge(_) | Self::AttestationFailed(_) => StatusCode::FORBIDDEN,
+            Self::InvalidPublicKey(_) | Self::InvalidSecretName(_) => StatusCode::BAD_REQUEST,
+            Self::SecretNotFound(_) => StatusCode::NOT_FOUND,
+            Self::Service(error) => error.status(),
+            Self::Rejected { status, .. } => {
+                StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY)
+            }
+            Self::Transport(_) => StatusCode::BAD_GATEWAY,
+            Self::Store(_) | Self::Crypto(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    /// Returns `true` if the same request may succeed when retried later, with a new
+    /// challenge.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Rejected { retryable, .. } => *retryable,
+            Self::Service(error) => error.is_retryable(),
+            Self::Transport(error) => error.is_connect() || error.is_timeout(),
+            _ => false,
+        }
+    }
+}
+
+impl IntoResponse for BrokerError {
+    fn into_response(self) -> Response {
+        let mut body = json!({
+            "error": self.to_string(),
+            "kind": self.kind(),
+            "retryable": self.is_retryable(),
+        });
+        // The verdict tells the node which checks its evidence failed
+        if let Self::AttestationFailed(verdict) = &self {
+            body["verdict"] = json!(verdict);
+        }
+        (self.status(), Json(body)).into_response()
+    }
+}
@@ -0,0 +1,39 @@
+//! `nvbroker`: key broker releasing secrets to nodes whose NVIDIA attestation evidence
+//! is verified.
+//!
+//! The broker serves the following endpoints:
+//!
+//! * `POST /v1/challenge` - issues a single-use [`Challenge`]
+//! * `POST /v1/secrets/{name}` - releases the secret `name` for a [`ReleaseRequest`],
+//!   wrapped to the ephemeral key of the node as a [`WrappedSecret`]
+//! * `GET /healthz` - liveness check
+//!
+//! A release goes as follows:
+//!
+//! 1. The node requests a challenge, and generates an ephemeral X25519 key
+//! 2. The node collects the evidence of its devices with the nonce derived from the
+//!    challenge and its public key with
+//!    [`NonceBinding`](remote_attestation_verifier::NonceBinding)
+//! 3. The node sends the evidence, its public key and the binding timestamp
+//! 4. The broker redeems the challenge, checks the nonce binding, then appraises the
+//!    evidence like the verifier service does (policy, topology checks for `PPCIe`, and
+//!    NRAS with `verify_gpu_attestation`), see [`nvverifier::appraise`]
+//! 5. If the evidence passed, the broker wraps the secret to the public key of the
+//!    node, see [`wrap`]
+//!
+//! [`BrokerClient`] runs the node side, and [`FileSecretStore`] keeps secrets in a
+//! directory, for testing.
+
+pub mod client;
+pub mod error;
+pub mod service;
+pub mod store;
+#[cfg(test)]
+mod tests;
+pub mod wrap;
+
+pub use client::{BrokerClient, PendingRelease};
+pub use error::{BrokerError, Result};
+pub use service::{router, Broker, Challenge, ReleaseRequest};
+pub use store::{FileSecretStore, SecretStore};
+pub use wrap::{UnwrapKey, WrappedSecret};
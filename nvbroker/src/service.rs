@@ -0,0 +1,269 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, PoisonError},
+    time::Duration,
+};
+
+use axum::{
+    extract::{Path, State},
+    routing::{get, post},
+    Json, Router,
+};
+use nvverifier::{appraise, Scope, ServiceState};
+use remote_attestation_verifier::{utils::unix_now, EvidenceBundle, NonceBinding};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    error::{BrokerError, Result},
+    store::{check_secret_name, SecretStore},
+    wrap::{decode_public_key, wrap_secret, WrappedSecret},
+};
+
+/// Path of the challenge endpoint.
+pub const CHALLENGE_PATH: &str = "/v1/challenge";
+
+/// Path of the secret release endpoint, followed by the name of the secret.
+pub const SECRETS_PATH: &str = "/v1/secrets";
+
+/// Path of the health check endpoint.
+pub const HEALTHZ_PATH: &str = "/healthz";
+
+/// Default time a challenge can be redeemed within.
+pub const DEFAULT_CHALLENGE_TTL: Duration = Duration::from_secs(5 * 60);
+
+/// Default maximum number of challenges issued and not yet redeemed, beyond which the
+/// oldest pending challenge is evicted.
+pub const DEFAULT_MAX_PENDING_CHALLENGES: usize = 1024;
+
+/// Length of challenges, in bytes.
+pub const CHALLENGE_LENGTH: usize = 32;
+
+/// A single-use challenge issued by the broker.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Challenge {
+    /// Hex encoded challenge
+    pub challenge: String,
+    /// Time the challenge must be redeemed before, in seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+/// A request to release a secret.
+///
+/// The evidence of the bundle must be collected with the nonce derived from the
+/// challenge, the public key and the timestamp, see [`NonceBinding`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReleaseRequest {
+    /// Hex encoded challenge issued by the broker
+    pub challenge: String,
+    /// Hex encoded ephemeral X25519 public key the secret is wrapped to
+    pub public_key: String,
+    /// Time the nonce was derived at, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// Evidence of the node
+    pub bundle: EvidenceBundle,
+}
+
+/// Challenges issued and not yet redeemed.
+#[derive(Default)]
+struct PendingChallenges {
+    /// Sequence number of the next challenge, to find the oldest one
+    next_sequence: u64,
+    /// Pending challenges, with their sequence number and expiration time
+    challenges: HashMap<[u8; CHALLENGE_LENGTH], (u64, u64)>,
+}
+
+/// Releases secrets to nodes whose evidence is verified.
+pub struct Broker {
+    state: ServiceState,
+    scope: Scope,
+    store: Arc<dyn SecretStore>,
+    challenge_ttl: Duration,
+    max_pending_challenges: usize,
+    challenges: Mutex<PendingChallenges>,
+    rng: SystemRandom,
+}
+
+impl Broker {
+    /// Creates a broker verifying the evidence in `scope` with the NRAS options and the
+    /// policy of `state`, and releasing the secrets of `store`.
+    ///
+    /// With [`Scope::Ppcie`], the GPU and switch topology checks must pass as well.
+    pub fn new(state: ServiceState, scope: Scope, store: Arc<dyn SecretStore>) -> Self {
+        Self {
+            state,
+            scope,
+            store,
+            challenge_ttl: DEFAULT_CHALLENGE_TTL,
+            max_pending_challenges: DEFAULT_MAX_PENDING_CHALLENGES,
+            challenges: Mutex::new(PendingChallenges::default()),
+            rng: SystemRandom::new(),
+        }
+    }
+
+    /// Sets the time a challenge can be redeemed within.
+    #[must_use]
+    pub const fn with_challenge_ttl(mut self, challenge_ttl: Duration) -> Self {
+        self.challenge_ttl = challenge_ttl;
+        self
+    }
+
+    /// Sets the maximum number of challenges issued and not yet redeemed.
+    ///
+    /// Once reached, issuing a challenge evicts the oldest pending one, so that clients
+    /// requesting challenges they never redeem cannot lock other nodes out.
+    #[must_use]
+    pub const fn with_max_pending_challenges(mut self, max_pending_challenges: usize) -> Self {
+        self.max_pending_challenges = max_pending_challenges;
+        self
+    }
+
+    /// Issues a challenge, to be redeemed once within the challenge TTL.
+    ///
+    /// If the maximum number of pending challenges is reached, the oldest one is evicted.
+    ///
+    /// # Errors
+    ///
+    /// Returns `BrokerError::Crypto` if the system random number generator fails.
+    pub fn issue_challenge(&self) -> Result<Challenge> {
+        let mut challenge = [0; CHALLENGE_LENGTH];
+        self.rng
+            .fill(&mut challenge)
+            .map_err(|_| BrokerError::Crypto("Failed to generate a challenge".to_string()))?;
+        let now = unix_now();
+        let expires_at = now.saturating_add(self.challenge_ttl.as_secs());
+        let mut pending = self
+            .challenges
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        pending
+            .challenges
+            .retain(|_, (_, expires_at)| *expires_at > now);
+        if pending.challenges.len() >= self.max_pending_challenges {
+            let oldest = pending
+                .challenges
+                .iter()
+                .min_by_key(|(_, (sequence, _))| *sequence)
+                .map(|(challenge, _)| *challenge);
+            if let Some(oldest) = oldest {
+                pending.challenges.remove(&oldest);
+                warn!("Too many pending challenges, evicted the oldest one");
+            }
+        }
+        let sequence = pending.next_sequence;
+        pending.next_sequence += 1;
+        pending.challenges.insert(challenge, (sequence, expires_at));
+        drop(pending);
+        Ok(Challenge {
+            challenge: hex::encode(challenge),
+            expires_at,
+        })
+    }
+
+    /// Redeems a pending challenge, which cannot be redeemed again.
+    pub(crate) fn redeem_challenge(&self, challenge: &str) -> Result<[u8; CHALLENGE_LENGTH]> {
+        let challenge: [u8; CHALLENGE_LENGTH] = hex::decode(challenge)
+            .ok()
+            .and_then(|bytes| bytes.try_into().ok())
+            .ok_or_else(|| {
+                BrokerError::InvalidChallenge(format!(
+                    "expected {CHALLENGE_LENGTH} hex encoded bytes"
+                ))
+            })?;
+        let (_, expires_at) = self
+            .challenges
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .challenges
+            .remove(&challenge)
+            .ok_or_else(|| {
+                BrokerError::InvalidChallenge("unknown or already redeemed".to_string())
+            })?;
+        if unix_now() >= expires_at {
+            return Err(BrokerError::InvalidChallenge("expired".to_string()));
+        }
+        Ok(challenge)
+    }
+
+    /// Releases the secret `name` to the node that sent `request`, wrapped to its
+    /// public key.
+    ///
+    /// The challenge is redeemed, then the nonce of the bundle is checked to be derived
+    /// from the challenge and the public key, and the bundle is appraised as by the
+    /// verifier service. The store is only read once the evidence is verified, so that
+    /// unverified nodes cannot learn which secrets exist.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::InvalidSecretName` - If `name` is not a valid secret name
+    /// * `BrokerError::InvalidPublicKey` - If the public key is invalid
+    /// * `BrokerError::InvalidChallenge` - If the challenge is unknown, expired or
+    ///   already redeemed, or the nonce of the bundle is not bound to it
+    /// * `BrokerError::Service` - If the bundle is invalid, or NRAS cannot verify it
+    /// * `BrokerError::AttestationFailed` - If the bundle fails the appraisal
+    /// * `BrokerError::SecretNotFound` - If the store has no secret `name`
+    /// * `BrokerError::Store` - If the store cannot be read
+    /// * `BrokerError::Crypto` - If the secret cannot be wrapped
+    #[instrument(level = "info", name = "broker_release", skip(self, request))]
+    pub async fn release(&self, name: &str, request: &ReleaseRequest) -> Result<WrappedSecret> {
+        check_secret_name(name)?;
+        let public_key = decode_public_key(&request.public_key)?;
+        let challenge = self.redeem_challenge(&request.challenge)?;
+        let bundle = &request.bundle;
+        bundle.validate()?;
+        let nonce = bundle.nonce_bytes()?;
+        NonceBinding::new(challenge, public_key, request.timestamp)
+            .verify(&nonce, self.challenge_ttl)
+            .map_err(|e| BrokerError::InvalidChallenge(e.to_string()))?;
+
+        let verdict = appraise(&self.state, self.scope, bundle, Some(&bundle.nonce)).await?;
+        if !verdict.attestation_passed {
+            warn!(
+                level = "broker_release",
+                secret = name,
+                bundle_digest = %verdict.bundle_digest,
+                "Evidence failed the appraisal, secret not released"
+            );
+            return Err(BrokerError::AttestationFailed(Box::new(verdict)));
+        }
+        let secret = self
+            .store
+            .get(name)?
+            .ok_or_else(|| BrokerError::SecretNotFound(name.to_string()))?;
+        let wrapped = wrap_secret(name, &secret, &public_key, &nonce)?;
+        info!(
+            level = "broker_release",
+            secret = name,
+            bundle_digest = %verdict.bundle_digest,
+            "Secret released"
+        );
+        Ok(wrapped)
+    }
+}
+
+/// Creates the router of the key broker.
+pub fn router(broker: Arc<Broker>) -> Router {
+    Router::new()
+        .route(CHALLENGE_PATH, post(challenge))
+        .route(&format!("{SECRETS_PATH}/{{name}}"), post(release))
+        .route(HEALTHZ_PATH, get(healthz))
+        .with_state(broker)
+}
+
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn challenge(State(broker): State<Arc<Broker>>) -> Result<Json<Challenge>> {
+    broker.issue_challenge().map(Json)
+}
+
+async fn release(
+    State(broker): State<Arc<Broker>>,
+    Path(name): Path<String>,
+    Json(request): Json<ReleaseRequest>,
+) -> Result<Json<WrappedSecret>> {
+    broker.release(&name, &request).await.map(Json)
+}
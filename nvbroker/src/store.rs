@@ -0,0 +1,145 @@
+use std::{
+    fs,
+    io::{ErrorKind, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+use crate::error::{BrokerError, Result};
+
+/// Permissions of the secret files, readable by the current user only.
+const SECRET_FILE_MODE: u32 = 0o600;
+
+/// Maximum length of a secret name.
+pub const MAX_SECRET_NAME_LENGTH: usize = 128;
+
+/// Source of the secrets released by the broker, e.g. a KMS or a vault.
+pub trait SecretStore: Send + Sync {
+    /// Returns the secret `name`, or `None` if the store has no such secret.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::Store` - If the store cannot be read
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// Checks that `name` is a valid secret name: 1 to [`MAX_SECRET_NAME_LENGTH`] ASCII
+/// letters, digits, `-`, `_` or `.`, not starting with `.`.
+///
+/// # Errors
+///
+/// * `BrokerError::InvalidSecretName` - If `name` is not a valid secret name
+pub fn check_secret_name(name: &str) -> Result<()> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_SECRET_NAME_LENGTH
+        && !name.starts_with('.')
+        && name
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(BrokerError::InvalidSecretName(name.to_string()))
+    }
+}
+
+/// A secret store keeping each secret in a file of a directory, named after the secret.
+///
+/// Meant for tests and development: secrets are stored in the clear.
+#[derive(Debug, Clone)]
+pub struct FileSecretStore {
+    directory: PathBuf,
+}
+
+impl FileSecretStore {
+    /// Opens the store in `directory`, creating the directory if missing.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::Store` - If the directory cannot be created
+    pub fn open(directory: impl Into<PathBuf>) -> Result<Self> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory).map_err(|e| {
+            BrokerError::Store(format!("Failed to create {}: {e}", directory.display()))
+        })?;
+        Ok(Self { directory })
+    }
+
+    /// Returns the directory of the store.
+    #[must_use]
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Stores `secret` as `name`, readable by the current user only.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::InvalidSecretName` - If `name` is not a valid secret name
+    /// * `BrokerError::Store` - If the secret cannot be written
+    pub fn put(&self, name: &str, secret: &[u8]) -> Result<()> {
+        check_secret_name(name)?;
+        let path = self.directory.join(name);
+        fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(SECRET_FILE_MODE)
+            .open(&path)
+            .and_then(|mut file| {
+                // The mode only applies to new files: restrict existing ones before writing
+                file.set_permissions(fs::Permissions::from_mode(SECRET_FILE_MODE))?;
+                file.write_all(secret)
+            })
+            .map_err(|e| BrokerError::Store(format!("Failed to write {}: {e}", path.display())))
+    }
+}
+
+impl SecretStore for FileSecretStore {
+    fn get(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        check_secret_name(name)?;
+        let path = self.directory.join(name);
+        match fs::read(&path) {
+            Ok(secret) => Ok(Some(secret)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(BrokerError::Store(format!(
+                "Failed to read {}: {e}",
+                path.display()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_secret_store() {
+        let directory = std::env::temp_dir().join(format!("nvbroker-store-{}", std::process::id()));
+        let store = FileSecretStore::open(&directory).unwrap();
+        store.put("llama-3.1-70b.key", b"weights key").unwrap();
+        assert_eq!(
+            store.get("llama-3.1-70b.key").unwrap().as_deref(),
+            Some(b"weights key".as_slice())
+        );
+        let mode = |path: &Path| fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        let path = directory.join("llama-3.1-70b.key");
+        assert_eq!(mode(&path), SECRET_FILE_MODE);
+
+        // Overwriting a secret restricts the permissions of the existing file
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+        store.put("llama-3.1-70b.key", b"new weights key").unwrap();
+        assert_eq!(mode(&path), SECRET_FILE_MODE);
+        assert_eq!(fs::read(&path).unwrap(), b"new weights key");
+        assert_eq!(store.get("missing").unwrap(), None);
+
+        for name in ["", "../secret", ".hidden", "a/b", &"a".repeat(129)] {
+            assert!(
+                matches!(store.get(name), Err(BrokerError::InvalidSecretName(_))),
+                "`{name}` is rejected"
+            );
+        }
+        fs::remove_dir_all(directory).unwrap();
+    }
+}
@@ -0,0 +1,243 @@
+use std::{sync::Arc, time::Duration};
+
+use nvverifier::{Policy, Scope, ServiceState};
+use remote_attestation_verifier::{
+    test_support::{synthetic_bundle, MockNras},
+    AttestRemoteOptions, EvidenceBundle, HostMetadata, NonceBinding,
+};
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use crate::{
+    router,
+    service::{CHALLENGE_PATH, HEALTHZ_PATH, SECRETS_PATH},
+    Broker, BrokerClient, BrokerError, Challenge, FileSecretStore, ReleaseRequest, SecretStore,
+    UnwrapKey,
+};
+
+const SECRET_NAME: &str = "model-weights.key";
+const SECRET: &[u8] = b"0123456789abcdef0123456789abcdef";
+
+/// Serves a `PPCIe` broker on a random local port, pointing at the mock NRAS, with the
+/// test secret in a temporary file store.
+async fn start_broker(nras: &MockNras, policy: Policy) -> (String, JoinHandle<()>) {
+    let directory = std::env::temp_dir().join(format!(
+        "nvbroker-tests-{}-{}",
+        std::process::id(),
+        nras.url().rsplit(':').next().unwrap()
+    ));
+    let store = FileSecretStore::open(&directory).unwrap();
+    store.put(SECRET_NAME, SECRET).unwrap();
+    let state = ServiceState {
+        gpu_options: nras.gpu_options(),
+        switch_options: nras.switch_options(),
+        policy,
+        trusted_agent_keys: Vec::new(),
+    };
+    let broker = Broker::new(state, Scope::Ppcie, Arc::new(store));
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let task = tokio::spawn(async move {
+        axum::serve(listener, router(Arc::new(broker))).await.ok();
+    });
+    (url, task)
+}
+
+fn assert_rejected(error: &BrokerError, expected_status: u16, expected_kind: &str) {
+    assert!(
+        matches!(error, BrokerError::Rejected { status, kind, .. }
+            if *status == expected_status && kind == expected_kind),
+        "unexpected error {error:?}"
+    );
+}
+
+#[tokio::test]
+async fn test_secret_release() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (url, task) = start_broker(&nras, Policy::default()).await;
+    let health: Value = reqwest::get(format!("{url}{HEALTHZ_PATH}"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(health["status"], "ok");
+
+    let client = BrokerClient::new(format!("{url}/"));
+    let release = client.start_release().await.unwrap();
+    let bundle = synthetic_bundle(release.nonce());
+    let secret = release.fetch_secret(SECRET_NAME, &bundle).await.unwrap();
+    assert_eq!(secret, SECRET);
+    // The GPUs and the switches are verified
+    assert_eq!(nras.requests().len(), 2);
+
+    // The store is only read once the evidence is verified
+    let release = client.start_release().await.unwrap();
+    let bundle = synthetic_bundle(release.nonce());
+    let error = release.fetch_secret("missing", &bundle).await.unwrap_err();
+    assert_rejected(&error, 404, "secret_not_found");
+    assert_eq!(nras.requests().len(), 4);
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_challenge_rejections() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (url, task) = start_broker(&nras, Policy::default()).await;
+    let client = BrokerClient::new(url.clone());
+
+    // Challenges are single-use
+    let release = client.start_release().await.unwrap();
+    let challenge = release.challenge().clone();
+    let bundle = synthetic_bundle(release.nonce());
+    release.fetch_secret(SECRET_NAME, &bundle).await.unwrap();
+    let release = client.start_release().await.unwrap();
+    let replayed = json!({
+        "challenge": challenge.challenge,
+        "public_key": "00".repeat(32),
+        "timestamp": 0,
+        "bundle": bundle,
+    });
+    let response = reqwest::Client::new()
+        .post(format!("{url}{SECRETS_PATH}/{SECRET_NAME}"))
+        .json(&replayed)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "invalid_challenge", "{body}");
+
+    // Evidence collected with a nonce not bound to the challenge and the key
+    let error = release
+        .fetch_secret(SECRET_NAME, &synthetic_bundle([0x42; 32]))
+        .await
+        .unwrap_err();
+    assert_rejected(&error, 403, "invalid_challenge");
+
+    // Secret names cannot escape the store, nor change the URL of the request
+    let release = client.start_release().await.unwrap();
+    let bundle = synthetic_bundle(release.nonce());
+    let error = release
+        .fetch_secret("../secret?name=x", &bundle)
+        .await
+        .unwrap_err();
+    assert!(matches!(error, BrokerError::InvalidSecretName(_)));
+    let response = reqwest::Client::new()
+        .post(format!("{url}{SECRETS_PATH}/..%2Fsecret"))
+        .json(&replayed)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "invalid_secret_name", "{body}");
+    assert_eq!(nras.requests().len(), 2);
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_attestation_failures() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let policy = Policy {
+        gpus: Some(8),
+        ..Policy::default()
+    };
+    let (url, task) = start_broker(&nras, policy).await;
+    let client = BrokerClient::new(url.clone());
+
+    nras.set_attestation_result(false);
+    let release = client.start_release().await.unwrap();
+    let bundle = synthetic_bundle(release.nonce());
+    let error = release
+        .fetch_secret(SECRET_NAME, &bundle)
+        .await
+        .unwrap_err();
+    assert_rejected(&error, 403, "attestation_failed");
+    assert!(!error.is_retryable());
+    nras.set_attestation_result(true);
+    let requests = nras.requests().len();
+
+    // A bundle failing the policy is answered with the verdict, without querying NRAS
+    let challenge: Challenge = reqwest::Client::new()
+        .post(format!("{url}{CHALLENGE_PATH}"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    let key = UnwrapKey::generate().unwrap();
+    let binding = NonceBinding::now(
+        hex::decode(&challenge.challenge).unwrap(),
+        key.public_key().as_slice(),
+    );
+    let full = synthetic_bundle(binding.derive());
+    let bundle = EvidenceBundle::new(
+        &binding.derive(),
+        full.gpu_evidence[1..].to_vec(),
+        full.switch_evidence,
+        HostMetadata::default(),
+    );
+    let request = ReleaseRequest {
+        challenge: challenge.challenge,
+        public_key: hex::encode(key.public_key()),
+        timestamp: binding.timestamp,
+        bundle,
+    };
+    let response = reqwest::Client::new()
+        .post(format!("{url}{SECRETS_PATH}/{SECRET_NAME}"))
+        .json(&request)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "attestation_failed", "{body}");
+    assert_eq!(body["verdict"]["attestation_passed"], false, "{body}");
+    assert_eq!(
+        body["verdict"]["policy_violations"][0]["violation"], "gpu_count",
+        "{body}"
+    );
+    assert_eq!(nras.requests().len(), requests);
+
+    task.abort();
+}
+
+#[test]
+fn test_challenge_limits() {
+    let state = ServiceState {
+        gpu_options: AttestRemoteOptions::default(),
+        switch_options: AttestRemoteOptions::default(),
+        policy: Policy::default(),
+        trusted_agent_keys: Vec::new(),
+    };
+    let directory =
+        std::env::temp_dir().join(format!("nvbroker-tests-{}-limits", std::process::id()));
+    let store: Arc<dyn SecretStore> = Arc::new(FileSecretStore::open(&directory).unwrap());
+    let broker =
+        Broker::new(state.clone(), Scope::Gpu, store.clone()).with_max_pending_challenges(2);
+    let first = broker.issue_challenge().unwrap();
+    let second = broker.issue_challenge().unwrap();
+    assert_ne!(first, second);
+
+    // The oldest pending challenge is evicted
+    let third = broker.issue_challenge().unwrap();
+    assert!(matches!(
+        broker.redeem_challenge(&first.challenge),
+        Err(BrokerError::InvalidChallenge(_))
+    ));
+    broker.redeem_challenge(&second.challenge).unwrap();
+    broker.redeem_challenge(&third.challenge).unwrap();
+
+    // Expired challenges are pruned
+    let broker = Broker::new(state, Scope::Gpu, store)
+        .with_challenge_ttl(Duration::ZERO)
+        .with_max_pending_challenges(1);
+    broker.issue_challenge().unwrap();
+    broker.issue_challenge().unwrap();
+    std::fs::remove_dir_all(directory).unwrap();
+}
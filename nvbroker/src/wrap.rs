@@ -0,0 +1,240 @@
+//! Wrapping of released secrets to an ephemeral key of the node.
+//!
+//! The node sends an ephemeral X25519 public key with its evidence. The broker answers
+//! with a [`WrappedSecret`]: the secret encrypted with ChaCha20-Poly1305, with the secret
+//! name as associated data, under the key
+//!
+//! ```text
+//! key = blake3::derive_key("nvrust 2025-05 key broker wrap v1",
+//!           X25519(broker_private_key, node_public_key) || node_public_key
+//!           || broker_public_key || nonce)
+//! ```
+//!
+//! where `broker_private_key` is an ephemeral key of the broker, and `nonce` the nonce
+//! of the evidence. Both keys are used for a single secret, so the AEAD nonce is zero.
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ring::{
+    aead::{self, Aad, LessSafeKey, UnboundKey, CHACHA20_POLY1305},
+    agreement::{self, EphemeralPrivateKey, UnparsedPublicKey, X25519},
+    rand::SystemRandom,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{BrokerError, Result};
+
+/// Length of X25519 public keys, in bytes.
+pub const PUBLIC_KEY_LENGTH: usize = 32;
+
+/// Context string used to derive wrapping keys with blake3.
+const WRAP_KEY_CONTEXT: &str = "nvrust 2025-05 key broker wrap v1";
+
+/// A secret encrypted to the ephemeral key of a node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedSecret {
+    /// Name of the secret, authenticated as associated data
+    pub name: String,
+    /// Hex encoded ephemeral X25519 public key of the broker
+    pub broker_public_key: String,
+    /// Encrypted secret followed by its authentication tag, in base64 encoded format
+    pub ciphertext: String,
+}
+
+/// Decodes a hex encoded X25519 public key.
+///
+/// # Errors
+///
+/// * `BrokerError::InvalidPublicKey` - If `public_key` is not 32 hex encoded bytes
+pub fn decode_public_key(public_key: &str) -> Result<[u8; PUBLIC_KEY_LENGTH]> {
+    hex::decode(public_key)
+        .map_err(|e| BrokerError::InvalidPublicKey(e.to_string()))?
+        .try_into()
+        .map_err(|bytes: Vec<u8>| {
+            BrokerError::InvalidPublicKey(format!(
+                "expected {PUBLIC_KEY_LENGTH} bytes, got {}",
+                bytes.len()
+            ))
+        })
+}
+
+/// Encrypts `secret` to the X25519 public key `node_public_key` of a node, for
+/// evidence collected with `nonce`.
+///
+/// # Errors
+///
+/// * `BrokerError::Crypto` - If no ephemeral key can be generated, or `node_public_key`
+///   is not a valid X25519 public key
+pub fn wrap_secret(
+    name: &str,
+    secret: &[u8],
+    node_public_key: &[u8; PUBLIC_KEY_LENGTH],
+    nonce: &[u8],
+) -> Result<WrappedSecret> {
+    let (private_key, broker_public_key) = generate_key_pair()?;
+    let key = agree(private_key, node_public_key, |shared_secret| {
+        wrapping_key(shared_secret, node_public_key, &broker_public_key, nonce)
+    })??;
+    let mut ciphertext = secret.to_vec();
+    key.seal_in_place_append_tag(zero_nonce(), Aad::from(name.as_bytes()), &mut ciphertext)
+        .map_err(|_| BrokerError::Crypto("Failed to encrypt the secret".to_string()))?;
+    Ok(WrappedSecret {
+        name: name.to_string(),
+        broker_public_key: hex::encode(broker_public_key),
+        ciphertext: STANDARD.encode(ciphertext),
+    })
+}
+
+/// The ephemeral X25519 key a node receives a secret with.
+pub struct UnwrapKey {
+    private_key: EphemeralPrivateKey,
+    public_key: [u8; PUBLIC_KEY_LENGTH],
+}
+
+impl std::fmt::Debug for UnwrapKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("UnwrapKey")
+            .field("public_key", &hex::encode(self.public_key))
+            .finish_non_exhaustive()
+    }
+}
+
+impl UnwrapKey {
+    /// Generates an ephemeral key.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::Crypto` - If the system random number generator fails
+    pub fn generate() -> Result<Self> {
+        let (private_key, public_key) = generate_key_pair()?;
+        Ok(Self {
+            private_key,
+            public_key,
+        })
+    }
+
+    /// Returns the public key to send to the broker.
+    #[must_use]
+    pub const fn public_key(&self) -> &[u8; PUBLIC_KEY_LENGTH] {
+        &self.public_key
+    }
+
+    /// Decrypts a secret wrapped to this key for evidence collected with `nonce`,
+    /// consuming the key.
+    ///
+    /// # Errors
+    ///
+    /// * `BrokerError::InvalidPublicKey` - If the broker public key is invalid
+    /// * `BrokerError::Crypto` - If the ciphertext is not valid base64, or was not
+    ///   wrapped to this key, for `nonce` and the name of the secret
+    pub fn unwrap(self, wrapped: &WrappedSecret, nonce: &[u8]) -> Result<Vec<u8>> {
+        let broker_public_key = decode_public_key(&wrapped.broker_public_key)?;
+        let mut ciphertext = STANDARD
+            .decode(&wrapped.ciphertext)
+            .map_err(|e| BrokerError::Crypto(format!("Invalid ciphertext: {e}")))?;
+        let key = agree(self.private_key, &broker_public_key, |shared_secret| {
+            wrapping_key(shared_secret, &self.public_key, &broker_public_key, nonce)
+        })??;
+        let secret = key
+            .open_in_place(
+                zero_nonce(),
+                Aad::from(wrapped.name.as_bytes()),
+                &mut ciphertext,
+            )
+            .map_err(|_| BrokerError::Crypto("Failed to decrypt the secret".to_string()))?;
+        Ok(secret.to_vec())
+    }
+}
+
+fn generate_key_pair() -> Result<(EphemeralPrivateKey, [u8; PUBLIC_KEY_LENGTH])> {
+    let private_key = EphemeralPrivateKey::generate(&X25519, &SystemRandom::new())
+        .map_err(|_| BrokerError::Crypto("Failed to generate an X25519 key".to_string()))?;
+    let public_key = private_key
+        .compute_public_key()
+        .map_err(|_| BrokerError::Crypto("Failed to compute an X25519 public key".to_string()))?
+        .as_ref()
+        .try_into()
+        .map_err(|_| BrokerError::Crypto("Unexpected X25519 public key length".to_string()))?;
+    Ok((private_key, public_key))
+}
+
+fn agree<R>(
+    private_key: EphemeralPrivateKey,
+    peer_public_key: &[u8; PUBLIC_KEY_LENGTH],
+    kdf: impl FnOnce(&[u8]) -> R,
+) -> Result<R> {
+    agreement::agree_ephemeral(
+        private_key,
+        &UnparsedPublicKey::new(&X25519, peer_public_key),
+        kdf,
+    )
+    .map_err(|_| BrokerError::Crypto("X25519 key agreement failed".to_string()))
+}
+
+fn wrapping_key(
+    shared_secret: &[u8],
+    node_public_key: &[u8; PUBLIC_KEY_LENGTH],
+    broker_public_key: &[u8; PUBLIC_KEY_LENGTH],
+    nonce: &[u8],
+) -> Result<LessSafeKey> {
+    let key = blake3::Hasher::new_derive_key(WRAP_KEY_CONTEXT)
+        .update(shared_secret)
+        .update(node_public_key)
+        .update(broker_public_key)
+        .update(nonce)
+        .finalize();
+    let key = UnboundKey::new(&CHACHA20_POLY1305, key.as_bytes())
+        .map_err(|_| BrokerError::Crypto("Failed to create the wrapping key".to_string()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Every wrapping key encrypts a single secret, so a fixed AEAD nonce is safe.
+fn zero_nonce() -> aead::Nonce {
+    aead::Nonce::assume_unique_for_key([0; aead::NONCE_LEN])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NONCE: [u8; 32] = [0x5c; 32];
+
+    #[test]
+    fn test_wrap_unwrap() {
+        let key = UnwrapKey::generate().unwrap();
+        let wrapped = wrap_secret("model.key", b"weights key", key.public_key(), &NONCE).unwrap();
+        assert_eq!(wrapped.name, "model.key");
+        assert_ne!(
+            STANDARD.decode(&wrapped.ciphertext).unwrap()[..11],
+            b"weights key"[..]
+        );
+        assert_eq!(key.unwrap(&wrapped, &NONCE).unwrap(), b"weights key");
+    }
+
+    #[test]
+    fn test_unwrap_failures() {
+        let wrap = |key: &UnwrapKey| {
+            wrap_secret("model.key", b"weights key", key.public_key(), &NONCE).unwrap()
+        };
+
+        // Another key, another nonce, or another name
+        let wrapped = wrap(&UnwrapKey::generate().unwrap());
+        let other = UnwrapKey::generate().unwrap();
+        assert!(matches!(
+            other.unwrap(&wrapped, &NONCE),
+            Err(BrokerError::Crypto(_))
+        ));
+        let key = UnwrapKey::generate().unwrap();
+        let wrapped = wrap(&key);
+        assert!(key.unwrap(&wrapped, &[0x5d; 32]).is_err());
+        let key = UnwrapKey::generate().unwrap();
+        let mut wrapped = wrap(&key);
+        wrapped.name = "other.key".to_string();
+        assert!(key.unwrap(&wrapped, &NONCE).is_err());
+
+        assert!(matches!(
+            decode_public_key(&"ab".repeat(31)),
+            Err(BrokerError::InvalidPublicKey(_))
+        ));
+        assert!(decode_public_key("zz").is_err());
+    }
+}
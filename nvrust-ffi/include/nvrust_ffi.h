@@ -0,0 +1,278 @@
+#ifndef NVRUST_FFI_H
+#define NVRUST_FFI_H
+
+/* Generated by cbindgen from nvrust-ffi. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// Result of a call. `NVRUST_STATUS_OK` is zero, every other value is a failure
+// described by the error message of the call.
+//
+// Values are stable: new codes may be added, existing ones are never renumbered.
+typedef enum NvrustStatus {
+  // The call succeeded
+  NVRUST_STATUS_OK = 0,
+  // A required pointer was NULL, or a string was not valid UTF-8
+  NVRUST_STATUS_INVALID_ARGUMENT = 1,
+  // The remote attestation service could not be reached
+  NVRUST_STATUS_TRANSPORT = 2,
+  // The remote attestation service answered with a non-success HTTP status
+  NVRUST_STATUS_HTTP_STATUS = 3,
+  // The remote attestation service answered with an unexpected body
+  NVRUST_STATUS_MALFORMED_RESPONSE = 4,
+  // The JWKS used to verify NRAS tokens could not be retrieved
+  NVRUST_STATUS_JWKS = 5,
+  // An NRAS token, or the key used to verify it, is invalid
+  NVRUST_STATUS_TOKEN_SIGNATURE = 6,
+  // An NRAS token is well signed, but its claims are not acceptable
+  NVRUST_STATUS_CLAIM_VALIDATION = 7,
+  // Attestation evidence could not be decoded
+  NVRUST_STATUS_EVIDENCE_DECODE = 8,
+  // The remote attestation options are invalid
+  NVRUST_STATUS_CONFIGURATION = 9,
+  // Evidence collection through NSCQ failed
+  NVRUST_STATUS_NSCQ = 10,
+  // Evidence collection through NVML failed
+  NVRUST_STATUS_NVML = 11,
+  // The attestation result was negative
+  NVRUST_STATUS_ATTESTATION_FAILED = 12,
+  // The attestation decision could not be recorded in the audit log
+  NVRUST_STATUS_AUDIT_LOG = 13,
+  // The verification could not run on the client runtime
+  NVRUST_STATUS_RUNTIME = 14,
+  // A GPU or switch topology check failed
+  NVRUST_STATUS_TOPOLOGY = 15,
+  // The library panicked; the message describes the panic
+  NVRUST_STATUS_PANIC = 16,
+} NvrustStatus;
+
+// A client verifying evidence and decoding tokens, owning the threads it runs them on.
+//
+// Created with `nvrust_client_new` and freed with `nvrust_client_free`. A client is
+// thread-safe: calls through the same handle may run concurrently, each blocking its
+// calling thread until it completes.
+typedef struct NvrustClient NvrustClient;
+
+// Options of the remote attestation, see `AttestRemoteOptions`.
+//
+// Values are layered like in Rust: a config file, then the `NV_*` environment variables
+// unless disabled with `nvrust_options_set_read_env`, then the values set explicitly.
+// The config file section and the verifier URL variable are those of the kind of
+// evidence verified, so one handle can verify both GPU and switch evidence.
+// They are validated by the verification calls, which fail with
+// `NVRUST_STATUS_CONFIGURATION` when a value is invalid.
+//
+// Created with `nvrust_options_new` and freed with `nvrust_options_free`. A handle may be
+// shared by concurrent verifications, but must not be modified while one uses it.
+typedef struct NvrustOptions NvrustOptions;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Frees a string returned by this library. Does nothing if `value` is NULL.
+//
+// # Safety
+//
+// `value` must be NULL or a string returned by this library, not freed yet.
+void nvrust_string_free(char *value);
+
+// Returns the version of the library, as a static string that must not be freed.
+const char *nvrust_version(void);
+
+// Creates a client.
+//
+// Returns NULL, and stores the reason in `*error_message` when it is not NULL, if the
+// client threads cannot be started. The returned handle is owned by the caller and must
+// be freed with `nvrust_client_free`.
+//
+// # Safety
+//
+// `error_message` must be NULL or valid for writes.
+struct NvrustClient *nvrust_client_new(char **error_message);
+
+// Frees a client created with `nvrust_client_new`, waiting for its threads to stop.
+// Does nothing if `client` is NULL.
+//
+// # Safety
+//
+// `client` must be NULL or a handle returned by `nvrust_client_new`, not freed yet, and
+// not used by any other thread.
+void nvrust_client_free(struct NvrustClient *client);
+
+// Verifies GPU evidence against NRAS.
+//
+// `evidence_json` is a JSON array of `{"certificate": ..., "evidence": ...}` objects, with
+// base64 encoded fields, and `nonce` the hex encoded nonce the evidence was collected with.
+// `options` may be NULL to use the defaults with the `NV_*` environment variables.
+//
+// On success, `*attestation_passed` is set to the overall result, and `*response_json` to
+// the full NRAS response, to be freed with `nvrust_string_free`.
+//
+// # Safety
+//
+// `client` must be a handle returned by `nvrust_client_new`, `options` NULL or a handle
+// returned by `nvrust_options_new`, `evidence_json` and `nonce` NUL-terminated strings,
+// `attestation_passed` and `response_json` valid for writes, and `error_message` NULL or
+// valid for writes.
+enum NvrustStatus nvrust_verify_gpu_evidence(const struct NvrustClient *client,
+                                             const struct NvrustOptions *options,
+                                             const char *evidence_json,
+                                             const char *nonce,
+                                             bool *attestation_passed,
+                                             char **response_json,
+                                             char **error_message);
+
+// Verifies `NVSwitch` evidence against NRAS.
+//
+// Arguments and results are those of `nvrust_verify_gpu_evidence`.
+//
+// # Safety
+//
+// See `nvrust_verify_gpu_evidence`.
+enum NvrustStatus nvrust_verify_switch_evidence(const struct NvrustClient *client,
+                                                const struct NvrustOptions *options,
+                                                const char *evidence_json,
+                                                const char *nonce,
+                                                bool *attestation_passed,
+                                                char **response_json,
+                                                char **error_message);
+
+// Decodes an NRAS token, and verifies it against the JWKS of `verifier_url`.
+//
+// On success, `*claims_json` is set to the claims of the token as a JSON object, to be
+// freed with `nvrust_string_free`.
+//
+// # Safety
+//
+// `client` must be a handle returned by `nvrust_client_new`, `verifier_url` and `token`
+// NUL-terminated strings, `claims_json` valid for writes, and `error_message` NULL or
+// valid for writes.
+enum NvrustStatus nvrust_decode_token(const struct NvrustClient *client,
+                                      const char *verifier_url,
+                                      const char *token,
+                                      char **claims_json,
+                                      char **error_message);
+
+// Creates options holding the defaults, reading the `NV_*` environment variables.
+//
+// The returned handle is owned by the caller and must be freed with `nvrust_options_free`.
+struct NvrustOptions *nvrust_options_new(void);
+
+// Frees options created with `nvrust_options_new`. Does nothing if `options` is NULL.
+//
+// # Safety
+//
+// `options` must be NULL or a handle returned by `nvrust_options_new`, not freed yet.
+void nvrust_options_free(struct NvrustOptions *options);
+
+// Sets the URL of the verification service.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, `verifier_url` a
+// NUL-terminated string, and `error_message` NULL or valid for writes.
+enum NvrustStatus nvrust_options_set_verifier_url(struct NvrustOptions *options,
+                                                  const char *verifier_url,
+                                                  char **error_message);
+
+// Sets the request timeout, in milliseconds.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, and `error_message` NULL
+// or valid for writes.
+enum NvrustStatus nvrust_options_set_timeout_ms(struct NvrustOptions *options,
+                                                uint64_t timeout_ms,
+                                                char **error_message);
+
+// Sets the claims version requested from the verification service.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, `claims_version` a
+// NUL-terminated string, and `error_message` NULL or valid for writes.
+enum NvrustStatus nvrust_options_set_claims_version(struct NvrustOptions *options,
+                                                    const char *claims_version,
+                                                    char **error_message);
+
+// Sets the service key, taking precedence over any service key file.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, `service_key` a
+// NUL-terminated string, and `error_message` NULL or valid for writes.
+enum NvrustStatus nvrust_options_set_service_key(struct NvrustOptions *options,
+                                                 const char *service_key,
+                                                 char **error_message);
+
+// Sets the path of a file containing the service key.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, `path` a NUL-terminated
+// string, and `error_message` NULL or valid for writes.
+enum NvrustStatus nvrust_options_set_service_key_file(struct NvrustOptions *options,
+                                                      const char *path,
+                                                      char **error_message);
+
+// Sets whether certificates on hold are allowed during OCSP checks.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, and `error_message` NULL
+// or valid for writes.
+enum NvrustStatus nvrust_options_set_allow_hold_cert(struct NvrustOptions *options,
+                                                     bool allow_hold_cert,
+                                                     char **error_message);
+
+// Loads values from a TOML or JSON config file when verifying, with the `[gpu]` or
+// `[switch]` section of the kind of evidence verified.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, `path` a NUL-terminated
+// string, and `error_message` NULL or valid for writes.
+enum NvrustStatus nvrust_options_set_config_file(struct NvrustOptions *options,
+                                                 const char *path,
+                                                 char **error_message);
+
+// Sets whether the `NV_*` environment variables are read, which they are by default.
+//
+// # Safety
+//
+// `options` must be a handle returned by `nvrust_options_new`, and `error_message` NULL
+// or valid for writes.
+enum NvrustStatus nvrust_options_set_read_env(struct NvrustOptions *options,
+                                              bool read_env,
+                                              char **error_message);
+
+// Checks that the GPU attestation reports all see the same switches, and that the switch
+// attestation reports are consistent with them.
+//
+// Report `i` of each kind is the `gpu_report_lengths[i]` (or `switch_report_lengths[i]`)
+// bytes at `gpu_reports[i]` (or `switch_reports[i]`). A failed check returns
+// `NVRUST_STATUS_TOPOLOGY`.
+//
+// # Safety
+//
+// When the matching count is not zero, the report and length arrays must point to that
+// many elements, and each report to at least its length in bytes. `error_message` must be
+// NULL or valid for writes.
+enum NvrustStatus nvrust_check_topology(const uint8_t *const *gpu_reports,
+                                        const size_t *gpu_report_lengths,
+                                        size_t gpu_report_count,
+                                        const uint8_t *const *switch_reports,
+                                        const size_t *switch_report_lengths,
+                                        size_t switch_report_count,
+                                        char **error_message);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* NVRUST_FFI_H */
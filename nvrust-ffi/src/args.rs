@@ -0,0 +1,62 @@
+//! Conversion of C arguments, failing with `NVRUST_STATUS_INVALID_ARGUMENT`.
+
+use std::ffi::{c_char, CStr};
+
+use crate::status::FfiError;
+
+/// Borrows the NUL-terminated UTF-8 string `value`, named `name` in errors.
+///
+/// # Safety
+///
+/// `value` must be NULL or point to a NUL-terminated string that outlives the returned
+/// borrow.
+pub unsafe fn str_arg<'a>(value: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    if value.is_null() {
+        return Err(FfiError::invalid_argument(format!("`{name}` is NULL")));
+    }
+    CStr::from_ptr(value)
+        .to_str()
+        .map_err(|e| FfiError::invalid_argument(format!("`{name}` is not valid UTF-8: {e}")))
+}
+
+/// Borrows the `count` byte buffers described by `buffers` and `lengths`.
+///
+/// # Safety
+///
+/// When `count` is not zero, `buffers` and `lengths` must point to `count` elements, and
+/// each buffer to at least its length in bytes, all outliving the returned borrow.
+pub unsafe fn buffers_arg<'a>(
+    buffers: *const *const u8,
+    lengths: *const usize,
+    count: usize,
+    name: &str,
+) -> Result<Vec<&'a [u8]>, FfiError> {
+    if count == 0 {
+        return Ok(Vec::new());
+    }
+    if buffers.is_null() || lengths.is_null() {
+        return Err(FfiError::invalid_argument(format!("`{name}` is NULL")));
+    }
+    let buffers = std::slice::from_raw_parts(buffers, count);
+    let lengths = std::slice::from_raw_parts(lengths, count);
+    buffers
+        .iter()
+        .zip(lengths)
+        .enumerate()
+        .map(|(i, (&buffer, &length))| match (buffer.is_null(), length) {
+            (_, 0) => Ok(&[][..]),
+            (true, _) => Err(FfiError::invalid_argument(format!("`{name}[{i}]` is NULL"))),
+            (false, _) => Ok(std::slice::from_raw_parts(buffer, length)),
+        })
+        .collect()
+}
+
+/// Returns `out` as a mutable reference, failing if it is NULL.
+///
+/// # Safety
+///
+/// `out` must be NULL or valid for writes.
+pub unsafe fn out_arg<'a, T>(out: *mut T, name: &str) -> Result<&'a mut T, FfiError> {
+    out.as_mut()
+        .ok_or_else(|| FfiError::invalid_argument(format!("`{name}` is NULL")))
+}
@@ -0,0 +1,224 @@
+//! The `NvrustClient` handle, running verifications against NRAS.
+
+use std::{ffi::c_char, future::Future};
+
+use remote_attestation_verifier::{
+    utils::nras_token, AttestError, AttestRemoteOptions, DeviceEvidence, DeviceKind,
+    NvSwitchEvidence,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use tokio::runtime::Runtime;
+
+use crate::{
+    args::{out_arg, str_arg},
+    options::NvrustOptions,
+    status::{ffi_call, into_c_string, FfiError, NvrustStatus},
+};
+
+/// A client verifying evidence and decoding tokens, owning the threads it runs them on.
+///
+/// Created with `nvrust_client_new` and freed with `nvrust_client_free`. A client is
+/// thread-safe: calls through the same handle may run concurrently, each blocking its
+/// calling thread until it completes.
+pub struct NvrustClient {
+    runtime: Runtime,
+}
+
+impl NvrustClient {
+    fn block_on<T>(
+        &self,
+        future: impl Future<Output = Result<T, AttestError>>,
+    ) -> Result<T, FfiError> {
+        Ok(self.runtime.block_on(future)?)
+    }
+}
+
+/// Returns the options to verify the evidence of `kind` with, the defaults with the
+/// `NV_*` environment variables when `options` is NULL.
+///
+/// # Safety
+///
+/// `options` must be NULL or a handle returned by `nvrust_options_new`.
+unsafe fn options_or_default(
+    options: *const NvrustOptions,
+    kind: DeviceKind,
+) -> Result<AttestRemoteOptions, FfiError> {
+    options.as_ref().map_or_else(
+        || NvrustOptions::default().build(kind),
+        |options| options.build(kind),
+    )
+}
+
+/// Parses the JSON array of evidence `evidence_json`.
+fn evidence_arg<T: DeserializeOwned>(evidence_json: &str) -> Result<Vec<T>, FfiError> {
+    serde_json::from_str(evidence_json)
+        .map_err(|e| AttestError::EvidenceDecode(format!("Invalid `evidence_json`: {e}")).into())
+}
+
+/// Returns the client of `client`, failing if it is NULL.
+///
+/// # Safety
+///
+/// `client` must be NULL or a handle returned by `nvrust_client_new`.
+unsafe fn client_arg<'a>(client: *const NvrustClient) -> Result<&'a NvrustClient, FfiError> {
+    client
+        .as_ref()
+        .ok_or_else(|| FfiError::invalid_argument("`client` is NULL"))
+}
+
+/// Creates a client.
+///
+/// Returns NULL, and stores the reason in `*error_message` when it is not NULL, if the
+/// client threads cannot be started. The returned handle is owned by the caller and must
+/// be freed with `nvrust_client_free`.
+///
+/// # Safety
+///
+/// `error_message` must be NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_client_new(error_message: *mut *mut c_char) -> *mut NvrustClient {
+    let mut client = std::ptr::null_mut();
+    ffi_call(error_message, || {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .thread_name("nvrust-ffi")
+            .enable_all()
+            .build()
+            .map_err(|e| FfiError {
+                status: NvrustStatus::Runtime,
+                message: format!("Failed to create the runtime: {e}"),
+            })?;
+        client = Box::into_raw(Box::new(NvrustClient { runtime }));
+        Ok(())
+    });
+    client
+}
+
+/// Frees a client created with `nvrust_client_new`, waiting for its threads to stop.
+/// Does nothing if `client` is NULL.
+///
+/// # Safety
+///
+/// `client` must be NULL or a handle returned by `nvrust_client_new`, not freed yet, and
+/// not used by any other thread.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_client_free(client: *mut NvrustClient) {
+    if !client.is_null() {
+        drop(Box::from_raw(client));
+    }
+}
+
+/// Writes the result of a verification to the out-parameters.
+fn write_verification(
+    (passed, response): (bool, Value),
+    attestation_passed: &mut bool,
+    response_json: &mut *mut c_char,
+) {
+    *attestation_passed = passed;
+    *response_json = into_c_string(response.to_string());
+}
+
+/// Verifies GPU evidence against NRAS.
+///
+/// `evidence_json` is a JSON array of `{"certificate": ..., "evidence": ...}` objects, with
+/// base64 encoded fields, and `nonce` the hex encoded nonce the evidence was collected with.
+/// `options` may be NULL to use the defaults with the `NV_*` environment variables.
+///
+/// On success, `*attestation_passed` is set to the overall result, and `*response_json` to
+/// the full NRAS response, to be freed with `nvrust_string_free`.
+///
+/// # Safety
+///
+/// `client` must be a handle returned by `nvrust_client_new`, `options` NULL or a handle
+/// returned by `nvrust_options_new`, `evidence_json` and `nonce` NUL-terminated strings,
+/// `attestation_passed` and `response_json` valid for writes, and `error_message` NULL or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_verify_gpu_evidence(
+    client: *const NvrustClient,
+    options: *const NvrustOptions,
+    evidence_json: *const c_char,
+    nonce: *const c_char,
+    attestation_passed: *mut bool,
+    response_json: *mut *mut c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    ffi_call(error_message, || {
+        let client = client_arg(client)?;
+        let evidence: Vec<DeviceEvidence> = evidence_arg(str_arg(evidence_json, "evidence_json")?)?;
+        let nonce = str_arg(nonce, "nonce")?;
+        let attestation_passed = out_arg(attestation_passed, "attestation_passed")?;
+        let response_json = out_arg(response_json, "response_json")?;
+        let options = options_or_default(options, DeviceKind::Gpu)?;
+        let result = client.block_on(remote_attestation_verifier::verify_gpu_attestation(
+            &evidence, nonce, options,
+        ))?;
+        write_verification(result, attestation_passed, response_json);
+        Ok(())
+    })
+}
+
+/// Verifies `NVSwitch` evidence against NRAS.
+///
+/// Arguments and results are those of `nvrust_verify_gpu_evidence`.
+///
+/// # Safety
+///
+/// See `nvrust_verify_gpu_evidence`.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_verify_switch_evidence(
+    client: *const NvrustClient,
+    options: *const NvrustOptions,
+    evidence_json: *const c_char,
+    nonce: *const c_char,
+    attestation_passed: *mut bool,
+    response_json: *mut *mut c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    ffi_call(error_message, || {
+        let client = client_arg(client)?;
+        let evidence: Vec<NvSwitchEvidence> =
+            evidence_arg(str_arg(evidence_json, "evidence_json")?)?;
+        let nonce = str_arg(nonce, "nonce")?;
+        let attestation_passed = out_arg(attestation_passed, "attestation_passed")?;
+        let response_json = out_arg(response_json, "response_json")?;
+        let options = options_or_default(options, DeviceKind::Switch)?;
+        let result = client.block_on(remote_attestation_verifier::verify_nvswitch_attestation(
+            &evidence, nonce, options,
+        ))?;
+        write_verification(result, attestation_passed, response_json);
+        Ok(())
+    })
+}
+
+/// Decodes an NRAS token, and verifies it against the JWKS of `verifier_url`.
+///
+/// On success, `*claims_json` is set to the claims of the token as a JSON object, to be
+/// freed with `nvrust_string_free`.
+///
+/// # Safety
+///
+/// `client` must be a handle returned by `nvrust_client_new`, `verifier_url` and `token`
+/// NUL-terminated strings, `claims_json` valid for writes, and `error_message` NULL or
+/// valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_decode_token(
+    client: *const NvrustClient,
+    verifier_url: *const c_char,
+    token: *const c_char,
+    claims_json: *mut *mut c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    ffi_call(error_message, || {
+        let client = client_arg(client)?;
+        let verifier_url = str_arg(verifier_url, "verifier_url")?;
+        let token = str_arg(token, "token")?;
+        let claims_json = out_arg(claims_json, "claims_json")?;
+        let claims = client.block_on(async {
+            let claims = nras_token::decode_nras_token(verifier_url, token).await?;
+            Ok(serde_json::to_string(&claims)?)
+        })?;
+        *claims_json = into_c_string(claims);
+        Ok(())
+    })
+}
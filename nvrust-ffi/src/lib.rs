@@ -0,0 +1,60 @@
+//! Stable C API for the NVIDIA attestation verifier and topology checks.
+//!
+//! The `nvrust_ffi` library, built as a `cdylib` and a `staticlib`, lets non-Rust
+//! control planes (e.g. Go through cgo) verify GPU and `NVSwitch` evidence against NRAS,
+//! decode NRAS tokens and run the `PPCIe` topology checks with the same verifier as the
+//! Rust crates. Its header, `include/nvrust_ffi.h`, is generated by cbindgen.
+//!
+//! # Conventions
+//!
+//! * Fallible functions return an [`NvrustStatus`], `NVRUST_STATUS_OK` on success, and
+//!   take a last `char **error_message` parameter. On failure it is set to a message
+//!   owned by the caller, and on success to NULL; it may be NULL to ignore the message.
+//! * Strings are NUL-terminated UTF-8. Strings returned through out-parameters are owned
+//!   by the caller and must be freed with [`nvrust_string_free`]; out-parameters are only
+//!   written on success.
+//! * [`NvrustClient`] and [`NvrustOptions`] are opaque handles owned by the caller, freed
+//!   with `nvrust_client_free` and `nvrust_options_free`. Arguments are borrowed for the
+//!   duration of the call only.
+//! * Panics never unwind into the caller; they are reported as `NVRUST_STATUS_PANIC`.
+
+use std::ffi::{c_char, CString};
+
+mod args;
+mod client;
+mod options;
+mod status;
+#[cfg(test)]
+mod tests;
+mod topology_check;
+
+pub use client::{
+    nvrust_client_free, nvrust_client_new, nvrust_decode_token, nvrust_verify_gpu_evidence,
+    nvrust_verify_switch_evidence, NvrustClient,
+};
+pub use options::{
+    nvrust_options_free, nvrust_options_new, nvrust_options_set_allow_hold_cert,
+    nvrust_options_set_claims_version, nvrust_options_set_config_file, nvrust_options_set_read_env,
+    nvrust_options_set_service_key, nvrust_options_set_service_key_file,
+    nvrust_options_set_timeout_ms, nvrust_options_set_verifier_url, NvrustOptions,
+};
+pub use status::NvrustStatus;
+pub use topology_check::nvrust_check_topology;
+
+/// Frees a string returned by this library. Does nothing if `value` is NULL.
+///
+/// # Safety
+///
+/// `value` must be NULL or a string returned by this library, not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_string_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+/// Returns the version of the library, as a static string that must not be freed.
+#[no_mangle]
+pub const extern "C" fn nvrust_version() -> *const c_char {
+    concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast()
+}
@@ -0,0 +1,265 @@
+//! The `NvrustOptions` handle, configuring a verification.
+
+use std::{ffi::c_char, path::PathBuf, time::Duration};
+
+use remote_attestation_verifier::{AttestRemoteOptions, DeviceKind};
+
+use crate::{
+    args::str_arg,
+    status::{ffi_call, FfiError, NvrustStatus},
+};
+
+/// Options of the remote attestation, see `AttestRemoteOptions`.
+///
+/// Values are layered like in Rust: a config file, then the `NV_*` environment variables
+/// unless disabled with `nvrust_options_set_read_env`, then the values set explicitly.
+/// The config file section and the verifier URL variable are those of the kind of
+/// evidence verified, so one handle can verify both GPU and switch evidence.
+/// They are validated by the verification calls, which fail with
+/// `NVRUST_STATUS_CONFIGURATION` when a value is invalid.
+///
+/// Created with `nvrust_options_new` and freed with `nvrust_options_free`. A handle may be
+/// shared by concurrent verifications, but must not be modified while one uses it.
+pub struct NvrustOptions {
+    config_file: Option<PathBuf>,
+    read_env: bool,
+    verifier_url: Option<String>,
+    timeout: Option<Duration>,
+    claims_version: Option<String>,
+    service_key: Option<String>,
+    service_key_file: Option<PathBuf>,
+    allow_hold_cert: Option<bool>,
+}
+
+impl Default for NvrustOptions {
+    fn default() -> Self {
+        Self {
+            config_file: None,
+            read_env: true,
+            verifier_url: None,
+            timeout: None,
+            claims_version: None,
+            service_key: None,
+            service_key_file: None,
+            allow_hold_cert: None,
+        }
+    }
+}
+
+impl NvrustOptions {
+    /// Builds the options to verify the evidence of `kind` with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value is invalid, or a config file cannot be read.
+    pub fn build(&self, kind: DeviceKind) -> Result<AttestRemoteOptions, FfiError> {
+        let mut builder = AttestRemoteOptions::builder().layered(
+            self.config_file.as_deref(),
+            self.read_env,
+            kind,
+        );
+        if let Some(verifier_url) = &self.verifier_url {
+            builder = builder.verifier_url(verifier_url);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(claims_version) = &self.claims_version {
+            builder = builder.claims_version(claims_version);
+        }
+        if let Some(service_key) = &self.service_key {
+            builder = builder.service_key(service_key);
+        }
+        if let Some(path) = &self.service_key_file {
+            builder = builder.service_key_file(path);
+        }
+        if let Some(allow_hold_cert) = self.allow_hold_cert {
+            builder = builder.allow_hold_cert(allow_hold_cert);
+        }
+        Ok(builder.build()?)
+    }
+}
+
+/// Sets a field of `options`, after checking the handle.
+///
+/// # Safety
+///
+/// `options` must be NULL or a handle returned by `nvrust_options_new`, and
+/// `error_message` NULL or valid for writes.
+unsafe fn set_option(
+    options: *mut NvrustOptions,
+    error_message: *mut *mut c_char,
+    set: impl FnOnce(&mut NvrustOptions) -> Result<(), FfiError>,
+) -> NvrustStatus {
+    ffi_call(error_message, || {
+        let options = options
+            .as_mut()
+            .ok_or_else(|| FfiError::invalid_argument("`options` is NULL"))?;
+        set(options)
+    })
+}
+
+/// Creates options holding the defaults, reading the `NV_*` environment variables.
+///
+/// The returned handle is owned by the caller and must be freed with `nvrust_options_free`.
+#[no_mangle]
+pub extern "C" fn nvrust_options_new() -> *mut NvrustOptions {
+    Box::into_raw(Box::default())
+}
+
+/// Frees options created with `nvrust_options_new`. Does nothing if `options` is NULL.
+///
+/// # Safety
+///
+/// `options` must be NULL or a handle returned by `nvrust_options_new`, not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_free(options: *mut NvrustOptions) {
+    if !options.is_null() {
+        drop(Box::from_raw(options));
+    }
+}
+
+/// Sets the URL of the verification service.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, `verifier_url` a
+/// NUL-terminated string, and `error_message` NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_verifier_url(
+    options: *mut NvrustOptions,
+    verifier_url: *const c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.verifier_url = Some(str_arg(verifier_url, "verifier_url")?.to_string());
+        Ok(())
+    })
+}
+
+/// Sets the request timeout, in milliseconds.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, and `error_message` NULL
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_timeout_ms(
+    options: *mut NvrustOptions,
+    timeout_ms: u64,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.timeout = Some(Duration::from_millis(timeout_ms));
+        Ok(())
+    })
+}
+
+/// Sets the claims version requested from the verification service.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, `claims_version` a
+/// NUL-terminated string, and `error_message` NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_claims_version(
+    options: *mut NvrustOptions,
+    claims_version: *const c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.claims_version = Some(str_arg(claims_version, "claims_version")?.to_string());
+        Ok(())
+    })
+}
+
+/// Sets the service key, taking precedence over any service key file.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, `service_key` a
+/// NUL-terminated string, and `error_message` NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_service_key(
+    options: *mut NvrustOptions,
+    service_key: *const c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.service_key = Some(str_arg(service_key, "service_key")?.to_string());
+        Ok(())
+    })
+}
+
+/// Sets the path of a file containing the service key.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, `path` a NUL-terminated
+/// string, and `error_message` NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_service_key_file(
+    options: *mut NvrustOptions,
+    path: *const c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.service_key_file = Some(str_arg(path, "path")?.into());
+        Ok(())
+    })
+}
+
+/// Sets whether certificates on hold are allowed during OCSP checks.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, and `error_message` NULL
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_allow_hold_cert(
+    options: *mut NvrustOptions,
+    allow_hold_cert: bool,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.allow_hold_cert = Some(allow_hold_cert);
+        Ok(())
+    })
+}
+
+/// Loads values from a TOML or JSON config file when verifying, with the `[gpu]` or
+/// `[switch]` section of the kind of evidence verified.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, `path` a NUL-terminated
+/// string, and `error_message` NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_config_file(
+    options: *mut NvrustOptions,
+    path: *const c_char,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.config_file = Some(str_arg(path, "path")?.into());
+        Ok(())
+    })
+}
+
+/// Sets whether the `NV_*` environment variables are read, which they are by default.
+///
+/// # Safety
+///
+/// `options` must be a handle returned by `nvrust_options_new`, and `error_message` NULL
+/// or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_options_set_read_env(
+    options: *mut NvrustOptions,
+    read_env: bool,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    set_option(options, error_message, |options| {
+        options.read_env = read_env;
+        Ok(())
+    })
+}
@@ -0,0 +1,160 @@
+//! Status codes returned by every fallible function, and the error message out-parameter.
+
+use std::{
+    any::Any,
+    ffi::{c_char, CString},
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use remote_attestation_verifier::{AttestError, AttestErrorKind};
+use topology::error::NvidiaRemoteAttestationError;
+
+/// Result of a call. `NVRUST_STATUS_OK` is zero, every other value is a failure
+/// described by the error message of the call.
+///
+/// Values are stable: new codes may be added, existing ones are never renumbered.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NvrustStatus {
+    /// The call succeeded
+    Ok = 0,
+    /// A required pointer was NULL, or a string was not valid UTF-8
+    InvalidArgument = 1,
+    /// The remote attestation service could not be reached
+    Transport = 2,
+    /// The remote attestation service answered with a non-success HTTP status
+    HttpStatus = 3,
+    /// The remote attestation service answered with an unexpected body
+    MalformedResponse = 4,
+    /// The JWKS used to verify NRAS tokens could not be retrieved
+    Jwks = 5,
+    /// An NRAS token, or the key used to verify it, is invalid
+    TokenSignature = 6,
+    /// An NRAS token is well signed, but its claims are not acceptable
+    ClaimValidation = 7,
+    /// Attestation evidence could not be decoded
+    EvidenceDecode = 8,
+    /// The remote attestation options are invalid
+    Configuration = 9,
+    /// Evidence collection through NSCQ failed
+    Nscq = 10,
+    /// Evidence collection through NVML failed
+    Nvml = 11,
+    /// The attestation result was negative
+    AttestationFailed = 12,
+    /// The attestation decision could not be recorded in the audit log
+    AuditLog = 13,
+    /// The verification could not run on the client runtime
+    Runtime = 14,
+    /// A GPU or switch topology check failed
+    Topology = 15,
+    /// The library panicked; the message describes the panic
+    Panic = 16,
+}
+
+impl From<AttestErrorKind> for NvrustStatus {
+    fn from(kind: AttestErrorKind) -> Self {
+        match kind {
+            AttestErrorKind::Transport => Self::Transport,
+            AttestErrorKind::HttpStatus => Self::HttpStatus,
+            AttestErrorKind::MalformedResponse => Self::MalformedResponse,
+            AttestErrorKind::Jwks => Self::Jwks,
+            AttestErrorKind::TokenSignature => Self::TokenSignature,
+            AttestErrorKind::ClaimValidation => Self::ClaimValidation,
+            AttestErrorKind::EvidenceDecode => Self::EvidenceDecode,
+            AttestErrorKind::Configuration => Self::Configuration,
+            AttestErrorKind::Nscq => Self::Nscq,
+            AttestErrorKind::Nvml => Self::Nvml,
+            AttestErrorKind::AttestationFailed => Self::AttestationFailed,
+            AttestErrorKind::AuditLog => Self::AuditLog,
+            AttestErrorKind::Runtime => Self::Runtime,
+        }
+    }
+}
+
+/// A failed call: the status to return, and the message to hand to the caller.
+pub struct FfiError {
+    pub status: NvrustStatus,
+    pub message: String,
+}
+
+impl FfiError {
+    pub fn invalid_argument(message: impl Into<String>) -> Self {
+        Self {
+            status: NvrustStatus::InvalidArgument,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<AttestError> for FfiError {
+    fn from(error: AttestError) -> Self {
+        Self {
+            status: error.kind().into(),
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<NvidiaRemoteAttestationError> for FfiError {
+    fn from(error: NvidiaRemoteAttestationError) -> Self {
+        Self {
+            status: NvrustStatus::Topology,
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Converts `value` to a C string owned by the caller, to be freed with `nvrust_string_free`.
+///
+/// Interior NUL bytes, which C strings cannot carry, are dropped.
+pub fn into_c_string(value: String) -> *mut c_char {
+    CString::new(value)
+        .unwrap_or_else(|e| {
+            let mut bytes = e.into_vec();
+            bytes.retain(|&b| b != 0);
+            CString::new(bytes).expect("NUL bytes were removed")
+        })
+        .into_raw()
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    let message = payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic");
+    format!("nvrust-ffi panicked: {message}")
+}
+
+/// Runs the body of an exported function.
+///
+/// Panics are caught rather than unwound into C. On failure, the message is stored in
+/// `*error_message` when it is not NULL, and on success `*error_message` is set to NULL.
+///
+/// # Safety
+///
+/// `error_message` must be NULL or valid for writes.
+pub unsafe fn ffi_call(
+    error_message: *mut *mut c_char,
+    body: impl FnOnce() -> Result<(), FfiError>,
+) -> NvrustStatus {
+    let error = match catch_unwind(AssertUnwindSafe(body)) {
+        Ok(Ok(())) => None,
+        Ok(Err(error)) => Some(error),
+        Err(payload) => Some(FfiError {
+            status: NvrustStatus::Panic,
+            message: panic_message(payload.as_ref()),
+        }),
+    };
+    let Some(error) = error else {
+        if !error_message.is_null() {
+            *error_message = std::ptr::null_mut();
+        }
+        return NvrustStatus::Ok;
+    };
+    if !error_message.is_null() {
+        *error_message = into_c_string(error.message);
+    }
+    error.status
+}
@@ -0,0 +1,288 @@
+use std::{
+    ffi::{c_char, CStr, CString},
+    path::Path,
+    ptr,
+};
+
+use remote_attestation_verifier::test_support::{MockFailure, MockNras};
+use topology::test_support::SyntheticTopology;
+
+use crate::*;
+
+const EVIDENCE_JSON: &str = r#"[{"certificate": "Y2VydGlmaWNhdGU=", "evidence": "ZXZpZGVuY2U="}]"#;
+
+/// Takes ownership of a string returned by the library.
+fn take_string(value: *mut c_char) -> String {
+    assert!(!value.is_null(), "the library returned a NULL string");
+    let string = unsafe { CStr::from_ptr(value) }
+        .to_str()
+        .expect("the library returned invalid UTF-8")
+        .to_string();
+    unsafe { nvrust_string_free(value) };
+    string
+}
+
+fn c_string(value: &str) -> CString {
+    CString::new(value).unwrap()
+}
+
+/// Creates options verifying against `verifier_url`, without reading the environment.
+fn options(verifier_url: &str) -> *mut NvrustOptions {
+    let options = nvrust_options_new();
+    let verifier_url = c_string(verifier_url);
+    unsafe {
+        assert_eq!(
+            nvrust_options_set_verifier_url(options, verifier_url.as_ptr(), ptr::null_mut()),
+            NvrustStatus::Ok
+        );
+        assert_eq!(
+            nvrust_options_set_timeout_ms(options, 5_000, ptr::null_mut()),
+            NvrustStatus::Ok
+        );
+        assert_eq!(
+            nvrust_options_set_read_env(options, false, ptr::null_mut()),
+            NvrustStatus::Ok
+        );
+    }
+    options
+}
+
+#[test]
+fn test_header_is_up_to_date() {
+    let crate_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let config = cbindgen::Config::from_file(crate_dir.join("cbindgen.toml")).unwrap();
+    let mut generated = Vec::new();
+    cbindgen::Builder::new()
+        .with_config(config)
+        .with_src(crate_dir.join("src/lib.rs"))
+        .generate()
+        .expect("Failed to generate the header")
+        .write(&mut generated);
+
+    let header = crate_dir.join("include/nvrust_ffi.h");
+    if std::env::var_os("NVRUST_FFI_UPDATE_HEADER").is_some() {
+        std::fs::write(&header, &generated).unwrap();
+    }
+    assert!(
+        std::fs::read(&header).unwrap_or_default() == generated,
+        "include/nvrust_ffi.h is out of date, regenerate it with \
+         `NVRUST_FFI_UPDATE_HEADER=1 cargo test -p nvrust-ffi test_header_is_up_to_date`"
+    );
+}
+
+#[test]
+fn test_verification_and_errors() {
+    // The mock NRAS runs on a runtime of its own, independent of the client's runtime
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+    let nras = runtime
+        .block_on(MockNras::start())
+        .expect("Failed to start mock NRAS");
+    nras.push_failure(MockFailure::Status {
+        status: 503,
+        body: r#"{"errorCode": 503, "message": "Service unavailable"}"#.to_string(),
+    });
+
+    let client = unsafe { nvrust_client_new(ptr::null_mut()) };
+    assert!(!client.is_null());
+    let gpu_options = options(&nras.gpu_verifier_url());
+    let switch_options = options(&nras.switch_verifier_url());
+    let evidence = c_string(EVIDENCE_JSON);
+    let nonce = c_string(&"00".repeat(32));
+
+    let mut passed = false;
+    let mut response = ptr::null_mut();
+    let mut error = ptr::null_mut();
+    let status = unsafe {
+        nvrust_verify_gpu_evidence(
+            client,
+            gpu_options,
+            evidence.as_ptr(),
+            nonce.as_ptr(),
+            &raw mut passed,
+            &raw mut response,
+            &raw mut error,
+        )
+    };
+    assert_eq!(status, NvrustStatus::HttpStatus);
+    assert!(take_string(error).contains("503"));
+    assert!(
+        response.is_null(),
+        "out-parameters are only written on success"
+    );
+
+    let mut tokens = Vec::new();
+    for (verify, options) in [
+        (
+            nvrust_verify_gpu_evidence as unsafe extern "C" fn(_, _, _, _, _, _, _) -> _,
+            gpu_options,
+        ),
+        (nvrust_verify_switch_evidence, switch_options),
+    ] {
+        let status = unsafe {
+            verify(
+                client,
+                options,
+                evidence.as_ptr(),
+                nonce.as_ptr(),
+                &raw mut passed,
+                &raw mut response,
+                &raw mut error,
+            )
+        };
+        assert_eq!(status, NvrustStatus::Ok);
+        assert!(error.is_null());
+        assert!(passed);
+        let response: serde_json::Value = serde_json::from_str(&take_string(response)).unwrap();
+        assert_eq!(response[0][0], "JWT");
+        tokens.push(response[0][1].as_str().unwrap().to_string());
+    }
+
+    let verifier_url = c_string(&nras.gpu_verifier_url());
+    let token = c_string(&tokens[0]);
+    let mut claims = ptr::null_mut();
+    let status = unsafe {
+        nvrust_decode_token(
+            client,
+            verifier_url.as_ptr(),
+            token.as_ptr(),
+            &raw mut claims,
+            &raw mut error,
+        )
+    };
+    assert_eq!(status, NvrustStatus::Ok);
+    let claims: serde_json::Value = serde_json::from_str(&take_string(claims)).unwrap();
+    assert_eq!(claims["x-nvidia-overall-att-result"], true);
+
+    let invalid_evidence = c_string("not json");
+    let status = unsafe {
+        nvrust_verify_gpu_evidence(
+            client,
+            gpu_options,
+            invalid_evidence.as_ptr(),
+            nonce.as_ptr(),
+            &raw mut passed,
+            &raw mut response,
+            &raw mut error,
+        )
+    };
+    assert_eq!(status, NvrustStatus::EvidenceDecode);
+    assert!(take_string(error).contains("evidence_json"));
+
+    unsafe {
+        nvrust_options_free(gpu_options);
+        nvrust_options_free(switch_options);
+        nvrust_client_free(client);
+    }
+}
+
+#[test]
+fn test_invalid_arguments() {
+    let mut error = ptr::null_mut();
+    let status = unsafe {
+        nvrust_verify_gpu_evidence(
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null(),
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &raw mut error,
+        )
+    };
+    assert_eq!(status, NvrustStatus::InvalidArgument);
+    assert_eq!(take_string(error), "`client` is NULL");
+
+    let options = nvrust_options_new();
+
+    let invalid_utf8 = CString::new(vec![0xff, 0xfe]).unwrap();
+    let status =
+        unsafe { nvrust_options_set_verifier_url(options, invalid_utf8.as_ptr(), &raw mut error) };
+    assert_eq!(status, NvrustStatus::InvalidArgument);
+    assert!(take_string(error).contains("UTF-8"));
+
+    // Invalid values are reported by the verification, as configuration errors
+    let claims_version = c_string("not a version");
+    unsafe {
+        nvrust_options_set_claims_version(options, claims_version.as_ptr(), ptr::null_mut());
+        nvrust_options_set_read_env(options, false, ptr::null_mut());
+    }
+    let client = unsafe { nvrust_client_new(ptr::null_mut()) };
+    let evidence = c_string(EVIDENCE_JSON);
+    let nonce = c_string(&"00".repeat(32));
+    let (mut passed, mut response) = (false, ptr::null_mut());
+    let status = unsafe {
+        nvrust_verify_gpu_evidence(
+            client,
+            options,
+            evidence.as_ptr(),
+            nonce.as_ptr(),
+            &raw mut passed,
+            &raw mut response,
+            &raw mut error,
+        )
+    };
+    assert_eq!(status, NvrustStatus::Configuration);
+    assert!(take_string(error).contains("claims_version"));
+
+    // A NULL error message is allowed, and NULL handles are ignored when freeing
+    let status = unsafe { nvrust_options_set_timeout_ms(ptr::null_mut(), 1, ptr::null_mut()) };
+    assert_eq!(status, NvrustStatus::InvalidArgument);
+    unsafe {
+        nvrust_options_free(options);
+        nvrust_client_free(client);
+        nvrust_options_free(ptr::null_mut());
+        nvrust_client_free(ptr::null_mut());
+        nvrust_string_free(ptr::null_mut());
+    }
+
+    let version = unsafe { CStr::from_ptr(nvrust_version()) };
+    assert_eq!(version.to_str().unwrap(), env!("CARGO_PKG_VERSION"));
+}
+
+#[test]
+fn test_topology_check() {
+    let topology = SyntheticTopology::hgx_h100(5);
+    let check = |gpu_reports: &[Vec<u8>], switch_reports: &[Vec<u8>]| {
+        let gpu_pointers: Vec<_> = gpu_reports.iter().map(Vec::as_ptr).collect();
+        let gpu_lengths: Vec<_> = gpu_reports.iter().map(Vec::len).collect();
+        let switch_pointers: Vec<_> = switch_reports.iter().map(Vec::as_ptr).collect();
+        let switch_lengths: Vec<_> = switch_reports.iter().map(Vec::len).collect();
+        let mut error = ptr::null_mut();
+        let status = unsafe {
+            nvrust_check_topology(
+                gpu_pointers.as_ptr(),
+                gpu_lengths.as_ptr(),
+                gpu_reports.len(),
+                switch_pointers.as_ptr(),
+                switch_lengths.as_ptr(),
+                switch_reports.len(),
+                &raw mut error,
+            )
+        };
+        let error = (!error.is_null()).then(|| take_string(error));
+        (status, error)
+    };
+
+    let (gpu_reports, switch_reports) = (topology.gpu_reports(), topology.switch_reports());
+    assert_eq!(
+        check(&gpu_reports, &switch_reports),
+        (NvrustStatus::Ok, None)
+    );
+
+    let (status, error) = check(&gpu_reports[1..], &switch_reports);
+    assert_eq!(status, NvrustStatus::Topology);
+    assert!(error.is_some());
+
+    let status = unsafe {
+        nvrust_check_topology(
+            ptr::null(),
+            ptr::null(),
+            1,
+            ptr::null(),
+            ptr::null(),
+            0,
+            ptr::null_mut(),
+        )
+    };
+    assert_eq!(status, NvrustStatus::InvalidArgument);
+}
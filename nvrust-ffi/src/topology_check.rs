@@ -0,0 +1,51 @@
+//! `PPCIe` topology checks on GPU and switch attestation reports.
+
+use std::ffi::c_char;
+
+use remote_attestation_verifier::ppcie::check_report_topology;
+
+use crate::{
+    args::buffers_arg,
+    status::{ffi_call, NvrustStatus},
+};
+
+/// Checks that the GPU attestation reports all see the same switches, and that the switch
+/// attestation reports are consistent with them.
+///
+/// Report `i` of each kind is the `gpu_report_lengths[i]` (or `switch_report_lengths[i]`)
+/// bytes at `gpu_reports[i]` (or `switch_reports[i]`). A failed check returns
+/// `NVRUST_STATUS_TOPOLOGY`.
+///
+/// # Safety
+///
+/// When the matching count is not zero, the report and length arrays must point to that
+/// many elements, and each report to at least its length in bytes. `error_message` must be
+/// NULL or valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn nvrust_check_topology(
+    gpu_reports: *const *const u8,
+    gpu_report_lengths: *const usize,
+    gpu_report_count: usize,
+    switch_reports: *const *const u8,
+    switch_report_lengths: *const usize,
+    switch_report_count: usize,
+    error_message: *mut *mut c_char,
+) -> NvrustStatus {
+    ffi_call(error_message, || {
+        let gpu_reports = buffers_arg(
+            gpu_reports,
+            gpu_report_lengths,
+            gpu_report_count,
+            "gpu_reports",
+        )?;
+        let switch_reports = buffers_arg(
+            switch_reports,
+            switch_report_lengths,
+            switch_report_count,
+            "switch_reports",
+        )?;
+        check_report_topology(&gpu_reports, &switch_reports)
+            .error
+            .map_or(Ok(()), |e| Err(e.into()))
+    })
+}
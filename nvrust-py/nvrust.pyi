@@ -0,0 +1,104 @@
+"""Python bindings for the NVIDIA attestation verifier and topology checks."""
+
+import asyncio
+from os import PathLike
+from typing import Any, Iterable, Optional
+
+__version__: str
+
+class NvrustError(Exception):
+    """Base class of the errors raised by `nvrust`."""
+
+class AttestationError(NvrustError):
+    """Evidence could not be decoded or verified, or NRAS could not be reached."""
+
+    kind: str
+    """Machine-readable category of the error, e.g. `transport` or `token_signature`."""
+    retryable: bool
+    """Whether the same request may succeed later."""
+    status: Optional[int]
+    """HTTP status returned by NRAS, if any."""
+
+class TopologyError(NvrustError):
+    """A GPU or switch topology check failed."""
+
+class DeviceEvidence:
+    """Attestation evidence of a GPU, with base64 encoded fields."""
+
+    def __init__(self, certificate: str, evidence: str) -> None: ...
+    @property
+    def certificate(self) -> str: ...
+    @property
+    def evidence(self) -> str: ...
+    def evidence_bytes(self) -> bytes: ...
+    def certificate_bytes(self) -> bytes: ...
+
+class NvSwitchEvidence:
+    """Attestation evidence of an NVSwitch, with base64 encoded fields."""
+
+    def __init__(self, certificate: str, evidence: str) -> None: ...
+    @property
+    def certificate(self) -> str: ...
+    @property
+    def evidence(self) -> str: ...
+    def evidence_bytes(self) -> bytes: ...
+    def certificate_bytes(self) -> bytes: ...
+
+class EvidenceBundle:
+    """An evidence bundle, as collected by `nvattest collect` or served by `nvagent`."""
+
+    @staticmethod
+    def decode(data: bytes) -> EvidenceBundle: ...
+    @property
+    def version(self) -> int: ...
+    @property
+    def nonce(self) -> str: ...
+    @property
+    def collected_at(self) -> int: ...
+    @property
+    def hostname(self) -> Optional[str]: ...
+    @property
+    def node_id(self) -> Optional[str]: ...
+    @property
+    def gpu_evidence(self) -> list[DeviceEvidence]: ...
+    @property
+    def switch_evidence(self) -> list[NvSwitchEvidence]: ...
+    @property
+    def digest(self) -> str: ...
+    def verify_signer(self, public_key: bytes) -> None: ...
+
+class AttestOptions:
+    """Options of the remote attestation.
+
+    Values are layered: a config file, then the `NV_*` environment variables unless
+    `env` is false, then the explicit arguments. GPU and switch evidence use the
+    `[gpu]` and `[switch]` sections of the config file, and the
+    `NV_ATTESTATION_VERIFIER_URL` and `NV_ATTESTATION_SWITCH_VERIFIER_URL` variables.
+    """
+
+    def __init__(
+        self,
+        *,
+        verifier_url: Optional[str] = None,
+        timeout_secs: Optional[float] = None,
+        claims_version: Optional[str] = None,
+        service_key: Optional[str] = None,
+        service_key_file: Optional[str | PathLike[str]] = None,
+        allow_hold_cert: Optional[bool] = None,
+        config_file: Optional[str | PathLike[str]] = None,
+        env: bool = True,
+    ) -> None: ...
+
+# The functions below must be called while an asyncio event loop is running.
+
+def verify_gpu_attestation(
+    evidence: list[DeviceEvidence], nonce: str, options: Optional[AttestOptions] = None
+) -> asyncio.Future[tuple[bool, Any]]: ...
+def verify_nvswitch_attestation(
+    evidence: list[NvSwitchEvidence], nonce: str, options: Optional[AttestOptions] = None
+) -> asyncio.Future[tuple[bool, Any]]: ...
+def decode_nras_token(verifier_url: str, token: str) -> asyncio.Future[dict[str, Any]]: ...
+def gpu_topology_check(reports: list[bytes]) -> set[bytes]: ...
+def switch_topology_check(
+    reports: list[bytes], num_gpus: int, unique_switch_pdis: Iterable[bytes]
+) -> None: ...
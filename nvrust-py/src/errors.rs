@@ -0,0 +1,63 @@
+//! Python exceptions raised by the `nvrust` module.
+//!
+//! ```text
+//! Exception
+//! └── NvrustError
+//!     ├── AttestationError   kind, retryable, status
+//!     └── TopologyError
+//! ```
+
+use pyo3::{create_exception, exceptions::PyException, prelude::*};
+use remote_attestation_verifier::AttestError;
+use topology::error::NvidiaRemoteAttestationError;
+
+create_exception!(
+    nvrust,
+    NvrustError,
+    PyException,
+    "Base class of the errors raised by `nvrust`."
+);
+create_exception!(
+    nvrust,
+    AttestationError,
+    NvrustError,
+    "Evidence could not be decoded or verified, or NRAS could not be reached.\n\n\
+     `kind` is the machine-readable category of the error, `retryable` tells whether \
+     the same request may succeed later, and `status` is the HTTP status returned by \
+     NRAS, if any."
+);
+create_exception!(
+    nvrust,
+    TopologyError,
+    NvrustError,
+    "A GPU or switch topology check failed."
+);
+
+/// Adds the exception classes to the module.
+pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = m.py();
+    m.add("NvrustError", py.get_type::<NvrustError>())?;
+    m.add("AttestationError", py.get_type::<AttestationError>())?;
+    m.add("TopologyError", py.get_type::<TopologyError>())?;
+    Ok(())
+}
+
+/// Converts an attestation error to an `AttestationError` exception.
+pub fn attest_error(error: &AttestError) -> PyErr {
+    let exception = AttestationError::new_err(error.to_string());
+    let attributes = Python::with_gil(|py| {
+        let value = exception.value(py);
+        value.setattr("kind", error.kind().as_str())?;
+        value.setattr("retryable", error.is_retryable())?;
+        value.setattr("status", error.status())
+    });
+    match attributes {
+        Ok(()) => exception,
+        Err(e) => e,
+    }
+}
+
+/// Converts a topology check error to a `TopologyError` exception.
+pub fn topology_error(error: &NvidiaRemoteAttestationError) -> PyErr {
+    TopologyError::new_err(error.to_string())
+}
@@ -0,0 +1,163 @@
+//! Python classes for device evidence and evidence bundles.
+
+use pyo3::prelude::*;
+use remote_attestation_verifier::{DeviceEvidence, EvidenceBundle, NvSwitchEvidence};
+
+use crate::errors::attest_error;
+
+/// Defines a Python class wrapping an evidence type, constructed from, and exposing,
+/// its base64 encoded certificate chain and attestation report.
+macro_rules! evidence_class {
+    ($class:ident, $name:literal, $inner:ident) => {
+        #[pyclass(name = $name, module = "nvrust", frozen, eq)]
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $class {
+            pub inner: $inner,
+        }
+
+        #[pymethods]
+        impl $class {
+            #[new]
+            const fn new(certificate: String, evidence: String) -> Self {
+                Self {
+                    inner: $inner {
+                        certificate,
+                        evidence,
+                    },
+                }
+            }
+
+            /// The certificate chain of the device, in base64 encoded format.
+            #[getter]
+            fn certificate(&self) -> &str {
+                &self.inner.certificate
+            }
+
+            /// The attestation report of the device, in base64 encoded format.
+            #[getter]
+            fn evidence(&self) -> &str {
+                &self.inner.evidence
+            }
+
+            /// Decodes the attestation report.
+            fn evidence_bytes(&self) -> PyResult<Vec<u8>> {
+                self.inner.evidence_bytes().map_err(|e| attest_error(&e))
+            }
+
+            /// Decodes the certificate chain.
+            fn certificate_bytes(&self) -> PyResult<Vec<u8>> {
+                self.inner.certificate_bytes().map_err(|e| attest_error(&e))
+            }
+
+            fn __repr__(&self) -> String {
+                format!(
+                    "{}(certificate=<{} bytes>, evidence=<{} bytes>)",
+                    $name,
+                    self.inner.certificate.len(),
+                    self.inner.evidence.len()
+                )
+            }
+        }
+
+        impl From<$inner> for $class {
+            fn from(inner: $inner) -> Self {
+                Self { inner }
+            }
+        }
+    };
+}
+
+evidence_class!(PyDeviceEvidence, "DeviceEvidence", DeviceEvidence);
+evidence_class!(PyNvSwitchEvidence, "NvSwitchEvidence", NvSwitchEvidence);
+
+/// An evidence bundle, as collected by `nvattest collect` or served by `nvagent`.
+#[pyclass(name = "EvidenceBundle", module = "nvrust", frozen)]
+pub struct PyEvidenceBundle {
+    inner: EvidenceBundle,
+}
+
+#[pymethods]
+impl PyEvidenceBundle {
+    /// Decodes a JSON or CBOR encoded bundle, and checks its version, nonce, evidence
+    /// encoding and digest.
+    #[staticmethod]
+    fn decode(data: &[u8]) -> PyResult<Self> {
+        EvidenceBundle::decode_detect(data)
+            .map(|inner| Self { inner })
+            .map_err(|e| attest_error(&e))
+    }
+
+    /// The version of the bundle format.
+    #[getter]
+    const fn version(&self) -> u16 {
+        self.inner.version
+    }
+
+    /// The hex encoded nonce the evidence was collected for.
+    #[getter]
+    fn nonce(&self) -> &str {
+        &self.inner.nonce
+    }
+
+    /// The collection time, in seconds since the Unix epoch.
+    #[getter]
+    const fn collected_at(&self) -> u64 {
+        self.inner.collected_at
+    }
+
+    /// The hostname of the node the evidence was collected on, if recorded.
+    #[getter]
+    fn hostname(&self) -> Option<&str> {
+        self.inner.host.hostname.as_deref()
+    }
+
+    /// The identifier of the node the evidence was collected on, if recorded.
+    #[getter]
+    fn node_id(&self) -> Option<&str> {
+        self.inner.host.node_id.as_deref()
+    }
+
+    /// The evidence of the GPUs of the node.
+    #[getter]
+    fn gpu_evidence(&self) -> Vec<PyDeviceEvidence> {
+        self.inner
+            .gpu_evidence
+            .iter()
+            .cloned()
+            .map(PyDeviceEvidence::from)
+            .collect()
+    }
+
+    /// The evidence of the `NVSwitch` devices of the node.
+    #[getter]
+    fn switch_evidence(&self) -> Vec<PyNvSwitchEvidence> {
+        self.inner
+            .switch_evidence
+            .iter()
+            .cloned()
+            .map(PyNvSwitchEvidence::from)
+            .collect()
+    }
+
+    /// The blake3 digest of the bundle content.
+    #[getter]
+    fn digest(&self) -> &str {
+        &self.inner.digest
+    }
+
+    /// Checks that the bundle is signed by the Ed25519 `public_key`.
+    fn verify_signer(&self, public_key: &[u8]) -> PyResult<()> {
+        self.inner
+            .verify_signer(public_key)
+            .map_err(|e| attest_error(&e))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "EvidenceBundle(nonce={:?}, gpu_evidence=<{} devices>, switch_evidence=<{} devices>)",
+            self.inner.nonce,
+            self.inner.gpu_evidence.len(),
+            self.inner.switch_evidence.len()
+        )
+    }
+}
@@ -0,0 +1,37 @@
+//! Python bindings for the NVIDIA attestation verifier and topology checks.
+//!
+//! The `nvrust` extension module, packaged with maturin, exposes the evidence types,
+//! GPU and `NVSwitch` verification against NRAS, NRAS token decoding and the `PPCIe`
+//! topology checks, so Python applications reuse the same verifier as Rust ones.
+//! Verification and token decoding return asyncio futures, run on the Tokio runtime of
+//! `pyo3-async-runtimes`, and failures are raised as the exceptions of [`errors`].
+
+use pyo3::prelude::*;
+
+mod errors;
+mod evidence;
+#[cfg(test)]
+mod tests;
+mod topology_check;
+mod verify;
+
+/// The `nvrust` Python module.
+///
+/// # Errors
+///
+/// Returns an error if a class, function or exception cannot be added to the module.
+#[pymodule]
+pub fn nvrust(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    errors::register(m)?;
+    m.add_class::<evidence::PyDeviceEvidence>()?;
+    m.add_class::<evidence::PyNvSwitchEvidence>()?;
+    m.add_class::<evidence::PyEvidenceBundle>()?;
+    m.add_class::<verify::PyAttestOptions>()?;
+    m.add_function(wrap_pyfunction!(verify::verify_gpu_attestation, m)?)?;
+    m.add_function(wrap_pyfunction!(verify::verify_nvswitch_attestation, m)?)?;
+    m.add_function(wrap_pyfunction!(verify::decode_nras_token, m)?)?;
+    m.add_function(wrap_pyfunction!(topology_check::gpu_topology_check, m)?)?;
+    m.add_function(wrap_pyfunction!(topology_check::switch_topology_check, m)?)?;
+    Ok(())
+}
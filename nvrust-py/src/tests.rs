@@ -0,0 +1,166 @@
+use std::ffi::CStr;
+
+use pyo3::{ffi::c_str, prelude::*, types::PyDict};
+use remote_attestation_verifier::{
+    test_support::{MockFailure, MockNras},
+    BundleEncoding, DeviceEvidence, EvidenceBundle, HostMetadata,
+};
+use topology::test_support::SyntheticTopology;
+
+/// Runs `code` with the `nvrust` module importable, and the variables set by `globals`.
+fn run_python(code: &CStr, globals: impl FnOnce(&Bound<'_, PyDict>) -> PyResult<()>) {
+    Python::with_gil(|py| {
+        let module = PyModule::new(py, "nvrust")?;
+        crate::nvrust(&module)?;
+        py.import("sys")?
+            .getattr("modules")?
+            .set_item("nvrust", &module)?;
+        let dict = PyDict::new(py);
+        globals(&dict)?;
+        py.run(code, Some(&dict), None)
+    })
+    .unwrap_or_else(|e| Python::with_gil(|py| panic!("Python error: {}", e.value(py))));
+}
+
+#[test]
+fn test_evidence_types_and_errors() {
+    let bundle = EvidenceBundle::new(
+        &[0x11; 32],
+        vec![DeviceEvidence {
+            certificate: "Y2VydGlmaWNhdGU=".to_string(),
+            evidence: "ZXZpZGVuY2U=".to_string(),
+        }],
+        vec![],
+        HostMetadata::default(),
+    )
+    .encode(BundleEncoding::Cbor)
+    .unwrap();
+
+    run_python(
+        c_str!(
+            r#"
+import nvrust
+
+gpu = nvrust.DeviceEvidence("Y2VydGlmaWNhdGU=", "ZXZpZGVuY2U=")
+assert gpu.evidence_bytes() == b"evidence"
+assert gpu.certificate_bytes() == b"certificate"
+
+decoded = nvrust.EvidenceBundle.decode(bundle)
+assert decoded.nonce == "11" * 32
+assert decoded.gpu_evidence == [gpu]
+assert decoded.switch_evidence == []
+
+try:
+    nvrust.NvSwitchEvidence("certificate", "not base64!").evidence_bytes()
+    raise AssertionError("invalid base64 was decoded")
+except nvrust.AttestationError as e:
+    assert isinstance(e, nvrust.NvrustError)
+    assert e.kind == "evidence_decode"
+    assert e.retryable is False
+    assert e.status is None
+
+try:
+    nvrust.EvidenceBundle.decode(b"{}")
+    raise AssertionError("an invalid bundle was decoded")
+except nvrust.AttestationError as e:
+    assert e.kind in ("evidence_decode", "malformed_response")
+"#
+        ),
+        |globals| globals.set_item("bundle", bundle.as_slice()),
+    );
+}
+
+#[test]
+fn test_topology_checks() {
+    let topology = SyntheticTopology::hgx_h100(5);
+    run_python(
+        c_str!(
+            r#"
+import nvrust
+
+pdis = nvrust.gpu_topology_check(gpu_reports)
+assert len(pdis) == 4
+assert all(isinstance(pdi, bytes) and len(pdi) == 8 for pdi in pdis)
+nvrust.switch_topology_check(switch_reports, len(gpu_reports), pdis)
+
+try:
+    nvrust.switch_topology_check(switch_reports, len(gpu_reports) - 1, pdis)
+    raise AssertionError("the switch topology check passed with a missing GPU")
+except nvrust.TopologyError:
+    pass
+
+try:
+    nvrust.gpu_topology_check(gpu_reports[1:])
+    raise AssertionError("the GPU topology check passed with a missing GPU")
+except nvrust.TopologyError as e:
+    assert isinstance(e, nvrust.NvrustError)
+"#
+        ),
+        |globals| {
+            let py = globals.py();
+            let to_bytes = |reports: Vec<Vec<u8>>| {
+                reports
+                    .into_iter()
+                    .map(|report| pyo3::types::PyBytes::new(py, &report))
+                    .collect::<Vec<_>>()
+            };
+            globals.set_item("gpu_reports", to_bytes(topology.gpu_reports()))?;
+            globals.set_item("switch_reports", to_bytes(topology.switch_reports()))
+        },
+    );
+}
+
+#[test]
+fn test_asyncio_verification() {
+    // The mock NRAS runs on a runtime of its own, independent of the runtime of
+    // `pyo3-async-runtimes`
+    let runtime = tokio::runtime::Runtime::new().expect("Failed to create runtime");
+    let nras = runtime
+        .block_on(MockNras::start())
+        .expect("Failed to start mock NRAS");
+    nras.push_failure(MockFailure::Status {
+        status: 503,
+        body: r#"{"errorCode": 503, "message": "Service unavailable"}"#.to_string(),
+    });
+
+    run_python(
+        c_str!(
+            r#"
+import asyncio
+import nvrust
+
+async def main():
+    gpu = nvrust.DeviceEvidence("Y2VydGlmaWNhdGU=", "ZXZpZGVuY2U=")
+    switch = nvrust.NvSwitchEvidence("Y2VydGlmaWNhdGU=", "ZXZpZGVuY2U=")
+    nonce = "00" * 32
+    gpu_options = nvrust.AttestOptions(verifier_url=gpu_url, timeout_secs=5, env=False)
+    switch_options = nvrust.AttestOptions(verifier_url=switch_url, env=False)
+
+    try:
+        await nvrust.verify_gpu_attestation([gpu], nonce, gpu_options)
+        raise AssertionError("the scripted NRAS failure was not raised")
+    except nvrust.AttestationError as e:
+        assert e.kind == "http_status"
+        assert e.status == 503
+        assert e.retryable is True
+
+    (gpu_passed, response), (switch_passed, _) = await asyncio.gather(
+        nvrust.verify_gpu_attestation([gpu], nonce, gpu_options),
+        nvrust.verify_nvswitch_attestation([switch], nonce, switch_options),
+    )
+    assert gpu_passed and switch_passed
+    assert response[0][0] == "JWT"
+
+    claims = await nvrust.decode_nras_token(gpu_url, response[0][1])
+    assert claims["x-nvidia-overall-att-result"] is True
+
+asyncio.run(main())
+"#
+        ),
+        |globals| {
+            globals.set_item("gpu_url", nras.gpu_verifier_url())?;
+            globals.set_item("switch_url", nras.switch_verifier_url())
+        },
+    );
+    assert_eq!(nras.requests().len(), 3);
+}
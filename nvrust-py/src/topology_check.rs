@@ -0,0 +1,51 @@
+//! `PPCIe` topology checks on GPU and switch attestation reports.
+
+use std::collections::HashSet;
+
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    types::{PyBytes, PySet},
+};
+use topology::{
+    switch_pdis::opaque_data_field_size::PDI_DATA_FIELD_SIZE,
+    topology::{gpu_topology_check as check_gpu, switch_topology_check as check_switch},
+};
+
+use crate::errors::topology_error;
+
+/// Checks that the GPU attestation reports all see the same switches.
+///
+/// Returns the set of switch PDIs, as `bytes`, to pass to `switch_topology_check`.
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // PyO3 extracts arguments by value
+pub fn gpu_topology_check<'py>(
+    py: Python<'py>,
+    reports: Vec<Bound<'py, PyBytes>>,
+) -> PyResult<Bound<'py, PySet>> {
+    let reports: Vec<&[u8]> = reports.iter().map(PyBytesMethods::as_bytes).collect();
+    let pdis = check_gpu(&reports).map_err(|e| topology_error(&e))?;
+    PySet::new(py, pdis.iter().map(|pdi| PyBytes::new(py, pdi)))
+}
+
+/// Checks that the switch attestation reports are consistent with the `num_gpus` GPUs,
+/// and with the switch PDIs returned by `gpu_topology_check`.
+#[pyfunction]
+#[allow(clippy::needless_pass_by_value)] // PyO3 extracts arguments by value
+pub fn switch_topology_check(
+    reports: Vec<Bound<'_, PyBytes>>,
+    num_gpus: usize,
+    unique_switch_pdis: &Bound<'_, PyAny>,
+) -> PyResult<()> {
+    let reports: Vec<&[u8]> = reports.iter().map(PyBytesMethods::as_bytes).collect();
+    let pdis = unique_switch_pdis
+        .try_iter()?
+        .map(|pdi| {
+            let pdi = pdi?;
+            <[u8; PDI_DATA_FIELD_SIZE]>::try_from(pdi.downcast::<PyBytes>()?.as_bytes()).map_err(
+                |_| PyValueError::new_err(format!("A PDI must be {PDI_DATA_FIELD_SIZE} bytes")),
+            )
+        })
+        .collect::<PyResult<HashSet<_>>>()?;
+    check_switch(&reports, num_gpus, pdis).map_err(|e| topology_error(&e))
+}
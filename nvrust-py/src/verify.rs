@@ -0,0 +1,206 @@
+//! Remote attestation of GPU and `NVSwitch` evidence, and NRAS token decoding.
+//!
+//! The functions must be called while an asyncio event loop is running, and return
+//! asyncio futures of that loop, through `pyo3_async_runtimes::tokio::future_into_py`.
+//! The verification runs on the Tokio runtime of `pyo3-async-runtimes`, without holding
+//! the GIL.
+
+use std::{path::PathBuf, time::Duration};
+
+use pyo3::{exceptions::PyValueError, prelude::*};
+use pyo3_async_runtimes::tokio::future_into_py;
+use remote_attestation_verifier::{
+    utils::nras_token, AttestRemoteOptions, DeviceEvidence, DeviceKind, NvSwitchEvidence,
+};
+use serde_json::Value;
+
+use crate::{
+    errors::attest_error,
+    evidence::{PyDeviceEvidence, PyNvSwitchEvidence},
+};
+
+/// Options of the remote attestation, see `AttestRemoteOptions`.
+///
+/// Values are layered like in Rust: a config file, then the `NV_*` environment
+/// variables unless `env` is false, then the explicit arguments. The options of GPU and
+/// switch evidence are built separately, with the config file section and the verifier
+/// URL variable of their kind.
+#[pyclass(name = "AttestOptions", module = "nvrust", frozen)]
+pub struct PyAttestOptions {
+    gpu: AttestRemoteOptions,
+    switch: AttestRemoteOptions,
+}
+
+impl PyAttestOptions {
+    /// Returns the options to verify the evidence of `kind` with.
+    const fn options(&self, kind: DeviceKind) -> &AttestRemoteOptions {
+        match kind {
+            DeviceKind::Gpu => &self.gpu,
+            DeviceKind::Switch => &self.switch,
+        }
+    }
+}
+
+#[pymethods]
+impl PyAttestOptions {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        verifier_url = None,
+        timeout_secs = None,
+        claims_version = None,
+        service_key = None,
+        service_key_file = None,
+        allow_hold_cert = None,
+        config_file = None,
+        env = true,
+    ))]
+    #[allow(clippy::too_many_arguments, clippy::needless_pass_by_value)]
+    fn new(
+        verifier_url: Option<String>,
+        timeout_secs: Option<f64>,
+        claims_version: Option<String>,
+        service_key: Option<String>,
+        service_key_file: Option<PathBuf>,
+        allow_hold_cert: Option<bool>,
+        config_file: Option<PathBuf>,
+        env: bool,
+    ) -> PyResult<Self> {
+        let timeout = timeout_secs
+            .map(Duration::try_from_secs_f64)
+            .transpose()
+            .map_err(|e| PyValueError::new_err(format!("Invalid `timeout_secs`: {e}")))?;
+        let build = |kind| {
+            let mut builder =
+                AttestRemoteOptions::builder().layered(config_file.as_deref(), env, kind);
+            if let Some(verifier_url) = &verifier_url {
+                builder = builder.verifier_url(verifier_url);
+            }
+            if let Some(timeout) = timeout {
+                builder = builder.timeout(timeout);
+            }
+            if let Some(claims_version) = &claims_version {
+                builder = builder.claims_version(claims_version);
+            }
+            if let Some(service_key) = &service_key {
+                builder = builder.service_key(service_key);
+            }
+            if let Some(path) = &service_key_file {
+                builder = builder.service_key_file(path);
+            }
+            if let Some(allow_hold_cert) = allow_hold_cert {
+                builder = builder.allow_hold_cert(allow_hold_cert);
+            }
+            builder.build().map_err(|e| attest_error(&e))
+        };
+        Ok(Self {
+            gpu: build(DeviceKind::Gpu)?,
+            switch: build(DeviceKind::Switch)?,
+        })
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "AttestOptions(gpu: {}; switch: {})",
+            self.gpu.effective_config(DeviceKind::Gpu),
+            self.switch.effective_config(DeviceKind::Switch)
+        )
+    }
+}
+
+/// Returns the options to verify the evidence of `kind` with, the defaults with the
+/// `NV_*` environment variables when none are given.
+fn options_or_default(
+    options: Option<&PyAttestOptions>,
+    kind: DeviceKind,
+) -> PyResult<AttestRemoteOptions> {
+    options.map_or_else(
+        || {
+            AttestRemoteOptions::builder()
+                .with_env(kind)
+                .build()
+                .map_err(|e| attest_error(&e))
+        },
+        |options| Ok(options.options(kind).clone()),
+    )
+}
+
+/// Converts a JSON value to the equivalent Python object.
+fn json_to_py(py: Python<'_>, value: &Value) -> PyResult<PyObject> {
+    Ok(py
+        .import("json")?
+        .call_method1("loads", (value.to_string(),))?
+        .unbind())
+}
+
+/// Converts the result of a verification to `(attestation_passed, response)`.
+fn verification_to_py(py: Python<'_>, (passed, response): (bool, Value)) -> PyResult<PyObject> {
+    Ok((passed, json_to_py(py, &response)?)
+        .into_pyobject(py)?
+        .into_any()
+        .unbind())
+}
+
+/// Verifies GPU evidence against NRAS.
+///
+/// Returns an asyncio future resolving to `(attestation_passed, response)`, where `response`
+/// is the full NRAS response.
+#[pyfunction]
+#[pyo3(signature = (evidence, nonce, options = None))]
+pub fn verify_gpu_attestation<'py>(
+    py: Python<'py>,
+    evidence: Vec<PyDeviceEvidence>,
+    nonce: String,
+    options: Option<&Bound<'py, PyAttestOptions>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let options = options_or_default(options.map(Bound::get), DeviceKind::Gpu)?;
+    let evidence: Vec<DeviceEvidence> = evidence.into_iter().map(|e| e.inner).collect();
+    future_into_py(py, async move {
+        let verification =
+            remote_attestation_verifier::verify_gpu_attestation(&evidence, &nonce, options)
+                .await
+                .map_err(|e| attest_error(&e))?;
+        Python::with_gil(|py| verification_to_py(py, verification))
+    })
+}
+
+/// Verifies `NVSwitch` evidence against NRAS.
+///
+/// Returns an asyncio future resolving to `(attestation_passed, response)`, where `response`
+/// is the full NRAS response.
+#[pyfunction]
+#[pyo3(signature = (evidence, nonce, options = None))]
+pub fn verify_nvswitch_attestation<'py>(
+    py: Python<'py>,
+    evidence: Vec<PyNvSwitchEvidence>,
+    nonce: String,
+    options: Option<&Bound<'py, PyAttestOptions>>,
+) -> PyResult<Bound<'py, PyAny>> {
+    let options = options_or_default(options.map(Bound::get), DeviceKind::Switch)?;
+    let evidence: Vec<NvSwitchEvidence> = evidence.into_iter().map(|e| e.inner).collect();
+    future_into_py(py, async move {
+        let verification =
+            remote_attestation_verifier::verify_nvswitch_attestation(&evidence, &nonce, options)
+                .await
+                .map_err(|e| attest_error(&e))?;
+        Python::with_gil(|py| verification_to_py(py, verification))
+    })
+}
+
+/// Decodes an NRAS token, and verifies it against the JWKS of `verifier_url`.
+///
+/// Returns an asyncio future resolving to the claims of the token, as a dict.
+#[pyfunction]
+pub fn decode_nras_token(
+    py: Python<'_>,
+    verifier_url: String,
+    token: String,
+) -> PyResult<Bound<'_, PyAny>> {
+    future_into_py(py, async move {
+        let claims = nras_token::decode_nras_token(&verifier_url, &token)
+            .await
+            .and_then(|claims| Ok(serde_json::to_value(claims)?))
+            .map_err(|e| attest_error(&e))?;
+        Python::with_gil(|py| json_to_py(py, &claims))
+    })
+}
@@ -0,0 +1,210 @@
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use remote_attestation_verifier::{
+    audit::AuditLog, cache::AttestationCache, options::read_config_file, AttestError,
+    AttestRemoteOptions, DeviceKind, Result,
+};
+use serde::Deserialize;
+
+use crate::policy::Policy;
+
+/// Address the service listens on when none is configured.
+pub const DEFAULT_LISTEN_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Length of an Ed25519 public key, in bytes.
+const ED25519_PUBLIC_KEY_LEN: usize = 32;
+
+/// Configuration of the verifier service, as it appears in a TOML or JSON config file.
+///
+/// Every field is optional:
+///
+/// ```toml
+/// listen = "0.0.0.0:8080"
+/// attestation_config = "/etc/nvverifier/attestation.toml"
+/// audit_log = "/var/lib/nvverifier/audit.jsonl"
+/// cache_max_entries = 1024
+/// trusted_agent_keys = ["<64 hex digits>"]
+///
+/// [policy]
+/// max_bundle_age_secs = 300
+/// max_clock_skew_secs = 60
+/// gpus = 8
+/// switches = 4
+/// require_nonce = true
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceConfig {
+    /// Address the service listens on, defaults to [`DEFAULT_LISTEN_ADDRESS`]
+    pub listen: Option<SocketAddr>,
+    /// Remote attestation config file, with the options of GPU and `NVSwitch` evidence in
+    /// its `[gpu]` and `[switch]` sections, see `AttestRemoteConfig`
+    pub attestation_config: Option<PathBuf>,
+    /// Path of a file audit log recording every attestation decision
+    pub audit_log: Option<PathBuf>,
+    /// If set, verified results are cached in memory, up to this number of entries
+    pub cache_max_entries: Option<usize>,
+    /// Policy applied to every bundle before it is sent to NRAS
+    #[serde(default)]
+    pub policy: Policy,
+    /// Hex encoded Ed25519 public keys of the agents allowed to sign bundles. If set,
+    /// unsigned bundles and bundles signed by another key are rejected.
+    #[serde(default)]
+    pub trusted_agent_keys: Vec<String>,
+}
+
+impl ServiceConfig {
+    /// Loads a config file, choosing the format from the file extension
+    /// (`.toml` or `.json`).
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::ConfigError` - If the file cannot be read, has an unknown
+    ///   extension, or cannot be parsed
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_config_file(path)
+    }
+
+    /// Returns the decoded trusted agent keys.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::ConfigError` - If a key is not a hex encoded Ed25519 public key
+    pub fn trusted_agent_keys(&self) -> Result<Vec<Vec<u8>>> {
+        self.trusted_agent_keys
+            .iter()
+            .map(|key| match hex::decode(key) {
+                Ok(key) if key.len() == ED25519_PUBLIC_KEY_LEN => Ok(key),
+                _ => Err(AttestError::ConfigError(format!(
+                    "Trusted agent key `{key}` is not a hex encoded Ed25519 public key"
+                ))),
+            })
+            .collect()
+    }
+
+    /// Returns the address the service listens on.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`DEFAULT_LISTEN_ADDRESS`] is not a valid socket address, which cannot happen.
+    #[must_use]
+    pub fn listen_address(&self) -> SocketAddr {
+        self.listen.unwrap_or_else(|| {
+            DEFAULT_LISTEN_ADDRESS
+                .parse()
+                .expect("Default listen address is valid")
+        })
+    }
+}
+
+/// Builds the remote attestation options of one device kind, from its section of the
+/// attestation config file if any, overridden by the `NV_*` environment variables.
+///
+/// The cache and audit log are shared by the GPU and `NVSwitch` options.
+///
+/// # Errors
+///
+/// * `AttestError::ConfigError` - If the config file or the service key file cannot be
+///   read or parsed
+/// * `AttestError::InvalidOption` - If a value fails validation
+pub(crate) fn build_options(
+    config: &ServiceConfig,
+    kind: DeviceKind,
+    cache: Option<&Arc<dyn AttestationCache>>,
+    audit_log: Option<&Arc<dyn AuditLog>>,
+) -> Result<AttestRemoteOptions> {
+    let mut builder =
+        AttestRemoteOptions::builder().layered(config.attestation_config.as_deref(), true, kind);
+    if let Some(cache) = cache {
+        builder = builder.cache(Arc::clone(cache));
+    }
+    if let Some(audit_log) = audit_log {
+        builder = builder.audit_log(Arc::clone(audit_log));
+    }
+    builder.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn test_service_config_from_toml() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("nvverifier-{}.toml", std::process::id()));
+        let attestation_path = dir.join(format!("nvverifier-nras-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            format!(
+                r#"
+listen = "0.0.0.0:9000"
+cache_max_entries = 16
+attestation_config = "{}"
+trusted_agent_keys = ["{}"]
+
+[policy]
+max_bundle_age_secs = 60
+gpus = 8
+"#,
+                attestation_path.display(),
+                "ab".repeat(32)
+            ),
+        )
+        .unwrap();
+        std::fs::write(
+            &attestation_path,
+            r#"
+timeout_secs = 5
+
+[gpu]
+verifier_url = "http://127.0.0.1:1234/v3/attest/gpu"
+
+[switch]
+verifier_url = "not a url"
+"#,
+        )
+        .unwrap();
+        let config = ServiceConfig::from_file(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(config.listen_address(), "0.0.0.0:9000".parse().unwrap());
+        assert_eq!(config.cache_max_entries, Some(16));
+        assert_eq!(config.policy.max_bundle_age_secs, Some(60));
+        assert_eq!(config.policy.gpus, Some(8));
+        assert_eq!(config.policy.switches, None);
+        assert_eq!(config.trusted_agent_keys().unwrap(), vec![vec![0xab; 32]]);
+        let options = build_options(&config, DeviceKind::Gpu, None, None).unwrap();
+        assert_eq!(options.timeout, Some(Duration::from_secs(5)));
+        assert_eq!(
+            options.verifier_url.as_deref(),
+            Some("http://127.0.0.1:1234/v3/attest/gpu")
+        );
+        assert!(matches!(
+            build_options(&config, DeviceKind::Switch, None, None),
+            Err(AttestError::InvalidOption {
+                field: "verifier_url",
+                ..
+            })
+        ));
+        std::fs::remove_file(&attestation_path).unwrap();
+
+        let config = ServiceConfig::default();
+        assert_eq!(
+            config.listen_address(),
+            DEFAULT_LISTEN_ADDRESS.parse().unwrap()
+        );
+        let options = build_options(&config, DeviceKind::Switch, None, None).unwrap();
+        assert!(options.verifier_url.is_none());
+        let config = ServiceConfig {
+            trusted_agent_keys: vec!["ab".repeat(31)],
+            ..ServiceConfig::default()
+        };
+        assert!(config.trusted_agent_keys().is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use axum::{
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use remote_attestation_verifier::{AttestError, AttestErrorKind};
+use serde_json::json;
+use thiserror::Error;
+
+/// An attestation request that could not be verified.
+///
+/// Evidence that is verified and rejected is not an error: it is reported as a
+/// failed [`Verdict`](crate::verdict::Verdict).
+#[derive(Debug, Error)]
+pub enum ServiceError {
+    #[error("Unsupported content type `{0}`, expected application/json or application/cbor")]
+    UnsupportedMediaType(String),
+    #[error("Bundle does not contain any {0} evidence")]
+    MissingEvidence(&'static str),
+    #[error("Invalid nonce: {0}")]
+    InvalidNonce(String),
+    #[error(transparent)]
+    Attest(Box<AttestError>),
+}
+
+impl From<AttestError> for ServiceError {
+    fn from(error: AttestError) -> Self {
+        Self::Attest(Box::new(error))
+    }
+}
+
+impl ServiceError {
+    /// Returns a stable `snake_case` category of the error.
+    #[must_use]
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::UnsupportedMediaType(_) => "unsupported_media_type",
+            Self::MissingEvidence(_) => "missing_evidence",
+            Self::InvalidNonce(_) => "invalid_nonce",
+            Self::Attest(error) => error.kind().as_str(),
+        }
+    }
+
+    /// Returns the HTTP status answered for the error.
+    ///
+    /// Invalid bundles are client errors. Failures to reach NRAS or to verify its
+    /// response are gateway errors, reported as `503 Service Unavailable` when the
+    /// request may succeed if retried.
+    #[must_use]
+    pub fn status(&self) -> StatusCode {
+        match self {
+            Self::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::MissingEvidence(_) | Self::InvalidNonce(_) => StatusCode::BAD_REQUEST,
+            Self::Attest(error) => match error.kind() {
+                AttestErrorKind::EvidenceDecode => StatusCode::BAD_REQUEST,
+                _ if error.is_retryable() => StatusCode::SERVICE_UNAVAILABLE,
+                AttestErrorKind::Transport
+                | AttestErrorKind::HttpStatus
+                | AttestErrorKind::MalformedResponse
+                | AttestErrorKind::Jwks
+                | AttestErrorKind::TokenSignature
+                | AttestErrorKind::ClaimValidation => StatusCode::BAD_GATEWAY,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        }
+    }
+
+    /// Returns `true` if the same request may succeed when retried later.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Attest(error) if error.is_retryable())
+    }
+}
+
+impl IntoResponse for ServiceError {
+    fn into_response(self) -> Response {
+        let body = json!({
+            "error": self.to_string(),
+            "kind": self.kind(),
+            "retryable": self.is_retryable(),
+        });
+        (self.status(), Json(body)).into_response()
+    }
+}
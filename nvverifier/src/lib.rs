@@ -0,0 +1,35 @@
+//! `nvverifier`: relying-party verification service for NVIDIA attestation evidence.
+//!
+//! The service accepts [`EvidenceBundle`](remote_attestation_verifier::EvidenceBundle)s,
+//! in JSON or CBOR, on the following endpoints:
+//!
+//! * `POST /v1/attest/gpu` - verifies the GPU evidence of a bundle against NRAS
+//! * `POST /v1/attest/switch` - verifies the `NVSwitch` evidence of a bundle against NRAS
+//! * `POST /v1/attest/ppcie` - runs the GPU and switch topology checks, then verifies
+//!   the GPU and `NVSwitch` evidence of a bundle against NRAS
+//! * `GET /healthz` - liveness check
+//! * `GET /metrics` - attestation metrics in the Prometheus text format, with the
+//!   `metrics` feature
+//!
+//! Every bundle is first checked against the configured [`Policy`]. The relying party
+//! may bind a request to the nonce it issued with the `nonce` query parameter. Verified
+//! requests are answered with a [`Verdict`], whether attestation passed or not, so that
+//! services written in any language can share one verification point.
+//! Requests accepting `application/eat-ucs+json` are answered with the verdict as an
+//! EAT Attestation Result (EAR) instead, see [`Verdict::ear`].
+
+pub mod config;
+pub mod error;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod policy;
+pub mod service;
+#[cfg(test)]
+mod tests;
+pub mod verdict;
+
+pub use config::ServiceConfig;
+pub use error::ServiceError;
+pub use policy::{Policy, PolicyViolation};
+pub use service::{appraise, router, Scope, ServiceState};
+pub use verdict::{DeviceVerdict, TopologyVerdict, Verdict};
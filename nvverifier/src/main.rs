@@ -0,0 +1,97 @@
+//! `nvverifier` daemon: serves the verification endpoints on the configured address.
+
+use std::{net::SocketAddr, path::PathBuf, process::ExitCode, sync::Arc};
+
+use clap::Parser;
+use nvverifier::{router, ServiceConfig, ServiceState};
+use remote_attestation_verifier::DeviceKind;
+use tokio::net::TcpListener;
+use tracing::{error, info, warn};
+use tracing_subscriber::{
+    filter::{LevelFilter, Targets},
+    layer::SubscriberExt,
+    util::SubscriberInitExt,
+};
+
+/// HTTP service verifying NVIDIA attestation evidence bundles for relying parties
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    /// TOML or JSON configuration file
+    #[arg(long, short)]
+    config: Option<PathBuf>,
+    /// Address to listen on, overriding the configuration file
+    #[arg(long)]
+    listen: Option<SocketAddr>,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let args = Args::parse();
+    init_tracing();
+    match run(args).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!(level = "nvverifier", "{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Installs a `fmt` subscriber, filtered by the `RUST_LOG` directives, or at the `info`
+/// level if `RUST_LOG` is unset or invalid.
+fn init_tracing() {
+    let directives = std::env::var("RUST_LOG").ok();
+    let filter = directives.as_deref().map(str::parse::<Targets>);
+    tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+        .with(match &filter {
+            Some(Ok(targets)) => targets.clone(),
+            _ => Targets::new().with_default(LevelFilter::INFO),
+        })
+        .init();
+    if let Some(Err(e)) = filter {
+        warn!(
+            level = "nvverifier",
+            "Ignoring invalid RUST_LOG directives: {e}"
+        );
+    }
+}
+
+async fn run(args: Args) -> Result<(), Box<dyn std::error::Error>> {
+    let mut config = match &args.config {
+        Some(path) => ServiceConfig::from_file(path)?,
+        None => ServiceConfig::default(),
+    };
+    if args.listen.is_some() {
+        config.listen = args.listen;
+    }
+    let state = ServiceState::from_config(&config)?;
+    info!(
+        level = "nvverifier",
+        "GPU remote attestation configuration: {}",
+        state.gpu_options.effective_config(DeviceKind::Gpu)
+    );
+    info!(
+        level = "nvverifier",
+        "Switch remote attestation configuration: {}",
+        state.switch_options.effective_config(DeviceKind::Switch)
+    );
+    let router = router(Arc::new(state));
+    #[cfg(feature = "metrics")]
+    let router = router.merge(nvverifier::metrics::metrics_router(
+        nvverifier::metrics::install_recorder()?,
+    ));
+    let listener = TcpListener::bind(config.listen_address()).await?;
+    info!(
+        level = "nvverifier",
+        "Listening on http://{}",
+        listener.local_addr()?
+    );
+    axum::serve(listener, router)
+        .with_graceful_shutdown(async {
+            tokio::signal::ctrl_c().await.ok();
+        })
+        .await?;
+    Ok(())
+}
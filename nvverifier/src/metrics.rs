@@ -0,0 +1,55 @@
+use axum::{
+    extract::State,
+    http::{header::CONTENT_TYPE, HeaderValue},
+    response::IntoResponse,
+    routing::get,
+    Router,
+};
+use metrics_exporter_prometheus::{BuildError, Matcher, PrometheusBuilder, PrometheusHandle};
+
+/// Path of the Prometheus metrics endpoint.
+pub const METRICS_PATH: &str = "/metrics";
+
+/// Content type of the Prometheus text exposition format.
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Histogram buckets of the latency metrics, in seconds, from local NSCQ calls to
+/// slow NRAS requests.
+const LATENCY_BUCKETS_SECONDS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0,
+];
+
+/// Installs a Prometheus recorder as the global metrics recorder, and describes the
+/// attestation and topology metrics.
+///
+/// # Errors
+///
+/// Returns an error if a global recorder is already installed.
+pub fn install_recorder() -> Result<PrometheusHandle, BuildError> {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Suffix("_seconds".to_string()),
+            LATENCY_BUCKETS_SECONDS,
+        )?
+        .install_recorder()?;
+    remote_attestation_verifier::metrics::describe_metrics();
+    topology::metrics::describe_metrics();
+    Ok(handle)
+}
+
+/// Creates a router serving the metrics of `handle` on [`METRICS_PATH`].
+pub fn metrics_router(handle: PrometheusHandle) -> Router {
+    Router::new()
+        .route(METRICS_PATH, get(render))
+        .with_state(handle)
+}
+
+async fn render(State(handle): State<PrometheusHandle>) -> impl IntoResponse {
+    (
+        [(
+            CONTENT_TYPE,
+            HeaderValue::from_static(PROMETHEUS_CONTENT_TYPE),
+        )],
+        handle.render(),
+    )
+}
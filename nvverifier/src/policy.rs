@@ -0,0 +1,227 @@
+use std::fmt;
+
+use remote_attestation_verifier::{constants::MAX_NONCE_CLOCK_SKEW, EvidenceBundle};
+use serde::{Deserialize, Serialize};
+
+/// Local checks applied to an evidence bundle before its evidence is sent to NRAS.
+///
+/// A bundle violating the policy fails verification without reaching NRAS.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Policy {
+    /// Maximum age of a bundle, from its collection time, in seconds
+    pub max_bundle_age_secs: Option<u64>,
+    /// How far in the future a collection time may be, in seconds, to tolerate clock
+    /// differences between the node and the verifier. Only checked with
+    /// `max_bundle_age_secs`.
+    #[serde(default = "default_max_clock_skew_secs")]
+    pub max_clock_skew_secs: u64,
+    /// Exact number of GPUs a bundle must hold, when GPU evidence is verified
+    pub gpus: Option<usize>,
+    /// Exact number of `NVSwitch` devices a bundle must hold, when switch evidence is verified
+    pub switches: Option<usize>,
+    /// Whether the relying party must provide the nonce it expects the bundle to carry
+    #[serde(default)]
+    pub require_nonce: bool,
+}
+
+/// A policy check failed by an evidence bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "violation", rename_all = "snake_case")]
+pub enum PolicyViolation {
+    /// The bundle was collected longer ago than allowed
+    StaleBundle { age_secs: u64, max_age_secs: u64 },
+    /// The bundle claims to have been collected in the future
+    FutureBundle { collected_at: u64, now: u64 },
+    /// The bundle does not hold the expected number of GPUs
+    GpuCount { expected: usize, actual: usize },
+    /// The bundle does not hold the expected number of `NVSwitch` devices
+    SwitchCount { expected: usize, actual: usize },
+    /// The relying party did not provide the expected nonce
+    MissingNonce,
+    /// The bundle was collected with another nonce than the expected one
+    NonceMismatch { expected: String, actual: String },
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self {
+            max_bundle_age_secs: None,
+            max_clock_skew_secs: default_max_clock_skew_secs(),
+            gpus: None,
+            switches: None,
+            require_nonce: false,
+        }
+    }
+}
+
+const fn default_max_clock_skew_secs() -> u64 {
+    MAX_NONCE_CLOCK_SKEW.as_secs()
+}
+
+impl fmt::Display for PolicyViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::StaleBundle {
+                age_secs,
+                max_age_secs,
+            } => write!(
+                f,
+                "Bundle is {age_secs}s old, the maximum age is {max_age_secs}s"
+            ),
+            Self::FutureBundle { collected_at, now } => write!(
+                f,
+                "Bundle collection time {collected_at} is later than the current time {now}"
+            ),
+            Self::GpuCount { expected, actual } => {
+                write!(f, "Expected {expected} GPUs, bundle holds {actual}")
+            }
+            Self::SwitchCount { expected, actual } => {
+                write!(f, "Expected {expected} switches, bundle holds {actual}")
+            }
+            Self::MissingNonce => write!(f, "No expected nonce was provided"),
+            Self::NonceMismatch { expected, actual } => {
+                write!(f, "Expected nonce {expected}, bundle nonce is {actual}")
+            }
+        }
+    }
+}
+
+impl Policy {
+    /// Checks a bundle against the policy, and returns every violation found.
+    ///
+    /// # Arguments
+    ///
+    /// * `bundle` - The evidence bundle to check
+    /// * `expected_nonce` - The hex encoded nonce the relying party expects, if any
+    /// * `check_gpus` - Whether the GPU evidence of the bundle is verified
+    /// * `check_switches` - Whether the switch evidence of the bundle is verified
+    /// * `now` - The current time, in seconds since the Unix epoch
+    #[must_use]
+    pub fn check(
+        &self,
+        bundle: &EvidenceBundle,
+        expected_nonce: Option<&str>,
+        check_gpus: bool,
+        check_switches: bool,
+        now: u64,
+    ) -> Vec<PolicyViolation> {
+        let mut violations = Vec::new();
+        if let Some(max_age_secs) = self.max_bundle_age_secs {
+            if bundle.collected_at > now.saturating_add(self.max_clock_skew_secs) {
+                violations.push(PolicyViolation::FutureBundle {
+                    collected_at: bundle.collected_at,
+                    now,
+                });
+            } else if now.saturating_sub(bundle.collected_at) > max_age_secs {
+                violations.push(PolicyViolation::StaleBundle {
+                    age_secs: now - bundle.collected_at,
+                    max_age_secs,
+                });
+            }
+        }
+        match self.gpus {
+            Some(expected) if check_gpus && bundle.gpu_evidence.len() != expected => {
+                violations.push(PolicyViolation::GpuCount {
+                    expected,
+                    actual: bundle.gpu_evidence.len(),
+                });
+            }
+            _ => {}
+        }
+        match self.switches {
+            Some(expected) if check_switches && bundle.switch_evidence.len() != expected => {
+                violations.push(PolicyViolation::SwitchCount {
+                    expected,
+                    actual: bundle.switch_evidence.len(),
+                });
+            }
+            _ => {}
+        }
+        match expected_nonce {
+            Some(expected) if !expected.eq_ignore_ascii_case(&bundle.nonce) => {
+                violations.push(PolicyViolation::NonceMismatch {
+                    expected: expected.to_ascii_lowercase(),
+                    actual: bundle.nonce.clone(),
+                });
+            }
+            None if self.require_nonce => violations.push(PolicyViolation::MissingNonce),
+            _ => {}
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use remote_attestation_verifier::{DeviceEvidence, HostMetadata};
+
+    use super::*;
+
+    fn bundle(gpus: usize, nonce: &[u8; 32]) -> EvidenceBundle {
+        let evidence = DeviceEvidence {
+            evidence: "AAAA".to_string(),
+            certificate: "AAAA".to_string(),
+        };
+        EvidenceBundle::new(
+            nonce,
+            vec![evidence; gpus],
+            Vec::new(),
+            HostMetadata::default(),
+        )
+    }
+
+    #[test]
+    fn test_policy_check() {
+        let nonce = [7u8; 32];
+        let bundle = bundle(8, &nonce);
+        let now = bundle.collected_at;
+        let policy = Policy {
+            max_bundle_age_secs: Some(60),
+            max_clock_skew_secs: 5,
+            gpus: Some(8),
+            switches: Some(4),
+            require_nonce: true,
+        };
+
+        // Switch counts are only enforced when switch evidence is verified
+        let nonce = hex::encode(nonce);
+        assert!(policy
+            .check(&bundle, Some(&nonce.to_uppercase()), true, false, now + 60)
+            .is_empty());
+        assert_eq!(
+            policy.check(&bundle, None, true, true, now + 61),
+            vec![
+                PolicyViolation::StaleBundle {
+                    age_secs: 61,
+                    max_age_secs: 60
+                },
+                PolicyViolation::SwitchCount {
+                    expected: 4,
+                    actual: 0
+                },
+                PolicyViolation::MissingNonce,
+            ]
+        );
+        // Collection times slightly in the future are tolerated
+        assert!(policy
+            .check(&bundle, Some(&nonce), true, false, now - 5)
+            .is_empty());
+        assert_eq!(
+            policy.check(&bundle, Some(&"00".repeat(32)), true, false, now - 6),
+            vec![
+                PolicyViolation::FutureBundle {
+                    collected_at: now,
+                    now: now - 6
+                },
+                PolicyViolation::NonceMismatch {
+                    expected: "00".repeat(32),
+                    actual: nonce,
+                },
+            ]
+        );
+        assert!(Policy::default()
+            .check(&bundle, None, true, true, 0)
+            .is_empty());
+    }
+}
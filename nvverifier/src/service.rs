@@ -0,0 +1,393 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::{
+        header::{ACCEPT, CONTENT_TYPE},
+        HeaderMap,
+    },
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use remote_attestation_verifier::{
+    audit::{AuditLog, FileAuditLog},
+    cache::{AttestationCache, InMemoryAttestationCache},
+    check_bundle_topology,
+    constants::{REMOTE_GPU_VERIFIER_SERVICE_URL, REMOTE_NVSWITCH_VERIFIER_SERVICE_URL},
+    utils::{accepts_media_type, unix_now},
+    verify_gpu_attestation, verify_nvswitch_attestation, AttestError, AttestRemoteOptions,
+    BundleEncoding, DeviceKind, EvidenceBundle, Result as AttestResult,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tracing::{info, instrument, warn};
+
+use crate::{
+    config::{build_options, ServiceConfig},
+    error::ServiceError,
+    policy::Policy,
+    verdict::{DeviceVerdict, TopologyVerdict, Verdict},
+};
+
+/// Path of the GPU attestation endpoint.
+pub const GPU_ATTEST_PATH: &str = "/v1/attest/gpu";
+
+/// Path of the `NVSwitch` attestation endpoint.
+pub const SWITCH_ATTEST_PATH: &str = "/v1/attest/switch";
+
+/// Path of the protected `PCIe` (`PPCIe`) attestation endpoint.
+pub const PPCIE_ATTEST_PATH: &str = "/v1/attest/ppcie";
+
+/// Path of the health check endpoint.
+pub const HEALTHZ_PATH: &str = "/healthz";
+
+/// Media type of the unsigned EAT Attestation Results (EAR) answered to requests
+/// accepting it.
+pub const EAR_MEDIA_TYPE: &str = "application/eat-ucs+json";
+
+/// The evidence verified by an attestation endpoint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// GPU evidence only
+    Gpu,
+    /// `NVSwitch` evidence only
+    Switch,
+    /// GPU and `NVSwitch` evidence, and the topology they report
+    Ppcie,
+}
+
+impl Scope {
+    /// Returns `true` if the GPU evidence of a bundle is verified.
+    #[must_use]
+    pub const fn verifies_gpus(self) -> bool {
+        matches!(self, Self::Gpu | Self::Ppcie)
+    }
+
+    /// Returns `true` if the `NVSwitch` evidence of a bundle is verified.
+    #[must_use]
+    pub const fn verifies_switches(self) -> bool {
+        matches!(self, Self::Switch | Self::Ppcie)
+    }
+}
+
+/// Query parameters of the attestation endpoints.
+#[derive(Debug, Default, Deserialize)]
+pub struct AttestQuery {
+    /// Hex encoded nonce the relying party expects the bundle to carry
+    pub nonce: Option<String>,
+}
+
+/// Options and policy shared by the request handlers.
+#[derive(Debug, Clone)]
+pub struct ServiceState {
+    pub gpu_options: AttestRemoteOptions,
+    pub switch_options: AttestRemoteOptions,
+    pub policy: Policy,
+    /// Ed25519 public keys of the agents allowed to sign bundles. If not empty, unsigned
+    /// bundles and bundles signed by another key are rejected.
+    pub trusted_agent_keys: Vec<Vec<u8>>,
+}
+
+impl ServiceState {
+    /// Builds the state of the service from its configuration, opening the audit log
+    /// and creating the result cache if configured.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::AuditLog` - If the audit log cannot be opened
+    /// * `AttestError::ConfigError` - If a service key file cannot be read, or a trusted
+    ///   agent key is not a hex encoded Ed25519 public key
+    /// * `AttestError::InvalidOption` - If a remote attestation option fails validation
+    pub fn from_config(config: &ServiceConfig) -> AttestResult<Self> {
+        let cache = config.cache_max_entries.map(|max_entries| {
+            Arc::new(InMemoryAttestationCache::new(max_entries)) as Arc<dyn AttestationCache>
+        });
+        let audit_log = config
+            .audit_log
+            .as_ref()
+            .map(|path| FileAuditLog::open(path).map(|log| Arc::new(log) as Arc<dyn AuditLog>))
+            .transpose()?;
+        Ok(Self {
+            gpu_options: build_options(
+                config,
+                DeviceKind::Gpu,
+                cache.as_ref(),
+                audit_log.as_ref(),
+            )?,
+            switch_options: build_options(
+                config,
+                DeviceKind::Switch,
+                cache.as_ref(),
+                audit_log.as_ref(),
+            )?,
+            policy: config.policy.clone(),
+            trusted_agent_keys: config.trusted_agent_keys()?,
+        })
+    }
+}
+
+/// Creates the router of the verifier service.
+pub fn router(state: Arc<ServiceState>) -> Router {
+    Router::new()
+        .route(GPU_ATTEST_PATH, post(attest_gpu))
+        .route(SWITCH_ATTEST_PATH, post(attest_switch))
+        .route(PPCIE_ATTEST_PATH, post(attest_ppcie))
+        .route(HEALTHZ_PATH, get(healthz))
+        .with_state(state)
+}
+
+async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+async fn attest_gpu(
+    State(state): State<Arc<ServiceState>>,
+    Query(query): Query<AttestQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ServiceError> {
+    let verdict = attest(&state, Scope::Gpu, &query, &headers, &body).await?;
+    respond(&state, &headers, verdict).await
+}
+
+async fn attest_switch(
+    State(state): State<Arc<ServiceState>>,
+    Query(query): Query<AttestQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ServiceError> {
+    let verdict = attest(&state, Scope::Switch, &query, &headers, &body).await?;
+    respond(&state, &headers, verdict).await
+}
+
+async fn attest_ppcie(
+    State(state): State<Arc<ServiceState>>,
+    Query(query): Query<AttestQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, ServiceError> {
+    let verdict = attest(&state, Scope::Ppcie, &query, &headers, &body).await?;
+    respond(&state, &headers, verdict).await
+}
+
+/// Decodes an evidence bundle and appraises it, see [`appraise`].
+#[instrument(level = "info", name = "verifier_attest", skip_all, fields(scope = ?scope))]
+async fn attest(
+    state: &ServiceState,
+    scope: Scope,
+    query: &AttestQuery,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<Json<Verdict>, ServiceError> {
+    let bundle = decode_bundle(headers, body)?;
+    appraise(state, scope, &bundle, query.nonce.as_deref())
+        .await
+        .map(Json)
+}
+
+/// Applies the policy and, for [`Scope::Ppcie`], the topology checks to a bundle, then
+/// verifies its evidence in scope against NRAS.
+///
+/// NRAS is only queried if every local check passed. A bundle failing a check is not an
+/// error: it is reported in the returned verdict.
+///
+/// # Errors
+///
+/// * `ServiceError::MissingEvidence` - If the bundle holds no evidence of a kind in scope
+/// * `ServiceError::InvalidNonce` - If `expected_nonce` is not a hex encoded 32-byte value
+/// * `ServiceError::Attest` - If the evidence cannot be decoded, or NRAS cannot verify it
+pub async fn appraise(
+    state: &ServiceState,
+    scope: Scope,
+    bundle: &EvidenceBundle,
+    expected_nonce: Option<&str>,
+) -> Result<Verdict, ServiceError> {
+    if scope.verifies_gpus() && bundle.gpu_evidence.is_empty() {
+        return Err(ServiceError::MissingEvidence("GPU"));
+    }
+    if scope.verifies_switches() && bundle.switch_evidence.is_empty() {
+        return Err(ServiceError::MissingEvidence("switch"));
+    }
+    if let Some(nonce) = expected_nonce {
+        check_nonce(nonce)?;
+    }
+    check_signer(&state.trusted_agent_keys, bundle)?;
+
+    let mut verdict = Verdict {
+        attestation_passed: false,
+        bundle_digest: bundle.digest.clone(),
+        nonce: bundle.nonce.clone(),
+        collected_at: bundle.collected_at,
+        policy_violations: state.policy.check(
+            bundle,
+            expected_nonce,
+            scope.verifies_gpus(),
+            scope.verifies_switches(),
+            unix_now(),
+        ),
+        topology: (scope == Scope::Ppcie)
+            .then(|| check_topology(bundle))
+            .transpose()?,
+        gpu: None,
+        switch: None,
+    };
+    let local_checks_passed = verdict.policy_violations.is_empty()
+        && verdict
+            .topology
+            .as_ref()
+            .is_none_or(TopologyVerdict::passed);
+    if local_checks_passed {
+        if scope.verifies_gpus() {
+            let (attestation_passed, response) = verify_gpu_attestation(
+                &bundle.gpu_evidence,
+                &bundle.nonce,
+                state.gpu_options.clone(),
+            )
+            .await?;
+            verdict.gpu = Some(DeviceVerdict {
+                attestation_passed,
+                devices: bundle.gpu_evidence.len(),
+                response,
+            });
+        }
+        if scope.verifies_switches() {
+            let (attestation_passed, response) = verify_nvswitch_attestation(
+                &bundle.switch_evidence,
+                &bundle.nonce,
+                state.switch_options.clone(),
+            )
+            .await?;
+            verdict.switch = Some(DeviceVerdict {
+                attestation_passed,
+                devices: bundle.switch_evidence.len(),
+                response,
+            });
+        }
+    } else {
+        warn!(
+            level = "verifier_attest",
+            bundle_digest = %verdict.bundle_digest,
+            policy_violations = ?verdict.policy_violations,
+            topology = ?verdict.topology,
+            "Bundle failed local checks, skipping NRAS verification"
+        );
+    }
+    verdict.conclude();
+    info!(
+        level = "verifier_attest",
+        bundle_digest = %verdict.bundle_digest,
+        nonce = %verdict.nonce,
+        attestation_passed = verdict.attestation_passed,
+        "Attestation request verified"
+    );
+    Ok(verdict)
+}
+
+/// Answers with the verdict, or with the verdict as an EAR if the `Accept` header
+/// accepts [`EAR_MEDIA_TYPE`].
+async fn respond(
+    state: &ServiceState,
+    headers: &HeaderMap,
+    Json(verdict): Json<Verdict>,
+) -> Result<Response, ServiceError> {
+    let accepts_ear = accepts_media_type(
+        headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|value| value.to_str().ok()),
+        EAR_MEDIA_TYPE,
+    );
+    if !accepts_ear {
+        return Ok(Json(verdict).into_response());
+    }
+    let ear = verdict
+        .ear(
+            state
+                .gpu_options
+                .verifier_url
+                .as_deref()
+                .unwrap_or(REMOTE_GPU_VERIFIER_SERVICE_URL),
+            state
+                .switch_options
+                .verifier_url
+                .as_deref()
+                .unwrap_or(REMOTE_NVSWITCH_VERIFIER_SERVICE_URL),
+        )
+        .await?;
+    Ok(([(CONTENT_TYPE, EAR_MEDIA_TYPE)], Json(ear)).into_response())
+}
+
+/// Decodes a bundle, using the encoding given by the `Content-Type` header, or detecting
+/// it if the header is absent.
+fn decode_bundle(headers: &HeaderMap, body: &[u8]) -> Result<EvidenceBundle, ServiceError> {
+    let content_type = headers
+        .get(CONTENT_TYPE)
+        .map(|value| {
+            value
+                .to_str()
+                .unwrap_or_default()
+                .split(';')
+                .next()
+                .unwrap_or_default()
+                .trim()
+                .to_ascii_lowercase()
+        })
+        .filter(|content_type| !content_type.is_empty());
+    let encoding = match content_type.as_deref() {
+        None => BundleEncoding::detect(body),
+        Some("application/json") => BundleEncoding::Json,
+        Some("application/cbor") => BundleEncoding::Cbor,
+        Some(other) => return Err(ServiceError::UnsupportedMediaType(other.to_string())),
+    };
+    Ok(EvidenceBundle::decode(body, encoding)?)
+}
+
+/// Checks that an expected nonce is a hex encoded 32-byte value.
+fn check_nonce(nonce: &str) -> Result<(), ServiceError> {
+    let bytes = hex::decode(nonce).map_err(|e| ServiceError::InvalidNonce(e.to_string()))?;
+    if bytes.len() != 32 {
+        return Err(ServiceError::InvalidNonce(format!(
+            "expected 32 bytes, got {}",
+            bytes.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Checks that a bundle is signed by one of `trusted_agent_keys`, if any.
+fn check_signer(trusted_agent_keys: &[Vec<u8>], bundle: &EvidenceBundle) -> AttestResult<()> {
+    if trusted_agent_keys.is_empty() {
+        return Ok(());
+    }
+    let Some(signature) = &bundle.signature else {
+        return Err(AttestError::InvalidEvidenceBundle(
+            "Bundle is not signed by an agent".to_string(),
+        ));
+    };
+    let signer = trusted_agent_keys
+        .iter()
+        .find(|key| signature.public_key.eq_ignore_ascii_case(&hex::encode(key)))
+        .ok_or_else(|| {
+            AttestError::InvalidEvidenceBundle(format!(
+                "Bundle is signed by {}, which is not a trusted agent key",
+                signature.public_key
+            ))
+        })?;
+    bundle.verify_signer(signer)
+}
+
+/// Runs the topology checks on the reports of a bundle.
+///
+/// A failed check is reported in the returned verdict rather than as an error.
+fn check_topology(bundle: &EvidenceBundle) -> Result<TopologyVerdict, AttestError> {
+    let report = check_bundle_topology(bundle)?;
+    Ok(TopologyVerdict {
+        gpu_topology_passed: report.gpu_topology_passed,
+        switch_topology_passed: report.switch_topology_passed,
+        switch_pdis: report.switch_pdis,
+        error: report.error.map(|e| e.to_string()),
+    })
+}
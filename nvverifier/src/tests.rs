@@ -0,0 +1,434 @@
+use std::sync::Arc;
+
+use remote_attestation_verifier::{
+    test_support::{synthetic_bundle, MockFailure, MockNras},
+    BundleEncoding, DeviceEvidence, EvidenceBundle, HostMetadata, NvSwitchEvidence,
+};
+use reqwest::{
+    header::{ACCEPT, CONTENT_TYPE},
+    StatusCode,
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{Ed25519KeyPair, KeyPair},
+};
+use serde_json::Value;
+use tokio::{net::TcpListener, task::JoinHandle};
+
+use crate::{
+    policy::Policy,
+    router,
+    service::{
+        EAR_MEDIA_TYPE, GPU_ATTEST_PATH, HEALTHZ_PATH, PPCIE_ATTEST_PATH, SWITCH_ATTEST_PATH,
+    },
+    ServiceState,
+};
+
+/// Nonce of the bundles of the tests.
+const NONCE: [u8; 32] = [0x5c; 32];
+
+/// Re-creates a bundle with the given evidence, keeping its nonce.
+fn with_evidence(
+    bundle: &EvidenceBundle,
+    gpu_evidence: Vec<DeviceEvidence>,
+    switch_evidence: Vec<NvSwitchEvidence>,
+) -> EvidenceBundle {
+    EvidenceBundle::new(
+        &bundle.nonce_bytes().unwrap(),
+        gpu_evidence,
+        switch_evidence,
+        HostMetadata::default(),
+    )
+}
+
+/// Serves the verifier on a random local port, pointing at the mock NRAS.
+async fn start_service(nras: &MockNras, policy: Policy) -> (String, JoinHandle<()>) {
+    start_service_with_keys(nras, policy, Vec::new()).await
+}
+
+/// Serves the verifier on a random local port, only accepting bundles signed by one of
+/// `trusted_agent_keys` if not empty.
+async fn start_service_with_keys(
+    nras: &MockNras,
+    policy: Policy,
+    trusted_agent_keys: Vec<Vec<u8>>,
+) -> (String, JoinHandle<()>) {
+    let state = ServiceState {
+        gpu_options: nras.gpu_options(),
+        switch_options: nras.switch_options(),
+        policy,
+        trusted_agent_keys,
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let task = tokio::spawn(async move {
+        axum::serve(listener, router(Arc::new(state))).await.ok();
+    });
+    (url, task)
+}
+
+/// Posts an encoded bundle, and returns the response status and JSON body.
+async fn post(
+    url: &str,
+    content_type: Option<&str>,
+    bundle: &EvidenceBundle,
+    encoding: BundleEncoding,
+) -> (StatusCode, Value) {
+    let mut request = reqwest::Client::new()
+        .post(url)
+        .body(bundle.encode(encoding).unwrap());
+    if let Some(content_type) = content_type {
+        request = request.header(CONTENT_TYPE, content_type);
+    }
+    let response = request.send().await.unwrap();
+    (response.status(), response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn test_attestation_endpoints() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (url, task) = start_service(&nras, Policy::default()).await;
+    let bundle = synthetic_bundle(NONCE);
+
+    let health: Value = reqwest::get(format!("{url}{HEALTHZ_PATH}"))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(health["status"], "ok");
+
+    let ppcie_url = format!("{url}{PPCIE_ATTEST_PATH}?nonce={}", bundle.nonce);
+    let (status, verdict) = post(
+        &ppcie_url,
+        Some("application/cbor"),
+        &bundle,
+        BundleEncoding::Cbor,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{verdict}");
+    assert_eq!(verdict["attestation_passed"], true, "{verdict}");
+    assert_eq!(verdict["bundle_digest"], bundle.digest);
+    assert_eq!(verdict["topology"]["gpu_topology_passed"], true);
+    assert_eq!(verdict["topology"]["switch_topology_passed"], true);
+    assert_eq!(
+        verdict["topology"]["switch_pdis"].as_array().unwrap().len(),
+        4
+    );
+    assert_eq!(verdict["gpu"]["devices"], 8);
+    assert_eq!(verdict["switch"]["devices"], 4);
+
+    // The encoding is detected when no content type is given
+    let (status, verdict) = post(
+        &format!("{url}{GPU_ATTEST_PATH}"),
+        None,
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["attestation_passed"], true);
+    assert!(verdict.get("switch").is_none());
+    assert!(verdict.get("topology").is_none());
+
+    nras.set_attestation_result(false);
+    let (status, verdict) = post(
+        &format!("{url}{SWITCH_ATTEST_PATH}"),
+        Some("application/json"),
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["attestation_passed"], false);
+    assert_eq!(verdict["switch"]["attestation_passed"], false);
+    assert_eq!(nras.requests().len(), 4);
+
+    // A PPCIe bundle missing a GPU fails the topology check without reaching NRAS
+    nras.set_attestation_result(true);
+    let incomplete = with_evidence(
+        &bundle,
+        bundle.gpu_evidence[1..].to_vec(),
+        bundle.switch_evidence.clone(),
+    );
+    let (status, verdict) = post(
+        &format!("{url}{PPCIE_ATTEST_PATH}"),
+        None,
+        &incomplete,
+        BundleEncoding::Cbor,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["attestation_passed"], false);
+    assert_eq!(verdict["topology"]["gpu_topology_passed"], false);
+    assert!(verdict.get("gpu").is_none());
+    assert_eq!(nras.requests().len(), 4);
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_ear_response() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (url, task) = start_service(&nras, Policy::default()).await;
+    let bundle = synthetic_bundle(NONCE);
+    let request = |bundle: &EvidenceBundle| {
+        reqwest::Client::new()
+            .post(format!("{url}{PPCIE_ATTEST_PATH}"))
+            .header(CONTENT_TYPE, "application/json")
+            .header(ACCEPT, format!("application/json;q=0.5, {EAR_MEDIA_TYPE}"))
+            .body(bundle.encode(BundleEncoding::Json).unwrap())
+            .send()
+    };
+
+    let response = request(&bundle).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CONTENT_TYPE], EAR_MEDIA_TYPE);
+    let ear: Value = response.json().await.unwrap();
+    assert_eq!(ear["eat_profile"], "tag:github.com,2023:veraison/ear");
+    assert_eq!(ear["eat_nonce"], bundle.nonce);
+    let submods = ear["submods"].as_object().unwrap();
+    assert_eq!(submods.len(), 2 + 8 + 4, "{ear}");
+    assert!(submods
+        .values()
+        .all(|appraisal| appraisal["ear.status"] == "affirming"));
+    assert_eq!(
+        submods["GPU-0"]["ear.trustworthiness-vector"]["hardware"],
+        2
+    );
+
+    // A failed topology check is contraindicated, and no device is appraised
+    let incomplete = with_evidence(
+        &bundle,
+        bundle.gpu_evidence[1..].to_vec(),
+        bundle.switch_evidence.clone(),
+    );
+    let ear: Value = request(&incomplete).await.unwrap().json().await.unwrap();
+    assert_eq!(ear["submods"]["topology"]["ear.status"], "contraindicated");
+    assert_eq!(ear["submods"]["policy"]["ear.status"], "affirming");
+    assert_eq!(ear["submods"].as_object().unwrap().len(), 2);
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_policy_violations() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let policy = Policy {
+        max_bundle_age_secs: Some(300),
+        gpus: Some(8),
+        switches: Some(4),
+        require_nonce: true,
+        ..Policy::default()
+    };
+    let (url, task) = start_service(&nras, policy).await;
+    let bundle = synthetic_bundle(NONCE);
+    let gpu_url = format!("{url}{GPU_ATTEST_PATH}");
+
+    let (status, verdict) = post(&gpu_url, None, &bundle, BundleEncoding::Json).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["attestation_passed"], false);
+    assert_eq!(
+        verdict["policy_violations"][0]["violation"],
+        "missing_nonce"
+    );
+
+    let incomplete = with_evidence(&bundle, bundle.gpu_evidence[1..].to_vec(), Vec::new());
+    let url_with_nonce = format!("{gpu_url}?nonce={}", "ab".repeat(32));
+    let (status, verdict) = post(&url_with_nonce, None, &incomplete, BundleEncoding::Json).await;
+    assert_eq!(status, StatusCode::OK);
+    let violations: Vec<&str> = verdict["policy_violations"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|violation| violation["violation"].as_str().unwrap())
+        .collect();
+    assert_eq!(violations, ["gpu_count", "nonce_mismatch"]);
+    assert_eq!(verdict["policy_violations"][0]["actual"], 7);
+    assert!(nras.requests().is_empty());
+
+    let url_with_nonce = format!("{gpu_url}?nonce={}", bundle.nonce);
+    let (status, verdict) = post(&url_with_nonce, None, &bundle, BundleEncoding::Json).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["attestation_passed"], true, "{verdict}");
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_request_errors() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (url, task) = start_service(&nras, Policy::default()).await;
+    let bundle = synthetic_bundle(NONCE);
+    let gpu_only = with_evidence(&bundle, bundle.gpu_evidence.clone(), Vec::new());
+
+    let (status, error) = post(
+        &format!("{url}{SWITCH_ATTEST_PATH}"),
+        None,
+        &gpu_only,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["kind"], "missing_evidence");
+
+    let (status, error) = post(
+        &format!("{url}{GPU_ATTEST_PATH}"),
+        Some("text/plain"),
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(error["kind"], "unsupported_media_type");
+
+    let (status, error) = post(
+        &format!("{url}{GPU_ATTEST_PATH}?nonce=xyz"),
+        None,
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["kind"], "invalid_nonce");
+
+    let mut tampered = bundle.clone();
+    tampered.collected_at += 1;
+    let (status, error) = post(
+        &format!("{url}{GPU_ATTEST_PATH}"),
+        None,
+        &tampered,
+        BundleEncoding::Cbor,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["kind"], "evidence_decode");
+
+    nras.push_failure(MockFailure::Status {
+        status: 503,
+        body: String::new(),
+    });
+    let (status, error) = post(
+        &format!("{url}{GPU_ATTEST_PATH}"),
+        None,
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(error["kind"], "http_status");
+    assert_eq!(error["retryable"], true);
+
+    nras.push_failure(MockFailure::WrongKey);
+    let (status, error) = post(
+        &format!("{url}{GPU_ATTEST_PATH}"),
+        None,
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(error["kind"], "token_signature");
+    assert_eq!(error["retryable"], false);
+
+    task.abort();
+}
+
+#[tokio::test]
+async fn test_trusted_agent_keys() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    let agent_key = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+    let mut bundle = synthetic_bundle(NONCE);
+    bundle.sign(&agent_key);
+    let (url, task) = start_service_with_keys(
+        &nras,
+        Policy::default(),
+        vec![vec![0x11; 32], agent_key.public_key().as_ref().to_vec()],
+    )
+    .await;
+    let gpu_url = format!("{url}{GPU_ATTEST_PATH}");
+
+    let (status, verdict) = post(&gpu_url, None, &bundle, BundleEncoding::Json).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(verdict["attestation_passed"], true);
+
+    let mut unsigned = bundle.clone();
+    unsigned.signature = None;
+    let (status, error) = post(&gpu_url, None, &unsigned, BundleEncoding::Json).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(error["kind"], "evidence_decode");
+    task.abort();
+
+    let (url, task) = start_service_with_keys(&nras, Policy::default(), vec![vec![0x11; 32]]).await;
+    let (status, error) = post(
+        &format!("{url}{GPU_ATTEST_PATH}"),
+        None,
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(error["error"]
+        .as_str()
+        .unwrap()
+        .contains("not a trusted agent key"));
+    task.abort();
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn test_metrics_endpoint() {
+    use metrics_exporter_prometheus::PrometheusBuilder;
+
+    use crate::metrics::{metrics_router, METRICS_PATH};
+
+    // The test runtime is single-threaded, so the service records through the
+    // thread-local recorder
+    let recorder = PrometheusBuilder::new().build_recorder();
+    let handle = recorder.handle();
+    let _guard = ::metrics::set_default_local_recorder(&recorder);
+
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let state = ServiceState {
+        gpu_options: nras.gpu_options(),
+        switch_options: nras.switch_options(),
+        policy: Policy::default(),
+        trusted_agent_keys: Vec::new(),
+    };
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let app = router(Arc::new(state)).merge(metrics_router(handle));
+    let task = tokio::spawn(async move {
+        axum::serve(listener, app).await.ok();
+    });
+
+    let bundle = synthetic_bundle(NONCE);
+    let (status, verdict) = post(
+        &format!("{url}{PPCIE_ATTEST_PATH}"),
+        None,
+        &bundle,
+        BundleEncoding::Json,
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK, "{verdict}");
+
+    let response = reqwest::get(format!("{url}{METRICS_PATH}")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/plain"));
+    let body = response.text().await.unwrap();
+    for line in [
+        r#"nvidia_attestation_topology_checks_total{check="gpu",result="passed"} 1"#,
+        r#"nvidia_attestation_topology_checks_total{check="switch",result="passed"} 1"#,
+        r#"nvidia_attestation_nras_requests_total{arch="HOPPER",status="200"} 1"#,
+        r#"nvidia_attestation_nras_requests_total{arch="LS10",status="200"} 1"#,
+        r#"nvidia_attestation_attestations_total{arch="HOPPER",result="passed"} 1"#,
+        r#"nvidia_attestation_jwks_fetches_total{outcome="success"} 2"#,
+    ] {
+        assert!(body.contains(line), "Missing `{line}` in:\n{body}");
+    }
+    task.abort();
+}
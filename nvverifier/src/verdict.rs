@@ -0,0 +1,101 @@
+use remote_attestation_verifier::{Ear, Result as AttestResult};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::policy::PolicyViolation;
+
+/// The verdict of an attestation request, returned with `200 OK`.
+#[derive(Debug, Clone, Serialize)]
+pub struct Verdict {
+    /// Whether the bundle passed the policy, the topology checks if any, and NRAS
+    pub attestation_passed: bool,
+    /// Digest of the verified bundle
+    pub bundle_digest: String,
+    /// Hex encoded nonce of the verified bundle
+    pub nonce: String,
+    /// Collection time of the bundle, in seconds since the Unix epoch
+    pub collected_at: u64,
+    /// Policy checks failed by the bundle. NRAS is not queried if any check failed.
+    pub policy_violations: Vec<PolicyViolation>,
+    /// Result of the topology checks, for `PPCIe` requests
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topology: Option<TopologyVerdict>,
+    /// NRAS verdict for the GPUs of the bundle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gpu: Option<DeviceVerdict>,
+    /// NRAS verdict for the `NVSwitch` devices of the bundle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub switch: Option<DeviceVerdict>,
+}
+
+impl Verdict {
+    /// Recomputes `attestation_passed` from the individual checks.
+    pub(crate) fn conclude(&mut self) {
+        self.attestation_passed = self.policy_violations.is_empty()
+            && self.topology.as_ref().is_none_or(TopologyVerdict::passed)
+            && (self.gpu.is_some() || self.switch.is_some())
+            && [&self.gpu, &self.switch]
+                .into_iter()
+                .flatten()
+                .all(|device| device.attestation_passed);
+    }
+
+    /// Returns the verdict as an EAT Attestation Result (EAR).
+    ///
+    /// The policy and topology checks are appraised as the `policy` and `topology`
+    /// components, and every device verified by NRAS as a component of its own, from the
+    /// claims of its device token, verified against the JWKS of `gpu_verifier_url` or
+    /// `switch_verifier_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a device token cannot be decoded or verified.
+    pub async fn ear(
+        &self,
+        gpu_verifier_url: &str,
+        switch_verifier_url: &str,
+    ) -> AttestResult<Ear> {
+        let mut ear = Ear::new().with_nonce(&self.nonce);
+        ear.add_check("policy", self.policy_violations.is_empty());
+        if let Some(topology) = &self.topology {
+            ear.add_check("topology", topology.passed());
+        }
+        if let Some(gpu) = &self.gpu {
+            ear.add_nras_response(gpu_verifier_url, &gpu.response)
+                .await?;
+        }
+        if let Some(switch) = &self.switch {
+            ear.add_nras_response(switch_verifier_url, &switch.response)
+                .await?;
+        }
+        Ok(ear)
+    }
+}
+
+/// The NRAS verdict for the devices of one kind.
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceVerdict {
+    pub attestation_passed: bool,
+    pub devices: usize,
+    /// The full NRAS response, holding the signed attestation tokens
+    pub response: Value,
+}
+
+/// The result of the GPU and switch topology checks.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct TopologyVerdict {
+    pub gpu_topology_passed: bool,
+    pub switch_topology_passed: bool,
+    /// Hex encoded PDIs of the switches connected to every GPU, as reported by the GPUs
+    pub switch_pdis: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl TopologyVerdict {
+    /// Returns `true` if both topology checks passed.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.gpu_topology_passed && self.switch_topology_passed
+    }
+}
@@ -0,0 +1,501 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::errors::{AttestErrorKind, AuditError};
+
+/// Hash linked to by the first record of an audit log.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Context string used to derive audit record hashes with blake3.
+const AUDIT_RECORD_CONTEXT: &str = "nvrust 2025-05 attestation audit record v1";
+
+/// Context string used to derive evidence digests with blake3.
+const EVIDENCE_DIGEST_CONTEXT: &str = "nvrust 2025-05 attestation audit evidence digest v1";
+
+/// Outcome of an attestation, as recorded in the audit log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditVerdict {
+    /// NRAS verified the evidence and the overall attestation result is positive
+    Passed,
+    /// NRAS verified the evidence and the overall attestation result is negative
+    Failed,
+    /// The verification could not be completed
+    Error,
+}
+
+/// A single attestation decision, before it is linked into the audit log.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditEvent {
+    /// Architecture of the attested devices (e.g. `HOPPER` or `LS10`)
+    pub arch: String,
+    /// URL of the remote attestation service
+    pub verifier_url: String,
+    /// Nonce sent with the evidence
+    pub nonce: String,
+    /// Hex encoded blake3 digest of the evidence list, see [`evidence_digest`]
+    pub evidence_digest: String,
+    /// Outcome of the attestation
+    pub verdict: AuditVerdict,
+    /// Complete JSON response from NRAS, if one was received
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nras_response: Option<Value>,
+    /// Decoded claims of the overall NRAS token, if it was verified
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claims: Option<Value>,
+    /// Category of the error, if the verification could not be completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<AttestErrorKind>,
+    /// Error message, if the verification could not be completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Whether the result was served from the attestation cache
+    pub cached: bool,
+    /// Start of the verification, in seconds since the Unix epoch
+    pub started_at: u64,
+    /// Duration of the verification, in milliseconds
+    pub duration_ms: u64,
+}
+
+/// An audit log record, linked to its predecessor by hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Position of the record in the log, starting at 0
+    pub sequence: u64,
+    /// Hash of the previous record, or [`GENESIS_HASH`] for the first record
+    pub previous_hash: String,
+    /// The recorded attestation decision
+    #[serde(flatten)]
+    pub event: AuditEvent,
+    /// Hex encoded blake3 hash of all other fields of the record
+    pub hash: String,
+}
+
+/// The fields of an audit record covered by its hash.
+#[derive(Serialize)]
+struct RecordContent<'a> {
+    sequence: u64,
+    previous_hash: &'a str,
+    #[serde(flatten)]
+    event: &'a AuditEvent,
+}
+
+impl AuditRecord {
+    /// Links `event` after the record identified by `head`.
+    #[must_use]
+    pub fn chain(head: &AuditHead, event: AuditEvent) -> Self {
+        let hash = compute_record_hash(head.records, &head.hash, &event);
+        Self {
+            sequence: head.records,
+            previous_hash: head.hash.clone(),
+            event,
+            hash,
+        }
+    }
+
+    /// Returns `true` if the record hash matches its content.
+    #[must_use]
+    pub fn is_intact(&self) -> bool {
+        compute_record_hash(self.sequence, &self.previous_hash, &self.event) == self.hash
+    }
+
+    /// Returns the head of a log ending with this record.
+    #[must_use]
+    pub fn head(&self) -> AuditHead {
+        AuditHead {
+            records: self.sequence + 1,
+            hash: self.hash.clone(),
+        }
+    }
+}
+
+/// Computes the hex encoded blake3 hash of an audit record.
+fn compute_record_hash(sequence: u64, previous_hash: &str, event: &AuditEvent) -> String {
+    let content = serde_json::to_vec(&RecordContent {
+        sequence,
+        previous_hash,
+        event,
+    })
+    .expect("Audit record content is always serializable");
+    let mut hasher = blake3::Hasher::new_derive_key(AUDIT_RECORD_CONTEXT);
+    hasher.update(&content);
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Computes the hex encoded blake3 digest of an evidence list, as recorded in audit events.
+#[must_use]
+pub fn evidence_digest<E: Serialize>(evidence_list: &[E]) -> String {
+    let evidence = serde_json::to_vec(evidence_list).unwrap_or_default();
+    let mut hasher = blake3::Hasher::new_derive_key(EVIDENCE_DIGEST_CONTEXT);
+    hasher.update(&evidence);
+    hasher.finalize().to_hex().to_string()
+}
+
+/// Number of records and hash of the last record of an audit log.
+///
+/// Operators should periodically store the head outside of the host (e.g. in
+/// a monitoring system): verifying the log against a previously stored head
+/// detects records removed from the end of the log, which hash chaining alone
+/// cannot detect.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AuditHead {
+    /// Number of records in the log
+    pub records: u64,
+    /// Hash of the last record, or [`GENESIS_HASH`] if the log is empty
+    pub hash: String,
+}
+
+impl Default for AuditHead {
+    fn default() -> Self {
+        Self {
+            records: 0,
+            hash: GENESIS_HASH.to_string(),
+        }
+    }
+}
+
+/// Append-only storage for attestation decisions.
+///
+/// Implementations must be safe to share across tasks, and must only return
+/// once the record is durably stored.
+pub trait AuditLog: Send + Sync {
+    /// Links `event` to the last record of the log and appends it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the record cannot be stored.
+    fn append(&self, event: AuditEvent) -> Result<AuditRecord, AuditError>;
+}
+
+/// File-backed audit log, storing one JSON record per line.
+pub struct FileAuditLog {
+    path: PathBuf,
+    state: Mutex<FileAuditLogState>,
+}
+
+struct FileAuditLogState {
+    file: File,
+    head: AuditHead,
+    /// Set when a failed append could not be rolled back, so that no record is
+    /// appended after a partial line
+    poisoned: bool,
+}
+
+impl FileAuditLog {
+    /// Opens the audit log at `path`, creating it if needed.
+    ///
+    /// An existing log is verified before new records are appended to it.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be opened, or if the existing log fails verification.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, AuditError> {
+        let path = path.into();
+        let head = if path.exists() {
+            Self::verify(&path, None)?
+        } else {
+            AuditHead::default()
+        };
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            state: Mutex::new(FileAuditLogState {
+                file,
+                head,
+                poisoned: false,
+            }),
+        })
+    }
+
+    /// Returns the path of the log file.
+    #[must_use]
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns the current head of the log.
+    #[must_use]
+    pub fn head(&self) -> AuditHead {
+        self.state
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .head
+            .clone()
+    }
+
+    /// Verifies the audit log at `path`, see [`verify_audit_log`].
+    ///
+    /// # Errors
+    ///
+    /// See [`verify_audit_log`].
+    pub fn verify(
+        path: impl AsRef<Path>,
+        expected_head: Option<&AuditHead>,
+    ) -> Result<AuditHead, AuditError> {
+        verify_audit_log(BufReader::new(File::open(path)?), expected_head)
+    }
+}
+
+impl AuditLog for FileAuditLog {
+    /// Appends the record as one line.
+    ///
+    /// If the line cannot be fully written, the file is truncated back to its previous
+    /// length. If that fails too, the log is poisoned and every later append fails with
+    /// `AuditError::Poisoned`, as the file may end with a partial line.
+    fn append(&self, event: AuditEvent) -> Result<AuditRecord, AuditError> {
+        let mut state = self.state.lock().unwrap_or_else(PoisonError::into_inner);
+        if state.poisoned {
+            return Err(AuditError::Poisoned);
+        }
+        let record = AuditRecord::chain(&state.head, event);
+        let mut line = serde_json::to_vec(&record).map_err(std::io::Error::from)?;
+        line.push(b'\n');
+        let length = state.file.metadata()?.len();
+        let written = state
+            .file
+            .write_all(&line)
+            .and_then(|()| state.file.sync_data());
+        if let Err(e) = written {
+            if state
+                .file
+                .set_len(length)
+                .and_then(|()| state.file.sync_data())
+                .is_err()
+            {
+                state.poisoned = true;
+            }
+            return Err(e.into());
+        }
+        state.head = record.head();
+        drop(state);
+        Ok(record)
+    }
+}
+
+/// Verifies the hash chain of an audit log, one JSON record per line.
+///
+/// Every record must be well formed, numbered after its predecessor, linked to
+/// the hash of its predecessor and match its own hash. If `expected_head` is
+/// provided, the log must also contain the record it identifies, which detects
+/// records removed from the end of the log.
+///
+/// # Returns
+///
+/// The head of the verified log.
+///
+/// # Errors
+///
+/// * `AuditError::Io` - If the log cannot be read
+/// * `AuditError::MalformedRecord` - If a line is not a complete record
+/// * `AuditError::SequenceGap` - If records were removed or reordered
+/// * `AuditError::BrokenChain` - If a record is not linked to its predecessor
+/// * `AuditError::HashMismatch` - If a record was modified
+/// * `AuditError::Truncated` - If the log holds fewer records than `expected_head`
+/// * `AuditError::HeadMismatch` - If the record identified by `expected_head` differs
+pub fn verify_audit_log(
+    reader: impl BufRead,
+    expected_head: Option<&AuditHead>,
+) -> Result<AuditHead, AuditError> {
+    let mut head = AuditHead::default();
+    let mut line_number = 0;
+    let mut reader = reader;
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+        if !line.ends_with('\n') {
+            return Err(AuditError::MalformedRecord {
+                line: line_number,
+                message: "record is not terminated by a newline".to_string(),
+            });
+        }
+        let record: AuditRecord =
+            serde_json::from_str(&line).map_err(|e| AuditError::MalformedRecord {
+                line: line_number,
+                message: e.to_string(),
+            })?;
+        if record.sequence != head.records {
+            return Err(AuditError::SequenceGap {
+                expected: head.records,
+                found: record.sequence,
+            });
+        }
+        if record.previous_hash != head.hash {
+            return Err(AuditError::BrokenChain {
+                sequence: record.sequence,
+            });
+        }
+        if !record.is_intact() {
+            return Err(AuditError::HashMismatch {
+                sequence: record.sequence,
+            });
+        }
+        if let Some(expected) = expected_head {
+            if record.sequence + 1 == expected.records && record.hash != expected.hash {
+                return Err(AuditError::HeadMismatch {
+                    sequence: record.sequence,
+                });
+            }
+        }
+        head = record.head();
+    }
+    if let Some(expected) = expected_head {
+        if head.records < expected.records {
+            return Err(AuditError::Truncated {
+                expected: expected.records,
+                found: head.records,
+            });
+        }
+        if expected.records == 0 && expected.hash != GENESIS_HASH {
+            return Err(AuditError::HeadMismatch { sequence: 0 });
+        }
+    }
+    Ok(head)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn event(nonce: &str, verdict: AuditVerdict) -> AuditEvent {
+        AuditEvent {
+            arch: "HOPPER".to_string(),
+            verifier_url: "https://nras.attestation.nvidia.com/v3/attest/gpu".to_string(),
+            nonce: nonce.to_string(),
+            evidence_digest: evidence_digest(&["ZXZpZGVuY2U="]),
+            verdict,
+            nras_response: Some(json!([["JWT", "token"], {"GPU-0": "token"}])),
+            claims: Some(json!({"x-nvidia-overall-att-result": true, "exp": 1_700_000_000})),
+            error_kind: None,
+            error: None,
+            cached: false,
+            started_at: 1_700_000_000,
+            duration_ms: 42,
+        }
+    }
+
+    fn log_path(name: &str) -> PathBuf {
+        let path =
+            std::env::temp_dir().join(format!("nvrust-audit-{}-{name}.log", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        path
+    }
+
+    fn write_log(name: &str, records: usize) -> (PathBuf, AuditHead) {
+        let path = log_path(name);
+        let log = FileAuditLog::open(&path).expect("Failed to open audit log");
+        for i in 0..records {
+            log.append(event(&format!("{i:02}"), AuditVerdict::Passed))
+                .expect("Failed to append record");
+        }
+        (path, log.head())
+    }
+
+    fn rewrite_lines(path: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+        let contents = std::fs::read_to_string(path).unwrap();
+        let mut lines: Vec<String> = contents.lines().map(ToString::to_string).collect();
+        edit(&mut lines);
+        std::fs::write(path, lines.join("\n") + "\n").unwrap();
+    }
+
+    #[test]
+    fn test_audit_log_round_trip() {
+        let (path, head) = write_log("round-trip", 3);
+        assert_eq!(head.records, 3);
+        assert_eq!(FileAuditLog::verify(&path, Some(&head)).unwrap(), head);
+
+        // Reopening the log continues the chain
+        let log = FileAuditLog::open(&path).unwrap();
+        assert_eq!(log.head(), head);
+        let record = log.append(event("03", AuditVerdict::Failed)).unwrap();
+        assert_eq!(record.sequence, 3);
+        assert_eq!(record.previous_hash, head.hash);
+        assert_eq!(
+            FileAuditLog::verify(&path, Some(&head)).unwrap(),
+            record.head()
+        );
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_audit_log_detects_tampering() {
+        let (path, _) = write_log("tampering", 3);
+        rewrite_lines(&path, |lines| {
+            lines[1] = lines[1].replace("\"passed\"", "\"failed\"");
+        });
+        assert!(matches!(
+            FileAuditLog::verify(&path, None),
+            Err(AuditError::HashMismatch { sequence: 1 })
+        ));
+        assert!(FileAuditLog::open(&path).is_err());
+        std::fs::remove_file(path).ok();
+
+        let (path, _) = write_log("removal", 3);
+        rewrite_lines(&path, |lines| {
+            lines.remove(1);
+        });
+        assert!(matches!(
+            FileAuditLog::verify(&path, None),
+            Err(AuditError::SequenceGap {
+                expected: 1,
+                found: 2
+            })
+        ));
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_audit_log_detects_truncation() {
+        let (path, head) = write_log("truncation", 3);
+        rewrite_lines(&path, |lines| {
+            lines.pop();
+        });
+        assert!(FileAuditLog::verify(&path, None).is_ok());
+        assert!(matches!(
+            FileAuditLog::verify(&path, Some(&head)),
+            Err(AuditError::Truncated {
+                expected: 3,
+                found: 2
+            })
+        ));
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::write(&path, &contents[..contents.len() - 10]).unwrap();
+        assert!(matches!(
+            FileAuditLog::verify(&path, None),
+            Err(AuditError::MalformedRecord { line: 2, .. })
+        ));
+        std::fs::remove_file(path).ok();
+    }
+
+    #[test]
+    fn test_audit_log_poisoned_after_failed_append() {
+        let (path, head) = write_log("poisoned", 2);
+        let log = FileAuditLog::open(&path).unwrap();
+        // A read-only handle fails both the write and the rollback
+        log.state.lock().unwrap().file = File::open(&path).unwrap();
+        assert!(matches!(
+            log.append(event("02", AuditVerdict::Passed)),
+            Err(AuditError::Io(_))
+        ));
+        assert!(matches!(
+            log.append(event("03", AuditVerdict::Passed)),
+            Err(AuditError::Poisoned)
+        ));
+        assert_eq!(log.head(), head);
+        assert_eq!(FileAuditLog::verify(&path, Some(&head)).unwrap(), head);
+        std::fs::remove_file(path).ok();
+    }
+}
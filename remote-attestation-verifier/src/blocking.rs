@@ -0,0 +1,148 @@
+//! Synchronous remote attestation, for applications without an async runtime.
+//!
+//! The functions of this module, enabled by the `blocking` feature, mirror their
+//! async counterparts and block the calling thread until the verification completes.
+//! Each call runs the verification on its own single-threaded Tokio runtime, so they
+//! must not be called from within an async runtime; they return
+//! `AttestError::Runtime` if they are.
+
+use std::future::Future;
+
+use serde_json::Value;
+
+use crate::{
+    errors::{AttestError, Result},
+    options::AttestRemoteOptions,
+    types::{DeviceEvidence, NvSwitchEvidence},
+    utils::nras_token::NvidiaAttestationClaims,
+};
+
+/// Performs remote attestation of GPU devices, blocking until NRAS answers.
+///
+/// See [`crate::verify_gpu_attestation`].
+///
+/// # Errors
+///
+/// * `AttestError::Runtime` - If called from within an async runtime, or if the
+///   runtime cannot be created
+/// * Any error returned by [`crate::verify_gpu_attestation`]
+pub fn verify_gpu_attestation(
+    gpu_evidences: &[DeviceEvidence],
+    nonce: &str,
+    remote_attestation_options: AttestRemoteOptions,
+) -> Result<(bool, Value)> {
+    block_on(crate::verify_gpu_attestation(
+        gpu_evidences,
+        nonce,
+        remote_attestation_options,
+    ))?
+}
+
+/// Performs remote attestation of `NVSwitch` devices, blocking until NRAS answers.
+///
+/// See [`crate::verify_nvswitch_attestation`].
+///
+/// # Errors
+///
+/// * `AttestError::Runtime` - If called from within an async runtime, or if the
+///   runtime cannot be created
+/// * Any error returned by [`crate::verify_nvswitch_attestation`]
+pub fn verify_nvswitch_attestation(
+    nvswitch_evidences: &[NvSwitchEvidence],
+    nonce: &str,
+    remote_attestation_options: AttestRemoteOptions,
+) -> Result<(bool, Value)> {
+    block_on(crate::verify_nvswitch_attestation(
+        nvswitch_evidences,
+        nonce,
+        remote_attestation_options,
+    ))?
+}
+
+/// Decodes and verifies an NRAS token, blocking until the JWKS is fetched.
+///
+/// See [`crate::utils::nras_token::decode_nras_token`].
+///
+/// # Errors
+///
+/// * `AttestError::Runtime` - If called from within an async runtime, or if the
+///   runtime cannot be created
+/// * Any error returned by [`crate::utils::nras_token::decode_nras_token`]
+pub fn decode_nras_token(verifier_url: &str, token: &str) -> Result<NvidiaAttestationClaims> {
+    block_on(crate::utils::nras_token::decode_nras_token(
+        verifier_url,
+        token,
+    ))?
+}
+
+/// Runs `future` to completion on a new single-threaded runtime.
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    // Blocking inside a runtime would panic, or stall its worker thread
+    if tokio::runtime::Handle::try_current().is_ok() {
+        return Err(AttestError::Runtime(
+            "the blocking API cannot be called from within an async runtime".to_string(),
+        ));
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| AttestError::Runtime(format!("failed to create the runtime: {e}")))?;
+    Ok(runtime.block_on(future))
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    use super::*;
+    use crate::{test_support::MockNras, utils::get_overall_claims_token, AttestErrorKind};
+
+    /// Starts a mock NRAS on a runtime of its own, which keeps serving while the
+    /// blocking API runs on the test thread.
+    fn start_mock_nras() -> (tokio::runtime::Runtime, MockNras) {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .enable_all()
+            .build()
+            .expect("Failed to create runtime");
+        let nras = runtime
+            .block_on(MockNras::start())
+            .expect("Failed to start mock NRAS");
+        (runtime, nras)
+    }
+
+    #[test]
+    fn test_blocking_attestation() {
+        let (_runtime, nras) = start_mock_nras();
+        let gpu_evidence = vec![DeviceEvidence {
+            certificate: STANDARD.encode("certificate"),
+            evidence: STANDARD.encode("evidence"),
+        }];
+        let switch_evidence = vec![NvSwitchEvidence {
+            certificate: STANDARD.encode("certificate"),
+            evidence: STANDARD.encode("evidence"),
+        }];
+        let nonce = hex::encode([0u8; 32]);
+
+        let (passed, response) =
+            verify_gpu_attestation(&gpu_evidence, &nonce, nras.gpu_options()).unwrap();
+        assert!(passed);
+        let token = get_overall_claims_token(&response).unwrap();
+        let claims = decode_nras_token(&nras.gpu_verifier_url(), &token).unwrap();
+        assert!(claims.overall_attestation_result);
+
+        nras.set_attestation_result(false);
+        let (passed, _) =
+            verify_nvswitch_attestation(&switch_evidence, &nonce, nras.switch_options()).unwrap();
+        assert!(!passed);
+        assert_eq!(nras.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_blocking_rejects_async_context() {
+        let error =
+            verify_nvswitch_attestation(&[], "00", AttestRemoteOptions::default()).unwrap_err();
+        assert!(matches!(error, AttestError::Runtime(_)));
+        assert_eq!(error.kind(), AttestErrorKind::Runtime);
+    }
+}
@@ -0,0 +1,473 @@
+use std::collections::BTreeMap;
+
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    errors::{AttestError, Result},
+    types::{DeviceEvidence, NvSwitchEvidence},
+    utils::unix_now,
+};
+
+/// Current version of the evidence bundle format.
+pub const EVIDENCE_BUNDLE_VERSION: u16 = 1;
+
+/// Context string used to derive evidence bundle digests with blake3.
+const BUNDLE_DIGEST_CONTEXT: &str = "nvrust 2025-05 evidence bundle digest v1";
+
+/// Context prepended to the digest of an evidence bundle when it is signed.
+const BUNDLE_SIGNATURE_CONTEXT: &[u8] = b"nvrust 2025-05 evidence bundle signature v1";
+
+/// Algorithm of evidence bundle signatures.
+pub const BUNDLE_SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// Length of the nonce carried by an evidence bundle, in bytes.
+const NONCE_LENGTH: usize = 32;
+
+/// Encoding of an evidence bundle on the wire.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BundleEncoding {
+    /// JSON encoding, with evidence fields as base64 strings
+    Json,
+    /// CBOR encoding (RFC 8949), with the same structure as the JSON encoding
+    Cbor,
+}
+
+impl BundleEncoding {
+    /// Detects the encoding of `bytes`, treating anything that starts with a JSON
+    /// object as JSON and everything else as CBOR.
+    #[must_use]
+    pub fn detect(bytes: &[u8]) -> Self {
+        match bytes.iter().find(|byte| !byte.is_ascii_whitespace()) {
+            Some(b'{') => Self::Json,
+            _ => Self::Cbor,
+        }
+    }
+}
+
+/// Metadata about the host the evidence was collected on.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HostMetadata {
+    /// Hostname of the machine
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hostname: Option<String>,
+    /// Identifier of the node within the operator's fleet
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// Version of the NVIDIA driver
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub driver_version: Option<String>,
+    /// Any additional free-form metadata
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub additional_metadata: BTreeMap<String, String>,
+}
+
+/// Signature of an evidence bundle by the node that collected it.
+///
+/// The signature covers the bundle digest, so it authenticates every other field of
+/// the bundle.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BundleSignature {
+    /// Signature algorithm, always [`BUNDLE_SIGNATURE_ALGORITHM`]
+    pub algorithm: String,
+    /// Hex encoded public key of the signer
+    pub public_key: String,
+    /// Hex encoded signature
+    pub signature: String,
+}
+
+/// Versioned, integrity-protected bundle of GPU and `NVSwitch` attestation evidence.
+///
+/// A bundle is the wire format exchanged between evidence producers (e.g. Atoma
+/// nodes) and verifiers (e.g. the Atoma proxy). It carries the evidence of every
+/// device of a host, the nonce it was collected with, the collection time and host
+/// metadata. The `digest` field is a blake3 digest of all other fields, so that
+/// accidental or malicious modifications of the bundle are detected by `validate`.
+///
+/// Note that the digest protects the bundle's integrity, not its authenticity: the
+/// evidence itself is signed by the devices, and verified by NRAS. Nodes may also
+/// sign the bundle with [`EvidenceBundle::sign`], so that verifiers can check which
+/// node produced it with [`EvidenceBundle::verify_signer`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EvidenceBundle {
+    /// Version of the bundle format
+    pub version: u16,
+    /// Hex encoded 32-byte nonce used to collect the evidence
+    pub nonce: String,
+    /// Collection time, in seconds since the Unix epoch
+    pub collected_at: u64,
+    /// Metadata about the host the evidence was collected on
+    #[serde(default)]
+    pub host: HostMetadata,
+    /// Evidence of the GPUs of the host
+    #[serde(default)]
+    pub gpu_evidence: Vec<DeviceEvidence>,
+    /// Evidence of the `NVSwitch` devices of the host
+    #[serde(default)]
+    pub switch_evidence: Vec<NvSwitchEvidence>,
+    /// Hex encoded blake3 digest of all other fields
+    pub digest: String,
+    /// Signature of the digest by the node that collected the evidence, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<BundleSignature>,
+}
+
+/// The fields of an evidence bundle covered by its digest.
+#[derive(Serialize)]
+struct BundleContent<'a> {
+    version: u16,
+    nonce: &'a str,
+    collected_at: u64,
+    host: &'a HostMetadata,
+    gpu_evidence: &'a [DeviceEvidence],
+    switch_evidence: &'a [NvSwitchEvidence],
+}
+
+impl EvidenceBundle {
+    /// Creates a bundle collected now, and computes its digest.
+    ///
+    /// # Arguments
+    ///
+    /// * `nonce` - The 32-byte nonce used to collect the evidence
+    /// * `gpu_evidence` - The evidence of the GPUs of the host
+    /// * `switch_evidence` - The evidence of the `NVSwitch` devices of the host
+    /// * `host` - Metadata about the host
+    #[must_use]
+    pub fn new(
+        nonce: &[u8; NONCE_LENGTH],
+        gpu_evidence: Vec<DeviceEvidence>,
+        switch_evidence: Vec<NvSwitchEvidence>,
+        host: HostMetadata,
+    ) -> Self {
+        let mut bundle = Self {
+            version: EVIDENCE_BUNDLE_VERSION,
+            nonce: hex::encode(nonce),
+            collected_at: unix_now(),
+            host,
+            gpu_evidence,
+            switch_evidence,
+            digest: String::new(),
+            signature: None,
+        };
+        bundle.digest = bundle.compute_digest();
+        bundle
+    }
+
+    /// Computes the hex encoded blake3 digest of the bundle content.
+    ///
+    /// The digest is computed over the canonical JSON serialization of every field
+    /// but `digest`, so it does not depend on the wire encoding of the bundle.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bundle content cannot be serialized, which cannot happen as it
+    /// only holds strings, integers and string maps.
+    #[must_use]
+    pub fn compute_digest(&self) -> String {
+        let content = BundleContent {
+            version: self.version,
+            nonce: &self.nonce,
+            collected_at: self.collected_at,
+            host: &self.host,
+            gpu_evidence: &self.gpu_evidence,
+            switch_evidence: &self.switch_evidence,
+        };
+        let content =
+            serde_json::to_vec(&content).expect("Evidence bundle content is always serializable");
+        let mut hasher = blake3::Hasher::new_derive_key(BUNDLE_DIGEST_CONTEXT);
+        hasher.update(&content);
+        hasher.finalize().to_hex().to_string()
+    }
+
+    /// Signs the digest of the bundle, replacing any previous signature.
+    ///
+    /// The bundle must not be modified after it is signed.
+    pub fn sign(&mut self, key_pair: &Ed25519KeyPair) {
+        let signature = key_pair.sign(&self.signed_message());
+        self.signature = Some(BundleSignature {
+            algorithm: BUNDLE_SIGNATURE_ALGORITHM.to_string(),
+            public_key: hex::encode(key_pair.public_key()),
+            signature: hex::encode(signature),
+        });
+    }
+
+    /// Checks that the bundle is signed by the holder of `public_key`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidEvidenceBundle` - If the bundle is not signed, is signed
+    ///   with another key, or if its signature is invalid
+    pub fn verify_signer(&self, public_key: &[u8]) -> Result<()> {
+        let signature = self.signature.as_ref().ok_or_else(|| {
+            AttestError::InvalidEvidenceBundle("Bundle is not signed".to_string())
+        })?;
+        if !signature
+            .public_key
+            .eq_ignore_ascii_case(&hex::encode(public_key))
+        {
+            return Err(AttestError::InvalidEvidenceBundle(format!(
+                "Bundle is signed by {}, expected {}",
+                signature.public_key,
+                hex::encode(public_key)
+            )));
+        }
+        self.verify_signature(signature)
+    }
+
+    /// Verifies a signature over the digest of the bundle.
+    fn verify_signature(&self, signature: &BundleSignature) -> Result<()> {
+        if signature.algorithm != BUNDLE_SIGNATURE_ALGORITHM {
+            return Err(AttestError::InvalidEvidenceBundle(format!(
+                "Unsupported signature algorithm `{}`, expected {BUNDLE_SIGNATURE_ALGORITHM}",
+                signature.algorithm
+            )));
+        }
+        let decode = |value: &str| {
+            hex::decode(value).map_err(|e| {
+                AttestError::InvalidEvidenceBundle(format!("Signature is not hex encoded: {e}"))
+            })
+        };
+        UnparsedPublicKey::new(&ED25519, decode(&signature.public_key)?)
+            .verify(&self.signed_message(), &decode(&signature.signature)?)
+            .map_err(|_| {
+                AttestError::InvalidEvidenceBundle("Bundle signature is invalid".to_string())
+            })
+    }
+
+    /// Returns the message signed by [`EvidenceBundle::sign`].
+    fn signed_message(&self) -> Vec<u8> {
+        [BUNDLE_SIGNATURE_CONTEXT, self.digest.as_bytes()].concat()
+    }
+
+    /// Returns the decoded nonce.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidEvidenceBundle` - If the nonce is not 32 hex encoded bytes
+    pub fn nonce_bytes(&self) -> Result<[u8; NONCE_LENGTH]> {
+        let nonce = hex::decode(&self.nonce).map_err(|e| {
+            AttestError::InvalidEvidenceBundle(format!("Nonce is not hex encoded: {e}"))
+        })?;
+        nonce.try_into().map_err(|nonce: Vec<u8>| {
+            AttestError::InvalidEvidenceBundle(format!(
+                "Nonce must be {NONCE_LENGTH} bytes long, got {} bytes",
+                nonce.len()
+            ))
+        })
+    }
+
+    /// Validates the bundle.
+    ///
+    /// This checks that the bundle version is supported, the nonce is well formed,
+    /// the bundle holds at least one piece of evidence, every evidence and certificate
+    /// chain is valid base64, the digest matches the content, and the signature, if
+    /// any, is valid.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidEvidenceBundle` - If any of the checks fails
+    /// * `AttestError::EvidenceDecode` - If some evidence is not valid base64
+    pub fn validate(&self) -> Result<()> {
+        if self.version != EVIDENCE_BUNDLE_VERSION {
+            return Err(AttestError::InvalidEvidenceBundle(format!(
+                "Unsupported bundle version {}, expected {EVIDENCE_BUNDLE_VERSION}",
+                self.version
+            )));
+        }
+        self.nonce_bytes()?;
+        if self.gpu_evidence.is_empty() && self.switch_evidence.is_empty() {
+            return Err(AttestError::InvalidEvidenceBundle(
+                "Bundle does not contain any evidence".to_string(),
+            ));
+        }
+        for evidence in &self.gpu_evidence {
+            evidence.evidence_bytes()?;
+            evidence.certificate_bytes()?;
+        }
+        for evidence in &self.switch_evidence {
+            evidence.evidence_bytes()?;
+            evidence.certificate_bytes()?;
+        }
+        let digest = self.compute_digest();
+        if digest != self.digest {
+            return Err(AttestError::InvalidEvidenceBundle(format!(
+                "Digest mismatch: bundle declares {}, content hashes to {digest}",
+                self.digest
+            )));
+        }
+        if let Some(signature) = &self.signature {
+            self.verify_signature(signature)?;
+        }
+        Ok(())
+    }
+
+    /// Encodes the bundle.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidEvidenceBundle` - If the bundle cannot be encoded
+    pub fn encode(&self, encoding: BundleEncoding) -> Result<Vec<u8>> {
+        match encoding {
+            BundleEncoding::Json => serde_json::to_vec(self).map_err(|e| {
+                AttestError::InvalidEvidenceBundle(format!("Failed to encode JSON bundle: {e}"))
+            }),
+            BundleEncoding::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::into_writer(self, &mut bytes).map_err(|e| {
+                    AttestError::InvalidEvidenceBundle(format!("Failed to encode CBOR bundle: {e}"))
+                })?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decodes and validates a bundle.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidEvidenceBundle` - If the bundle cannot be decoded or is invalid
+    /// * `AttestError::EvidenceDecode` - If some evidence is not valid base64
+    pub fn decode(bytes: &[u8], encoding: BundleEncoding) -> Result<Self> {
+        let bundle: Self = match encoding {
+            BundleEncoding::Json => serde_json::from_slice(bytes).map_err(|e| {
+                AttestError::InvalidEvidenceBundle(format!("Failed to decode JSON bundle: {e}"))
+            })?,
+            BundleEncoding::Cbor => ciborium::from_reader(bytes).map_err(|e| {
+                AttestError::InvalidEvidenceBundle(format!("Failed to decode CBOR bundle: {e}"))
+            })?,
+        };
+        bundle.validate()?;
+        Ok(bundle)
+    }
+
+    /// Decodes and validates a bundle, detecting its encoding.
+    ///
+    /// # Errors
+    ///
+    /// See [`EvidenceBundle::decode`].
+    pub fn decode_detect(bytes: &[u8]) -> Result<Self> {
+        Self::decode(bytes, BundleEncoding::detect(bytes))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use super::*;
+
+    fn bundle() -> EvidenceBundle {
+        let file = Path::new("./evidence/evidence.json");
+        let evidence = fs::read_to_string(file).expect("Failed to read evidence file");
+        let gpu_evidence: Vec<DeviceEvidence> =
+            serde_json::from_str(&evidence).expect("Failed to parse evidence");
+        let nonce: [u8; NONCE_LENGTH] =
+            hex::decode("931d8dd0add203ac3d8b4fbde75e115278eefcdceac5b87671a748f32364dfcb")
+                .unwrap()
+                .try_into()
+                .unwrap();
+        EvidenceBundle::new(
+            &nonce,
+            gpu_evidence,
+            vec![],
+            HostMetadata {
+                hostname: Some("atoma-node-0".to_string()),
+                ..HostMetadata::default()
+            },
+        )
+    }
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let bundle = bundle();
+        bundle.validate().expect("Fresh bundle should be valid");
+        for encoding in [BundleEncoding::Json, BundleEncoding::Cbor] {
+            let bytes = bundle.encode(encoding).expect("Failed to encode bundle");
+            assert_eq!(BundleEncoding::detect(&bytes), encoding);
+            let decoded = EvidenceBundle::decode_detect(&bytes).expect("Failed to decode bundle");
+            assert_eq!(decoded, bundle);
+        }
+    }
+
+    #[test]
+    fn test_bundle_detects_tampering() {
+        let mut bundle = bundle();
+        bundle.gpu_evidence.swap(0, 1);
+        bundle.gpu_evidence[0].certificate = bundle.gpu_evidence[1].certificate.clone();
+        assert!(matches!(
+            bundle.validate(),
+            Err(AttestError::InvalidEvidenceBundle(_))
+        ));
+        let bytes = bundle.encode(BundleEncoding::Cbor).unwrap();
+        assert!(EvidenceBundle::decode(&bytes, BundleEncoding::Cbor).is_err());
+    }
+
+    #[test]
+    fn test_bundle_signature() {
+        let rng = ring::rand::SystemRandom::new();
+        let key_pair = |rng| {
+            let pkcs8 = Ed25519KeyPair::generate_pkcs8(rng).unwrap();
+            Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap()
+        };
+        let (node_key, other_key) = (key_pair(&rng), key_pair(&rng));
+        let mut bundle = bundle();
+        assert!(bundle
+            .verify_signer(node_key.public_key().as_ref())
+            .is_err());
+
+        bundle.sign(&node_key);
+        for encoding in [BundleEncoding::Json, BundleEncoding::Cbor] {
+            let bytes = bundle.encode(encoding).unwrap();
+            let decoded = EvidenceBundle::decode(&bytes, encoding).unwrap();
+            decoded
+                .verify_signer(node_key.public_key().as_ref())
+                .expect("Bundle should be signed by the node key");
+            assert!(decoded
+                .verify_signer(other_key.public_key().as_ref())
+                .is_err());
+        }
+
+        // The signature covers the digest, so a re-hashed modification is detected
+        let mut tampered = bundle.clone();
+        tampered.host.node_id = Some("atoma-node-1".to_string());
+        tampered.digest = tampered.compute_digest();
+        assert!(matches!(
+            tampered.validate(),
+            Err(AttestError::InvalidEvidenceBundle(_))
+        ));
+
+        // Re-signing with another key is detected by pinning the node key
+        tampered.sign(&other_key);
+        tampered.validate().unwrap();
+        assert!(tampered
+            .verify_signer(node_key.public_key().as_ref())
+            .is_err());
+    }
+
+    #[test]
+    fn test_bundle_rejects_invalid_fields() {
+        let mut bundle = bundle();
+        bundle.nonce = "abcd".to_string();
+        bundle.digest = bundle.compute_digest();
+        assert!(bundle.validate().is_err());
+
+        let mut bundle = self::bundle();
+        bundle.gpu_evidence[0].evidence = "not base64!".to_string();
+        bundle.digest = bundle.compute_digest();
+        assert!(matches!(
+            bundle.validate(),
+            Err(AttestError::EvidenceDecode(_))
+        ));
+
+        let mut bundle = self::bundle();
+        bundle.version = EVIDENCE_BUNDLE_VERSION + 1;
+        bundle.digest = bundle.compute_digest();
+        assert!(bundle.validate().is_err());
+
+        let mut bundle = self::bundle();
+        bundle.gpu_evidence.clear();
+        bundle.digest = bundle.compute_digest();
+        assert!(bundle.validate().is_err());
+    }
+}
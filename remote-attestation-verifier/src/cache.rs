@@ -0,0 +1,322 @@
+use std::{
+    collections::HashMap,
+    fmt,
+    path::PathBuf,
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::warn;
+
+use crate::utils::unix_now;
+
+/// Context string used to derive attestation cache keys with blake3.
+const CACHE_KEY_CONTEXT: &str = "nvrust 2025-05 remote attestation cache key v1";
+
+/// Default maximum number of entries kept by the in-memory cache.
+pub const DEFAULT_MAX_CACHE_ENTRIES: usize = 1024;
+
+/// Key of an attestation cache entry.
+///
+/// The key is the blake3 digest of the evidence list, the nonce and the options
+/// that influence the verification result (architecture, verifier URL, claims
+/// version and certificate hold policy).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CacheKey([u8; blake3::OUT_LEN]);
+
+impl CacheKey {
+    /// Computes the cache key of a remote attestation request.
+    ///
+    /// # Arguments
+    ///
+    /// * `evidence_list` - The evidence sent to the remote attestation service
+    /// * `nonce` - The nonce sent with the evidence
+    /// * `arch` - The architecture of the attested devices
+    /// * `verifier_url` - The URL of the remote attestation service
+    /// * `claims_version` - The claims version requested
+    /// * `allow_hold_cert` - Whether certificates on hold are allowed
+    #[must_use]
+    pub fn new<E: Serialize>(
+        evidence_list: &[E],
+        nonce: &str,
+        arch: &str,
+        verifier_url: &str,
+        claims_version: &str,
+        allow_hold_cert: bool,
+    ) -> Self {
+        let request = json!({
+            "evidence_list": evidence_list,
+            "nonce": nonce,
+            "arch": arch,
+            "verifier_url": verifier_url,
+            "claims_version": claims_version,
+            "allow_hold_cert": allow_hold_cert,
+        });
+        let mut hasher = blake3::Hasher::new_derive_key(CACHE_KEY_CONTEXT);
+        hasher.update(request.to_string().as_bytes());
+        Self(*hasher.finalize().as_bytes())
+    }
+
+    /// Returns the raw digest bytes.
+    #[must_use]
+    pub const fn as_bytes(&self) -> &[u8; blake3::OUT_LEN] {
+        &self.0
+    }
+}
+
+impl fmt::Display for CacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&hex::encode(self.0))
+    }
+}
+
+/// A previously verified remote attestation result.
+///
+/// Only the NRAS response is stored, not the attestation result. On every hit, the
+/// overall token of the response is verified again, and must have been issued for the
+/// nonce and number of devices of the request. A store that can be written by others,
+/// such as [`FileAttestationCache`], can therefore only serve NRAS responses that are
+/// still valid for the request, or cause a new attestation request.
+/// Verifying the token fetches the NRAS JWKS, so a hit only skips the attestation request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedAttestation {
+    /// The complete JSON response from the remote attestation service, holding the
+    /// NRAS tokens
+    pub response: Value,
+    /// Expiration time of the NRAS token (`exp` claim), in seconds since the Unix epoch
+    pub expires_at: u64,
+}
+
+impl CachedAttestation {
+    /// Returns `true` if the NRAS token of this result has expired at `now`,
+    /// in seconds since the Unix epoch.
+    #[must_use]
+    pub const fn is_expired_at(&self, now: u64) -> bool {
+        self.expires_at <= now
+    }
+}
+
+/// Storage backend for verified remote attestation results.
+///
+/// Implementations must be safe to share across tasks. Storage failures should be
+/// logged and otherwise ignored, as the cache must never make a verification fail.
+///
+/// A hit saves the attestation request to NRAS, but not the JWKS request needed to
+/// verify the cached token again.
+pub trait AttestationCache: Send + Sync {
+    /// Returns the cached result for `key`, if any and not yet expired.
+    fn get(&self, key: &CacheKey) -> Option<CachedAttestation>;
+
+    /// Stores a verified result under `key`.
+    fn insert(&self, key: CacheKey, attestation: CachedAttestation);
+}
+
+/// In-memory attestation cache, bounded to a maximum number of entries.
+pub struct InMemoryAttestationCache {
+    entries: Mutex<HashMap<CacheKey, CachedAttestation>>,
+    max_entries: usize,
+}
+
+impl InMemoryAttestationCache {
+    /// Creates a cache holding at most `max_entries` results.
+    #[must_use]
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            max_entries,
+        }
+    }
+
+    /// Returns the number of entries currently stored, including expired ones
+    /// that have not been evicted yet.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .len()
+    }
+
+    /// Returns `true` if the cache holds no entries.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for InMemoryAttestationCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_CACHE_ENTRIES)
+    }
+}
+
+impl AttestationCache for InMemoryAttestationCache {
+    fn get(&self, key: &CacheKey) -> Option<CachedAttestation> {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        match entries.get(key) {
+            Some(attestation) if attestation.is_expired_at(unix_now()) => {
+                entries.remove(key);
+                None
+            }
+            attestation => attestation.cloned(),
+        }
+    }
+
+    fn insert(&self, key: CacheKey, attestation: CachedAttestation) {
+        let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+        if entries.len() >= self.max_entries && !entries.contains_key(&key) {
+            let now = unix_now();
+            entries.retain(|_, attestation| !attestation.is_expired_at(now));
+            if entries.len() >= self.max_entries {
+                // Evict the entry that expires first
+                if let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, attestation)| attestation.expires_at)
+                    .map(|(key, _)| *key)
+                {
+                    entries.remove(&oldest);
+                }
+            }
+        }
+        entries.insert(key, attestation);
+    }
+}
+
+/// On-disk attestation cache, storing one JSON file per entry in a directory.
+///
+/// The cache survives process restarts, and can be shared by processes running
+/// on the same host. Entries are not trusted: their token is verified again on every hit.
+pub struct FileAttestationCache {
+    directory: PathBuf,
+}
+
+impl FileAttestationCache {
+    /// Creates a cache storing its entries in `directory`, creating it if needed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be created.
+    pub fn new(directory: impl Into<PathBuf>) -> std::io::Result<Self> {
+        let directory = directory.into();
+        std::fs::create_dir_all(&directory)?;
+        Ok(Self { directory })
+    }
+
+    /// Returns the path of the file holding the entry for `key`.
+    fn entry_path(&self, key: &CacheKey) -> PathBuf {
+        self.directory.join(format!("{key}.json"))
+    }
+}
+
+impl AttestationCache for FileAttestationCache {
+    fn get(&self, key: &CacheKey) -> Option<CachedAttestation> {
+        let path = self.entry_path(key);
+        let contents = std::fs::read(&path).ok()?;
+        let attestation = match serde_json::from_slice::<CachedAttestation>(&contents) {
+            Ok(attestation) => attestation,
+            Err(e) => {
+                warn!(
+                    level = "attestation_cache",
+                    "Discarding corrupted attestation cache entry {}: {e}",
+                    path.display()
+                );
+                std::fs::remove_file(&path).ok();
+                return None;
+            }
+        };
+        if attestation.is_expired_at(unix_now()) {
+            std::fs::remove_file(&path).ok();
+            return None;
+        }
+        Some(attestation)
+    }
+
+    fn insert(&self, key: CacheKey, attestation: CachedAttestation) {
+        let path = self.entry_path(&key);
+        let temporary_path = path.with_extension(format!("{}.tmp", std::process::id()));
+        let result = serde_json::to_vec(&attestation)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| std::fs::write(&temporary_path, contents))
+            .and_then(|()| std::fs::rename(&temporary_path, &path));
+        if let Err(e) = result {
+            warn!(
+                level = "attestation_cache",
+                "Failed to write attestation cache entry {}: {e}",
+                path.display()
+            );
+            std::fs::remove_file(&temporary_path).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DeviceEvidence;
+
+    fn evidence() -> Vec<DeviceEvidence> {
+        vec![DeviceEvidence {
+            certificate: "Y2VydGlmaWNhdGU=".to_string(),
+            evidence: "ZXZpZGVuY2U=".to_string(),
+        }]
+    }
+
+    fn key(nonce: &str, claims_version: &str) -> CacheKey {
+        CacheKey::new(
+            &evidence(),
+            nonce,
+            "HOPPER",
+            "https://nras.attestation.nvidia.com/v3/attest/gpu",
+            claims_version,
+            false,
+        )
+    }
+
+    fn attestation(expires_at: u64) -> CachedAttestation {
+        CachedAttestation {
+            response: json!([["JWT", "token"], {"GPU-0": "token"}]),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn test_cache_key_depends_on_request() {
+        assert_eq!(key("00", "2.0"), key("00", "2.0"));
+        assert_ne!(key("00", "2.0"), key("01", "2.0"));
+        assert_ne!(key("00", "2.0"), key("00", "3.0"));
+        assert_eq!(key("00", "2.0").to_string().len(), 64);
+    }
+
+    #[test]
+    fn test_in_memory_cache_expiry_and_eviction() {
+        let cache = InMemoryAttestationCache::new(2);
+        let now = unix_now();
+        cache.insert(key("00", "2.0"), attestation(now + 60));
+        assert_eq!(cache.get(&key("00", "2.0")), Some(attestation(now + 60)));
+        cache.insert(key("01", "2.0"), attestation(now.saturating_sub(1)));
+        assert_eq!(cache.get(&key("01", "2.0")), None);
+        assert_eq!(cache.len(), 1);
+        cache.insert(key("02", "2.0"), attestation(now + 120));
+        cache.insert(key("03", "2.0"), attestation(now + 180));
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&key("00", "2.0")), None);
+        assert!(cache.get(&key("03", "2.0")).is_some());
+    }
+
+    #[test]
+    fn test_file_cache_round_trip() {
+        let directory =
+            std::env::temp_dir().join(format!("nvrust-attestation-cache-{}", std::process::id()));
+        let cache = FileAttestationCache::new(&directory).expect("Failed to create cache");
+        let now = unix_now();
+        cache.insert(key("00", "2.0"), attestation(now + 60));
+        assert_eq!(cache.get(&key("00", "2.0")), Some(attestation(now + 60)));
+        cache.insert(key("01", "2.0"), attestation(now.saturating_sub(1)));
+        assert_eq!(cache.get(&key("01", "2.0")), None);
+        assert!(!cache.entry_path(&key("01", "2.0")).exists());
+        std::fs::write(cache.entry_path(&key("02", "2.0")), b"not json").unwrap();
+        assert_eq!(cache.get(&key("02", "2.0")), None);
+        std::fs::remove_dir_all(directory).ok();
+    }
+}
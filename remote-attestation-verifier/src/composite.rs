@@ -0,0 +1,302 @@
+//! Composite attestation of a CPU TEE and its GPUs.
+//!
+//! Confidential AI needs both the CPU TEE running the workload and the GPUs it drives to
+//! be attested, and bound to each other: otherwise a quote from one machine could be
+//! paired with GPU evidence from another. A [`CompositeEvidence`] pairs an evidence
+//! bundle with a TDX quote or SNP report whose report data commits to the bundle:
+//!
+//! * bytes 0 to 31 hold the nonce the GPU evidence was collected with
+//! * bytes 32 to 63 hold the digest of the evidence bundle
+//!
+//! The node collects the GPU evidence first, then requests the CPU quote with
+//! [`binding_report_data`]. The verifier checks the binding with
+//! [`CompositeEvidence::check_binding`], the quote with a [`CpuQuoteVerifier`], then the
+//! GPU evidence against NRAS:
+//!
+//! ```rust,ignore
+//! let bundle = EvidenceBundle::new(&nonce, gpu_evidence, vec![], host);
+//! let quote = tdx_get_quote(&binding_report_data(&bundle)?)?;
+//! let evidence = CompositeEvidence::new(CpuTee::Tdx, &quote, bundle);
+//!
+//! let (attestation_passed, response) = evidence.verify(&dcap_verifier, options).await?;
+//! ```
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    bundle::EvidenceBundle,
+    errors::{AttestError, Result},
+    options::AttestRemoteOptions,
+    remote_gpu_attestation::verify_gpu_attestation,
+    tee::{CpuQuote, CpuTee, REPORT_DATA_LENGTH},
+    types::decode_base64_field,
+};
+
+/// Verifies the authenticity of CPU quotes.
+///
+/// Implementations check the signature of the quote and its certificate chain up to the
+/// vendor root (Intel DCAP collateral for TDX, the AMD VCEK chain for SNP), and that the
+/// TCB and measurements are acceptable. The binding to the GPU evidence is checked
+/// separately, by [`CompositeEvidence::check_binding`].
+pub trait CpuQuoteVerifier: Send + Sync {
+    /// Verifies `quote`, parsed from the raw report `raw_quote`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::CpuQuoteRejected` - If the quote is not authentic or acceptable
+    fn verify(&self, quote: &CpuQuote, raw_quote: &[u8]) -> Result<()>;
+}
+
+/// The CPU quote of a composite evidence.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CpuEvidence {
+    /// The TEE that produced the quote
+    pub tee: CpuTee,
+    /// The TDX quote or SNP report, in base64 encoded format
+    pub quote: String,
+}
+
+impl CpuEvidence {
+    /// Decodes the base64 encoded quote.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::EvidenceDecode` - If the quote is not valid base64
+    pub fn quote_bytes(&self) -> Result<Vec<u8>> {
+        decode_base64_field(&self.quote, "quote")
+    }
+}
+
+/// Evidence of a CPU TEE and of the GPUs attached to it, bound to each other.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompositeEvidence {
+    /// The CPU quote, whose report data commits to `gpu`
+    pub cpu: CpuEvidence,
+    /// The GPU evidence
+    pub gpu: EvidenceBundle,
+}
+
+/// Returns the report data binding a CPU quote to `bundle`: the bundle nonce followed by
+/// the bundle digest.
+///
+/// # Errors
+///
+/// * `AttestError::InvalidEvidenceBundle` - If the nonce or the digest of the bundle is
+///   not 32 hex encoded bytes
+pub fn binding_report_data(bundle: &EvidenceBundle) -> Result<[u8; REPORT_DATA_LENGTH]> {
+    let nonce = bundle.nonce_bytes()?;
+    let digest = hex::decode(&bundle.digest)
+        .ok()
+        .filter(|digest| digest.len() == REPORT_DATA_LENGTH - nonce.len())
+        .ok_or_else(|| {
+            AttestError::InvalidEvidenceBundle(format!(
+                "Digest `{}` is not a hex encoded 32-byte value",
+                bundle.digest
+            ))
+        })?;
+    let mut report_data = [0; REPORT_DATA_LENGTH];
+    report_data[..nonce.len()].copy_from_slice(&nonce);
+    report_data[nonce.len()..].copy_from_slice(&digest);
+    Ok(report_data)
+}
+
+impl CompositeEvidence {
+    /// Pairs the raw CPU quote `quote` of a `tee` TEE with the GPU evidence `gpu`.
+    #[must_use]
+    pub fn new(tee: CpuTee, quote: &[u8], gpu: EvidenceBundle) -> Self {
+        Self {
+            cpu: CpuEvidence {
+                tee,
+                quote: STANDARD.encode(quote),
+            },
+            gpu,
+        }
+    }
+
+    /// Validates the GPU evidence bundle, parses the CPU quote, and checks that its
+    /// report data commits to the bundle nonce and digest.
+    ///
+    /// Returns the parsed quote.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidEvidenceBundle` - If the bundle is invalid
+    /// * `AttestError::EvidenceDecode` - If the quote or some evidence is not valid base64
+    /// * `AttestError::InvalidCpuQuote` - If the quote cannot be parsed
+    /// * `AttestError::CpuBinding` - If the report data does not match the bundle
+    pub fn check_binding(&self) -> Result<CpuQuote> {
+        self.gpu.validate()?;
+        let quote = CpuQuote::parse(self.cpu.tee, &self.cpu.quote_bytes()?)?;
+        let expected = binding_report_data(&self.gpu)?;
+        let report_data = quote.report_data();
+        let nonce_length = self.gpu.nonce_bytes()?.len();
+        if report_data[..nonce_length] != expected[..nonce_length] {
+            return Err(AttestError::CpuBinding(format!(
+                "{} report data commits to nonce {}, expected {}",
+                self.cpu.tee,
+                hex::encode(&report_data[..nonce_length]),
+                self.gpu.nonce
+            )));
+        }
+        if report_data[nonce_length..] != expected[nonce_length..] {
+            return Err(AttestError::CpuBinding(format!(
+                "{} report data commits to bundle digest {}, expected {}",
+                self.cpu.tee,
+                hex::encode(&report_data[nonce_length..]),
+                self.gpu.digest
+            )));
+        }
+        Ok(quote)
+    }
+
+    /// Verifies the composite evidence: checks the binding, verifies the CPU quote with
+    /// `cpu_verifier`, then verifies the GPU evidence against NRAS.
+    ///
+    /// NRAS is only queried if the binding and the CPU quote are valid. `NVSwitch`
+    /// evidence held by the bundle is not verified.
+    ///
+    /// # Errors
+    ///
+    /// * Errors of [`CompositeEvidence::check_binding`]
+    /// * `AttestError::CpuQuoteRejected` - If `cpu_verifier` rejects the quote
+    /// * Errors of [`verify_gpu_attestation`]
+    pub async fn verify(
+        &self,
+        cpu_verifier: &dyn CpuQuoteVerifier,
+        gpu_options: AttestRemoteOptions,
+    ) -> Result<(bool, Value)> {
+        let quote = self.check_binding()?;
+        cpu_verifier.verify(&quote, &self.cpu.quote_bytes()?)?;
+        tracing::debug!(
+            level = "composite_attestation",
+            tee = %self.cpu.tee,
+            bundle_digest = %self.gpu.digest,
+            "CPU quote verified and bound to the GPU evidence"
+        );
+        verify_gpu_attestation(&self.gpu.gpu_evidence, &self.gpu.nonce, gpu_options).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::{test_support::MockNras, AttestErrorKind};
+
+    const COMPOSITE_EVIDENCE: &str = include_str!("../evidence/composite_evidence.json");
+    const SNP_REPORT: &[u8] = include_bytes!("../evidence/snp_report.bin");
+
+    fn fixture() -> CompositeEvidence {
+        serde_json::from_str(COMPOSITE_EVIDENCE).unwrap()
+    }
+
+    /// Accepts quotes whose measurement is in an allowlist, recording the quotes it saw.
+    #[derive(Default)]
+    struct AllowlistVerifier {
+        measurements: Vec<Vec<u8>>,
+        verified: Mutex<Vec<CpuTee>>,
+    }
+
+    impl CpuQuoteVerifier for AllowlistVerifier {
+        fn verify(&self, quote: &CpuQuote, _raw_quote: &[u8]) -> Result<()> {
+            let measurement = match quote {
+                CpuQuote::Tdx(quote) => quote.td_report.mr_td.to_vec(),
+                CpuQuote::Snp(report) => report.measurement.to_vec(),
+            };
+            self.verified.lock().unwrap().push(quote.tee());
+            if self.measurements.contains(&measurement) {
+                Ok(())
+            } else {
+                Err(AttestError::CpuQuoteRejected(format!(
+                    "Measurement {} is not allowed",
+                    hex::encode(measurement)
+                )))
+            }
+        }
+    }
+
+    #[test]
+    fn test_tdx_binding() {
+        let evidence = fixture();
+        assert_eq!(evidence.cpu.tee, CpuTee::Tdx);
+        let quote = evidence.check_binding().unwrap();
+        assert_eq!(quote.tee(), CpuTee::Tdx);
+        assert_eq!(
+            quote.report_data(),
+            &binding_report_data(&evidence.gpu).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_snp_binding() {
+        let evidence = CompositeEvidence::new(CpuTee::Snp, SNP_REPORT, fixture().gpu);
+        let quote = evidence.check_binding().unwrap();
+        assert!(matches!(quote, CpuQuote::Snp(_)));
+
+        // The SNP report does not parse as a TDX quote
+        let evidence = CompositeEvidence::new(CpuTee::Tdx, SNP_REPORT, fixture().gpu);
+        assert!(matches!(
+            evidence.check_binding(),
+            Err(AttestError::InvalidCpuQuote(_))
+        ));
+    }
+
+    #[test]
+    fn test_binding_mismatches() {
+        // Another nonce
+        let mut evidence = fixture();
+        evidence.gpu.nonce = "00".repeat(32);
+        evidence.gpu.digest = evidence.gpu.compute_digest();
+        let error = evidence.check_binding().unwrap_err();
+        assert!(matches!(&error, AttestError::CpuBinding(message) if message.contains("nonce")));
+        assert_eq!(error.kind(), AttestErrorKind::ClaimValidation);
+
+        // Other GPU evidence, with the same nonce
+        let mut evidence = fixture();
+        evidence.gpu.gpu_evidence.pop();
+        evidence.gpu.digest = evidence.gpu.compute_digest();
+        let error = evidence.check_binding().unwrap_err();
+        assert!(matches!(&error, AttestError::CpuBinding(message) if message.contains("digest")));
+
+        // A modified bundle is rejected before the binding is checked
+        let mut evidence = fixture();
+        evidence.gpu.gpu_evidence.pop();
+        assert!(matches!(
+            evidence.check_binding(),
+            Err(AttestError::InvalidEvidenceBundle(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_composite_verification() {
+        let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+        let evidence = fixture();
+        let CpuQuote::Tdx(quote) = evidence.check_binding().unwrap() else {
+            panic!("The fixture holds a TDX quote");
+        };
+        let verifier = AllowlistVerifier {
+            measurements: vec![quote.td_report.mr_td.to_vec()],
+            ..AllowlistVerifier::default()
+        };
+        let (attestation_passed, _) = evidence
+            .verify(&verifier, nras.gpu_options())
+            .await
+            .unwrap();
+        assert!(attestation_passed);
+        assert_eq!(*verifier.verified.lock().unwrap(), [CpuTee::Tdx]);
+        assert_eq!(nras.requests().len(), 1);
+
+        // NRAS is not queried for a rejected quote, nor for a quote bound to other evidence
+        let error = evidence
+            .verify(&AllowlistVerifier::default(), nras.gpu_options())
+            .await
+            .unwrap_err();
+        assert_eq!(error.kind(), AttestErrorKind::AttestationFailed);
+        let unbound = CompositeEvidence::new(CpuTee::Snp, &[0; 0x4A0], evidence.gpu.clone());
+        assert!(unbound.verify(&verifier, nras.gpu_options()).await.is_err());
+        assert_eq!(nras.requests().len(), 1);
+    }
+}
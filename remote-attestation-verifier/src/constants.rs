@@ -13,6 +13,31 @@ pub const REMOTE_NVSWITCH_VERIFIER_SERVICE_URL: &str =
 /// This key is used to check if certificates should be allowed to be held.
 pub const NV_ALLOW_HOLD_CERT_KEY: &str = "NV_ALLOW_HOLD_CERT";
 
+/// Environment variable key for the URL of the remote GPU verifier service.
+///
+/// Read by `AttestRemoteOptionsBuilder::with_env` for GPU evidence.
+pub const NV_ATTESTATION_VERIFIER_URL_KEY: &str = "NV_ATTESTATION_VERIFIER_URL";
+
+/// Environment variable key for the URL of the remote switch verifier service.
+///
+/// Read by `AttestRemoteOptionsBuilder::with_env` for switch evidence.
+pub const NV_ATTESTATION_SWITCH_VERIFIER_URL_KEY: &str = "NV_ATTESTATION_SWITCH_VERIFIER_URL";
+
+/// Environment variable key for the remote attestation request timeout, in seconds.
+///
+/// Read by `AttestRemoteOptionsBuilder::with_env`.
+pub const NV_ATTESTATION_TIMEOUT_SECS_KEY: &str = "NV_ATTESTATION_TIMEOUT_SECS";
+
+/// Environment variable key for the claims version of remote attestation requests.
+///
+/// Read by `AttestRemoteOptionsBuilder::with_env`.
+pub const NV_ATTESTATION_CLAIMS_VERSION_KEY: &str = "NV_ATTESTATION_CLAIMS_VERSION";
+
+/// Environment variable key for the path of the file holding the NRAS service key.
+///
+/// Read by `AttestRemoteOptionsBuilder::with_env`.
+pub const NV_ATTESTATION_SERVICE_KEY_FILE_KEY: &str = "NV_ATTESTATION_SERVICE_KEY_FILE";
+
 /// Header key for allowing certificate holds in NVIDIA OCSP requests.
 ///
 /// This header is used to indicate that the OCSP responder should allow
@@ -25,6 +50,12 @@ pub const NV_ALLOW_HOLD_CERT_KEY: &str = "NV_ALLOW_HOLD_CERT";
 /// ```
 pub const NVIDIA_OCSP_ALLOW_CERT_HOLD_HEADER: &str = "X-NVIDIA-OCSP-ALLOW-CERT-HOLD";
 
+/// Response header carrying the identifier NRAS assigned to a request.
+///
+/// The identifier is recorded as the `request_id` attribute of the `nras_request` span,
+/// to correlate a verification with the NRAS logs.
+pub const NRAS_REQUEST_ID_HEADER: &str = "x-request-id";
+
 /// Hopper architecture for remote attestation requests.
 ///
 /// This architecture is used to identify the architecture in the remote attestation request.
@@ -41,6 +72,17 @@ pub const LS10_ARCH: &str = "LS10";
 /// attestation service.
 pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
 
+/// Default lifetime of the attestation credentials issued by a `CredentialIssuer`.
+pub const DEFAULT_CREDENTIAL_LIFETIME: Duration = Duration::from_secs(60 * 60);
+
+/// Maximum clock skew tolerated between nodes and verifiers, when checking the timestamp
+/// of a `NonceBinding`.
+pub const MAX_NONCE_CLOCK_SKEW: Duration = Duration::from_secs(60);
+
+/// Default maximum age of the evidence embedded in RA-TLS certificates, from the time
+/// its nonce was derived. Nodes should issue a new certificate before it is reached.
+pub const DEFAULT_RATLS_MAX_EVIDENCE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
 /// Default claims version for remote attestation requests.
 ///
 /// This version is used as the default claims version in the remote attestation request.
@@ -80,3 +122,13 @@ pub const KEYS_KEY: &str = "keys";
 ///
 /// This key is used to identify the KID in the remote attestation request.
 pub const KID_KEY: &str = "kid";
+
+/// Expiration time claim of NRAS tokens.
+///
+/// This claim holds the expiration time of the token, in seconds since the Unix epoch.
+pub const EXP_CLAIM: &str = "exp";
+
+/// Issue time claim of NRAS tokens.
+///
+/// This claim holds the issue time of the token, in seconds since the Unix epoch.
+pub const IAT_CLAIM: &str = "iat";
@@ -0,0 +1,532 @@
+//! COSE-signed EAT tokens (CWT in a `COSE_Sign1` structure, RFC 8392 and RFC 9052).
+//!
+//! NRAS and other RATS verifiers may issue their attestation results as CBOR-encoded
+//! EAT tokens rather than JWTs. Such a token travels as the base64url encoding of a
+//! `COSE_Sign1` structure, optionally wrapped in the CWT (61) and `COSE_Sign1` (18) CBOR
+//! tags. [`CoseSign1Token`] decodes it, verifies its ES384 signature, and maps its claims
+//! to the same claims as the JWT path, e.g. [`NvidiaAttestationClaims`]:
+//!
+//! * Registered CWT and EAT claim keys are renamed to their JWT names, e.g. `4` to
+//!   `exp` and `10` to `eat_nonce`; other integer keys become decimal strings.
+//! * Byte strings are hex encoded, so nonces compare as in JWTs.
+//! * Tags are dropped, keeping the tagged value.
+//!
+//! [`NvidiaAttestationClaims`]: crate::utils::nras_token::NvidiaAttestationClaims
+
+use base64::{
+    engine::general_purpose::{STANDARD_NO_PAD, URL_SAFE_NO_PAD},
+    Engine,
+};
+use ciborium::Value as CborValue;
+use ring::signature::{UnparsedPublicKey, ECDSA_P384_SHA384_FIXED};
+use serde::de::DeserializeOwned;
+use serde_json::{Map, Number, Value};
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::{
+    constants::EXP_CLAIM,
+    errors::{AttestError, Result},
+    utils::unix_now,
+};
+
+/// CBOR tag of a `COSE_Sign1` structure.
+const COSE_SIGN1_TAG: u64 = 18;
+
+/// CBOR tag of a CWT.
+const CWT_TAG: u64 = 61;
+
+/// COSE header label of the signature algorithm.
+const ALG_LABEL: i64 = 1;
+
+/// COSE header label of the key identifier.
+const KID_LABEL: i64 = 4;
+
+/// COSE algorithm identifier of ES384.
+const ES384: i64 = -35;
+
+/// Context string of the `Sig_structure` signed by a `COSE_Sign1`.
+const SIGNATURE1_CONTEXT: &str = "Signature1";
+
+/// Leeway allowed when checking the expiration time, the `jsonwebtoken` default.
+const EXP_LEEWAY_SECS: u64 = 60;
+
+/// JWT names of the registered CWT (RFC 8392) and EAT (RFC 9711) claim keys.
+const CLAIM_NAMES: &[(i64, &str)] = &[
+    (1, "iss"),
+    (2, "sub"),
+    (3, "aud"),
+    (4, "exp"),
+    (5, "nbf"),
+    (6, "iat"),
+    (7, "jti"),
+    (10, "eat_nonce"),
+    (256, "ueid"),
+    (258, "oemid"),
+    (259, "hwmodel"),
+    (260, "hwversion"),
+    (263, "dbgstat"),
+    (265, "eat_profile"),
+    (266, "submods"),
+    (273, "measurements"),
+    (274, "measres"),
+];
+
+/// Encoding of an NRAS token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenFormat {
+    /// A JWT, signed with JWS
+    Jwt,
+    /// A CWT, signed with `COSE_Sign1` and base64url encoded
+    Cwt,
+}
+
+impl TokenFormat {
+    /// Detects the format of `token`.
+    ///
+    /// A token is a CWT if it is base64 (or base64url) encoded, and starts with the CWT
+    /// tag, the `COSE_Sign1` tag or a four element CBOR array. Any other token is taken as
+    /// a JWT, and reported as such if it fails to decode.
+    #[must_use]
+    pub fn detect(token: &str) -> Self {
+        match decode_base64(token).as_deref() {
+            Some([0xd8, 0x3d, ..] | [0xd2 | 0x84, ..]) => Self::Cwt,
+            _ => Self::Jwt,
+        }
+    }
+}
+
+/// Decodes base64url or base64, with or without padding.
+fn decode_base64(token: &str) -> Option<Vec<u8>> {
+    let token = token.trim().trim_end_matches('=');
+    URL_SAFE_NO_PAD
+        .decode(token)
+        .or_else(|_| STANDARD_NO_PAD.decode(token))
+        .ok()
+}
+
+/// A decoded, not yet verified, `COSE_Sign1` token.
+#[derive(Debug, Clone)]
+pub struct CoseSign1Token {
+    protected: Vec<u8>,
+    alg: Option<i64>,
+    kid: Option<String>,
+    payload: Vec<u8>,
+    signature: Vec<u8>,
+}
+
+impl CoseSign1Token {
+    /// Decodes a base64url (or base64) encoded token.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidCoseToken` - If the token is not a valid `COSE_Sign1` structure
+    pub fn decode(token: &str) -> Result<Self> {
+        let bytes = decode_base64(token)
+            .ok_or_else(|| invalid("the token is not base64url encoded".to_string()))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Decodes a CBOR encoded token.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidCoseToken` - If the token is not a valid `COSE_Sign1` structure
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        let mut value: CborValue = ciborium::from_reader(bytes)
+            .map_err(|e| invalid(format!("the token is not valid CBOR: {e}")))?;
+        if let CborValue::Tag(CWT_TAG, inner) = value {
+            value = *inner;
+        }
+        if let CborValue::Tag(COSE_SIGN1_TAG, inner) = value {
+            value = *inner;
+        }
+        let CborValue::Array(items) = value else {
+            return Err(invalid("the token is not a COSE_Sign1 array".to_string()));
+        };
+        let items: [CborValue; 4] = items.try_into().map_err(|items: Vec<CborValue>| {
+            invalid(format!(
+                "a COSE_Sign1 has 4 elements, the token has {}",
+                items.len()
+            ))
+        })?;
+        let [CborValue::Bytes(protected), CborValue::Map(unprotected), CborValue::Bytes(payload), CborValue::Bytes(signature)] =
+            items
+        else {
+            return Err(invalid(
+                "the COSE_Sign1 elements do not have the expected types".to_string(),
+            ));
+        };
+        let protected_header = if protected.is_empty() {
+            Vec::new()
+        } else {
+            match ciborium::from_reader(protected.as_slice()) {
+                Ok(CborValue::Map(header)) => header,
+                _ => {
+                    return Err(invalid(
+                        "the protected header is not a CBOR map".to_string(),
+                    ))
+                }
+            }
+        };
+        let alg = header_value(&protected_header, ALG_LABEL).and_then(as_i64);
+        let kid = header_value(&protected_header, KID_LABEL)
+            .or_else(|| header_value(&unprotected, KID_LABEL))
+            .map(|kid| match kid {
+                CborValue::Bytes(bytes) => String::from_utf8(bytes.clone())
+                    .map_err(|_| invalid("the key identifier is not UTF-8".to_string())),
+                CborValue::Text(text) => Ok(text.clone()),
+                _ => Err(invalid("the key identifier is not a string".to_string())),
+            })
+            .transpose()?;
+        Ok(Self {
+            protected,
+            alg,
+            kid,
+            payload,
+            signature,
+        })
+    }
+
+    /// Returns the key identifier of the signing key, if any.
+    #[must_use]
+    pub fn kid(&self) -> Option<&str> {
+        self.kid.as_deref()
+    }
+
+    /// Verifies the ES384 signature with the public key of `cert_der`, and returns the
+    /// claims of the token.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::CertificateParseError` - If the certificate cannot be parsed
+    /// * `AttestError::InvalidCoseToken` - If the algorithm is not ES384, the signature
+    ///   does not match, or the payload is not a CBOR map of claims
+    /// * `AttestError::ClaimValidation` - If the token has no expiration time, has expired,
+    ///   or its claims do not match `T`
+    pub fn verify<T: DeserializeOwned>(&self, cert_der: &[u8]) -> Result<T> {
+        if self.alg != Some(ES384) {
+            return Err(invalid(format!(
+                "the algorithm must be ES384 ({ES384}), not {:?}",
+                self.alg
+            )));
+        }
+        let (_, cert) = X509Certificate::from_der(cert_der)?;
+        let public_key = cert.public_key().subject_public_key.data.as_ref();
+        UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, public_key)
+            .verify(&self.signed_data()?, &self.signature)
+            .map_err(|_| invalid("signature verification failed".to_string()))?;
+
+        let claims: CborValue = ciborium::from_reader(self.payload.as_slice())
+            .map_err(|e| invalid(format!("the payload is not valid CBOR: {e}")))?;
+        let CborValue::Map(claims) = claims else {
+            return Err(invalid("the payload is not a CBOR map".to_string()));
+        };
+        let claims = claims_to_json(&claims);
+        validate_exp(&claims)?;
+        serde_json::from_value(Value::Object(claims))
+            .map_err(|e| AttestError::ClaimValidation(e.to_string()))
+    }
+
+    /// Returns the encoded `Sig_structure` covered by the signature.
+    fn signed_data(&self) -> Result<Vec<u8>> {
+        let structure = CborValue::Array(vec![
+            CborValue::Text(SIGNATURE1_CONTEXT.to_string()),
+            CborValue::Bytes(self.protected.clone()),
+            CborValue::Bytes(Vec::new()),
+            CborValue::Bytes(self.payload.clone()),
+        ]);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&structure, &mut bytes)
+            .map_err(|e| invalid(format!("failed to encode the signed data: {e}")))?;
+        Ok(bytes)
+    }
+}
+
+const fn invalid(message: String) -> AttestError {
+    AttestError::InvalidCoseToken(message)
+}
+
+/// Returns `value` if it is an integer in the `i64` range.
+fn as_i64(value: &CborValue) -> Option<i64> {
+    value
+        .as_integer()
+        .and_then(|integer| i64::try_from(integer).ok())
+}
+
+/// Returns the value of the integer `label` in a COSE header.
+fn header_value(header: &[(CborValue, CborValue)], label: i64) -> Option<&CborValue> {
+    header
+        .iter()
+        .find(|(key, _)| as_i64(key) == Some(label))
+        .map(|(_, value)| value)
+}
+
+/// Checks that the claims hold an expiration time that has not passed.
+fn validate_exp(claims: &Map<String, Value>) -> Result<()> {
+    let exp = claims
+        .get(EXP_CLAIM)
+        .and_then(Value::as_u64)
+        .ok_or_else(|| AttestError::ClaimValidation("Missing required claim `exp`".to_string()))?;
+    if exp.saturating_add(EXP_LEEWAY_SECS) < unix_now() {
+        return Err(AttestError::ClaimValidation(
+            "Token has expired".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Converts a CBOR map of claims to JSON, naming registered claims like JWTs do.
+fn claims_to_json(claims: &[(CborValue, CborValue)]) -> Map<String, Value> {
+    claims
+        .iter()
+        .map(|(key, value)| {
+            let name = as_i64(key).and_then(|label| {
+                CLAIM_NAMES
+                    .iter()
+                    .find(|(registered, _)| *registered == label)
+                    .map(|(_, name)| (*name).to_string())
+            });
+            let key = name.unwrap_or_else(|| map_key(key));
+            (key, cbor_to_json(value))
+        })
+        .collect()
+}
+
+/// Converts a CBOR map key other than a registered claim to a JSON object key.
+fn map_key(key: &CborValue) -> String {
+    match key {
+        CborValue::Text(text) => text.clone(),
+        CborValue::Integer(integer) => i128::from(*integer).to_string(),
+        key => cbor_to_json(key).to_string(),
+    }
+}
+
+/// Converts a CBOR value to JSON.
+fn cbor_to_json(value: &CborValue) -> Value {
+    match value {
+        CborValue::Integer(integer) => {
+            let integer = i128::from(*integer);
+            u64::try_from(integer)
+                .map(Value::from)
+                .or_else(|_| i64::try_from(integer).map(Value::from))
+                .unwrap_or_else(|_| Value::String(integer.to_string()))
+        }
+        CborValue::Bytes(bytes) => Value::String(hex::encode(bytes)),
+        CborValue::Float(float) => Number::from_f64(*float).map_or(Value::Null, Value::Number),
+        CborValue::Text(text) => Value::String(text.clone()),
+        CborValue::Bool(boolean) => Value::Bool(*boolean),
+        CborValue::Tag(_, inner) => cbor_to_json(inner),
+        CborValue::Array(items) => Value::Array(items.iter().map(cbor_to_json).collect()),
+        CborValue::Map(entries) => Value::Object(
+            entries
+                .iter()
+                .map(|(key, value)| (map_key(key), cbor_to_json(value)))
+                .collect(),
+        ),
+        _ => Value::Null,
+    }
+}
+
+/// Encodes `claims` as a CWT signed with ES384 by `signing_key`, base64url encoded.
+///
+/// Registered claims are stored under their integer keys, and the `jti`, `eat_nonce` and
+/// `ueid` claims as byte strings when they are hex encoded, so that [`CoseSign1Token`]
+/// decodes the same claims.
+#[cfg(any(test, feature = "test-support"))]
+pub(crate) fn encode_cwt(
+    claims: &Value,
+    kid: &str,
+    signing_key: &ring::signature::EcdsaKeyPair,
+) -> std::result::Result<String, String> {
+    const BYTE_CLAIMS: &[&str] = &["jti", "eat_nonce", "ueid"];
+
+    fn json_to_cbor(value: &Value) -> CborValue {
+        match value {
+            Value::Null => CborValue::Null,
+            Value::Bool(boolean) => CborValue::Bool(*boolean),
+            Value::Number(number) => number
+                .as_u64()
+                .map(CborValue::from)
+                .or_else(|| number.as_i64().map(CborValue::from))
+                .unwrap_or_else(|| CborValue::Float(number.as_f64().unwrap_or_default())),
+            Value::String(text) => CborValue::Text(text.clone()),
+            Value::Array(items) => CborValue::Array(items.iter().map(json_to_cbor).collect()),
+            Value::Object(entries) => CborValue::Map(
+                entries
+                    .iter()
+                    .map(|(key, value)| (CborValue::Text(key.clone()), json_to_cbor(value)))
+                    .collect(),
+            ),
+        }
+    }
+
+    fn to_bytes(value: &CborValue) -> std::result::Result<Vec<u8>, String> {
+        let mut bytes = Vec::new();
+        ciborium::into_writer(value, &mut bytes).map_err(|e| e.to_string())?;
+        Ok(bytes)
+    }
+
+    let Value::Object(claims) = claims else {
+        return Err("claims must be a JSON object".to_string());
+    };
+    let claims = CborValue::Map(
+        claims
+            .iter()
+            .map(|(name, value)| {
+                let key = CLAIM_NAMES
+                    .iter()
+                    .find(|(_, registered)| registered == name)
+                    .map_or_else(
+                        || CborValue::Text(name.clone()),
+                        |(label, _)| CborValue::from(*label),
+                    );
+                let value = match value.as_str().map(hex::decode) {
+                    Some(Ok(bytes)) if BYTE_CLAIMS.contains(&name.as_str()) => {
+                        CborValue::Bytes(bytes)
+                    }
+                    _ => json_to_cbor(value),
+                };
+                (key, value)
+            })
+            .collect(),
+    );
+    let protected = to_bytes(&CborValue::Map(vec![
+        (CborValue::from(ALG_LABEL), CborValue::from(ES384)),
+        (
+            CborValue::from(KID_LABEL),
+            CborValue::Bytes(kid.as_bytes().to_vec()),
+        ),
+    ]))?;
+    let mut token = CoseSign1Token {
+        protected,
+        alg: Some(ES384),
+        kid: Some(kid.to_string()),
+        payload: to_bytes(&claims)?,
+        signature: Vec::new(),
+    };
+    let signed_data = token.signed_data().map_err(|e| e.to_string())?;
+    token.signature = signing_key
+        .sign(&ring::rand::SystemRandom::new(), &signed_data)
+        .map_err(|e| e.to_string())?
+        .as_ref()
+        .to_vec();
+    let cose_sign1 = CborValue::Tag(
+        CWT_TAG,
+        Box::new(CborValue::Tag(
+            COSE_SIGN1_TAG,
+            Box::new(CborValue::Array(vec![
+                CborValue::Bytes(token.protected),
+                CborValue::Map(Vec::new()),
+                CborValue::Bytes(token.payload),
+                CborValue::Bytes(token.signature),
+            ])),
+        )),
+    );
+    Ok(URL_SAFE_NO_PAD.encode(to_bytes(&cose_sign1)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{CertificateParams, KeyPair, PKCS_ECDSA_P384_SHA384};
+    use ring::{
+        rand::SystemRandom,
+        signature::{EcdsaKeyPair, ECDSA_P384_SHA384_FIXED_SIGNING},
+    };
+    use serde_json::json;
+
+    use super::*;
+    use crate::utils::nras_token::NvidiaAttestationClaims;
+
+    /// Returns a signing key and the DER encoded certificate of its public key.
+    fn generate_key() -> (EcdsaKeyPair, Vec<u8>) {
+        let key_pair = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384).unwrap();
+        let certificate = CertificateParams::new(vec!["nras".to_string()])
+            .unwrap()
+            .self_signed(&key_pair)
+            .unwrap();
+        let signing_key = EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P384_SHA384_FIXED_SIGNING,
+            &key_pair.serialize_der(),
+            &SystemRandom::new(),
+        )
+        .unwrap();
+        (signing_key, certificate.der().to_vec())
+    }
+
+    fn claims(exp: u64) -> Value {
+        json!({
+            "iss": "https://nras.example",
+            "exp": exp,
+            "eat_nonce": "00ff",
+            "x-nvidia-overall-att-result": true,
+            "submods": {"GPU-0": ["DETACHED", "GPU-0"]},
+        })
+    }
+
+    #[test]
+    fn test_decode_and_verify() {
+        let (signing_key, cert_der) = generate_key();
+        let token = encode_cwt(&claims(unix_now() + 3600), "nras-key", &signing_key).unwrap();
+        assert_eq!(TokenFormat::detect(&token), TokenFormat::Cwt);
+
+        let cose = CoseSign1Token::decode(&token).unwrap();
+        assert_eq!(cose.kid(), Some("nras-key"));
+        let claims: NvidiaAttestationClaims = cose.verify(&cert_der).unwrap();
+        assert!(claims.overall_attestation_result);
+        assert_eq!(claims.additional_claims["iss"], "https://nras.example");
+        assert_eq!(claims.additional_claims["eat_nonce"], "00ff");
+        assert_eq!(
+            claims.additional_claims["submods"]["GPU-0"],
+            json!(["DETACHED", "GPU-0"])
+        );
+    }
+
+    #[test]
+    fn test_verify_failures() {
+        let (signing_key, cert_der) = generate_key();
+        let (_, other_cert_der) = generate_key();
+
+        let token = encode_cwt(&claims(unix_now() + 3600), "nras-key", &signing_key).unwrap();
+        let error = CoseSign1Token::decode(&token)
+            .unwrap()
+            .verify::<NvidiaAttestationClaims>(&other_cert_der)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::InvalidCoseToken(_)));
+        assert_eq!(error.kind(), crate::AttestErrorKind::TokenSignature);
+
+        let mut tampered = CoseSign1Token::decode(&token).unwrap();
+        tampered.payload.push(0);
+        assert!(tampered
+            .verify::<NvidiaAttestationClaims>(&cert_der)
+            .is_err());
+
+        let expired = encode_cwt(&claims(unix_now() - 3600), "nras-key", &signing_key).unwrap();
+        let error = CoseSign1Token::decode(&expired)
+            .unwrap()
+            .verify::<NvidiaAttestationClaims>(&cert_der)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::ClaimValidation(_)));
+
+        let mut unsupported = CoseSign1Token::decode(&token).unwrap();
+        unsupported.alg = Some(-7);
+        assert!(matches!(
+            unsupported.verify::<NvidiaAttestationClaims>(&cert_der),
+            Err(AttestError::InvalidCoseToken(_))
+        ));
+    }
+
+    #[test]
+    fn test_detect_format() {
+        assert_eq!(
+            TokenFormat::detect("eyJhbGciOiJFUzM4NCJ9.eyJleHAiOjB9.c2ln"),
+            TokenFormat::Jwt
+        );
+        assert_eq!(TokenFormat::detect("not a token"), TokenFormat::Jwt);
+        // An untagged COSE_Sign1, in padded standard base64
+        assert_eq!(TokenFormat::detect("hEOhASI="), TokenFormat::Cwt);
+        assert!(matches!(
+            CoseSign1Token::decode("hEOhASI="),
+            Err(AttestError::InvalidCoseToken(_))
+        ));
+    }
+}
@@ -0,0 +1,510 @@
+//! Attestation credentials issued by the relying party.
+//!
+//! Once NRAS has verified a node's evidence, a relying party (e.g. the Atoma proxy) can
+//! hand its clients a compact credential stating "node X's devices were attested at
+//! time T with result R", signed with its own key rather than NRAS's:
+//!
+//! ```rust,ignore
+//! let issuer = CredentialIssuer::new("https://proxy.atoma.network", "proxy-2025-01", &pkcs8)?;
+//! let (_, response) = verify_gpu_attestation(&evidence, &nonce, options).await?;
+//! let credential = issuer.issue("node-42", verifier_url, &response).await?;
+//!
+//! // Clients fetch the published JWKS, e.g. from `/.well-known/jwks.json`
+//! let verifier = CredentialVerifier::new("https://proxy.atoma.network", &issuer.jwks())?;
+//! let claims = verifier.verify(&credential)?;
+//! ```
+//!
+//! Credentials are JWTs signed with Ed25519 (`EdDSA`), the key type nodes already use to
+//! sign evidence bundles. Issuers are identified by a key id, so several keys can be
+//! published in the same JWKS while keys are rotated.
+
+use std::time::Duration;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{
+    decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation,
+};
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use crate::{
+    constants::{DEFAULT_CREDENTIAL_LIFETIME, EXP_CLAIM, IAT_CLAIM, KEYS_KEY},
+    errors::{AttestError, Result},
+    utils::{
+        get_overall_claims_token,
+        nras_token::{decode_nras_token, get_matching_key, NvidiaAttestationClaims},
+        unix_now,
+    },
+};
+
+/// Claims of an attestation credential.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationCredentialClaims {
+    /// Issuer of the credential, the relying party
+    pub iss: String,
+    /// Attested node
+    pub sub: String,
+    /// Intended audience of the credential, if restricted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Issue time, in seconds since the Unix epoch
+    pub iat: u64,
+    /// Expiration time, in seconds since the Unix epoch
+    pub exp: u64,
+    /// Unique identifier of the credential
+    pub jti: String,
+    /// Overall attestation result returned by NRAS
+    #[serde(rename = "x-nvrust-att-result")]
+    pub attestation_result: bool,
+    /// Time NRAS issued the attestation result, in seconds since the Unix epoch
+    #[serde(rename = "x-nvrust-attested-at")]
+    pub attested_at: u64,
+    /// Devices covered by the attestation, as named by NRAS (e.g. `GPU-0`)
+    #[serde(rename = "x-nvrust-devices", default)]
+    pub devices: Vec<String>,
+    /// Blake3 digest of the overall NRAS token, linking the credential to the NRAS result
+    #[serde(rename = "x-nvrust-nras-token-digest")]
+    pub nras_token_digest: String,
+}
+
+/// Mints attestation credentials, signed with the relying party's Ed25519 key.
+pub struct CredentialIssuer {
+    issuer: String,
+    key_id: String,
+    encoding_key: EncodingKey,
+    public_key: Vec<u8>,
+    lifetime: Duration,
+    audience: Option<String>,
+}
+
+impl CredentialIssuer {
+    /// Creates an issuer named `issuer`, signing with the PKCS#8 encoded Ed25519 key
+    /// `pkcs8`, published under `key_id`.
+    ///
+    /// Credentials are valid for [`DEFAULT_CREDENTIAL_LIFETIME`] unless set otherwise with
+    /// [`CredentialIssuer::with_lifetime`].
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidSigningKey` - If `pkcs8` is not a valid Ed25519 key
+    pub fn new(issuer: impl Into<String>, key_id: impl Into<String>, pkcs8: &[u8]) -> Result<Self> {
+        let key_pair = Ed25519KeyPair::from_pkcs8(pkcs8)
+            .map_err(|e| AttestError::InvalidSigningKey(e.to_string()))?;
+        Ok(Self {
+            issuer: issuer.into(),
+            key_id: key_id.into(),
+            encoding_key: EncodingKey::from_ed_der(pkcs8),
+            public_key: key_pair.public_key().as_ref().to_vec(),
+            lifetime: DEFAULT_CREDENTIAL_LIFETIME,
+            audience: None,
+        })
+    }
+
+    /// Sets how long credentials are valid after they are issued. Credentials never
+    /// outlive the NRAS token they are minted from.
+    #[must_use]
+    pub const fn with_lifetime(mut self, lifetime: Duration) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// Restricts credentials to `audience`.
+    #[must_use]
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Returns the issuer name.
+    #[must_use]
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Returns the key id of the signing key.
+    #[must_use]
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Returns how long credentials are valid after they are issued.
+    #[must_use]
+    pub const fn lifetime(&self) -> Duration {
+        self.lifetime
+    }
+
+    /// Returns the audience credentials are restricted to, if any.
+    #[must_use]
+    pub fn audience(&self) -> Option<&str> {
+        self.audience.as_deref()
+    }
+
+    /// Mints a credential for `subject` from an NRAS response, as returned by
+    /// [`crate::verify_gpu_attestation`] or [`crate::verify_nvswitch_attestation`].
+    ///
+    /// The overall token of the response is decoded and verified against the JWKS of
+    /// `verifier_url`, so that the credential states the result of the very token it
+    /// carries the digest of. The credential states the attestation time of the token,
+    /// and expires with it at the latest.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::MalformedResponse` - If `nras_response` holds no overall NRAS token
+    /// * Errors propagated from [`decode_nras_token`] if the overall token cannot be
+    ///   decoded or verified
+    /// * `AttestError::RemoteAttestationFailed` - If the overall attestation result is
+    ///   negative
+    /// * `AttestError::ClaimValidation` - If the token has no issue or expiration time, or
+    ///   has expired
+    /// * `AttestError::TokenSignature` - If the credential cannot be signed
+    pub async fn issue(
+        &self,
+        subject: &str,
+        verifier_url: &str,
+        nras_response: &Value,
+    ) -> Result<String> {
+        let nras_token = get_overall_claims_token(nras_response)?;
+        let claims = decode_nras_token(verifier_url, &nras_token).await?;
+        self.issue_at(subject, &claims, nras_response, unix_now())
+    }
+
+    /// Mints a credential from the claims of the overall token of `nras_response`, which
+    /// the caller has decoded and verified.
+    fn issue_at(
+        &self,
+        subject: &str,
+        claims: &NvidiaAttestationClaims,
+        nras_response: &Value,
+        now: u64,
+    ) -> Result<String> {
+        if !claims.overall_attestation_result {
+            return Err(AttestError::RemoteAttestationFailed);
+        }
+        let time_claim = |name: &str| {
+            claims
+                .additional_claims
+                .get(name)
+                .and_then(Value::as_u64)
+                .ok_or_else(|| {
+                    AttestError::ClaimValidation(format!("NRAS token has no `{name}` claim"))
+                })
+        };
+        let (attested_at, token_expires_at) = (time_claim(IAT_CLAIM)?, time_claim(EXP_CLAIM)?);
+        if token_expires_at <= now {
+            return Err(AttestError::ClaimValidation(
+                "NRAS token has expired".to_string(),
+            ));
+        }
+        let nras_token = get_overall_claims_token(nras_response)?;
+        let mut devices: Vec<String> = nras_response
+            .get(1)
+            .and_then(Value::as_object)
+            .map(|device_tokens| device_tokens.keys().cloned().collect())
+            .unwrap_or_default();
+        devices.sort();
+        let claims = AttestationCredentialClaims {
+            iss: self.issuer.clone(),
+            sub: subject.to_string(),
+            aud: self.audience.clone(),
+            iat: now,
+            exp: now
+                .saturating_add(self.lifetime.as_secs())
+                .min(token_expires_at),
+            jti: hex::encode(rand::random::<[u8; 16]>()),
+            attestation_result: true,
+            attested_at,
+            devices,
+            nras_token_digest: blake3::hash(nras_token.as_bytes()).to_hex().to_string(),
+        };
+        self.sign(&claims)
+    }
+
+    /// Signs `claims` as an `EdDSA` JWT, with the key id of the signing key.
+    pub(crate) fn sign(&self, claims: &impl Serialize) -> Result<String> {
+        let mut header = Header::new(Algorithm::EdDSA);
+        header.kid = Some(self.key_id.clone());
+        encode(&header, claims, &self.encoding_key).map_err(AttestError::TokenSignature)
+    }
+
+    /// Returns the JWK of the signing key.
+    #[must_use]
+    pub fn jwk(&self) -> Value {
+        json!({
+            "kty": "OKP",
+            "crv": "Ed25519",
+            "use": "sig",
+            "alg": "EdDSA",
+            "kid": self.key_id,
+            "x": URL_SAFE_NO_PAD.encode(&self.public_key),
+        })
+    }
+
+    /// Returns the JWKS to publish, holding the signing key only.
+    ///
+    /// During a key rotation, publish the keys of both issuers with [`jwks`].
+    #[must_use]
+    pub fn jwks(&self) -> Value {
+        jwks(&[self])
+    }
+}
+
+/// Returns a JWKS holding the signing keys of `issuers`.
+#[must_use]
+pub fn jwks(issuers: &[&CredentialIssuer]) -> Value {
+    json!({ KEYS_KEY: issuers.iter().map(|issuer| issuer.jwk()).collect::<Vec<_>>() })
+}
+
+/// Verifies attestation credentials against the published JWKS of their issuer.
+pub struct CredentialVerifier {
+    issuer: String,
+    jwks: Value,
+    audience: Option<String>,
+}
+
+impl CredentialVerifier {
+    /// Creates a verifier accepting credentials of `issuer`, signed by a key of `jwks`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidSigningKey` - If `jwks` holds no keys
+    pub fn new(issuer: impl Into<String>, jwks: &Value) -> Result<Self> {
+        if jwks
+            .get(KEYS_KEY)
+            .and_then(Value::as_array)
+            .is_none_or(Vec::is_empty)
+        {
+            return Err(AttestError::InvalidSigningKey(
+                "The JWKS holds no keys".to_string(),
+            ));
+        }
+        Ok(Self {
+            issuer: issuer.into(),
+            jwks: jwks.clone(),
+            audience: None,
+        })
+    }
+
+    /// Only accepts credentials issued for `audience`.
+    #[must_use]
+    pub fn with_audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Verifies the signature, issuer, audience and expiration of `credential`, and
+    /// returns its claims.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::HeaderDecodeError` - If the credential is not a JWT
+    /// * `AttestError::InvalidJwtToken` - If the credential has no key id, or its key is
+    ///   not in the JWKS
+    /// * `AttestError::TokenSignature` - If the signature is invalid
+    /// * `AttestError::ClaimValidation` - If the credential has expired, or was issued by
+    ///   another issuer or for another audience
+    pub fn verify(&self, credential: &str) -> Result<AttestationCredentialClaims> {
+        self.verify_claims(credential)
+    }
+
+    /// Verifies a token signed by the issuer like a credential, e.g. a
+    /// [`crate::ear::SignedEar`], and returns its claims as `T`.
+    ///
+    /// # Errors
+    ///
+    /// The errors of [`CredentialVerifier::verify`]; claims that cannot be deserialized
+    /// as `T` are reported as `AttestError::TokenSignature`.
+    pub fn verify_claims<T: DeserializeOwned>(&self, credential: &str) -> Result<T> {
+        let kid = decode_header(credential)?.kid.ok_or_else(|| {
+            AttestError::InvalidJwtToken("Kid not found in credential header".to_string())
+        })?;
+        let x = get_matching_key(&self.jwks, &kid)
+            .filter(|key| key.get("crv").and_then(Value::as_str) == Some("Ed25519"))
+            .and_then(|key| key.get("x"))
+            .and_then(Value::as_str)
+            .ok_or_else(|| {
+                AttestError::InvalidJwtToken(format!("No Ed25519 key `{kid}` in the JWKS"))
+            })?;
+        let decoding_key =
+            DecodingKey::from_ed_components(x).map_err(AttestError::TokenSignature)?;
+        let mut validation = Validation::new(Algorithm::EdDSA);
+        validation.set_issuer(&[&self.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "sub"]);
+        match &self.audience {
+            Some(audience) => validation.set_audience(&[audience]),
+            None => validation.validate_aud = false,
+        }
+        decode::<T>(credential, &decoding_key, &validation)
+            .map(|token_data| token_data.claims)
+            .map_err(AttestError::from_jwt_error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::rand::SystemRandom;
+
+    use super::*;
+    use crate::AttestErrorKind;
+
+    const ISSUER: &str = "https://proxy.example";
+
+    fn issuer(key_id: &str) -> CredentialIssuer {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        CredentialIssuer::new(ISSUER, key_id, pkcs8.as_ref()).unwrap()
+    }
+
+    fn nras_response() -> Value {
+        json!([["JWT", "overall-token"], {"GPU-1": "token", "GPU-0": "token"}])
+    }
+
+    fn nras_claims(attestation_result: bool, iat: u64, exp: u64) -> NvidiaAttestationClaims {
+        serde_json::from_value(json!({
+            "x-nvidia-overall-att-result": attestation_result,
+            "iat": iat,
+            "exp": exp,
+        }))
+        .unwrap()
+    }
+
+    fn passed_claims() -> NvidiaAttestationClaims {
+        let now = unix_now();
+        nras_claims(true, now - 10, now + 86_400)
+    }
+
+    #[test]
+    fn test_issue_and_verify() {
+        let issuer = issuer("key-1");
+        let now = unix_now();
+        let token_claims = nras_claims(true, now - 10, now + 86_400);
+        let credential = issuer
+            .issue_at("node-42", &token_claims, &nras_response(), now)
+            .unwrap();
+        let verifier = CredentialVerifier::new(ISSUER, &issuer.jwks()).unwrap();
+        let claims = verifier.verify(&credential).unwrap();
+        assert_eq!(claims.iss, ISSUER);
+        assert_eq!(claims.sub, "node-42");
+        assert!(claims.attestation_result);
+        assert_eq!(claims.attested_at, now - 10);
+        assert_eq!(claims.devices, ["GPU-0", "GPU-1"]);
+        assert_eq!(
+            claims.exp - claims.iat,
+            DEFAULT_CREDENTIAL_LIFETIME.as_secs()
+        );
+        assert_eq!(
+            claims.nras_token_digest,
+            blake3::hash(b"overall-token").to_hex().to_string()
+        );
+
+        // Credentials expire with the NRAS token
+        let credential = issuer
+            .issue_at(
+                "node-42",
+                &nras_claims(true, now, now + 60),
+                &nras_response(),
+                now,
+            )
+            .unwrap();
+        assert_eq!(verifier.verify(&credential).unwrap().exp, now + 60);
+
+        assert!(matches!(
+            issuer.issue_at(
+                "node-42",
+                &nras_claims(false, now, now + 60),
+                &nras_response(),
+                now
+            ),
+            Err(AttestError::RemoteAttestationFailed)
+        ));
+        assert!(matches!(
+            issuer.issue_at(
+                "node-42",
+                &nras_claims(true, now - 60, now),
+                &nras_response(),
+                now
+            ),
+            Err(AttestError::ClaimValidation(_))
+        ));
+        let claims = serde_json::from_value(json!({"x-nvidia-overall-att-result": true}));
+        assert!(matches!(
+            issuer.issue_at("node-42", &claims.unwrap(), &nras_response(), now),
+            Err(AttestError::ClaimValidation(_))
+        ));
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let (old, new) = (issuer("key-1"), issuer("key-2"));
+        let verifier = CredentialVerifier::new(ISSUER, &jwks(&[&old, &new])).unwrap();
+        for issuer in [&old, &new] {
+            let credential = issuer
+                .issue_at("node-42", &passed_claims(), &nras_response(), unix_now())
+                .unwrap();
+            assert!(verifier.verify(&credential).is_ok());
+        }
+
+        // A credential of a key absent from the JWKS is rejected
+        let verifier = CredentialVerifier::new(ISSUER, &new.jwks()).unwrap();
+        let credential = old
+            .issue_at("node-42", &passed_claims(), &nras_response(), unix_now())
+            .unwrap();
+        assert!(matches!(
+            verifier.verify(&credential),
+            Err(AttestError::InvalidJwtToken(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_failures() {
+        let issuer = issuer("key-1").with_audience("clients");
+        let credential = issuer
+            .issue_at("node-42", &passed_claims(), &nras_response(), unix_now())
+            .unwrap();
+
+        let error = CredentialVerifier::new("https://other.example", &issuer.jwks())
+            .unwrap()
+            .verify(&credential)
+            .unwrap_err();
+        assert_eq!(error.kind(), AttestErrorKind::ClaimValidation);
+
+        let verifier = CredentialVerifier::new(ISSUER, &issuer.jwks()).unwrap();
+        assert!(verifier
+            .with_audience("servers")
+            .verify(&credential)
+            .is_err());
+
+        let verifier = CredentialVerifier::new(ISSUER, &issuer.jwks())
+            .unwrap()
+            .with_audience("clients");
+        assert!(verifier.verify(&credential).is_ok());
+
+        let expired = issuer
+            .issue_at(
+                "node-42",
+                &passed_claims(),
+                &nras_response(),
+                unix_now() - 86_400,
+            )
+            .unwrap();
+        let error = verifier.verify(&expired).unwrap_err();
+        assert_eq!(error.kind(), AttestErrorKind::ClaimValidation);
+
+        // A credential signed by another key under the same key id is rejected
+        let impostor = self::issuer("key-1").with_audience("clients");
+        let forged = impostor
+            .issue_at("node-42", &passed_claims(), &nras_response(), unix_now())
+            .unwrap();
+        let error = verifier.verify(&forged).unwrap_err();
+        assert_eq!(error.kind(), AttestErrorKind::TokenSignature);
+
+        assert!(matches!(
+            issuer.issue_at("node-42", &passed_claims(), &json!({}), unix_now()),
+            Err(AttestError::MalformedResponse(_))
+        ));
+        assert!(matches!(
+            CredentialIssuer::new(ISSUER, "key-1", b"not a key"),
+            Err(AttestError::InvalidSigningKey(_))
+        ));
+        assert!(CredentialVerifier::new(ISSUER, &json!({"keys": []})).is_err());
+    }
+}
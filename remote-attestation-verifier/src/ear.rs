@@ -0,0 +1,561 @@
+//! Attestation results in the EAR (EAT Attestation Result) format.
+//!
+//! An [`Ear`] reports the appraisal of every attested component, the GPUs and switches
+//! verified by NRAS as well as the relying party's own checks (e.g. topology), in the
+//! format of [draft-fv-rats-ear], so that Veraison-compatible tooling and policy engines
+//! can consume it without NVIDIA-specific parsing:
+//!
+//! ```rust,ignore
+//! let (attestation_passed, response) = verify_gpu_attestation(&evidence, &nonce, options).await?;
+//! let mut ear = Ear::new().with_nonce(&nonce);
+//! ear.add_nras_response(REMOTE_GPU_VERIFIER_SERVICE_URL, &response).await?;
+//! ear.add_check("topology", topology_passed);
+//! let document = serde_json::to_string(&ear)?;
+//! ```
+//!
+//! Each component is appraised with an AR4SI trustworthiness vector ([draft-ietf-rats-ar4si])
+//! and a status, the worst trust tier of its vector. NRAS device claims are mapped to the
+//! `instance-identity`, `hardware`, `executables` and `configuration` claims of the
+//! vector, and local checks to its `configuration` claim.
+//!
+//! [draft-fv-rats-ear]: https://datatracker.ietf.org/doc/draft-fv-rats-ear/
+//! [draft-ietf-rats-ar4si]: https://datatracker.ietf.org/doc/draft-ietf-rats-ar4si/
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    credential::CredentialIssuer,
+    errors::Result,
+    utils::{
+        nras_token::{decode_device_tokens, NvidiaDeviceClaims},
+        unix_now,
+    },
+};
+
+/// Profile of the EAR documents, as registered by Veraison.
+pub const EAR_PROFILE: &str = "tag:github.com,2023:veraison/ear";
+
+/// The evidence could not be appraised, because the verifier malfunctioned.
+pub const VERIFIER_MALFUNCTION: i8 = -1;
+/// The evidence makes no claim on this aspect of the component.
+pub const NO_CLAIM: i8 = 0;
+/// The evidence was not expected, e.g. it has an unknown format.
+pub const UNEXPECTED_EVIDENCE: i8 = 1;
+/// The evidence failed cryptographic validation, e.g. its signature is invalid.
+pub const CRYPTO_VALIDATION_FAILED: i8 = 99;
+
+/// `instance-identity`: the component is a recognized, trustworthy instance.
+pub const TRUSTWORTHY_INSTANCE: i8 = 2;
+/// `instance-identity`: the component is recognized, but is not trustworthy.
+pub const UNTRUSTWORTHY_INSTANCE: i8 = 96;
+/// `instance-identity`: the component is not recognized.
+pub const UNRECOGNIZED_INSTANCE: i8 = 97;
+
+/// `configuration`: the configuration is approved by the appraisal policy.
+pub const APPROVED_CONFIG: i8 = 2;
+/// `configuration`: the configuration has no known vulnerabilities.
+pub const NO_CONFIG_VULNS: i8 = 3;
+/// `configuration`: the configuration has known vulnerabilities.
+pub const UNSAFE_CONFIG: i8 = 32;
+/// `configuration`: the configuration is unacceptable to the appraisal policy.
+pub const UNSUPPORTABLE_CONFIG: i8 = 96;
+
+/// `executables`: the runtime matches approved reference values.
+pub const APPROVED_RUNTIME: i8 = 2;
+/// `executables`: the boot firmware matches approved reference values.
+pub const APPROVED_BOOT: i8 = 3;
+/// `executables`: the runtime has known vulnerabilities.
+pub const UNSAFE_RUNTIME: i8 = 32;
+/// `executables`: the runtime does not match any known reference values.
+pub const UNRECOGNIZED_RUNTIME: i8 = 33;
+/// `executables`: the runtime contradicts the approved reference values.
+pub const CONTRAINDICATED_RUNTIME: i8 = 96;
+
+/// `hardware`: the hardware is genuine.
+pub const GENUINE_HARDWARE: i8 = 2;
+/// `hardware`: the hardware has known vulnerabilities.
+pub const UNSAFE_HARDWARE: i8 = 32;
+/// `hardware`: the hardware is known to be compromised, e.g. its certificate is revoked.
+pub const CONTRAINDICATED_HARDWARE: i8 = 96;
+/// `hardware`: the hardware is not recognized.
+pub const UNRECOGNIZED_HARDWARE: i8 = 97;
+
+/// Trust tier of an appraisal, from the least to the most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustTier {
+    /// No claim was made
+    None,
+    /// The component is trustworthy
+    Affirming,
+    /// The component may be trustworthy, with caveats
+    Warning,
+    /// The component is not trustworthy
+    Contraindicated,
+}
+
+impl TrustTier {
+    /// Returns the tier of an AR4SI trustworthiness claim.
+    #[must_use]
+    pub const fn of(claim: i8) -> Self {
+        match claim {
+            -1..=1 => Self::None,
+            2..=31 => Self::Affirming,
+            32..=95 => Self::Warning,
+            _ => Self::Contraindicated,
+        }
+    }
+}
+
+/// AR4SI trustworthiness vector of a component. Claims set to [`NO_CLAIM`] are omitted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrustVector {
+    /// Whether the component is a recognized, trustworthy instance
+    #[serde(
+        rename = "instance-identity",
+        default,
+        skip_serializing_if = "is_no_claim"
+    )]
+    pub instance_identity: i8,
+    /// Whether the configuration of the component is approved
+    #[serde(default, skip_serializing_if = "is_no_claim")]
+    pub configuration: i8,
+    /// Whether the firmware and runtime match approved reference values
+    #[serde(default, skip_serializing_if = "is_no_claim")]
+    pub executables: i8,
+    /// Whether the hardware is genuine and not compromised
+    #[serde(default, skip_serializing_if = "is_no_claim")]
+    pub hardware: i8,
+}
+
+#[allow(clippy::trivially_copy_pass_by_ref)] // serde passes skipped fields by reference
+const fn is_no_claim(claim: &i8) -> bool {
+    *claim == NO_CLAIM
+}
+
+impl TrustVector {
+    /// Returns the worst trust tier of the claims of the vector.
+    #[must_use]
+    pub fn tier(&self) -> TrustTier {
+        [
+            self.instance_identity,
+            self.configuration,
+            self.executables,
+            self.hardware,
+        ]
+        .into_iter()
+        .map(TrustTier::of)
+        .max()
+        .unwrap_or(TrustTier::None)
+    }
+
+    /// Maps the claims of an NRAS device token to a trustworthiness vector.
+    ///
+    /// GPU (`x-nvidia-gpu-*`) and switch (`x-nvidia-switch-*`) claims are both
+    /// recognized. Claims absent from the token leave the matching entry at [`NO_CLAIM`].
+    #[must_use]
+    pub fn from_nvidia_claims(claims: &NvidiaDeviceClaims) -> Self {
+        let claims = &claims.claims;
+        let device_claim = |suffix: &str| {
+            claims
+                .get(&format!("x-nvidia-gpu-{suffix}"))
+                .or_else(|| claims.get(&format!("x-nvidia-switch-{suffix}")))
+        };
+        let flag = |suffix: &str| device_claim(suffix).and_then(Value::as_bool);
+        let cert_chain = device_claim("attestation-report-cert-chain");
+        let cert_status = |key: &str| cert_chain.and_then(|chain| chain.get(key)?.as_str());
+
+        let instance_identity = match (
+            flag("attestation-report-signature-verified"),
+            flag("attestation-report-nonce-match"),
+        ) {
+            (Some(false), _) => CRYPTO_VALIDATION_FAILED,
+            (_, Some(false)) => UNTRUSTWORTHY_INSTANCE,
+            (Some(true), _) => TRUSTWORTHY_INSTANCE,
+            (None, _) => NO_CLAIM,
+        };
+        let chain_validated = flag("attestation-report-cert-chain-validated")
+            .or_else(|| cert_status("x-nvidia-cert-status").map(|status| status == "valid"));
+        let hardware = if cert_status("x-nvidia-cert-status") == Some("revoked")
+            || cert_status("x-nvidia-cert-ocsp-status") == Some("revoked")
+        {
+            CONTRAINDICATED_HARDWARE
+        } else if flag("arch-check") == Some(false) {
+            UNRECOGNIZED_HARDWARE
+        } else {
+            match chain_validated {
+                Some(true) => GENUINE_HARDWARE,
+                Some(false) => CRYPTO_VALIDATION_FAILED,
+                None => NO_CLAIM,
+            }
+        };
+        let executables = match claims.get("measres").and_then(Value::as_str) {
+            Some("success") => APPROVED_RUNTIME,
+            Some("fail") => CONTRAINDICATED_RUNTIME,
+            Some(_) => UNRECOGNIZED_RUNTIME,
+            None => NO_CLAIM,
+        };
+        let configuration = match (
+            claims.get("dbgstat").and_then(Value::as_str),
+            claims.get("secboot").and_then(Value::as_bool),
+        ) {
+            (Some(status), _) if status != "disabled" => UNSAFE_CONFIG,
+            (_, Some(false)) => UNSAFE_CONFIG,
+            (Some(_), Some(true)) => APPROVED_CONFIG,
+            _ => NO_CLAIM,
+        };
+        Self {
+            instance_identity,
+            configuration,
+            executables,
+            hardware,
+        }
+    }
+}
+
+/// Appraisal of one component.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Appraisal {
+    /// Overall status of the component, the worst trust tier of its vector
+    #[serde(rename = "ear.status")]
+    pub status: TrustTier,
+    /// Trustworthiness claims of the component
+    #[serde(rename = "ear.trustworthiness-vector")]
+    pub trust_vector: TrustVector,
+    /// Identifier of the policy the component was appraised against
+    #[serde(
+        rename = "ear.appraisal-policy-id",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub policy_id: Option<String>,
+}
+
+impl Appraisal {
+    /// Creates the appraisal of `trust_vector`, deriving its status.
+    #[must_use]
+    pub fn new(trust_vector: TrustVector) -> Self {
+        Self {
+            status: trust_vector.tier(),
+            trust_vector,
+            policy_id: None,
+        }
+    }
+
+    /// Sets the identifier of the appraisal policy.
+    #[must_use]
+    pub fn with_policy_id(mut self, policy_id: impl Into<String>) -> Self {
+        self.policy_id = Some(policy_id.into());
+        self
+    }
+}
+
+/// Identity of the verifier producing an EAR.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VerifierId {
+    /// Organization that develops the verifier
+    pub developer: String,
+    /// Name and version of the verifier build
+    pub build: String,
+}
+
+impl Default for VerifierId {
+    fn default() -> Self {
+        Self {
+            developer: "Atoma Network".to_string(),
+            build: concat!("remote-attestation-verifier ", env!("CARGO_PKG_VERSION")).to_string(),
+        }
+    }
+}
+
+/// An EAT Attestation Result, appraising every attested component as a submodule.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Ear {
+    /// Profile of the document, [`EAR_PROFILE`]
+    pub eat_profile: String,
+    /// Issue time, in seconds since the Unix epoch
+    pub iat: u64,
+    /// Identity of the verifier producing the EAR
+    #[serde(rename = "ear.verifier-id")]
+    pub verifier_id: VerifierId,
+    /// Nonce the evidence was collected with, if any
+    #[serde(rename = "eat_nonce", default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+    /// Appraisals, keyed by component name (e.g. `GPU-0` or `topology`)
+    pub submods: BTreeMap<String, Appraisal>,
+}
+
+impl Default for Ear {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ear {
+    /// Creates an empty EAR, issued now.
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            eat_profile: EAR_PROFILE.to_string(),
+            iat: unix_now(),
+            verifier_id: VerifierId::default(),
+            nonce: None,
+            submods: BTreeMap::new(),
+        }
+    }
+
+    /// Sets the nonce the evidence was collected with.
+    #[must_use]
+    pub fn with_nonce(mut self, nonce: impl Into<String>) -> Self {
+        self.nonce = Some(nonce.into());
+        self
+    }
+
+    /// Sets the identity of the verifier.
+    #[must_use]
+    pub fn with_verifier_id(mut self, verifier_id: VerifierId) -> Self {
+        self.verifier_id = verifier_id;
+        self
+    }
+
+    /// Adds the appraisal of the component `name`, replacing any previous one.
+    pub fn add_appraisal(&mut self, name: impl Into<String>, appraisal: Appraisal) {
+        self.submods.insert(name.into(), appraisal);
+    }
+
+    /// Adds the appraisal of every device of `device_claims`, as returned by
+    /// [`decode_device_tokens`], appraised against the policy `policy_id`.
+    pub fn add_devices(
+        &mut self,
+        device_claims: &BTreeMap<String, NvidiaDeviceClaims>,
+        policy_id: &str,
+    ) {
+        for (device, claims) in device_claims {
+            let appraisal =
+                Appraisal::new(TrustVector::from_nvidia_claims(claims)).with_policy_id(policy_id);
+            self.add_appraisal(device.clone(), appraisal);
+        }
+    }
+
+    /// Decodes the device tokens of an NRAS response, and adds the appraisal of every
+    /// device, with the verifier URL as policy identifier.
+    ///
+    /// # Errors
+    ///
+    /// * Errors propagated from [`decode_device_tokens`] if a device token cannot be
+    ///   decoded or verified
+    pub async fn add_nras_response(
+        &mut self,
+        verifier_url: &str,
+        nras_response: &Value,
+    ) -> Result<()> {
+        let device_claims = decode_device_tokens(verifier_url, nras_response).await?;
+        self.add_devices(&device_claims, verifier_url);
+        Ok(())
+    }
+
+    /// Adds the result of a local check, e.g. `topology`, as the `configuration` claim of
+    /// the component `name`.
+    pub fn add_check(&mut self, name: impl Into<String>, passed: bool) {
+        let trust_vector = TrustVector {
+            configuration: if passed {
+                APPROVED_CONFIG
+            } else {
+                UNSUPPORTABLE_CONFIG
+            },
+            ..TrustVector::default()
+        };
+        self.add_appraisal(name, Appraisal::new(trust_vector));
+    }
+
+    /// Returns the worst status of the appraised components, [`TrustTier::None`] if there
+    /// are none.
+    #[must_use]
+    pub fn status(&self) -> TrustTier {
+        self.submods
+            .values()
+            .map(|appraisal| appraisal.status)
+            .max()
+            .unwrap_or(TrustTier::None)
+    }
+
+    /// Signs the EAR about `subject` (e.g. the attested node) as an `EdDSA` JWT, with the
+    /// key of `issuer`.
+    ///
+    /// The signed claims are a [`SignedEar`]: the EAR, issued by the issuer and restricted
+    /// to its audience, expiring once the issuer's credential lifetime has passed since
+    /// the EAR was issued. It can be verified with
+    /// [`crate::CredentialVerifier::verify_claims`].
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::TokenSignature` - If the EAR cannot be signed
+    pub fn sign(&self, issuer: &CredentialIssuer, subject: &str) -> Result<String> {
+        issuer.sign(&SignedEar {
+            iss: issuer.issuer().to_string(),
+            sub: subject.to_string(),
+            aud: issuer.audience().map(str::to_string),
+            exp: self.iat.saturating_add(issuer.lifetime().as_secs()),
+            ear: self.clone(),
+        })
+    }
+}
+
+/// Claims of a signed EAR: the EAR, and the JWT claims binding it to its issuer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedEar {
+    /// Issuer of the EAR, the relying party
+    pub iss: String,
+    /// Subject of the EAR, e.g. the attested node
+    pub sub: String,
+    /// Intended audience of the EAR, if restricted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
+    /// Expiration time, in seconds since the Unix epoch
+    pub exp: u64,
+    /// The signed EAR
+    #[serde(flatten)]
+    pub ear: Ear,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, time::Duration};
+
+    use jsonwebtoken::decode_header;
+    use ring::{rand::SystemRandom, signature::Ed25519KeyPair};
+    use serde_json::json;
+
+    use super::*;
+    use crate::credential::CredentialVerifier;
+
+    fn claims(claims: Value) -> NvidiaDeviceClaims {
+        NvidiaDeviceClaims {
+            claims: serde_json::from_value::<HashMap<_, _>>(claims).unwrap(),
+        }
+    }
+
+    fn gpu_claims() -> Value {
+        json!({
+            "measres": "success",
+            "secboot": true,
+            "dbgstat": "disabled",
+            "x-nvidia-gpu-arch-check": true,
+            "x-nvidia-gpu-attestation-report-signature-verified": true,
+            "x-nvidia-gpu-attestation-report-nonce-match": true,
+            "x-nvidia-gpu-attestation-report-cert-chain-validated": true,
+        })
+    }
+
+    #[test]
+    fn test_trust_tiers() {
+        assert_eq!(TrustTier::of(VERIFIER_MALFUNCTION), TrustTier::None);
+        assert_eq!(TrustTier::of(NO_CLAIM), TrustTier::None);
+        assert_eq!(TrustTier::of(APPROVED_BOOT), TrustTier::Affirming);
+        assert_eq!(TrustTier::of(UNRECOGNIZED_RUNTIME), TrustTier::Warning);
+        assert_eq!(
+            TrustTier::of(CRYPTO_VALIDATION_FAILED),
+            TrustTier::Contraindicated
+        );
+        assert_eq!(TrustTier::of(-2), TrustTier::Contraindicated);
+    }
+
+    #[test]
+    fn test_trust_vector_from_nvidia_claims() {
+        let vector = TrustVector::from_nvidia_claims(&claims(gpu_claims()));
+        assert_eq!(
+            vector,
+            TrustVector {
+                instance_identity: TRUSTWORTHY_INSTANCE,
+                configuration: APPROVED_CONFIG,
+                executables: APPROVED_RUNTIME,
+                hardware: GENUINE_HARDWARE,
+            }
+        );
+        assert_eq!(vector.tier(), TrustTier::Affirming);
+
+        let mut failed = gpu_claims();
+        failed["measres"] = json!("fail");
+        failed["dbgstat"] = json!("enabled");
+        failed["x-nvidia-gpu-attestation-report-nonce-match"] = json!(false);
+        let vector = TrustVector::from_nvidia_claims(&claims(failed));
+        assert_eq!(vector.instance_identity, UNTRUSTWORTHY_INSTANCE);
+        assert_eq!(vector.configuration, UNSAFE_CONFIG);
+        assert_eq!(vector.executables, CONTRAINDICATED_RUNTIME);
+        assert_eq!(vector.tier(), TrustTier::Contraindicated);
+
+        // Claims version 3.0 reports the certificate chain status, and switch claims
+        // have their own prefix
+        let switch = json!({
+            "x-nvidia-switch-attestation-report-cert-chain": {
+                "x-nvidia-cert-status": "valid",
+                "x-nvidia-cert-ocsp-status": "revoked",
+            },
+        });
+        let vector = TrustVector::from_nvidia_claims(&claims(switch));
+        assert_eq!(vector.hardware, CONTRAINDICATED_HARDWARE);
+        assert_eq!(vector.executables, NO_CLAIM);
+
+        let vector = TrustVector::from_nvidia_claims(&claims(json!({})));
+        assert_eq!(vector, TrustVector::default());
+        assert_eq!(vector.tier(), TrustTier::None);
+    }
+
+    #[test]
+    fn test_ear_document() {
+        let mut device_claims = BTreeMap::new();
+        device_claims.insert("GPU-0".to_string(), claims(gpu_claims()));
+        let mut ear = Ear::new().with_nonce("00".repeat(32));
+        ear.add_devices(&device_claims, "https://nras.example/v3/attest/gpu");
+        ear.add_check("topology", true);
+        assert_eq!(ear.status(), TrustTier::Affirming);
+
+        let document = serde_json::to_value(&ear).unwrap();
+        assert_eq!(document["eat_profile"], EAR_PROFILE);
+        assert_eq!(document["eat_nonce"], "00".repeat(32));
+        assert_eq!(document["submods"]["GPU-0"]["ear.status"], "affirming");
+        assert_eq!(
+            document["submods"]["GPU-0"]["ear.trustworthiness-vector"],
+            json!({"instance-identity": 2, "configuration": 2, "executables": 2, "hardware": 2})
+        );
+        assert_eq!(
+            document["submods"]["topology"]["ear.trustworthiness-vector"],
+            json!({"configuration": 2})
+        );
+        assert_eq!(
+            serde_json::from_value::<Ear>(document).unwrap(),
+            ear,
+            "EAR documents round-trip"
+        );
+
+        ear.add_check("topology", false);
+        assert_eq!(ear.status(), TrustTier::Contraindicated);
+        assert_eq!(Ear::new().status(), TrustTier::None);
+    }
+
+    #[test]
+    fn test_signed_ear() {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        let issuer = CredentialIssuer::new("https://proxy.example", "key-1", pkcs8.as_ref())
+            .unwrap()
+            .with_lifetime(Duration::from_secs(60));
+        let mut ear = Ear::new();
+        ear.add_check("topology", true);
+
+        let token = ear.sign(&issuer, "node-42").unwrap();
+        assert_eq!(decode_header(&token).unwrap().kid.as_deref(), Some("key-1"));
+        let verifier = CredentialVerifier::new("https://proxy.example", &issuer.jwks()).unwrap();
+        let signed = verifier.verify_claims::<SignedEar>(&token).unwrap();
+        assert_eq!(signed.iss, "https://proxy.example");
+        assert_eq!(signed.sub, "node-42");
+        assert_eq!(signed.exp, ear.iat + 60);
+        assert_eq!(signed.ear, ear);
+
+        // EARs of another issuer are rejected
+        let other = CredentialVerifier::new("https://other.example", &issuer.jwks()).unwrap();
+        assert!(other.verify_claims::<SignedEar>(&token).is_err());
+    }
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use thiserror::Error;
 
 pub type Result<T> = std::result::Result<T, AttestError>;
@@ -6,11 +10,45 @@ pub type Result<T> = std::result::Result<T, AttestError>;
 pub enum AttestError {
     #[error("Failed to attest remote")]
     RemoteAttestationFailed,
-    #[error("Response error: {0}")]
-    ResponseError(String),
-    #[error("Failed to parse response")]
-    ParseResponseError(#[from] reqwest::Error),
-    #[error("Failed to get overall claims token")]
+    #[error("Failed to send request to `{url}`: {source}")]
+    Transport {
+        url: String,
+        #[source]
+        source: reqwest::Error,
+    },
+    #[error("Remote attestation service returned HTTP status {status}: {}", .body.as_deref().and_then(NrasErrorBody::non_empty_message).unwrap_or(.raw_body.as_str()))]
+    HttpStatus {
+        status: u16,
+        body: Option<Box<NrasErrorBody>>,
+        raw_body: String,
+    },
+    #[error("Malformed response from the remote attestation service: {0}")]
+    MalformedResponse(String),
+    #[error("Failed to retrieve JWKS from `{url}`: {source}")]
+    Jwks {
+        url: String,
+        #[source]
+        source: JwksError,
+    },
+    #[error("Invalid NRAS token signature: {0}")]
+    TokenSignature(#[source] jsonwebtoken::errors::Error),
+    #[error("NRAS token claim validation failed: {0}")]
+    ClaimValidation(String),
+    #[error("Failed to decode evidence: {0}")]
+    EvidenceDecode(String),
+    #[error("Invalid evidence bundle: {0}")]
+    InvalidEvidenceBundle(String),
+    #[error("Invalid CPU quote: {0}")]
+    InvalidCpuQuote(String),
+    #[error("CPU quote is not bound to the GPU evidence: {0}")]
+    CpuBinding(String),
+    #[error("CPU quote verification failed: {0}")]
+    CpuQuoteRejected(String),
+    #[error("Nonce is not bound to the session: {0}")]
+    NonceBinding(String),
+    #[error("Invalid RA-TLS certificate: {0}")]
+    InvalidRaTlsCertificate(String),
+    #[error("Failed to decode JSON: {0}")]
     JsonError(#[from] serde_json::Error),
     #[error("Failed to parse JWKS URL")]
     UrlParseError(#[from] url::ParseError),
@@ -18,6 +56,8 @@ pub enum AttestError {
     HeaderDecodeError(#[from] jsonwebtoken::errors::Error),
     #[error("Invalid JWT token: {0}")]
     InvalidJwtToken(String),
+    #[error("Invalid COSE token: {0}")]
+    InvalidCoseToken(String),
     #[error("Failed to decode certificate")]
     CertificateDecodeError(#[from] base64::DecodeError),
     #[error("Failed to parse certificate")]
@@ -26,8 +66,271 @@ pub enum AttestError {
     ),
     #[error("Failed to parse service key")]
     ServiceKeyParseError(#[from] reqwest::header::InvalidHeaderValue),
+    #[error("Invalid remote attestation configuration: {0}")]
+    ConfigError(String),
+    #[error("Invalid credential signing key: {0}")]
+    InvalidSigningKey(String),
+    #[error("Invalid remote attestation option `{field}`: {message}")]
+    InvalidOption {
+        field: &'static str,
+        message: String,
+    },
     #[error("Failed to get all switch UUID: `{0}`")]
     NscqError(#[from] NscqError),
+    #[cfg(feature = "nvml")]
+    #[error("Failed to collect GPU evidence through NVML: {0}")]
+    NvmlError(#[from] nvml_wrapper::error::NvmlError),
+    #[error("Attestation audit log failure: {0}")]
+    AuditLog(#[from] AuditError),
+    #[error("Failed to run blocking attestation: {0}")]
+    Runtime(String),
+}
+
+/// Machine-readable category of an [`AttestError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AttestErrorKind {
+    /// The remote attestation service could not be reached
+    Transport,
+    /// The remote attestation service answered with a non-success HTTP status
+    HttpStatus,
+    /// The remote attestation service answered with an unexpected body
+    MalformedResponse,
+    /// The JWKS used to verify NRAS tokens could not be retrieved
+    Jwks,
+    /// An NRAS token, or the key used to verify it, is invalid
+    TokenSignature,
+    /// An NRAS token is well signed, but its claims are not acceptable
+    ClaimValidation,
+    /// Attestation evidence could not be decoded
+    EvidenceDecode,
+    /// The remote attestation options are invalid
+    Configuration,
+    /// Evidence collection through NSCQ failed
+    Nscq,
+    /// Evidence collection through NVML failed
+    Nvml,
+    /// The attestation result was negative
+    AttestationFailed,
+    /// The attestation decision could not be recorded in the audit log
+    AuditLog,
+    /// The blocking API could not run the asynchronous verification
+    Runtime,
+}
+
+impl AttestErrorKind {
+    /// Returns the kind as a stable `snake_case` string, suitable for logs and metrics.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Transport => "transport",
+            Self::HttpStatus => "http_status",
+            Self::MalformedResponse => "malformed_response",
+            Self::Jwks => "jwks",
+            Self::TokenSignature => "token_signature",
+            Self::ClaimValidation => "claim_validation",
+            Self::EvidenceDecode => "evidence_decode",
+            Self::Configuration => "configuration",
+            Self::Nscq => "nscq",
+            Self::Nvml => "nvml",
+            Self::AttestationFailed => "attestation_failed",
+            Self::AuditLog => "audit_log",
+            Self::Runtime => "runtime",
+        }
+    }
+}
+
+impl std::fmt::Display for AttestErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl AttestError {
+    /// Returns the machine-readable category of the error.
+    #[must_use]
+    pub const fn kind(&self) -> AttestErrorKind {
+        match self {
+            Self::RemoteAttestationFailed | Self::CpuQuoteRejected(_) => {
+                AttestErrorKind::AttestationFailed
+            }
+            Self::Transport { .. } => AttestErrorKind::Transport,
+            Self::HttpStatus { .. } => AttestErrorKind::HttpStatus,
+            Self::MalformedResponse(_) | Self::JsonError(_) => AttestErrorKind::MalformedResponse,
+            Self::Jwks { .. } => AttestErrorKind::Jwks,
+            Self::TokenSignature(_)
+            | Self::HeaderDecodeError(_)
+            | Self::InvalidJwtToken(_)
+            | Self::InvalidCoseToken(_)
+            | Self::CertificateDecodeError(_)
+            | Self::CertificateParseError(_) => AttestErrorKind::TokenSignature,
+            Self::ClaimValidation(_) | Self::CpuBinding(_) | Self::NonceBinding(_) => {
+                AttestErrorKind::ClaimValidation
+            }
+            Self::EvidenceDecode(_)
+            | Self::InvalidEvidenceBundle(_)
+            | Self::InvalidCpuQuote(_)
+            | Self::InvalidRaTlsCertificate(_) => AttestErrorKind::EvidenceDecode,
+            Self::UrlParseError(_)
+            | Self::ServiceKeyParseError(_)
+            | Self::ConfigError(_)
+            | Self::InvalidSigningKey(_)
+            | Self::InvalidOption { .. } => AttestErrorKind::Configuration,
+            Self::NscqError(_) => AttestErrorKind::Nscq,
+            #[cfg(feature = "nvml")]
+            Self::NvmlError(_) => AttestErrorKind::Nvml,
+            Self::AuditLog(_) => AttestErrorKind::AuditLog,
+            Self::Runtime(_) => AttestErrorKind::Runtime,
+        }
+    }
+
+    /// Returns `true` if the same request may succeed when retried later.
+    ///
+    /// Network failures, timeouts, rate limiting and server-side (5xx) errors
+    /// are retryable. Rejected evidence, invalid service keys, bad tokens and
+    /// configuration errors are not.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport { source, .. } => !source.is_builder(),
+            Self::HttpStatus { status, .. } => is_retryable_status(*status),
+            Self::Jwks { source, .. } => source.is_retryable(),
+            _ => false,
+        }
+    }
+
+    /// Returns the HTTP status code returned by the remote attestation service, if any.
+    #[must_use]
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            Self::HttpStatus { status, .. }
+            | Self::Jwks {
+                source: JwksError::Status(status),
+                ..
+            } => Some(*status),
+            Self::Transport { source, .. } => source.status().map(|status| status.as_u16()),
+            _ => None,
+        }
+    }
+
+    /// Builds an `HttpStatus` error from a non-success response status and body,
+    /// parsing the body as an NRAS error when possible.
+    pub(crate) fn from_http_status(status: u16, raw_body: String) -> Self {
+        let body = serde_json::from_str::<NrasErrorBody>(&raw_body)
+            .ok()
+            .map(Box::new);
+        Self::HttpStatus {
+            status,
+            body,
+            raw_body,
+        }
+    }
+
+    /// Maps a `jsonwebtoken` error raised while decoding an NRAS token to the
+    /// matching error category.
+    pub(crate) fn from_jwt_error(error: jsonwebtoken::errors::Error) -> Self {
+        use jsonwebtoken::errors::ErrorKind;
+        match error.kind() {
+            ErrorKind::ExpiredSignature
+            | ErrorKind::ImmatureSignature
+            | ErrorKind::InvalidIssuer
+            | ErrorKind::InvalidAudience
+            | ErrorKind::InvalidSubject
+            | ErrorKind::MissingRequiredClaim(_) => Self::ClaimValidation(error.to_string()),
+            _ => Self::TokenSignature(error),
+        }
+    }
+}
+
+/// Returns `true` for HTTP statuses that indicate a transient failure.
+const fn is_retryable_status(status: u16) -> bool {
+    matches!(status, 408 | 429) || status >= 500
+}
+
+/// Error body returned by NRAS alongside a non-success HTTP status.
+///
+/// NRAS reports errors as a JSON object; the commonly used fields are
+/// extracted and any other field is kept in `additional_fields`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NrasErrorBody {
+    /// Error code reported by NRAS, if any
+    #[serde(default, alias = "errorCode", skip_serializing_if = "Option::is_none")]
+    pub error_code: Option<Value>,
+    /// Human readable error message, if any
+    #[serde(
+        default,
+        alias = "detail",
+        alias = "error",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub message: Option<String>,
+    /// Request identifier assigned by NRAS, if any
+    #[serde(default, alias = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Any additional field present in the error body
+    #[serde(flatten)]
+    pub additional_fields: HashMap<String, Value>,
+}
+
+impl NrasErrorBody {
+    /// Returns the error message, or an empty string if NRAS did not send one.
+    #[must_use]
+    pub fn message(&self) -> &str {
+        self.message.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the error message, or `None` if NRAS did not send one or sent an empty one.
+    fn non_empty_message(&self) -> Option<&str> {
+        self.message
+            .as_deref()
+            .filter(|message| !message.is_empty())
+    }
+}
+
+/// Reason why the JWKS used to verify NRAS tokens could not be retrieved.
+#[derive(Debug, Error)]
+pub enum JwksError {
+    #[error("request failed: {0}")]
+    Transport(#[source] reqwest::Error),
+    #[error("unexpected HTTP status {0}")]
+    Status(u16),
+    #[error("malformed JWKS: {0}")]
+    Malformed(String),
+    #[error("no key matches kid `{0}`")]
+    KeyNotFound(String),
+}
+
+impl JwksError {
+    /// Returns `true` if fetching the JWKS again may succeed.
+    #[must_use]
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Self::Transport(source) => !source.is_builder(),
+            Self::Status(status) => is_retryable_status(*status),
+            Self::Malformed(_) | Self::KeyNotFound(_) => false,
+        }
+    }
+}
+
+/// Failure to write, read or verify an attestation audit log.
+#[derive(Debug, Error)]
+pub enum AuditError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("malformed record on line {line}: {message}")]
+    MalformedRecord { line: u64, message: String },
+    #[error("expected record {expected}, found record {found}")]
+    SequenceGap { expected: u64, found: u64 },
+    #[error("record {sequence} is not linked to the previous record")]
+    BrokenChain { sequence: u64 },
+    #[error("record {sequence} does not match its hash")]
+    HashMismatch { sequence: u64 },
+    #[error("log holds {found} records, expected at least {expected}")]
+    Truncated { expected: u64, found: u64 },
+    #[error("record {sequence} does not match the expected head hash")]
+    HeadMismatch { sequence: u64 },
+    #[error("log may end with a partial record after a failed append, reopen it to recover")]
+    Poisoned,
 }
 
 #[derive(Debug, Error)]
@@ -75,3 +378,81 @@ impl std::fmt::Display for NscqError {
         write!(f, "{self:?}")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_http_status_parses_nras_error_body() {
+        let error = AttestError::from_http_status(
+            400,
+            r#"{"errorCode": 400, "message": "Invalid evidence", "requestId": "abc-123", "path": "/v3/attest/gpu"}"#
+                .to_string(),
+        );
+        assert_eq!(error.kind(), AttestErrorKind::HttpStatus);
+        assert_eq!(error.status(), Some(400));
+        assert!(!error.is_retryable());
+        let AttestError::HttpStatus {
+            body: Some(body), ..
+        } = &error
+        else {
+            panic!("Expected a parsed NRAS error body, got {error:?}");
+        };
+        assert_eq!(body.message(), "Invalid evidence");
+        assert_eq!(body.request_id.as_deref(), Some("abc-123"));
+        assert!(body.additional_fields.contains_key("path"));
+        assert!(error.to_string().contains("Invalid evidence"));
+    }
+
+    #[test]
+    fn test_http_status_keeps_raw_body_when_not_json() {
+        let error = AttestError::from_http_status(503, "Service Unavailable".to_string());
+        assert!(error.is_retryable());
+        assert!(matches!(
+            error,
+            AttestError::HttpStatus {
+                status: 503,
+                body: None,
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("Service Unavailable"));
+    }
+
+    #[test]
+    fn test_http_status_keeps_raw_body_without_message() {
+        let raw_body = r#"{"errorCode": 500, "requestId": "abc-123"}"#;
+        let error = AttestError::from_http_status(500, raw_body.to_string());
+        assert!(matches!(
+            &error,
+            AttestError::HttpStatus {
+                body: Some(body),
+                ..
+            } if body.request_id.as_deref() == Some("abc-123")
+        ));
+        assert!(error.to_string().ends_with(raw_body));
+    }
+
+    #[test]
+    fn test_retryable_statuses() {
+        for status in [408, 429, 500, 502, 503, 504] {
+            assert!(AttestError::from_http_status(status, String::new()).is_retryable());
+        }
+        for status in [400, 401, 403, 404, 422] {
+            assert!(!AttestError::from_http_status(status, String::new()).is_retryable());
+        }
+        assert!(JwksError::Status(502).is_retryable());
+        assert!(!JwksError::KeyNotFound("kid".to_string()).is_retryable());
+    }
+
+    #[test]
+    fn test_jwt_error_mapping() {
+        use jsonwebtoken::errors::ErrorKind;
+        let expired = AttestError::from_jwt_error(ErrorKind::ExpiredSignature.into());
+        assert_eq!(expired.kind(), AttestErrorKind::ClaimValidation);
+        let bad_signature = AttestError::from_jwt_error(ErrorKind::InvalidSignature.into());
+        assert_eq!(bad_signature.kind(), AttestErrorKind::TokenSignature);
+        assert_eq!(bad_signature.kind().as_str(), "token_signature");
+    }
+}
@@ -3,16 +3,50 @@
 //! This crate provides functionality for performing remote attestation
 //! of NVIDIA GPUs by sending evidence to a verification service.
 
+pub mod audit;
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod bundle;
+pub mod cache;
+pub mod composite;
 pub mod constants;
+pub mod cose;
+pub mod credential;
+pub mod ear;
 pub mod errors;
+pub mod metrics;
+pub mod nonce;
+mod nras;
+pub mod options;
+#[cfg(feature = "topology")]
+pub mod ppcie;
+mod propagation;
+#[cfg(feature = "ratls")]
+pub mod ratls;
 pub mod remote_gpu_attestation;
 pub mod remote_nvswitch_attestation;
+pub mod tee;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 #[cfg(test)]
 mod tests;
 pub mod types;
 pub mod utils;
 
-pub use errors::{AttestError, Result};
+pub use bundle::{BundleEncoding, BundleSignature, EvidenceBundle, HostMetadata};
+pub use composite::{CompositeEvidence, CpuEvidence, CpuQuoteVerifier};
+pub use credential::{AttestationCredentialClaims, CredentialIssuer, CredentialVerifier};
+pub use ear::{Appraisal, Ear, SignedEar, TrustTier, TrustVector};
+pub use errors::{AttestError, AttestErrorKind, Result};
+pub use nonce::NonceBinding;
+pub use options::{AttestRemoteOptions, AttestRemoteOptionsBuilder, DeviceKind};
+#[cfg(feature = "topology")]
+pub use ppcie::{check_bundle_topology, TopologyReport};
+#[cfg(feature = "ratls")]
+pub use ratls::{RaTlsCertificate, RaTlsEvidence, RaTlsKey, RaTlsScope, RaTlsVerifier};
+#[cfg(feature = "nvml")]
+pub use remote_gpu_attestation::collect_gpu_evidence;
 pub use remote_gpu_attestation::verify_gpu_attestation;
-pub use remote_nvswitch_attestation::verify_nvswitch_attestation;
+pub use remote_nvswitch_attestation::{collect_nvswitch_evidence, verify_nvswitch_attestation};
+pub use tee::{CpuQuote, CpuTee};
 pub use types::{DeviceEvidence, NvSwitchEvidence};
@@ -0,0 +1,155 @@
+//! Metrics recorded during evidence collection and remote attestation.
+//!
+//! With the `metrics` feature, the crate records the metrics below through the
+//! [`metrics`](https://docs.rs/metrics) facade, so they reach whichever recorder the
+//! application installs, e.g. `metrics-exporter-prometheus`. Without the feature,
+//! recording compiles to nothing.
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | [`NRAS_REQUESTS_TOTAL`] | counter | `arch`, `status` |
+//! | [`NRAS_REQUEST_DURATION_SECONDS`] | histogram | `arch` |
+//! | [`ATTESTATIONS_TOTAL`] | counter | `arch`, `result` |
+//! | [`JWKS_FETCHES_TOTAL`] | counter | `outcome` |
+//! | [`JWKS_FETCH_DURATION_SECONDS`] | histogram | |
+//! | [`TOKEN_VERIFICATION_FAILURES_TOTAL`] | counter | `reason` |
+//! | [`NSCQ_CALL_DURATION_SECONDS`] | histogram | `operation` |
+//! | [`NSCQ_CALL_ERRORS_TOTAL`] | counter | `operation` |
+
+use std::time::Duration;
+
+use crate::errors::{AttestError, JwksError};
+
+/// NRAS attestation requests, by device architecture and HTTP status, or
+/// `transport_error` if no response was received.
+pub const NRAS_REQUESTS_TOTAL: &str = "nvidia_attestation_nras_requests_total";
+
+/// Latency of NRAS attestation requests, by device architecture.
+pub const NRAS_REQUEST_DURATION_SECONDS: &str = "nvidia_attestation_nras_request_duration_seconds";
+
+/// Completed remote attestations, by device architecture and result: `passed`,
+/// `failed`, `cached_passed`, `cached_failed` or `error`.
+pub const ATTESTATIONS_TOTAL: &str = "nvidia_attestation_attestations_total";
+
+/// JWKS fetches, by outcome: `success`, or the kind of failure.
+pub const JWKS_FETCHES_TOTAL: &str = "nvidia_attestation_jwks_fetches_total";
+
+/// Latency of JWKS fetches.
+pub const JWKS_FETCH_DURATION_SECONDS: &str = "nvidia_attestation_jwks_fetch_duration_seconds";
+
+/// NRAS responses whose token could not be verified, by reason, the
+/// [`AttestErrorKind`](crate::AttestErrorKind) of the failure.
+pub const TOKEN_VERIFICATION_FAILURES_TOTAL: &str =
+    "nvidia_attestation_token_verification_failures_total";
+
+/// Latency of NSCQ calls, by operation.
+pub const NSCQ_CALL_DURATION_SECONDS: &str = "nvidia_attestation_nscq_call_duration_seconds";
+
+/// Failed NSCQ calls, by operation.
+pub const NSCQ_CALL_ERRORS_TOTAL: &str = "nvidia_attestation_nscq_call_errors_total";
+
+/// Registers the descriptions and units of the metrics with the installed recorder.
+///
+/// Applications call this once, after installing their recorder.
+#[cfg(feature = "metrics")]
+pub fn describe_metrics() {
+    use metrics::{describe_counter, describe_histogram, Unit};
+
+    describe_counter!(
+        NRAS_REQUESTS_TOTAL,
+        "NRAS attestation requests, by device architecture and HTTP status"
+    );
+    describe_histogram!(
+        NRAS_REQUEST_DURATION_SECONDS,
+        Unit::Seconds,
+        "Latency of NRAS attestation requests"
+    );
+    describe_counter!(
+        ATTESTATIONS_TOTAL,
+        "Completed remote attestations, by device architecture and result"
+    );
+    describe_counter!(JWKS_FETCHES_TOTAL, "JWKS fetches, by outcome");
+    describe_histogram!(
+        JWKS_FETCH_DURATION_SECONDS,
+        Unit::Seconds,
+        "Latency of JWKS fetches"
+    );
+    describe_counter!(
+        TOKEN_VERIFICATION_FAILURES_TOTAL,
+        "NRAS responses whose token could not be verified, by reason"
+    );
+    describe_histogram!(
+        NSCQ_CALL_DURATION_SECONDS,
+        Unit::Seconds,
+        "Latency of NSCQ calls, by operation"
+    );
+    describe_counter!(NSCQ_CALL_ERRORS_TOTAL, "Failed NSCQ calls, by operation");
+}
+
+/// Records an NRAS attestation request, answered with `status` unless it failed in
+/// transport.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_nras_request(arch: &str, status: Option<u16>, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        let status = status.map_or_else(|| "transport_error".to_string(), |s| s.to_string());
+        metrics::counter!(NRAS_REQUESTS_TOTAL, "arch" => arch.to_string(), "status" => status)
+            .increment(1);
+        metrics::histogram!(NRAS_REQUEST_DURATION_SECONDS, "arch" => arch.to_string())
+            .record(duration);
+    }
+}
+
+/// Records the result of a remote attestation.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_attestation(arch: &str, result: Result<(bool, bool), &AttestError>) {
+    #[cfg(feature = "metrics")]
+    {
+        let result = match result {
+            Ok((true, false)) => "passed",
+            Ok((false, false)) => "failed",
+            Ok((true, true)) => "cached_passed",
+            Ok((false, true)) => "cached_failed",
+            Err(_) => "error",
+        };
+        metrics::counter!(ATTESTATIONS_TOTAL, "arch" => arch.to_string(), "result" => result)
+            .increment(1);
+    }
+}
+
+/// Records a JWKS fetch, and its failure if any.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_jwks_fetch(error: Option<&JwksError>, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        let outcome = match error {
+            None => "success",
+            Some(JwksError::Transport(_)) => "transport",
+            Some(JwksError::Status(_)) => "http_status",
+            Some(JwksError::Malformed(_)) => "malformed",
+            Some(JwksError::KeyNotFound(_)) => "key_not_found",
+        };
+        metrics::counter!(JWKS_FETCHES_TOTAL, "outcome" => outcome).increment(1);
+        metrics::histogram!(JWKS_FETCH_DURATION_SECONDS).record(duration);
+    }
+}
+
+/// Records an NRAS response whose token could not be verified.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_token_verification_failure(error: &AttestError) {
+    #[cfg(feature = "metrics")]
+    metrics::counter!(TOKEN_VERIFICATION_FAILURES_TOTAL, "reason" => error.kind().as_str())
+        .increment(1);
+}
+
+/// Records an NSCQ call, and whether it failed.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_nscq_call(operation: &'static str, failed: bool, duration: Duration) {
+    #[cfg(feature = "metrics")]
+    {
+        metrics::histogram!(NSCQ_CALL_DURATION_SECONDS, "operation" => operation).record(duration);
+        if failed {
+            metrics::counter!(NSCQ_CALL_ERRORS_TOTAL, "operation" => operation).increment(1);
+        }
+    }
+}
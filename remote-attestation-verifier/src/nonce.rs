@@ -0,0 +1,201 @@
+//! Nonces bound to a verifier challenge and to a session key.
+//!
+//! Instead of a random nonce, evidence can be collected with a nonce derived from the
+//! verifier's challenge, the public key of the session that will carry the user's
+//! prompts (e.g. the TLS or key exchange public key of the node), and a timestamp:
+//!
+//! ```text
+//! nonce = blake3::derive_key("nvrust 2025-05 attestation nonce v1",
+//!             len(challenge) || challenge || len(session_public_key) || session_public_key
+//!             || timestamp)
+//! ```
+//!
+//! with lengths and the timestamp (in seconds since the Unix epoch) as little-endian
+//! `u64`. The evidence signed by the GPUs and switches then commits to the session: a
+//! verifier that recomputes the nonce with [`NonceBinding::verify`] knows the evidence
+//! was collected for this session, after it issued the challenge.
+//!
+//! ```rust,ignore
+//! // Node: collect evidence for the derived nonce
+//! let binding = NonceBinding::now(challenge, session_public_key);
+//! let evidence = collect_gpu_evidence(&nvml, &binding.derive())?;
+//!
+//! // Verifier: check the binding, then verify the evidence
+//! binding.verify(&bundle.nonce_bytes()?, Duration::from_secs(5 * 60))?;
+//! ```
+
+use std::time::Duration;
+
+use crate::{
+    constants::MAX_NONCE_CLOCK_SKEW,
+    errors::{AttestError, Result},
+    utils::unix_now,
+};
+
+/// Length of derived nonces, in bytes.
+pub const NONCE_LENGTH: usize = 32;
+
+/// Context string used to derive nonces with blake3.
+const NONCE_DERIVATION_CONTEXT: &str = "nvrust 2025-05 attestation nonce v1";
+
+/// The inputs a nonce is derived from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NonceBinding {
+    /// Challenge issued by the verifier
+    pub challenge: Vec<u8>,
+    /// Public key of the session the evidence is bound to
+    pub session_public_key: Vec<u8>,
+    /// Derivation time, in seconds since the Unix epoch
+    pub timestamp: u64,
+}
+
+impl NonceBinding {
+    /// Creates the binding of `challenge` and `session_public_key` at `timestamp`.
+    pub fn new(
+        challenge: impl Into<Vec<u8>>,
+        session_public_key: impl Into<Vec<u8>>,
+        timestamp: u64,
+    ) -> Self {
+        Self {
+            challenge: challenge.into(),
+            session_public_key: session_public_key.into(),
+            timestamp,
+        }
+    }
+
+    /// Creates the binding of `challenge` and `session_public_key` at the current time.
+    pub fn now(challenge: impl Into<Vec<u8>>, session_public_key: impl Into<Vec<u8>>) -> Self {
+        Self::new(challenge, session_public_key, unix_now())
+    }
+
+    /// Derives the nonce to collect evidence with.
+    #[must_use]
+    pub fn derive(&self) -> [u8; NONCE_LENGTH] {
+        let mut hasher = blake3::Hasher::new_derive_key(NONCE_DERIVATION_CONTEXT);
+        for field in [&self.challenge, &self.session_public_key] {
+            hasher.update(&(field.len() as u64).to_le_bytes());
+            hasher.update(field);
+        }
+        hasher.update(&self.timestamp.to_le_bytes());
+        *hasher.finalize().as_bytes()
+    }
+
+    /// Checks that `nonce` was derived from this binding, at most `max_age` ago.
+    ///
+    /// Timestamps up to [`MAX_NONCE_CLOCK_SKEW`] in the future are accepted, to tolerate
+    /// clock differences between the node and the verifier.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::NonceBinding` - If `nonce` does not match the binding, or the
+    ///   timestamp is older than `max_age` or too far in the future
+    pub fn verify(&self, nonce: &[u8], max_age: Duration) -> Result<()> {
+        self.verify_at(nonce, max_age, unix_now())
+    }
+
+    pub(crate) fn verify_at(&self, nonce: &[u8], max_age: Duration, now: u64) -> Result<()> {
+        let nonce: [u8; NONCE_LENGTH] = nonce.try_into().map_err(|_| {
+            AttestError::NonceBinding(format!(
+                "Nonce must be {NONCE_LENGTH} bytes long, got {} bytes",
+                nonce.len()
+            ))
+        })?;
+        // blake3 hashes compare in constant time
+        if blake3::Hash::from(nonce) != blake3::Hash::from(self.derive()) {
+            return Err(AttestError::NonceBinding(
+                "Nonce was not derived from the challenge and session key".to_string(),
+            ));
+        }
+        if self.timestamp > now.saturating_add(MAX_NONCE_CLOCK_SKEW.as_secs()) {
+            return Err(AttestError::NonceBinding(format!(
+                "Nonce timestamp {} is in the future",
+                self.timestamp
+            )));
+        }
+        if now.saturating_sub(self.timestamp) > max_age.as_secs() {
+            return Err(AttestError::NonceBinding(format!(
+                "Nonce timestamp {} is older than {} seconds",
+                self.timestamp,
+                max_age.as_secs()
+            )));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_AGE: Duration = Duration::from_secs(5 * 60);
+
+    fn binding() -> NonceBinding {
+        NonceBinding::new([0xc4; 32], [0x9b; 32], 1_746_000_000)
+    }
+
+    #[test]
+    fn test_derive_nonce() {
+        let nonce = binding().derive();
+        assert_eq!(nonce, binding().derive(), "derivation is deterministic");
+        assert_eq!(
+            nonce,
+            *blake3::Hasher::new_derive_key(NONCE_DERIVATION_CONTEXT)
+                .update(&32u64.to_le_bytes())
+                .update(&[0xc4; 32])
+                .update(&32u64.to_le_bytes())
+                .update(&[0x9b; 32])
+                .update(&1_746_000_000u64.to_le_bytes())
+                .finalize()
+                .as_bytes()
+        );
+
+        // Every input changes the nonce, and fields cannot be shifted into each other
+        let mut other = binding();
+        other.timestamp += 1;
+        assert_ne!(other.derive(), nonce);
+        let mut other = binding();
+        other.session_public_key[0] ^= 1;
+        assert_ne!(other.derive(), nonce);
+        let shifted = NonceBinding::new([0xc4; 31], [[0xc4].as_slice(), &[0x9b; 32]].concat(), 0);
+        let unshifted = NonceBinding::new([0xc4; 32], [0x9b; 32], 0);
+        assert_ne!(shifted.derive(), unshifted.derive());
+    }
+
+    #[test]
+    fn test_verify_nonce_binding() {
+        let binding = binding();
+        let nonce = binding.derive();
+        let now = binding.timestamp + 10;
+        assert!(binding.verify_at(&nonce, MAX_AGE, now).is_ok());
+        let fresh = NonceBinding::now(
+            binding.challenge.clone(),
+            binding.session_public_key.clone(),
+        );
+        assert!(fresh.verify(&fresh.derive(), MAX_AGE).is_ok());
+
+        let mut other_session = binding.clone();
+        other_session.session_public_key = vec![0x9c; 32];
+        let error = other_session.verify_at(&nonce, MAX_AGE, now).unwrap_err();
+        assert!(matches!(error, AttestError::NonceBinding(_)));
+        assert_eq!(error.kind(), crate::AttestErrorKind::ClaimValidation);
+
+        assert!(binding.verify_at(&nonce[..16], MAX_AGE, now).is_err());
+        assert!(binding
+            .verify_at(&nonce, MAX_AGE, binding.timestamp + MAX_AGE.as_secs() + 1)
+            .is_err());
+        assert!(binding
+            .verify_at(
+                &nonce,
+                MAX_AGE,
+                binding.timestamp - MAX_NONCE_CLOCK_SKEW.as_secs()
+            )
+            .is_ok());
+        assert!(binding
+            .verify_at(
+                &nonce,
+                MAX_AGE,
+                binding.timestamp - MAX_NONCE_CLOCK_SKEW.as_secs() - 1
+            )
+            .is_err());
+    }
+}
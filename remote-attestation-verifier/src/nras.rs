@@ -0,0 +1,485 @@
+use std::time::{Duration, Instant};
+
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use serde::Serialize;
+use serde_json::{json, Value};
+use tracing::{debug, error, info, warn, Instrument};
+
+use crate::{
+    audit::{evidence_digest, AuditEvent, AuditLog, AuditVerdict},
+    cache::{AttestationCache, CacheKey, CachedAttestation},
+    constants::{
+        ARCH_KEY, CLAIMS_VERSION_KEY, DEFAULT_CLAIMS_VERSION, DEFAULT_TIMEOUT, EVIDENCE_LIST_KEY,
+        EXP_CLAIM, NONCE_KEY, NRAS_REQUEST_ID_HEADER, NVIDIA_OCSP_ALLOW_CERT_HOLD_HEADER,
+    },
+    errors::{AttestError, Result},
+    options::{resolve_allow_hold_cert, AttestRemoteOptions},
+    propagation::inject_trace_context,
+    utils::{nras_token::NvidiaAttestationClaims, unix_now},
+};
+
+/// Sends an evidence list to the remote attestation service (NRAS) and verifies the response.
+///
+/// This is the request path shared by GPU and `NVSwitch` attestation, which only differ in
+/// the architecture sent in the payload and the default verifier URL.
+///
+/// If the options carry an attestation cache, a previously verified result for the same
+/// evidence, nonce and options is returned without sending the evidence to NRAS, as long
+/// as its token has not expired. The token of a cached result is verified again, and
+/// checked against the nonce and number of devices, before being used. This fetches the
+/// NRAS JWKS, so NRAS must still be reachable on a hit.
+///
+/// If the options carry an audit log, the outcome of the verification, including
+/// failures and cache hits, is recorded in it before being returned.
+///
+/// # Arguments
+///
+/// * `evidence_list` - The evidence to attest, serialized as the NRAS `evidence_list`
+/// * `nonce` - A unique string value to prevent replay attacks
+/// * `arch` - The architecture of the attested devices (e.g. `HOPPER` or `LS10`)
+/// * `default_verifier_url` - The URL used if the options do not override it
+/// * `remote_attestation_options` - The remote attestation options
+///
+/// # Returns
+///
+/// A tuple with the overall attestation result and the complete JSON response from NRAS.
+///
+/// # Errors
+///
+/// * `AttestError::Transport` - If the request cannot be sent or the response cannot be read
+/// * `AttestError::HttpStatus` - If NRAS answers with a non-success status code
+/// * `AttestError::MalformedResponse` - If the response is not the expected JSON structure
+/// * `AttestError::AuditLog` - If the outcome cannot be recorded in the audit log
+/// * Errors propagated from `decode_nras_token` if the returned token cannot be verified
+pub async fn attest_remote<E: Serialize + Sync>(
+    evidence_list: &[E],
+    nonce: &str,
+    arch: &str,
+    default_verifier_url: &str,
+    remote_attestation_options: AttestRemoteOptions,
+) -> Result<(bool, Value)> {
+    let AttestRemoteOptions {
+        verifier_url,
+        allow_hold_cert,
+        claims_version,
+        service_key,
+        timeout,
+        cache,
+        audit_log,
+    } = remote_attestation_options;
+    let allow_hold_cert = resolve_allow_hold_cert(allow_hold_cert);
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    if allow_hold_cert {
+        headers.insert(
+            NVIDIA_OCSP_ALLOW_CERT_HOLD_HEADER,
+            HeaderValue::from_static("true"),
+        );
+    }
+    if let Some(ref service_key) = service_key {
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(service_key)?);
+    }
+    let request = NrasRequest {
+        evidence_list,
+        nonce,
+        arch,
+        verifier_url: verifier_url.unwrap_or_else(|| default_verifier_url.to_string()),
+        claims_version: claims_version.unwrap_or_else(|| DEFAULT_CLAIMS_VERSION.to_string()),
+        allow_hold_cert,
+        headers,
+        timeout,
+    };
+    let started_at = unix_now();
+    let started = Instant::now();
+    let result = verify_remote(&request, cache.as_deref()).await;
+    crate::metrics::record_attestation(
+        arch,
+        result
+            .as_ref()
+            .map(|verification| (verification.attestation_passed, verification.cached)),
+    );
+    if let Some(audit_log) = audit_log {
+        let duration_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+        record_audit_event(
+            audit_log.as_ref(),
+            &request,
+            &result,
+            started_at,
+            duration_ms,
+        )?;
+    }
+    result.map(|verification| (verification.attestation_passed, verification.response))
+}
+
+/// A remote attestation request, with every option resolved.
+struct NrasRequest<'a, E> {
+    evidence_list: &'a [E],
+    nonce: &'a str,
+    arch: &'a str,
+    verifier_url: String,
+    claims_version: String,
+    allow_hold_cert: bool,
+    headers: HeaderMap,
+    timeout: Option<Duration>,
+}
+
+/// Outcome of a completed remote attestation.
+struct Verification {
+    /// The overall attestation result
+    attestation_passed: bool,
+    /// The complete JSON response from NRAS
+    response: Value,
+    /// The decoded claims of the overall NRAS token
+    claims: Option<NvidiaAttestationClaims>,
+    /// Whether the result was served from the cache
+    cached: bool,
+}
+
+/// Verifies the evidence with NRAS, or returns the cached result of an identical request.
+async fn verify_remote<E: Serialize + Sync>(
+    request: &NrasRequest<'_, E>,
+    cache: Option<&dyn AttestationCache>,
+) -> Result<Verification> {
+    let NrasRequest {
+        evidence_list,
+        nonce,
+        arch,
+        ref verifier_url,
+        ref claims_version,
+        allow_hold_cert,
+        ref headers,
+        timeout,
+    } = *request;
+    let cache_key = cache.map(|_| {
+        CacheKey::new(
+            evidence_list,
+            nonce,
+            arch,
+            verifier_url,
+            claims_version,
+            allow_hold_cert,
+        )
+    });
+    if let (Some(cache), Some(cache_key)) = (cache, &cache_key) {
+        if let Some(cached) = cache.get(cache_key) {
+            debug!(
+                level = "attestation_cache",
+                cache_key = %cache_key,
+                arch = %arch,
+                expires_at = cached.expires_at,
+                "Attestation cache hit"
+            );
+            let verified = decode_overall_token(verifier_url, &cached.response)
+                .await
+                .and_then(|claims| {
+                    check_cached_response(&claims, &cached.response, nonce, evidence_list.len())
+                        .map(|()| claims)
+                });
+            match verified {
+                Ok(claims) => {
+                    return Ok(Verification {
+                        attestation_passed: claims.overall_attestation_result,
+                        response: cached.response,
+                        claims: Some(claims),
+                        cached: true,
+                    });
+                }
+                Err(e) => warn!(
+                    level = "attestation_cache",
+                    cache_key = %cache_key,
+                    arch = %arch,
+                    "Ignoring cached attestation result that fails verification: {e}"
+                ),
+            }
+        }
+        debug!(
+            level = "attestation_cache",
+            cache_key = %cache_key,
+            arch = %arch,
+            "Attestation cache miss"
+        );
+    }
+    let payload = json!({
+        NONCE_KEY: nonce,
+        EVIDENCE_LIST_KEY: evidence_list,
+        CLAIMS_VERSION_KEY: claims_version,
+        ARCH_KEY: arch,
+    });
+    info!(
+        level = "attest_remote",
+        arch = %arch,
+        verifier_url = %verifier_url,
+        claims_version = %claims_version,
+        nonce = %nonce,
+        timeout = ?timeout,
+        "Sending attestation request to NRAS url {verifier_url}, with claims version {claims_version}, nonce {nonce}"
+    );
+    let response_json = send_attestation_request(
+        verifier_url,
+        arch,
+        claims_version,
+        headers.clone(),
+        &payload,
+        timeout,
+    )
+    .await?;
+    info!(
+        level = "attest_remote",
+        arch = %arch,
+        verifier_url = %verifier_url,
+        nonce = %nonce,
+        "Attestation request successful, response: {response_json}",
+    );
+    let decoded_main_jwt_token = decode_overall_token(verifier_url, &response_json)
+        .await
+        .inspect_err(crate::metrics::record_token_verification_failure)?;
+    if let (Some(cache), Some(cache_key)) = (cache, cache_key) {
+        cache_attestation_result(cache, cache_key, &decoded_main_jwt_token, &response_json);
+    }
+    Ok(Verification {
+        attestation_passed: decoded_main_jwt_token.overall_attestation_result,
+        response: response_json,
+        claims: Some(decoded_main_jwt_token),
+        cached: false,
+    })
+}
+
+/// Decodes and verifies the overall token of an NRAS response.
+async fn decode_overall_token(
+    verifier_url: &str,
+    response_json: &Value,
+) -> Result<NvidiaAttestationClaims> {
+    let main_jwt_token = crate::utils::get_overall_claims_token(response_json)?;
+    crate::utils::nras_token::decode_nras_token(verifier_url, &main_jwt_token).await
+}
+
+/// Checks that a cached NRAS response answers this request: its overall token must have
+/// been issued for `nonce`, and both the token's `submods` and the device tokens must
+/// cover `evidence_count` devices.
+///
+/// The cache key is not trusted for this, as a writable store can hold the valid response
+/// of another request under any key.
+///
+/// # Errors
+///
+/// * `AttestError::ClaimValidation` - If the token was issued for another nonce, or covers
+///   another number of devices
+/// * `AttestError::MalformedResponse` - If the response has another number of device tokens
+fn check_cached_response(
+    claims: &NvidiaAttestationClaims,
+    response: &Value,
+    nonce: &str,
+    evidence_count: usize,
+) -> Result<()> {
+    claims.check_nonce(nonce)?;
+    let submods = claims.submod_names().count();
+    if submods != evidence_count {
+        return Err(AttestError::ClaimValidation(format!(
+            "NRAS token covers {submods} devices, expected {evidence_count}"
+        )));
+    }
+    let device_tokens = response
+        .get(1)
+        .and_then(Value::as_object)
+        .map_or(0, serde_json::Map::len);
+    if device_tokens != evidence_count {
+        return Err(AttestError::MalformedResponse(format!(
+            "NRAS response holds {device_tokens} device tokens, expected {evidence_count}"
+        )));
+    }
+    Ok(())
+}
+
+/// Posts the attestation payload to NRAS and returns the JSON response.
+///
+/// # Errors
+///
+/// * `AttestError::Transport` - If the request cannot be sent or the response cannot be read
+/// * `AttestError::HttpStatus` - If NRAS answers with a non-success status code
+/// * `AttestError::MalformedResponse` - If the response is not valid JSON
+async fn send_attestation_request(
+    verifier_url: &str,
+    arch: &str,
+    claims_version: &str,
+    headers: HeaderMap,
+    payload: &Value,
+    timeout: Option<Duration>,
+) -> Result<Value> {
+    let transport_error = |source| AttestError::Transport {
+        url: verifier_url.to_string(),
+        source,
+    };
+    let client = reqwest::Client::builder()
+        .timeout(timeout.unwrap_or(DEFAULT_TIMEOUT))
+        .build()
+        .map_err(transport_error)?;
+    let request_span = tracing::info_span!(
+        "nras_request",
+        url = %verifier_url,
+        claims_version = %claims_version,
+        request_id = tracing::field::Empty,
+    );
+    let mut headers = headers;
+    inject_trace_context(&request_span, &mut headers);
+    let sent = Instant::now();
+    let response = client
+        .post(verifier_url)
+        .headers(headers)
+        .json(payload)
+        .send()
+        .instrument(request_span.clone())
+        .await
+        .map_err(|e| {
+            error!(
+                level = "attest_remote",
+                "Failed to send attestation request: {e}"
+            );
+            crate::metrics::record_nras_request(arch, None, sent.elapsed());
+            transport_error(e)
+        })?;
+    let status = response.status();
+    crate::metrics::record_nras_request(arch, Some(status.as_u16()), sent.elapsed());
+    let header_request_id = response
+        .headers()
+        .get(NRAS_REQUEST_ID_HEADER)
+        .and_then(|request_id| request_id.to_str().ok())
+        .map(str::to_string);
+    if let Some(request_id) = &header_request_id {
+        request_span.record("request_id", request_id.as_str());
+    }
+    let body = response.text().await.map_err(transport_error)?;
+    if !status.is_success() {
+        error!(
+            level = "attest_remote",
+            "Attestation request failed with status code {status}"
+        );
+        let error = AttestError::from_http_status(status.as_u16(), body);
+        // NRAS also reports the request identifier in its error bodies
+        if let (
+            None,
+            AttestError::HttpStatus {
+                body: Some(body), ..
+            },
+        ) = (&header_request_id, &error)
+        {
+            if let Some(request_id) = &body.request_id {
+                request_span.record("request_id", request_id.as_str());
+            }
+        }
+        return Err(error);
+    }
+    serde_json::from_str::<Value>(&body).map_err(|e| {
+        error!(
+            level = "attest_remote",
+            verifier_url = %verifier_url,
+            "Failed to parse response with error: {e}",
+        );
+        AttestError::MalformedResponse(format!("Response is not valid JSON: {e}"))
+    })
+}
+
+/// Stores a verified attestation result in the cache, until the NRAS token expires.
+///
+/// Results whose token has no `exp` claim are not cached.
+fn cache_attestation_result(
+    cache: &dyn AttestationCache,
+    cache_key: CacheKey,
+    claims: &NvidiaAttestationClaims,
+    response_json: &Value,
+) {
+    let Some(expires_at) = claims
+        .additional_claims
+        .get(EXP_CLAIM)
+        .and_then(Value::as_u64)
+    else {
+        debug!(
+            level = "attestation_cache",
+            cache_key = %cache_key,
+            "NRAS token has no `exp` claim, not caching the attestation result"
+        );
+        return;
+    };
+    cache.insert(
+        cache_key,
+        CachedAttestation {
+            response: response_json.clone(),
+            expires_at,
+        },
+    );
+}
+
+/// Records the outcome of a remote attestation in the audit log.
+///
+/// A verification whose outcome cannot be recorded is reported as failed, so that no
+/// unaudited result is ever returned. If the verification itself failed, the original
+/// error is returned and the audit failure is only logged.
+///
+/// # Errors
+///
+/// * `AttestError::AuditLog` - If a completed verification cannot be recorded
+fn record_audit_event<E: Serialize>(
+    audit_log: &dyn AuditLog,
+    request: &NrasRequest<'_, E>,
+    result: &Result<Verification>,
+    started_at: u64,
+    duration_ms: u64,
+) -> Result<()> {
+    let mut event = AuditEvent {
+        arch: request.arch.to_string(),
+        verifier_url: request.verifier_url.clone(),
+        nonce: request.nonce.to_string(),
+        evidence_digest: evidence_digest(request.evidence_list),
+        verdict: AuditVerdict::Error,
+        nras_response: None,
+        claims: None,
+        error_kind: None,
+        error: None,
+        cached: false,
+        started_at,
+        duration_ms,
+    };
+    match result {
+        Ok(verification) => {
+            event.verdict = if verification.attestation_passed {
+                AuditVerdict::Passed
+            } else {
+                AuditVerdict::Failed
+            };
+            event.nras_response = Some(verification.response.clone());
+            event.claims = verification
+                .claims
+                .as_ref()
+                .and_then(|claims| serde_json::to_value(claims).ok());
+            event.cached = verification.cached;
+        }
+        Err(e) => {
+            event.error_kind = Some(e.kind());
+            event.error = Some(e.to_string());
+        }
+    }
+    match audit_log.append(event) {
+        Ok(record) => {
+            debug!(
+                level = "attestation_audit",
+                sequence = record.sequence,
+                hash = %record.hash,
+                "Recorded attestation decision in the audit log"
+            );
+            Ok(())
+        }
+        Err(e) if result.is_err() => {
+            error!(
+                level = "attestation_audit",
+                "Failed to record failed attestation in the audit log: {e}"
+            );
+            Ok(())
+        }
+        Err(e) => {
+            error!(
+                level = "attestation_audit",
+                "Failed to record attestation decision in the audit log: {e}"
+            );
+            Err(e.into())
+        }
+    }
+}
@@ -0,0 +1,821 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use reqwest::header::HeaderValue;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    audit::AuditLog,
+    cache::AttestationCache,
+    constants::{
+        DEFAULT_CLAIMS_VERSION, DEFAULT_TIMEOUT, NV_ALLOW_HOLD_CERT_KEY,
+        NV_ATTESTATION_CLAIMS_VERSION_KEY, NV_ATTESTATION_SERVICE_KEY_FILE_KEY,
+        NV_ATTESTATION_SWITCH_VERIFIER_URL_KEY, NV_ATTESTATION_TIMEOUT_SECS_KEY,
+        NV_ATTESTATION_VERIFIER_URL_KEY, REMOTE_GPU_VERIFIER_SERVICE_URL,
+        REMOTE_NVSWITCH_VERIFIER_SERVICE_URL,
+    },
+    errors::{AttestError, Result},
+};
+
+/// Placeholder printed instead of secret values when reporting the configuration.
+const REDACTED: &str = "<redacted>";
+
+/// Kind of device whose evidence remote attestation options are used for.
+///
+/// GPUs and switches are verified by different NRAS endpoints, so the verifier URL is
+/// read from a different environment variable and config file section for each kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    /// GPU evidence, verified with `verify_gpu_attestation`
+    Gpu,
+    /// Switch evidence, verified with `verify_nvswitch_attestation`
+    Switch,
+}
+
+impl DeviceKind {
+    /// Returns the name of the kind, as used in config file sections.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gpu => "gpu",
+            Self::Switch => "switch",
+        }
+    }
+
+    /// Returns the environment variable the verifier URL is read from.
+    #[must_use]
+    pub const fn verifier_url_key(self) -> &'static str {
+        match self {
+            Self::Gpu => NV_ATTESTATION_VERIFIER_URL_KEY,
+            Self::Switch => NV_ATTESTATION_SWITCH_VERIFIER_URL_KEY,
+        }
+    }
+
+    /// Returns the URL of the NRAS endpoint verifying the evidence of this kind by default.
+    #[must_use]
+    pub const fn default_verifier_url(self) -> &'static str {
+        match self {
+            Self::Gpu => REMOTE_GPU_VERIFIER_SERVICE_URL,
+            Self::Switch => REMOTE_NVSWITCH_VERIFIER_SERVICE_URL,
+        }
+    }
+
+    /// Returns the prefix of the names NRAS gives devices of this kind (e.g. `GPU-0`).
+    #[must_use]
+    pub const fn submod_prefix(self) -> &'static str {
+        match self {
+            Self::Gpu => "GPU-",
+            Self::Switch => "SWITCH-",
+        }
+    }
+}
+
+impl fmt::Display for DeviceKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Options for remote attestation
+#[derive(Default, Clone)]
+pub struct AttestRemoteOptions {
+    /// Optional URL of the verification service. If `None`, uses the default URL
+    pub verifier_url: Option<String>,
+    /// Optional flag to allow certificate hold status. If `None`, uses the system default
+    pub allow_hold_cert: Option<bool>,
+    /// Optional claims version. If `None`, uses the system default
+    pub claims_version: Option<String>,
+    /// Optional service key for authorization
+    pub service_key: Option<String>,
+    /// Optional request timeout
+    pub timeout: Option<Duration>,
+    /// Optional cache of verified results, keyed by the digest of the request
+    pub cache: Option<Arc<dyn AttestationCache>>,
+    /// Optional audit log recording every attestation decision
+    pub audit_log: Option<Arc<dyn AuditLog>>,
+}
+
+impl AttestRemoteOptions {
+    /// Creates a builder that layers defaults, a config file, `NV_*` environment
+    /// variables and explicit values, in that order of precedence.
+    #[must_use]
+    pub fn builder() -> AttestRemoteOptionsBuilder {
+        AttestRemoteOptionsBuilder::default()
+    }
+
+    /// Returns the effective configuration used to verify the evidence of `kind`, with
+    /// the service key redacted.
+    ///
+    /// Values that are left unset are reported with the default the verifier falls back
+    /// to at request time.
+    #[must_use]
+    pub fn effective_config(&self, kind: DeviceKind) -> EffectiveConfig {
+        EffectiveConfig {
+            verifier_url: self
+                .verifier_url
+                .clone()
+                .unwrap_or_else(|| kind.default_verifier_url().to_string()),
+            allow_hold_cert: resolve_allow_hold_cert(self.allow_hold_cert),
+            claims_version: self
+                .claims_version
+                .clone()
+                .unwrap_or_else(|| DEFAULT_CLAIMS_VERSION.to_string()),
+            service_key: self.service_key.as_ref().map(|_| REDACTED.to_string()),
+            timeout_secs: self.timeout.unwrap_or(DEFAULT_TIMEOUT).as_secs_f64(),
+            cache_enabled: self.cache.is_some(),
+            audit_log_enabled: self.audit_log.is_some(),
+        }
+    }
+}
+
+/// Returns whether certificates on hold are allowed, given the value of
+/// [`AttestRemoteOptions::allow_hold_cert`].
+///
+/// Unset values fall back to the deprecated global setting and the `NV_ALLOW_HOLD_CERT`
+/// environment variable, as before the options carried the value.
+#[allow(deprecated)]
+pub(crate) fn resolve_allow_hold_cert(allow_hold_cert: Option<bool>) -> bool {
+    allow_hold_cert.unwrap_or_else(crate::utils::get_allow_hold_cert)
+}
+
+impl fmt::Debug for AttestRemoteOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AttestRemoteOptions")
+            .field("verifier_url", &self.verifier_url)
+            .field("allow_hold_cert", &self.allow_hold_cert)
+            .field("claims_version", &self.claims_version)
+            .field("service_key", &self.service_key.as_ref().map(|_| REDACTED))
+            .field("timeout", &self.timeout)
+            .field("cache_enabled", &self.cache.is_some())
+            .field("audit_log_enabled", &self.audit_log.is_some())
+            .finish()
+    }
+}
+
+/// Effective remote attestation configuration, safe to log.
+///
+/// Produced by [`AttestRemoteOptions::effective_config`]. Secrets are
+/// replaced by a `<redacted>` marker.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EffectiveConfig {
+    /// URL of the verification service
+    pub verifier_url: String,
+    /// Whether certificates on hold are allowed
+    pub allow_hold_cert: bool,
+    /// Claims version
+    pub claims_version: String,
+    /// Redacted marker if a service key is configured
+    pub service_key: Option<String>,
+    /// Request timeout in seconds
+    pub timeout_secs: f64,
+    /// Whether verified results are cached
+    pub cache_enabled: bool,
+    /// Whether attestation decisions are recorded in an audit log
+    pub audit_log_enabled: bool,
+}
+
+impl fmt::Display for EffectiveConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "verifier_url={}, allow_hold_cert={}, claims_version={}, service_key={}, timeout_secs={}, cache_enabled={}, audit_log_enabled={}",
+            self.verifier_url,
+            self.allow_hold_cert,
+            self.claims_version,
+            self.service_key.as_deref().unwrap_or("<none>"),
+            self.timeout_secs,
+            self.cache_enabled,
+            self.audit_log_enabled,
+        )
+    }
+}
+
+/// Remote attestation options as they appear in a TOML or JSON config file.
+///
+/// Every field is optional, so a file only needs to contain the values it overrides.
+/// Top-level values apply to both device kinds, and the `[gpu]` and `[switch]` sections
+/// override them for one kind. The verifier URL differs between kinds, so it can only
+/// be set in a section:
+///
+/// ```toml
+/// timeout_secs = 10
+/// claims_version = "2.0"
+/// service_key_file = "/etc/atoma/nras-service-key"
+/// allow_hold_cert = false
+///
+/// [gpu]
+/// verifier_url = "https://nras.attestation.nvidia.com/v3/attest/gpu"
+///
+/// [switch]
+/// verifier_url = "https://nras.attestation.nvidia.com/v3/attest/switch"
+/// ```
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AttestRemoteConfig {
+    /// URL of the verification service, only allowed in a device kind section of a
+    /// config file
+    pub verifier_url: Option<String>,
+    /// Request timeout, in seconds
+    pub timeout_secs: Option<u64>,
+    /// Claims version requested from the verification service
+    pub claims_version: Option<String>,
+    /// Path to a file containing the service key
+    pub service_key_file: Option<PathBuf>,
+    /// Whether certificates on hold are allowed during OCSP checks
+    pub allow_hold_cert: Option<bool>,
+    /// Values overriding the above for GPU evidence
+    pub gpu: Option<Box<Self>>,
+    /// Values overriding the above for switch evidence
+    pub switch: Option<Box<Self>>,
+}
+
+impl AttestRemoteConfig {
+    /// Loads a config file, choosing the format from the file extension
+    /// (`.toml` or `.json`).
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::ConfigError` - If the file cannot be read, has an unknown
+    ///   extension, or cannot be parsed
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        read_config_file(path)
+    }
+
+    /// Returns the values for `kind`: the top-level values, overridden by the section of
+    /// `kind`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::ConfigError` - If the verifier URL is set outside of a section,
+    ///   or a section holds nested sections
+    pub fn for_kind(&self, kind: DeviceKind) -> Result<Self> {
+        if self.verifier_url.is_some() {
+            return Err(AttestError::ConfigError(
+                "verifier_url must be set in the [gpu] or [switch] section".to_string(),
+            ));
+        }
+        let mut config = Self {
+            gpu: None,
+            switch: None,
+            ..self.clone()
+        };
+        let section = match kind {
+            DeviceKind::Gpu => self.gpu.as_deref(),
+            DeviceKind::Switch => self.switch.as_deref(),
+        };
+        if let Some(section) = section {
+            if section.gpu.is_some() || section.switch.is_some() {
+                return Err(AttestError::ConfigError(format!(
+                    "The [{kind}] section cannot hold nested sections"
+                )));
+            }
+            config.merge(section.clone());
+        }
+        Ok(config)
+    }
+
+    /// Reads the `NV_*` environment variables for `kind` through `lookup`.
+    fn from_env_lookup(lookup: impl Fn(&str) -> Option<String>, kind: DeviceKind) -> Result<Self> {
+        let timeout_secs = lookup(NV_ATTESTATION_TIMEOUT_SECS_KEY)
+            .map(|value| {
+                value
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|e| AttestError::InvalidOption {
+                        field: NV_ATTESTATION_TIMEOUT_SECS_KEY,
+                        message: format!("`{value}` is not a number of seconds: {e}"),
+                    })
+            })
+            .transpose()?;
+        let allow_hold_cert = lookup(NV_ALLOW_HOLD_CERT_KEY)
+            .map(|value| match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" => Ok(true),
+                "false" | "0" => Ok(false),
+                _ => Err(AttestError::InvalidOption {
+                    field: NV_ALLOW_HOLD_CERT_KEY,
+                    message: format!("`{value}` is not a boolean"),
+                }),
+            })
+            .transpose()?;
+        Ok(Self {
+            verifier_url: lookup(kind.verifier_url_key()),
+            timeout_secs,
+            claims_version: lookup(NV_ATTESTATION_CLAIMS_VERSION_KEY),
+            service_key_file: lookup(NV_ATTESTATION_SERVICE_KEY_FILE_KEY).map(PathBuf::from),
+            allow_hold_cert,
+            gpu: None,
+            switch: None,
+        })
+    }
+
+    /// Overrides the values of `self` with the values set in `other`.
+    fn merge(&mut self, other: Self) {
+        if other.verifier_url.is_some() {
+            self.verifier_url = other.verifier_url;
+        }
+        if other.timeout_secs.is_some() {
+            self.timeout_secs = other.timeout_secs;
+        }
+        if other.claims_version.is_some() {
+            self.claims_version = other.claims_version;
+        }
+        if other.service_key_file.is_some() {
+            self.service_key_file = other.service_key_file;
+        }
+        if other.allow_hold_cert.is_some() {
+            self.allow_hold_cert = other.allow_hold_cert;
+        }
+    }
+}
+
+/// Builder for [`AttestRemoteOptions`].
+///
+/// Values are layered with the following precedence, from lowest to highest:
+/// 1. Built-in defaults (left as `None` in the resulting options)
+/// 2. A TOML or JSON config file, see [`AttestRemoteConfig`]
+/// 3. `NV_*` environment variables, see the `NV_ATTESTATION_*` constants
+/// 4. Values set explicitly on the builder
+///
+/// # Example
+///
+/// ```rust,ignore
+/// let options = AttestRemoteOptions::builder()
+///     .config_file("/etc/atoma/attestation.toml", DeviceKind::Gpu)
+///     .with_env(DeviceKind::Gpu)
+///     .timeout(Duration::from_secs(10))
+///     .build()?;
+/// tracing::info!("Remote attestation configuration: {}", options.effective_config(DeviceKind::Gpu));
+/// ```
+#[derive(Default)]
+pub struct AttestRemoteOptionsBuilder {
+    config_file: Option<(PathBuf, DeviceKind)>,
+    env_kind: Option<DeviceKind>,
+    explicit: AttestRemoteConfig,
+    service_key: Option<String>,
+    timeout: Option<Duration>,
+    cache: Option<Arc<dyn AttestationCache>>,
+    audit_log: Option<Arc<dyn AuditLog>>,
+}
+
+impl AttestRemoteOptionsBuilder {
+    /// Loads the values for `kind` from a TOML or JSON config file when building, see
+    /// [`AttestRemoteConfig::for_kind`].
+    #[must_use]
+    pub fn config_file(mut self, path: impl Into<PathBuf>, kind: DeviceKind) -> Self {
+        self.config_file = Some((path.into(), kind));
+        self
+    }
+
+    /// Reads the `NV_*` environment variables for `kind` when building.
+    ///
+    /// The verifier URL is read from `NV_ATTESTATION_VERIFIER_URL` for GPUs, and from
+    /// `NV_ATTESTATION_SWITCH_VERIFIER_URL` for switches.
+    #[must_use]
+    pub const fn with_env(mut self, kind: DeviceKind) -> Self {
+        self.env_kind = Some(kind);
+        self
+    }
+
+    /// Layers the sources shared by every entry point: the values for `kind` in
+    /// `config_file` if any, then the `NV_*` environment variables for `kind` if
+    /// `read_env`. Explicit values set on the builder still take precedence.
+    #[must_use]
+    pub fn layered(self, config_file: Option<&Path>, read_env: bool, kind: DeviceKind) -> Self {
+        let mut builder = self;
+        if let Some(path) = config_file {
+            builder = builder.config_file(path, kind);
+        }
+        if read_env {
+            builder = builder.with_env(kind);
+        }
+        builder
+    }
+
+    /// Sets the URL of the verification service.
+    #[must_use]
+    pub fn verifier_url(mut self, verifier_url: impl Into<String>) -> Self {
+        self.explicit.verifier_url = Some(verifier_url.into());
+        self
+    }
+
+    /// Sets the request timeout.
+    #[must_use]
+    pub const fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the claims version requested from the verification service.
+    #[must_use]
+    pub fn claims_version(mut self, claims_version: impl Into<String>) -> Self {
+        self.explicit.claims_version = Some(claims_version.into());
+        self
+    }
+
+    /// Sets the service key directly, taking precedence over any service key file.
+    #[must_use]
+    pub fn service_key(mut self, service_key: impl Into<String>) -> Self {
+        self.service_key = Some(service_key.into());
+        self
+    }
+
+    /// Sets the path of a file containing the service key.
+    #[must_use]
+    pub fn service_key_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.explicit.service_key_file = Some(path.into());
+        self
+    }
+
+    /// Sets whether certificates on hold are allowed during OCSP checks.
+    #[must_use]
+    pub const fn allow_hold_cert(mut self, allow_hold_cert: bool) -> Self {
+        self.explicit.allow_hold_cert = Some(allow_hold_cert);
+        self
+    }
+
+    /// Caches verified results in `cache` until the NRAS token expires.
+    #[must_use]
+    pub fn cache(mut self, cache: Arc<dyn AttestationCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Records every attestation decision, including failures, in `audit_log`.
+    #[must_use]
+    pub fn audit_log(mut self, audit_log: Arc<dyn AuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// Builds and validates the options.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::ConfigError` - If the config file or the service key file
+    ///   cannot be read or parsed
+    /// * `AttestError::InvalidOption` - If a value fails validation
+    pub fn build(self) -> Result<AttestRemoteOptions> {
+        self.build_with_env(|key| std::env::var(key).ok())
+    }
+
+    /// Builds the options, reading environment variables through `lookup`.
+    pub(crate) fn build_with_env(
+        self,
+        lookup: impl Fn(&str) -> Option<String>,
+    ) -> Result<AttestRemoteOptions> {
+        let mut config = AttestRemoteConfig::default();
+        if let Some((ref path, kind)) = self.config_file {
+            config.merge(AttestRemoteConfig::from_file(path)?.for_kind(kind)?);
+        }
+        if let Some(kind) = self.env_kind {
+            config.merge(AttestRemoteConfig::from_env_lookup(lookup, kind)?);
+        }
+        config.merge(self.explicit);
+
+        let service_key = match (self.service_key, config.service_key_file) {
+            (Some(service_key), _) => Some(service_key),
+            (None, Some(path)) => Some(read_service_key_file(&path)?),
+            (None, None) => None,
+        };
+        let options = AttestRemoteOptions {
+            verifier_url: config.verifier_url,
+            allow_hold_cert: config.allow_hold_cert,
+            claims_version: config.claims_version,
+            service_key,
+            timeout: self
+                .timeout
+                .or_else(|| config.timeout_secs.map(Duration::from_secs)),
+            cache: self.cache,
+            audit_log: self.audit_log,
+        };
+        validate(&options)?;
+        Ok(options)
+    }
+}
+
+/// Loads a TOML or JSON config file, choosing the format from the file extension
+/// (`.toml` or `.json`).
+///
+/// # Errors
+///
+/// * `AttestError::ConfigError` - If the file cannot be read, has an unknown
+///   extension, or cannot be parsed
+pub fn read_config_file<T: DeserializeOwned>(path: impl AsRef<Path>) -> Result<T> {
+    let path = path.as_ref();
+    let contents = std::fs::read_to_string(path)
+        .map_err(|e| AttestError::ConfigError(format!("Failed to read {}: {e}", path.display())))?;
+    match path.extension().and_then(|extension| extension.to_str()) {
+        Some("toml") => toml::from_str(&contents).map_err(|e| {
+            AttestError::ConfigError(format!("Failed to parse {}: {e}", path.display()))
+        }),
+        Some("json") => serde_json::from_str(&contents).map_err(|e| {
+            AttestError::ConfigError(format!("Failed to parse {}: {e}", path.display()))
+        }),
+        _ => Err(AttestError::ConfigError(format!(
+            "Unsupported config file format for {}, expected a .toml or .json file",
+            path.display()
+        ))),
+    }
+}
+
+/// Reads a service key from a file, trimming surrounding whitespace.
+fn read_service_key_file(path: &Path) -> Result<String> {
+    let service_key = std::fs::read_to_string(path).map_err(|e| {
+        AttestError::ConfigError(format!(
+            "Failed to read service key file {}: {e}",
+            path.display()
+        ))
+    })?;
+    Ok(service_key.trim().to_string())
+}
+
+/// Validates the values of the remote attestation options.
+fn validate(options: &AttestRemoteOptions) -> Result<()> {
+    if let Some(ref verifier_url) = options.verifier_url {
+        let url = Url::parse(verifier_url).map_err(|e| AttestError::InvalidOption {
+            field: "verifier_url",
+            message: format!("`{verifier_url}` is not a valid URL: {e}"),
+        })?;
+        if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+            return Err(AttestError::InvalidOption {
+                field: "verifier_url",
+                message: format!("`{verifier_url}` must be an http(s) URL with a host"),
+            });
+        }
+    }
+    if options.timeout == Some(Duration::ZERO) {
+        return Err(AttestError::InvalidOption {
+            field: "timeout",
+            message: "Timeout must be greater than zero".to_string(),
+        });
+    }
+    if let Some(ref claims_version) = options.claims_version {
+        let is_valid = claims_version
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit()));
+        if !is_valid {
+            return Err(AttestError::InvalidOption {
+                field: "claims_version",
+                message: format!("`{claims_version}` is not a version such as `2.0`"),
+            });
+        }
+    }
+    if let Some(ref service_key) = options.service_key {
+        if service_key.is_empty() || HeaderValue::from_str(service_key).is_err() {
+            return Err(AttestError::InvalidOption {
+                field: "service_key",
+                message: "Service key must be a non-empty, valid header value".to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    fn write_temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("{}-{name}", std::process::id()));
+        std::fs::write(&path, contents).expect("Failed to write temp file");
+        path
+    }
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect();
+        move |key| vars.get(key).cloned()
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let options = AttestRemoteOptions::builder()
+            .with_env(DeviceKind::Gpu)
+            .build_with_env(env(&[]))
+            .expect("Failed to build options");
+        assert!(options.verifier_url.is_none());
+        assert!(options.allow_hold_cert.is_none());
+        assert!(options.claims_version.is_none());
+        assert!(options.service_key.is_none());
+        assert!(options.timeout.is_none());
+    }
+
+    #[test]
+    fn test_builder_layers_file_env_and_explicit_values() {
+        let key_file = write_temp_file("service-key", "  secret-key\n");
+        let config_file = write_temp_file(
+            "attestation.toml",
+            &format!(
+                "timeout_secs = 5\n\
+                 claims_version = \"2.0\"\n\
+                 service_key_file = \"{}\"\n\
+                 [gpu]\n\
+                 verifier_url = \"https://file.example.com/v3/attest/gpu\"\n",
+                key_file.display()
+            ),
+        );
+        let options = AttestRemoteOptions::builder()
+            .layered(Some(&config_file), true, DeviceKind::Gpu)
+            .claims_version("3.0")
+            .build_with_env(env(&[
+                (NV_ATTESTATION_TIMEOUT_SECS_KEY, "7"),
+                (NV_ATTESTATION_CLAIMS_VERSION_KEY, "2.1"),
+                (NV_ALLOW_HOLD_CERT_KEY, "true"),
+            ]))
+            .expect("Failed to build options");
+        assert_eq!(
+            options.verifier_url.as_deref(),
+            Some("https://file.example.com/v3/attest/gpu")
+        );
+        assert_eq!(options.timeout, Some(Duration::from_secs(7)));
+        assert_eq!(options.claims_version.as_deref(), Some("3.0"));
+        assert_eq!(options.service_key.as_deref(), Some("secret-key"));
+        assert_eq!(options.allow_hold_cert, Some(true));
+
+        // Without the environment, the values of the config file are kept
+        let options = AttestRemoteOptions::builder()
+            .layered(Some(&config_file), false, DeviceKind::Gpu)
+            .build_with_env(env(&[(NV_ATTESTATION_TIMEOUT_SECS_KEY, "7")]))
+            .expect("Failed to build options");
+        assert_eq!(options.timeout, Some(Duration::from_secs(5)));
+        std::fs::remove_file(key_file).ok();
+        std::fs::remove_file(config_file).ok();
+    }
+
+    #[test]
+    fn test_builder_reads_json_config_file() {
+        let config_file = write_temp_file(
+            "attestation.json",
+            r#"{"switch": {"verifier_url": "http://localhost:8080/v3/attest/switch"}, "allow_hold_cert": false}"#,
+        );
+        let options = AttestRemoteOptions::builder()
+            .config_file(&config_file, DeviceKind::Switch)
+            .build()
+            .expect("Failed to build options");
+        assert_eq!(
+            options.verifier_url.as_deref(),
+            Some("http://localhost:8080/v3/attest/switch")
+        );
+        assert_eq!(options.allow_hold_cert, Some(false));
+        std::fs::remove_file(config_file).ok();
+    }
+
+    #[test]
+    fn test_builder_reads_verifier_url_per_device_kind() {
+        let config_file = write_temp_file(
+            "per-kind.toml",
+            "timeout_secs = 5\n\
+             [gpu]\n\
+             verifier_url = \"https://file.example.com/v3/attest/gpu\"\n\
+             [switch]\n\
+             verifier_url = \"https://file.example.com/v3/attest/switch\"\n\
+             timeout_secs = 9\n",
+        );
+        let build = |kind, vars: &[(&str, &str)]| {
+            AttestRemoteOptions::builder()
+                .config_file(&config_file, kind)
+                .with_env(kind)
+                .build_with_env(env(vars))
+                .expect("Failed to build options")
+        };
+        let gpu = build(DeviceKind::Gpu, &[]);
+        assert_eq!(
+            gpu.verifier_url.as_deref(),
+            Some("https://file.example.com/v3/attest/gpu")
+        );
+        assert_eq!(gpu.timeout, Some(Duration::from_secs(5)));
+        let switch = build(DeviceKind::Switch, &[]);
+        assert_eq!(
+            switch.verifier_url.as_deref(),
+            Some("https://file.example.com/v3/attest/switch")
+        );
+        assert_eq!(switch.timeout, Some(Duration::from_secs(9)));
+
+        // The GPU verifier URL variable does not apply to switches
+        let vars = [
+            (
+                NV_ATTESTATION_VERIFIER_URL_KEY,
+                "https://env.example.com/gpu",
+            ),
+            (
+                NV_ATTESTATION_SWITCH_VERIFIER_URL_KEY,
+                "https://env.example.com/switch",
+            ),
+        ];
+        let gpu = build(DeviceKind::Gpu, &vars);
+        assert_eq!(
+            gpu.verifier_url.as_deref(),
+            Some("https://env.example.com/gpu")
+        );
+        let switch = build(DeviceKind::Switch, &vars[..1]);
+        assert_eq!(
+            switch.verifier_url.as_deref(),
+            Some("https://file.example.com/v3/attest/switch")
+        );
+        let switch = build(DeviceKind::Switch, &vars);
+        assert_eq!(
+            switch.verifier_url.as_deref(),
+            Some("https://env.example.com/switch")
+        );
+        std::fs::remove_file(config_file).ok();
+
+        // A shared verifier URL would send switch evidence to the GPU endpoint
+        let config_file = write_temp_file(
+            "shared-url.toml",
+            "verifier_url = \"https://file.example.com/v3/attest/gpu\"\n",
+        );
+        let result = AttestRemoteOptions::builder()
+            .config_file(&config_file, DeviceKind::Switch)
+            .build();
+        assert!(matches!(result, Err(AttestError::ConfigError(_))));
+        std::fs::remove_file(config_file).ok();
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_values() {
+        let result = AttestRemoteOptions::builder()
+            .verifier_url("ftp://example.com")
+            .build();
+        assert!(matches!(
+            result,
+            Err(AttestError::InvalidOption {
+                field: "verifier_url",
+                ..
+            })
+        ));
+        let result = AttestRemoteOptions::builder()
+            .timeout(Duration::ZERO)
+            .build();
+        assert!(matches!(
+            result,
+            Err(AttestError::InvalidOption {
+                field: "timeout",
+                ..
+            })
+        ));
+        let result = AttestRemoteOptions::builder()
+            .claims_version("latest")
+            .build();
+        assert!(matches!(
+            result,
+            Err(AttestError::InvalidOption {
+                field: "claims_version",
+                ..
+            })
+        ));
+        let result = AttestRemoteOptions::builder()
+            .with_env(DeviceKind::Gpu)
+            .build_with_env(env(&[(NV_ALLOW_HOLD_CERT_KEY, "maybe")]));
+        assert!(matches!(
+            result,
+            Err(AttestError::InvalidOption {
+                field: NV_ALLOW_HOLD_CERT_KEY,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_effective_config_redacts_service_key() {
+        let options = AttestRemoteOptions::builder()
+            .service_key("nvapi-secret")
+            .build()
+            .expect("Failed to build options");
+        let effective_config = options.effective_config(DeviceKind::Gpu);
+        assert_eq!(effective_config.service_key.as_deref(), Some(REDACTED));
+        assert!(!effective_config.to_string().contains("nvapi-secret"));
+        assert!(!format!("{options:?}").contains("nvapi-secret"));
+    }
+
+    #[test]
+    fn test_effective_config_resolves_defaults() {
+        let options = AttestRemoteOptions {
+            allow_hold_cert: Some(false),
+            ..AttestRemoteOptions::default()
+        };
+        let effective_config = options.effective_config(DeviceKind::Switch);
+        assert_eq!(
+            effective_config.verifier_url,
+            REMOTE_NVSWITCH_VERIFIER_SERVICE_URL
+        );
+        assert_eq!(effective_config.claims_version, DEFAULT_CLAIMS_VERSION);
+        assert!(
+            (effective_config.timeout_secs - DEFAULT_TIMEOUT.as_secs_f64()).abs() < f64::EPSILON
+        );
+        assert!(!effective_config.to_string().contains("<default>"));
+        assert_eq!(
+            options.effective_config(DeviceKind::Gpu).verifier_url,
+            REMOTE_GPU_VERIFIER_SERVICE_URL
+        );
+    }
+}
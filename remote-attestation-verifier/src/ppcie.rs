@@ -0,0 +1,138 @@
+//! `PPCIe` topology checks of evidence bundles.
+//!
+//! In `PPCIe` mode, the GPUs of a node and its `NVSwitch` devices must form the expected
+//! topology: every GPU reports the same set of switches, and every switch reports the GPUs
+//! of the node. [`check_bundle_topology`] runs both checks of the `topology` crate on the
+//! reports of an evidence bundle:
+//!
+//! ```rust,ignore
+//! let report = check_bundle_topology(&bundle)?;
+//! if !report.passed() {
+//!     tracing::warn!("Topology check failed: {}", report.error.unwrap());
+//! }
+//! ```
+
+use topology::{
+    error::NvidiaRemoteAttestationError,
+    topology::{gpu_topology_check, switch_topology_check},
+};
+
+use crate::{
+    bundle::EvidenceBundle,
+    errors::Result,
+    types::{DeviceEvidence, NvSwitchEvidence},
+};
+
+/// Outcome of the GPU and switch topology checks.
+#[derive(Debug, Default)]
+pub struct TopologyReport {
+    pub gpu_topology_passed: bool,
+    pub switch_topology_passed: bool,
+    /// Hex encoded PDIs of the switches connected to every GPU, as reported by the GPUs,
+    /// sorted. Empty if the GPU topology check failed.
+    pub switch_pdis: Vec<String>,
+    /// Error of the failed check, if any
+    pub error: Option<NvidiaRemoteAttestationError>,
+}
+
+impl TopologyReport {
+    /// Returns `true` if both topology checks passed.
+    #[must_use]
+    pub const fn passed(&self) -> bool {
+        self.gpu_topology_passed && self.switch_topology_passed
+    }
+
+    /// Returns the reason of the failed check, if any.
+    #[must_use]
+    pub fn failure_reason(&self) -> Option<String> {
+        let error = self.error.as_ref()?;
+        Some(if self.gpu_topology_passed {
+            format!("Switch topology check failed: {error}")
+        } else {
+            format!("GPU topology check failed: {error}")
+        })
+    }
+}
+
+/// Runs the GPU topology check on the GPU reports, then, if it passed, the switch topology
+/// check on the switch reports.
+///
+/// A failed check is reported in the returned report rather than as an error.
+#[must_use]
+pub fn check_report_topology(gpu_reports: &[&[u8]], switch_reports: &[&[u8]]) -> TopologyReport {
+    let mut report = TopologyReport::default();
+    let unique_switch_pdis_set = match gpu_topology_check(gpu_reports) {
+        Ok(unique_switch_pdis_set) => unique_switch_pdis_set,
+        Err(e) => {
+            report.error = Some(e);
+            return report;
+        }
+    };
+    report.gpu_topology_passed = true;
+    report.switch_pdis = unique_switch_pdis_set.iter().map(hex::encode).collect();
+    report.switch_pdis.sort();
+    match switch_topology_check(switch_reports, gpu_reports.len(), unique_switch_pdis_set) {
+        Ok(()) => report.switch_topology_passed = true,
+        Err(e) => report.error = Some(e),
+    }
+    report
+}
+
+/// Runs the GPU, then the switch, topology checks on the reports of a bundle.
+///
+/// A failed check is reported in the returned report rather than as an error.
+///
+/// # Errors
+///
+/// * `AttestError::EvidenceDecode` - If a report is not valid base64
+pub fn check_bundle_topology(bundle: &EvidenceBundle) -> Result<TopologyReport> {
+    let gpu_reports = bundle
+        .gpu_evidence
+        .iter()
+        .map(DeviceEvidence::evidence_bytes)
+        .collect::<Result<Vec<_>>>()?;
+    let switch_reports = bundle
+        .switch_evidence
+        .iter()
+        .map(NvSwitchEvidence::evidence_bytes)
+        .collect::<Result<Vec<_>>>()?;
+    let gpu_reports: Vec<&[u8]> = gpu_reports.iter().map(Vec::as_slice).collect();
+    let switch_reports: Vec<&[u8]> = switch_reports.iter().map(Vec::as_slice).collect();
+    Ok(check_report_topology(&gpu_reports, &switch_reports))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{synthetic_bundle, SYNTHETIC_SWITCHES};
+
+    #[test]
+    fn test_check_bundle_topology() {
+        let mut bundle = synthetic_bundle([0x36; 32]);
+        let report = check_bundle_topology(&bundle).unwrap();
+        assert!(report.passed(), "{:?}", report.error);
+        assert_eq!(report.switch_pdis.len(), SYNTHETIC_SWITCHES);
+        assert!(report.failure_reason().is_none());
+
+        bundle.switch_evidence.pop();
+        let report = check_bundle_topology(&bundle).unwrap();
+        assert!(report.gpu_topology_passed);
+        assert!(!report.passed());
+        assert!(report
+            .failure_reason()
+            .unwrap()
+            .starts_with("Switch topology check failed"));
+
+        bundle.gpu_evidence.pop();
+        let report = check_bundle_topology(&bundle).unwrap();
+        assert!(!report.gpu_topology_passed);
+        assert!(report.switch_pdis.is_empty());
+        assert!(report
+            .failure_reason()
+            .unwrap()
+            .starts_with("GPU topology check failed"));
+
+        bundle.gpu_evidence[0].evidence = "not base64".to_string();
+        assert!(check_bundle_topology(&bundle).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//! Trace context propagation to NRAS and JWKS requests.
+//!
+//! With the `otel` feature, the OpenTelemetry context of the span of every NRAS and
+//! JWKS request is injected into the request headers with the globally configured
+//! text map propagator, e.g. W3C `traceparent` and `tracestate` headers with
+//! `opentelemetry_sdk::propagation::TraceContextPropagator`. The span context is read
+//! through `tracing-opentelemetry`, so the application must install its layer. Without
+//! the feature, or without a global propagator, no header is added.
+
+use reqwest::header::HeaderMap;
+use tracing::Span;
+
+/// Injects the trace context of `span` into `headers`.
+#[cfg_attr(not(feature = "otel"), allow(unused_variables))]
+pub fn inject_trace_context(span: &Span, headers: &mut HeaderMap) {
+    #[cfg(feature = "otel")]
+    {
+        use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+        let context = span.context();
+        opentelemetry::global::get_text_map_propagator(|propagator| {
+            propagator.inject_context(&context, &mut HeaderInjector(headers));
+        });
+    }
+}
+
+/// Sets the fields of a propagator on request headers.
+#[cfg(feature = "otel")]
+struct HeaderInjector<'a>(&'a mut HeaderMap);
+
+#[cfg(feature = "otel")]
+impl opentelemetry::propagation::Injector for HeaderInjector<'_> {
+    fn set(&mut self, key: &str, value: String) {
+        use reqwest::header::{HeaderName, HeaderValue};
+
+        // Fields that are not valid headers are dropped rather than failing the request
+        if let (Ok(name), Ok(value)) = (
+            HeaderName::from_bytes(key.as_bytes()),
+            HeaderValue::from_str(&value),
+        ) {
+            self.0.insert(name, value);
+        }
+    }
+}
@@ -0,0 +1,892 @@
+//! RA-TLS: attestation evidence embedded in TLS certificates.
+//!
+//! A node generates a TLS key, collects its GPU and `NVSwitch` evidence for a nonce
+//! derived from the public key of that TLS key, and presents a self-signed certificate
+//! whose [`RATLS_EVIDENCE_OID`] extension carries the evidence, and optionally the NRAS
+//! tokens the node obtained for it. A client verifying the certificate with a
+//! [`RaTlsVerifier`] knows it is talking to an attested node, without a separate
+//! attestation round trip:
+//!
+//! ```rust,ignore
+//! // Node
+//! let key = RaTlsKey::generate()?;
+//! let binding = key.nonce_binding(unix_now());
+//! let gpu_evidence = collect_gpu_evidence(&nvml, &binding.derive())?;
+//! let bundle = EvidenceBundle::new(&binding.derive(), gpu_evidence, vec![], host);
+//! let certificate = key.certificate(&RaTlsEvidence::new(binding.timestamp, bundle), names)?;
+//! let server_config = ServerConfig::builder()
+//!     .with_no_client_auth()
+//!     .with_single_cert(vec![certificate.certificate], certificate.private_key)?;
+//!
+//! // Client
+//! let verifier = Arc::new(RaTlsVerifier::new(gpu_options, switch_options));
+//! let client_config = ClientConfig::builder()
+//!     .dangerous()
+//!     .with_custom_certificate_verifier(verifier)
+//!     .with_no_client_auth();
+//! ```
+//!
+//! The nonce is derived with [`NonceBinding`], from [`RATLS_CHALLENGE`], the DER encoded
+//! `SubjectPublicKeyInfo` of the certificate and the derivation time. The TLS handshake
+//! proves the node holds the certificate key, and the nonce proves the evidence was
+//! collected for that key, so evidence cannot be replayed in another node's certificate.
+//! The certificates are self-signed: trust comes from the evidence alone, and server
+//! names and certificate chains are not checked.
+//!
+//! The verifier checks the embedded NRAS tokens if the certificate holds some, and
+//! otherwise submits the evidence to NRAS. An embedded token must have been issued for
+//! the nonce, and only cover devices of its kind (`GPU-*` or `SWITCH-*` submodules). rustls verifiers are synchronous, so the
+//! handshake blocks until NRAS answers, which is queried from a runtime owned by the
+//! verifier; verified certificates are cached until their evidence gets too old. By
+//! default a certificate may carry GPU or `NVSwitch` evidence, or both;
+//! [`RaTlsVerifier::with_required_scope`] demands some. This module is enabled by the
+//! `ratls` feature.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    future::Future,
+    sync::{mpsc, Arc, Mutex, OnceLock},
+    time::Duration,
+};
+
+use rcgen::{CertificateParams, CustomExtension, KeyPair, PKCS_ECDSA_P256_SHA256};
+use rustls::{
+    client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier},
+    crypto::{verify_tls12_signature, verify_tls13_signature, WebPkiSupportedAlgorithms},
+    pki_types::{CertificateDer, PrivateKeyDer, PrivatePkcs8KeyDer, ServerName, UnixTime},
+    server::danger::{ClientCertVerified, ClientCertVerifier},
+    CertificateError, DigitallySignedStruct, DistinguishedName, OtherError, SignatureScheme,
+};
+use serde::{Deserialize, Serialize};
+use tokio::runtime::Runtime;
+use x509_parser::prelude::{FromDer, X509Certificate};
+
+use crate::{
+    bundle::EvidenceBundle,
+    constants::{
+        DEFAULT_RATLS_MAX_EVIDENCE_AGE, REMOTE_GPU_VERIFIER_SERVICE_URL,
+        REMOTE_NVSWITCH_VERIFIER_SERVICE_URL,
+    },
+    errors::{AttestError, Result},
+    nonce::NonceBinding,
+    options::{AttestRemoteOptions, DeviceKind},
+    remote_gpu_attestation::verify_gpu_attestation,
+    remote_nvswitch_attestation::verify_nvswitch_attestation,
+    utils::nras_token::decode_nras_token,
+};
+
+/// OID of the certificate extension carrying the evidence, in the private arc of this
+/// crate. The extension value is the CBOR encoded [`RaTlsEvidence`].
+pub const RATLS_EVIDENCE_OID: &[u64] = &[1, 3, 6, 1, 4, 1, 59712, 1, 1];
+
+/// Challenge of the nonces of RA-TLS evidence, which has no verifier-issued challenge.
+pub const RATLS_CHALLENGE: &[u8] = b"ra-tls";
+
+/// The evidence an RA-TLS certificate must carry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RaTlsScope {
+    /// GPU evidence
+    Gpu,
+    /// `NVSwitch` evidence
+    Switch,
+    /// GPU and `NVSwitch` evidence, as nodes in `PPCIe` mode collect
+    Ppcie,
+}
+
+impl RaTlsScope {
+    /// Returns `true` if certificates must carry GPU evidence.
+    #[must_use]
+    pub const fn requires_gpus(self) -> bool {
+        matches!(self, Self::Gpu | Self::Ppcie)
+    }
+
+    /// Returns `true` if certificates must carry `NVSwitch` evidence.
+    #[must_use]
+    pub const fn requires_switches(self) -> bool {
+        matches!(self, Self::Switch | Self::Ppcie)
+    }
+}
+
+/// Returns the binding of the nonce of evidence embedded in a certificate whose DER
+/// encoded `SubjectPublicKeyInfo` is `public_key_der`.
+#[must_use]
+pub fn ratls_binding(public_key_der: &[u8], timestamp: u64) -> NonceBinding {
+    NonceBinding::new(RATLS_CHALLENGE, public_key_der, timestamp)
+}
+
+/// The content of the evidence extension of an RA-TLS certificate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RaTlsEvidence {
+    /// Time the nonce of the bundle was derived, in seconds since the Unix epoch
+    pub timestamp: u64,
+    /// The evidence of the node, collected for the nonce bound to the certificate key
+    pub bundle: EvidenceBundle,
+    /// Overall NRAS token of the GPU evidence of the bundle, if the node attested it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gpu_token: Option<String>,
+    /// Overall NRAS token of the `NVSwitch` evidence of the bundle, if the node attested it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub switch_token: Option<String>,
+}
+
+impl RaTlsEvidence {
+    /// Creates the evidence of `bundle`, whose nonce was derived at `timestamp`.
+    #[must_use]
+    pub const fn new(timestamp: u64, bundle: EvidenceBundle) -> Self {
+        Self {
+            timestamp,
+            bundle,
+            gpu_token: None,
+            switch_token: None,
+        }
+    }
+
+    /// Embeds the overall NRAS token of the GPU evidence.
+    #[must_use]
+    pub fn with_gpu_token(mut self, token: impl Into<String>) -> Self {
+        self.gpu_token = Some(token.into());
+        self
+    }
+
+    /// Embeds the overall NRAS token of the `NVSwitch` evidence.
+    #[must_use]
+    pub fn with_switch_token(mut self, token: impl Into<String>) -> Self {
+        self.switch_token = Some(token.into());
+        self
+    }
+
+    /// Returns `true` if the certificate carries GPU evidence, or its NRAS token.
+    const fn has_gpu_evidence(&self) -> bool {
+        !self.bundle.gpu_evidence.is_empty() || self.gpu_token.is_some()
+    }
+
+    /// Returns `true` if the certificate carries `NVSwitch` evidence, or its NRAS token.
+    const fn has_switch_evidence(&self) -> bool {
+        !self.bundle.switch_evidence.is_empty() || self.switch_token.is_some()
+    }
+
+    /// Checks that the certificate carries the evidence `scope` requires.
+    fn check_scope(&self, scope: RaTlsScope) -> Result<()> {
+        if scope.requires_gpus() && !self.has_gpu_evidence() {
+            return Err(AttestError::InvalidRaTlsCertificate(
+                "The certificate carries no GPU evidence".to_string(),
+            ));
+        }
+        if scope.requires_switches() && !self.has_switch_evidence() {
+            return Err(AttestError::InvalidRaTlsCertificate(
+                "The certificate carries no NVSwitch evidence".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Checks that the bundle is valid, and that its nonce was derived for the
+    /// certificate key `public_key_der` at most `max_age` before `now`.
+    fn check_binding(&self, public_key_der: &[u8], max_age: Duration, now: u64) -> Result<()> {
+        self.bundle.validate()?;
+        ratls_binding(public_key_der, self.timestamp).verify_at(
+            &self.bundle.nonce_bytes()?,
+            max_age,
+            now,
+        )
+    }
+}
+
+/// A certificate and its private key, to configure a rustls server or client with.
+#[derive(Debug)]
+pub struct RaTlsCertificate {
+    /// The self-signed certificate, carrying the evidence
+    pub certificate: CertificateDer<'static>,
+    /// The PKCS#8 encoded private key of the certificate
+    pub private_key: PrivateKeyDer<'static>,
+}
+
+/// The TLS key of a node, to which its evidence is bound.
+pub struct RaTlsKey {
+    key_pair: KeyPair,
+}
+
+impl RaTlsKey {
+    /// Generates an ECDSA P-256 key.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidRaTlsCertificate` - If the key cannot be generated
+    pub fn generate() -> Result<Self> {
+        let key_pair = KeyPair::generate_for(&PKCS_ECDSA_P256_SHA256)
+            .map_err(|e| AttestError::InvalidRaTlsCertificate(e.to_string()))?;
+        Ok(Self { key_pair })
+    }
+
+    /// Returns the DER encoded `SubjectPublicKeyInfo` of the key.
+    #[must_use]
+    pub fn public_key_der(&self) -> Vec<u8> {
+        self.key_pair.public_key_der()
+    }
+
+    /// Returns the binding of the nonce to collect evidence with, derived at `timestamp`.
+    #[must_use]
+    pub fn nonce_binding(&self, timestamp: u64) -> NonceBinding {
+        ratls_binding(&self.public_key_der(), timestamp)
+    }
+
+    /// Issues a self-signed certificate for `subject_alt_names`, carrying `evidence`.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidRaTlsCertificate` - If the evidence cannot be encoded, or a
+    ///   subject alternative name is invalid
+    pub fn certificate(
+        &self,
+        evidence: &RaTlsEvidence,
+        subject_alt_names: Vec<String>,
+    ) -> Result<RaTlsCertificate> {
+        let mut content = Vec::new();
+        ciborium::into_writer(evidence, &mut content).map_err(|e| {
+            AttestError::InvalidRaTlsCertificate(format!("Failed to encode the evidence: {e}"))
+        })?;
+        let mut params = CertificateParams::new(subject_alt_names)
+            .map_err(|e| AttestError::InvalidRaTlsCertificate(e.to_string()))?;
+        params
+            .custom_extensions
+            .push(CustomExtension::from_oid_content(
+                RATLS_EVIDENCE_OID,
+                content,
+            ));
+        let certificate = params
+            .self_signed(&self.key_pair)
+            .map_err(|e| AttestError::InvalidRaTlsCertificate(e.to_string()))?;
+        Ok(RaTlsCertificate {
+            certificate: certificate.der().clone(),
+            private_key: PrivatePkcs8KeyDer::from(self.key_pair.serialize_der()).into(),
+        })
+    }
+}
+
+impl fmt::Debug for RaTlsKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RaTlsKey")
+            .field("public_key", &hex::encode(self.public_key_der()))
+            .finish_non_exhaustive()
+    }
+}
+
+/// Parses an RA-TLS certificate, returning its DER encoded `SubjectPublicKeyInfo` and
+/// the evidence it carries.
+fn parse_certificate(certificate: &[u8]) -> Result<(Vec<u8>, RaTlsEvidence)> {
+    let (_, certificate) = X509Certificate::from_der(certificate)
+        .map_err(|e| AttestError::InvalidRaTlsCertificate(e.to_string()))?;
+    let extension = certificate
+        .extensions()
+        .iter()
+        .find(|extension| {
+            extension
+                .oid
+                .iter()
+                .is_some_and(|arcs| arcs.eq(RATLS_EVIDENCE_OID.iter().copied()))
+        })
+        .ok_or_else(|| {
+            AttestError::InvalidRaTlsCertificate(
+                "The certificate has no evidence extension".to_string(),
+            )
+        })?;
+    let evidence = ciborium::from_reader(extension.value).map_err(|e| {
+        AttestError::InvalidRaTlsCertificate(format!("Failed to decode the evidence: {e}"))
+    })?;
+    Ok((certificate.public_key().raw.to_vec(), evidence))
+}
+
+/// Verifies RA-TLS certificates, as a rustls [`ServerCertVerifier`] for clients and a
+/// [`ClientCertVerifier`] for servers requiring attested clients.
+pub struct RaTlsVerifier {
+    gpu_options: AttestRemoteOptions,
+    switch_options: AttestRemoteOptions,
+    max_evidence_age: Duration,
+    required_scope: Option<RaTlsScope>,
+    algorithms: WebPkiSupportedAlgorithms,
+    /// Expiration time of verified certificates, keyed by their blake3 digest
+    verified: Mutex<HashMap<[u8; 32], u64>>,
+    /// Runtime querying NRAS, created by the first verification
+    runtime: OnceLock<std::result::Result<Runtime, String>>,
+}
+
+impl RaTlsVerifier {
+    /// Creates a verifier submitting GPU evidence to NRAS with `gpu_options`, and
+    /// `NVSwitch` evidence with `switch_options`.
+    #[must_use]
+    pub fn new(gpu_options: AttestRemoteOptions, switch_options: AttestRemoteOptions) -> Self {
+        Self {
+            gpu_options,
+            switch_options,
+            max_evidence_age: DEFAULT_RATLS_MAX_EVIDENCE_AGE,
+            required_scope: None,
+            algorithms: rustls::crypto::ring::default_provider().signature_verification_algorithms,
+            verified: Mutex::new(HashMap::new()),
+            runtime: OnceLock::new(),
+        }
+    }
+
+    /// Sets the maximum age of the evidence, [`DEFAULT_RATLS_MAX_EVIDENCE_AGE`] by default.
+    #[must_use]
+    pub const fn with_max_evidence_age(mut self, max_evidence_age: Duration) -> Self {
+        self.max_evidence_age = max_evidence_age;
+        self
+    }
+
+    /// Requires certificates to carry the evidence of `scope`, e.g. [`RaTlsScope::Ppcie`]
+    /// for nodes in `PPCIe` mode. Otherwise any GPU or `NVSwitch` evidence is accepted.
+    #[must_use]
+    pub const fn with_required_scope(mut self, scope: RaTlsScope) -> Self {
+        self.required_scope = Some(scope);
+        self
+    }
+
+    /// Verifies the RA-TLS certificate `certificate` at `now`, in seconds since the Unix
+    /// epoch, and returns the evidence it carries.
+    ///
+    /// Blocks until the evidence is verified on the runtime of the verifier, so it can be
+    /// called from within an async runtime.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidRaTlsCertificate` - If the certificate cannot be parsed, or
+    ///   carries no evidence, or not the evidence of the required scope
+    /// * `AttestError::InvalidEvidenceBundle` - If the bundle is invalid
+    /// * `AttestError::NonceBinding` - If the nonce of the bundle is not bound to the
+    ///   certificate key, or is too old
+    /// * `AttestError::ClaimValidation` - If an NRAS token was issued for another nonce
+    /// * `AttestError::RemoteAttestationFailed` - If an NRAS token or NRAS rejects the
+    ///   evidence
+    /// * `AttestError::Runtime` - If the runtime of the verifier cannot be created
+    /// * Errors of [`decode_nras_token`], [`verify_gpu_attestation`] and
+    ///   [`verify_nvswitch_attestation`]
+    pub fn verify_certificate(&self, certificate: &[u8], now: u64) -> Result<RaTlsEvidence> {
+        let (public_key, evidence) = parse_certificate(certificate)?;
+        if let Some(scope) = self.required_scope {
+            evidence.check_scope(scope)?;
+        }
+        evidence.check_binding(&public_key, self.max_evidence_age, now)?;
+        let digest = *blake3::hash(certificate).as_bytes();
+        if self
+            .verified
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(&digest)
+            .is_some_and(|&expires_at| now < expires_at)
+        {
+            return Ok(evidence);
+        }
+        let (gpu_options, switch_options) = (self.gpu_options.clone(), self.switch_options.clone());
+        let appraised = evidence.clone();
+        self.block_on(async move { appraise(&gpu_options, &switch_options, &appraised).await })??;
+        tracing::debug!(
+            level = "ratls",
+            bundle_digest = %evidence.bundle.digest,
+            host = ?evidence.bundle.host.node_id,
+            "RA-TLS certificate verified"
+        );
+        let mut verified = self
+            .verified
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+        verified.retain(|_, &mut expires_at| now < expires_at);
+        verified.insert(
+            digest,
+            evidence
+                .timestamp
+                .saturating_add(self.max_evidence_age.as_secs()),
+        );
+        drop(verified);
+        Ok(evidence)
+    }
+
+    /// Runs `future` to completion on the runtime of the verifier, blocking the calling
+    /// thread, which may belong to another runtime.
+    fn block_on<F>(&self, future: F) -> Result<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        let runtime = self
+            .runtime
+            .get_or_init(|| {
+                tokio::runtime::Builder::new_multi_thread()
+                    .worker_threads(1)
+                    .thread_name("ratls-verifier")
+                    .enable_all()
+                    .build()
+                    .map_err(|e| e.to_string())
+            })
+            .as_ref()
+            .map_err(|e| AttestError::Runtime(format!("failed to create the runtime: {e}")))?;
+        let (sender, receiver) = mpsc::sync_channel(1);
+        runtime.spawn(async move {
+            let _ = sender.send(future.await);
+        });
+        receiver
+            .recv()
+            .map_err(|_| AttestError::Runtime("the verification task panicked".to_string()))
+    }
+
+    fn verify_end_entity(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        now: UnixTime,
+    ) -> std::result::Result<(), rustls::Error> {
+        self.verify_certificate(end_entity, now.as_secs())
+            .map(drop)
+            .map_err(|error| {
+                tracing::warn!(level = "ratls", %error, "RA-TLS certificate rejected");
+                rustls::Error::InvalidCertificate(CertificateError::Other(OtherError(Arc::new(
+                    error,
+                ))))
+            })
+    }
+}
+
+impl fmt::Debug for RaTlsVerifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RaTlsVerifier")
+            .field("gpu_verifier_url", &self.gpu_options.verifier_url)
+            .field("switch_verifier_url", &self.switch_options.verifier_url)
+            .field("max_evidence_age", &self.max_evidence_age)
+            .field("required_scope", &self.required_scope)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for RaTlsVerifier {
+    fn drop(&mut self) {
+        // Dropping a runtime blocks until its tasks complete, which async contexts forbid
+        if let Some(Ok(runtime)) = self.runtime.take() {
+            runtime.shutdown_background();
+        }
+    }
+}
+
+impl ServerCertVerifier for RaTlsVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        now: UnixTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        self.verify_end_entity(end_entity, now)?;
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+impl ClientCertVerifier for RaTlsVerifier {
+    fn root_hint_subjects(&self) -> &[DistinguishedName] {
+        &[]
+    }
+
+    fn verify_client_cert(
+        &self,
+        end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        now: UnixTime,
+    ) -> std::result::Result<ClientCertVerified, rustls::Error> {
+        self.verify_end_entity(end_entity, now)?;
+        Ok(ClientCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> std::result::Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.algorithms.supported_schemes()
+    }
+}
+
+/// Verifies the NRAS tokens of `evidence`, or submits its evidence to NRAS.
+async fn appraise(
+    gpu_options: &AttestRemoteOptions,
+    switch_options: &AttestRemoteOptions,
+    evidence: &RaTlsEvidence,
+) -> Result<()> {
+    let bundle = &evidence.bundle;
+    if !evidence.has_gpu_evidence() && !evidence.has_switch_evidence() {
+        return Err(AttestError::InvalidRaTlsCertificate(
+            "The certificate carries no evidence".to_string(),
+        ));
+    }
+    if let Some(token) = &evidence.gpu_token {
+        let verifier_url = verifier_url(gpu_options, REMOTE_GPU_VERIFIER_SERVICE_URL);
+        check_token(verifier_url, token, &bundle.nonce, DeviceKind::Gpu).await?;
+    } else if !bundle.gpu_evidence.is_empty() {
+        let (attestation_passed, _) =
+            verify_gpu_attestation(&bundle.gpu_evidence, &bundle.nonce, gpu_options.clone())
+                .await?;
+        if !attestation_passed {
+            return Err(AttestError::RemoteAttestationFailed);
+        }
+    }
+    if let Some(token) = &evidence.switch_token {
+        let verifier_url = verifier_url(switch_options, REMOTE_NVSWITCH_VERIFIER_SERVICE_URL);
+        check_token(verifier_url, token, &bundle.nonce, DeviceKind::Switch).await?;
+    } else if !bundle.switch_evidence.is_empty() {
+        let (attestation_passed, _) = verify_nvswitch_attestation(
+            &bundle.switch_evidence,
+            &bundle.nonce,
+            switch_options.clone(),
+        )
+        .await?;
+        if !attestation_passed {
+            return Err(AttestError::RemoteAttestationFailed);
+        }
+    }
+    Ok(())
+}
+
+fn verifier_url<'a>(options: &'a AttestRemoteOptions, default: &'a str) -> &'a str {
+    options.verifier_url.as_deref().unwrap_or(default)
+}
+
+/// Verifies an overall NRAS token, and checks it was issued for `nonce` and only covers
+/// devices of `kind`.
+async fn check_token(verifier_url: &str, token: &str, nonce: &str, kind: DeviceKind) -> Result<()> {
+    let claims = decode_nras_token(verifier_url, token).await?;
+    claims.check_nonce(nonce)?;
+    let submods: Vec<_> = claims.submod_names().collect();
+    if submods.is_empty() {
+        return Err(AttestError::ClaimValidation(format!(
+            "NRAS {kind} token covers no devices"
+        )));
+    }
+    if let Some(submod) = submods
+        .iter()
+        .find(|submod| !submod.starts_with(kind.submod_prefix()))
+    {
+        return Err(AttestError::ClaimValidation(format!(
+            "NRAS {kind} token covers device {submod}"
+        )));
+    }
+    if !claims.overall_attestation_result {
+        return Err(AttestError::RemoteAttestationFailed);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use base64::{engine::general_purpose::STANDARD, Engine};
+    use rustls::{crypto::ring::default_provider, ClientConfig, ServerConfig};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+    use super::*;
+    use crate::{
+        bundle::HostMetadata,
+        test_support::MockNras,
+        utils::{get_overall_claims_token, unix_now},
+        AttestErrorKind, DeviceEvidence, NvSwitchEvidence,
+    };
+
+    /// Evidence of a node with a GPU and a switch, bound to `key`.
+    fn evidence(key: &RaTlsKey, timestamp: u64) -> RaTlsEvidence {
+        let nonce = key.nonce_binding(timestamp).derive();
+        let device = |name: &str| (STANDARD.encode(name), STANDARD.encode("evidence"));
+        let (certificate, evidence) = device("gpu");
+        let gpu_evidence = vec![DeviceEvidence {
+            certificate,
+            evidence,
+        }];
+        let (certificate, evidence) = device("switch");
+        let switch_evidence = vec![NvSwitchEvidence {
+            certificate,
+            evidence,
+        }];
+        let host = HostMetadata {
+            node_id: Some("ratls-node".to_string()),
+            ..HostMetadata::default()
+        };
+        let bundle = EvidenceBundle::new(&nonce, gpu_evidence, switch_evidence, host);
+        RaTlsEvidence::new(timestamp, bundle)
+    }
+
+    fn issue(key: &RaTlsKey, evidence: &RaTlsEvidence) -> CertificateDer<'static> {
+        key.certificate(evidence, vec!["localhost".to_string()])
+            .unwrap()
+            .certificate
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_verify_ratls_certificate() {
+        let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+        let verifier = RaTlsVerifier::new(nras.gpu_options(), nras.switch_options());
+        let key = RaTlsKey::generate().unwrap();
+        let now = unix_now();
+        let evidence = evidence(&key, now);
+        let certificate = issue(&key, &evidence);
+
+        assert_eq!(
+            verifier.verify_certificate(&certificate, now).unwrap(),
+            evidence
+        );
+        assert_eq!(
+            nras.requests().len(),
+            2,
+            "GPU and switch evidence are attested"
+        );
+        // Verified certificates are cached until their evidence gets too old
+        verifier.verify_certificate(&certificate, now + 60).unwrap();
+        assert_eq!(nras.requests().len(), 2);
+        let max_age = DEFAULT_RATLS_MAX_EVIDENCE_AGE.as_secs();
+        let error = verifier
+            .verify_certificate(&certificate, now + max_age + 1)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::NonceBinding(_)));
+
+        // Evidence bound to another key is rejected before NRAS is queried
+        let other_key = RaTlsKey::generate().unwrap();
+        let replayed = issue(&other_key, &evidence);
+        let error = verifier.verify_certificate(&replayed, now).unwrap_err();
+        assert!(matches!(error, AttestError::NonceBinding(_)));
+        assert_eq!(error.kind(), AttestErrorKind::ClaimValidation);
+        assert_eq!(nras.requests().len(), 2);
+
+        // Evidence rejected by NRAS
+        nras.set_attestation_result(false);
+        let certificate = issue(&key, &self::evidence(&key, now + 1));
+        let error = verifier
+            .verify_certificate(&certificate, now + 1)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::RemoteAttestationFailed));
+
+        // Certificates without evidence
+        let plain = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let error = verifier
+            .verify_certificate(plain.cert.der(), now)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::InvalidRaTlsCertificate(_)));
+        assert_eq!(error.kind(), AttestErrorKind::EvidenceDecode);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ratls_required_scope() {
+        let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+        let verifier = RaTlsVerifier::new(nras.gpu_options(), nras.switch_options())
+            .with_required_scope(RaTlsScope::Ppcie);
+        let key = RaTlsKey::generate().unwrap();
+        let now = unix_now();
+        let evidence = evidence(&key, now);
+        verifier
+            .verify_certificate(&issue(&key, &evidence), now)
+            .unwrap();
+
+        // A certificate without switch evidence is rejected before NRAS is queried
+        let bundle = &evidence.bundle;
+        let gpu_only = EvidenceBundle::new(
+            &key.nonce_binding(now).derive(),
+            bundle.gpu_evidence.clone(),
+            Vec::new(),
+            bundle.host.clone(),
+        );
+        let certificate = issue(&key, &RaTlsEvidence::new(now, gpu_only));
+        let error = verifier.verify_certificate(&certificate, now).unwrap_err();
+        assert!(matches!(error, AttestError::InvalidRaTlsCertificate(_)));
+        assert_eq!(nras.requests().len(), 2);
+
+        // Without a required scope, GPU evidence alone is enough
+        RaTlsVerifier::new(nras.gpu_options(), nras.switch_options())
+            .verify_certificate(&certificate, now)
+            .unwrap();
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_ratls_certificate_with_nras_tokens() {
+        let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+        let key = RaTlsKey::generate().unwrap();
+        let now = unix_now();
+        let evidence = evidence(&key, now);
+        let bundle = &evidence.bundle;
+        let (_, gpu_response) =
+            verify_gpu_attestation(&bundle.gpu_evidence, &bundle.nonce, nras.gpu_options())
+                .await
+                .unwrap();
+        let (_, switch_response) = verify_nvswitch_attestation(
+            &bundle.switch_evidence,
+            &bundle.nonce,
+            nras.switch_options(),
+        )
+        .await
+        .unwrap();
+        let evidence = evidence
+            .with_gpu_token(get_overall_claims_token(&gpu_response).unwrap())
+            .with_switch_token(get_overall_claims_token(&switch_response).unwrap());
+        let certificate = issue(&key, &evidence);
+
+        // The tokens are verified without submitting the evidence again
+        let verifier = RaTlsVerifier::new(nras.gpu_options(), nras.switch_options());
+        verifier.verify_certificate(&certificate, now).unwrap();
+        assert_eq!(nras.requests().len(), 2);
+
+        // Tokens issued for another nonce are rejected
+        let other = self::evidence(&key, now + 1)
+            .with_gpu_token(evidence.gpu_token.clone().unwrap())
+            .with_switch_token(evidence.switch_token.clone().unwrap());
+        let error = verifier
+            .verify_certificate(&issue(&key, &other), now + 1)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::ClaimValidation(_)));
+
+        // A switch token presented as the GPU token is rejected
+        let swapped = self::evidence(&key, now)
+            .with_gpu_token(evidence.switch_token.clone().unwrap())
+            .with_switch_token(evidence.switch_token.clone().unwrap());
+        let error = verifier
+            .verify_certificate(&issue(&key, &swapped), now)
+            .unwrap_err();
+        assert!(matches!(error, AttestError::ClaimValidation(_)));
+    }
+
+    #[test]
+    fn test_mutual_ratls_handshake() {
+        // Handshakes block their worker thread until the evidence is verified, so NRAS
+        // is served by a runtime of its own
+        let nras_runtime = tokio::runtime::Runtime::new().unwrap();
+        let nras = nras_runtime
+            .block_on(MockNras::start())
+            .expect("Failed to start mock NRAS");
+        tokio::runtime::Runtime::new()
+            .unwrap()
+            .block_on(mutual_ratls_handshake(&nras));
+    }
+
+    async fn mutual_ratls_handshake(nras: &MockNras) {
+        let verifier = Arc::new(RaTlsVerifier::new(
+            nras.gpu_options(),
+            nras.switch_options(),
+        ));
+        let provider = Arc::new(default_provider());
+        let identity = || {
+            let key = RaTlsKey::generate().unwrap();
+            key.certificate(&evidence(&key, unix_now()), vec!["localhost".to_string()])
+                .unwrap()
+        };
+
+        let server_identity = identity();
+        let server_config = ServerConfig::builder_with_provider(Arc::clone(&provider))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_client_cert_verifier(verifier.clone())
+            .with_single_cert(
+                vec![server_identity.certificate],
+                server_identity.private_key,
+            )
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut stream = acceptor.accept(stream).await.unwrap();
+            let mut request = [0; 4];
+            stream.read_exact(&mut request).await.unwrap();
+            stream.write_all(b"pong").await.unwrap();
+            stream.shutdown().await.unwrap();
+        });
+
+        let client_identity = identity();
+        let client_config = ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(verifier)
+            .with_client_auth_cert(
+                vec![client_identity.certificate],
+                client_identity.private_key,
+            )
+            .unwrap();
+        let stream = TcpStream::connect(address).await.unwrap();
+        let mut stream = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        stream.write_all(b"ping").await.unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).await.unwrap();
+        assert_eq!(response, b"pong");
+        server.await.unwrap();
+        // Both the server and the client evidence were attested
+        assert_eq!(nras.requests().len(), 4);
+
+        // A client rejects a server without evidence
+        let plain = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let server_config = ServerConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(
+                vec![plain.cert.der().clone()],
+                PrivatePkcs8KeyDer::from(plain.key_pair.serialize_der()).into(),
+            )
+            .unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let acceptor = TlsAcceptor::from(Arc::new(server_config));
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            acceptor.accept(stream).await.is_err()
+        });
+        let client_config = ClientConfig::builder_with_provider(Arc::new(default_provider()))
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(RaTlsVerifier::new(
+                nras.gpu_options(),
+                nras.switch_options(),
+            )))
+            .with_no_client_auth();
+        let stream = TcpStream::connect(address).await.unwrap();
+        let result = TlsConnector::from(Arc::new(client_config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await;
+        assert!(result.is_err());
+        assert!(server.await.unwrap());
+    }
+}
@@ -1,33 +1,55 @@
-use std::time::Duration;
-
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
-use serde_json::{json, Value};
-use tracing::{error, info, instrument, Instrument};
+#[cfg(feature = "nvml")]
+use base64::{engine::general_purpose::STANDARD, Engine};
+#[cfg(feature = "nvml")]
+use nvml_wrapper::Nvml;
+use serde_json::Value;
+use tracing::instrument;
 
 use crate::{
-    constants::{
-        ARCH_KEY, CLAIMS_VERSION_KEY, DEFAULT_CLAIMS_VERSION, DEFAULT_TIMEOUT, EVIDENCE_LIST_KEY,
-        HOPPER_ARCH, NONCE_KEY, NVIDIA_OCSP_ALLOW_CERT_HOLD_HEADER,
-        REMOTE_GPU_VERIFIER_SERVICE_URL,
-    },
-    errors::{AttestError, Result},
+    constants::{HOPPER_ARCH, REMOTE_GPU_VERIFIER_SERVICE_URL},
+    errors::Result,
+    nras::attest_remote,
     types::DeviceEvidence,
-    utils::get_allow_hold_cert,
 };
 
-/// Options for remote attestation
-#[derive(Debug, Default, Clone)]
-pub struct AttestRemoteOptions {
-    /// Optional URL of the verification service. If `None`, uses the default URL
-    pub verifier_url: Option<String>,
-    /// Optional flag to allow certificate hold status. If `None`, uses the system default
-    pub allow_hold_cert: Option<bool>,
-    /// Optional claims version. If `None`, uses the system default
-    pub claims_version: Option<String>,
-    /// Optional service key for authorization
-    pub service_key: Option<String>,
-    /// Optional request timeout
-    pub timeout: Option<Duration>,
+pub use crate::options::AttestRemoteOptions;
+
+/// Collects attestation evidence for all GPUs visible to NVML.
+///
+/// Requires the `nvml` feature.
+///
+/// For each GPU, this function retrieves the confidential computing attestation report
+/// generated with the provided nonce and the attestation certificate chain, and base64
+/// encodes both into a `DeviceEvidence`.
+///
+/// # Arguments
+///
+/// * `nvml` - A reference to an initialized `Nvml` instance
+/// * `nonce` - A 32-byte array used as a nonce for generating the attestation reports
+///
+/// # Returns
+///
+/// A `Result` containing a `Vec<DeviceEvidence>`, one per GPU, in NVML index order.
+///
+/// # Errors
+///
+/// * `AttestError::NvmlError` - If the GPUs cannot be enumerated, or if the attestation
+///   report or certificate chain of a GPU cannot be retrieved
+#[cfg(feature = "nvml")]
+#[instrument(name = "collect_gpu_evidence", skip_all)]
+pub fn collect_gpu_evidence(nvml: &Nvml, nonce: &[u8; 32]) -> Result<Vec<DeviceEvidence>> {
+    let num_devices = nvml.device_count()?;
+    let mut evidence_vec = Vec::with_capacity(num_devices as usize);
+    for index in 0..num_devices {
+        let device = nvml.device_by_index(index)?;
+        let attestation_report = device.confidential_compute_gpu_attestation_report(*nonce)?;
+        let certificate = device.confidential_compute_gpu_certificate()?;
+        evidence_vec.push(DeviceEvidence {
+            certificate: STANDARD.encode(certificate.attestation_cert_chain),
+            evidence: STANDARD.encode(attestation_report.attestation_report),
+        });
+    }
+    Ok(evidence_vec)
 }
 
 /// Performs remote attestation of GPU devices by sending evidence to a verification service.
@@ -52,11 +74,12 @@ pub struct AttestRemoteOptions {
 ///
 /// # Errors
 ///
-/// Returns `AttestError` if:
-/// * The HTTP request fails
-/// * The server returns a non-success status code
-/// * The response cannot be parsed
-/// * JWT token validation fails
+/// * `AttestError::Transport` - If the HTTP request fails
+/// * `AttestError::HttpStatus` - If the server returns a non-success status code, with the
+///   parsed NRAS error body when available
+/// * `AttestError::MalformedResponse` - If the response cannot be parsed
+/// * `AttestError::Jwks`, `AttestError::TokenSignature` or `AttestError::ClaimValidation` -
+///   If the NRAS token cannot be verified
 ///
 /// # Example
 ///
@@ -89,95 +112,12 @@ pub async fn verify_gpu_attestation(
     nonce: &str,
     remote_attestation_options: AttestRemoteOptions,
 ) -> Result<(bool, Value)> {
-    let AttestRemoteOptions {
-        verifier_url,
-        allow_hold_cert,
-        claims_version,
-        service_key,
-        timeout,
-    } = remote_attestation_options;
-    let verifier_url = verifier_url.unwrap_or_else(|| REMOTE_GPU_VERIFIER_SERVICE_URL.to_string());
-    let allow_hold_cert = allow_hold_cert.unwrap_or_else(get_allow_hold_cert);
-    let mut headers = HeaderMap::new();
-    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
-    if allow_hold_cert {
-        headers.insert(
-            NVIDIA_OCSP_ALLOW_CERT_HOLD_HEADER,
-            HeaderValue::from_static("true"),
-        );
-    }
-    if let Some(ref service_key) = service_key {
-        headers.insert(AUTHORIZATION, HeaderValue::from_str(service_key)?);
-    }
-    let claims_version = claims_version.unwrap_or_else(|| DEFAULT_CLAIMS_VERSION.to_string());
-    let payload = json!({
-        NONCE_KEY: nonce,
-        EVIDENCE_LIST_KEY: gpu_evidences,
-        CLAIMS_VERSION_KEY: claims_version,
-        ARCH_KEY: HOPPER_ARCH,
-    });
-    info!(
-        level = "GPU::attest_remote",
-        verifier_url = %verifier_url,
-        claims_version = %claims_version,
-        nonce = %nonce,
-        timeout = ?timeout,
-        "Sending attestation request to NRAS url {verifier_url}, with claims version {claims_version}, nonce {nonce}"
-    );
-    let client = reqwest::Client::builder()
-        .timeout(timeout.unwrap_or(DEFAULT_TIMEOUT))
-        .build()?;
-    let request_span =
-        tracing::info_span!("nras_request", url = %verifier_url, claims_version = %claims_version);
-    let response = client
-        .post(&verifier_url)
-        .headers(headers)
-        .json(&payload)
-        .send()
-        .instrument(request_span)
-        .await
-        .map_err(|e| {
-            error!(
-                level = "attest_remote",
-                "Failed to send attestation request: {e}"
-            );
-            AttestError::ParseResponseError(e)
-        })?;
-    if !response.status().is_success() {
-        error!(
-            level = "attest_remote",
-            "Attestation request failed with status code {}",
-            response.status()
-        );
-        let error_message = response.text().await?;
-        return Err(AttestError::ResponseError(error_message));
-    }
-    match response.json::<Value>().await {
-        Ok(response_json) => {
-            info!(
-                level = "GPU::attest_remote",
-                verifier_url = %verifier_url,
-                claims_version = %claims_version,
-                nonce = %nonce,
-                timeout = ?timeout,
-                "Attestation request successful, response: {response_json}",
-            );
-            let main_jwt_token = crate::utils::get_overall_claims_token(&response_json)?;
-            let decoded_main_jwt_token =
-                crate::utils::nras_token::decode_nras_token(&verifier_url, &main_jwt_token).await?;
-            let attestation_result = decoded_main_jwt_token.overall_attestation_result;
-            Ok((attestation_result, response_json))
-        }
-        Err(e) => {
-            error!(
-                level = "GPU::attest_remote",
-                verifier_url = %verifier_url,
-                claims_version = %claims_version,
-                nonce = %nonce,
-                timeout = ?timeout,
-                "Failed to parse response with error: {e}",
-            );
-            return Err(AttestError::ParseResponseError(e));
-        }
-    }
+    attest_remote(
+        gpu_evidences,
+        nonce,
+        HOPPER_ARCH,
+        REMOTE_GPU_VERIFIER_SERVICE_URL,
+        remote_attestation_options,
+    )
+    .await
 }
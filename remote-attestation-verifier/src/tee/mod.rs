@@ -0,0 +1,139 @@
+//! Parsers for the attestation reports of CPU trusted execution environments.
+//!
+//! Confidential AI workloads run in a CPU TEE, an Intel TDX trust domain or an AMD
+//! SEV-SNP guest, attached to confidential GPUs. The parsers extract the measurements
+//! and the 64-byte report data of a report, which [`crate::composite`] uses to bind it to
+//! GPU evidence. They do not verify signatures: that is the job of a
+//! [`CpuQuoteVerifier`](crate::composite::CpuQuoteVerifier).
+
+pub mod snp;
+pub mod tdx;
+
+use serde::{Deserialize, Serialize};
+
+pub use snp::SnpReport;
+pub use tdx::TdxQuote;
+
+use crate::errors::{AttestError, Result};
+
+/// Length of the report data of a CPU attestation report, in bytes.
+pub const REPORT_DATA_LENGTH: usize = 64;
+
+/// Kind of CPU trusted execution environment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CpuTee {
+    /// Intel Trust Domain Extensions
+    Tdx,
+    /// AMD Secure Encrypted Virtualization with Secure Nested Paging
+    Snp,
+}
+
+impl CpuTee {
+    /// Returns the name of the TEE, as serialized.
+    #[must_use]
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Tdx => "tdx",
+            Self::Snp => "snp",
+        }
+    }
+}
+
+impl std::fmt::Display for CpuTee {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A parsed CPU attestation report: a TDX quote or an SNP attestation report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CpuQuote {
+    /// An Intel TDX quote
+    Tdx(Box<TdxQuote>),
+    /// An AMD SEV-SNP attestation report
+    Snp(Box<SnpReport>),
+}
+
+impl CpuQuote {
+    /// Parses the attestation report `bytes` of a `tee` TEE.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidCpuQuote` - If the report is truncated, or has an
+    ///   unsupported version or type
+    pub fn parse(tee: CpuTee, bytes: &[u8]) -> Result<Self> {
+        Ok(match tee {
+            CpuTee::Tdx => Self::Tdx(Box::new(TdxQuote::parse(bytes)?)),
+            CpuTee::Snp => Self::Snp(Box::new(SnpReport::parse(bytes)?)),
+        })
+    }
+
+    /// Returns the kind of TEE that produced the report.
+    #[must_use]
+    pub const fn tee(&self) -> CpuTee {
+        match self {
+            Self::Tdx(_) => CpuTee::Tdx,
+            Self::Snp(_) => CpuTee::Snp,
+        }
+    }
+
+    /// Returns the report data, chosen by the guest when requesting the report.
+    #[must_use]
+    pub fn report_data(&self) -> &[u8; REPORT_DATA_LENGTH] {
+        match self {
+            Self::Tdx(quote) => &quote.td_report.report_data,
+            Self::Snp(report) => &report.report_data,
+        }
+    }
+}
+
+/// Bounds-checked reader of little-endian report fields.
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self { data, position: 0 }
+    }
+
+    fn take(&mut self, length: usize, field: &str) -> Result<&'a [u8]> {
+        let bytes = self
+            .position
+            .checked_add(length)
+            .and_then(|end| self.data.get(self.position..end))
+            .ok_or_else(|| {
+                AttestError::InvalidCpuQuote(format!(
+                    "`{field}` at offset {} is truncated: expected {length} bytes, {} remaining",
+                    self.position,
+                    self.data.len().saturating_sub(self.position)
+                ))
+            })?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn skip(&mut self, length: usize, field: &str) -> Result<()> {
+        self.take(length, field).map(drop)
+    }
+
+    fn array<const N: usize>(&mut self, field: &str) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N, field)?);
+        Ok(array)
+    }
+
+    fn u16_le(&mut self, field: &str) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array(field)?))
+    }
+
+    fn u32_le(&mut self, field: &str) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array(field)?))
+    }
+
+    fn u64_le(&mut self, field: &str) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array(field)?))
+    }
+}
@@ -0,0 +1,243 @@
+//! AMD SEV-SNP attestation reports (`ATTESTATION_REPORT`, versions 2 and later).
+
+use super::{Reader, REPORT_DATA_LENGTH};
+use crate::errors::{AttestError, Result};
+
+/// Length of an SNP attestation report, in bytes.
+pub const SNP_REPORT_LENGTH: usize = 0x4A0;
+
+/// Length of the part of the report covered by its signature, in bytes.
+pub const SNP_SIGNED_LENGTH: usize = 0x2A0;
+
+/// Oldest supported report version.
+pub const SNP_MIN_REPORT_VERSION: u32 = 2;
+
+/// Signature algorithm of SNP reports signed with ECDSA P-384 and SHA-384.
+pub const SNP_SIGNATURE_ALGORITHM_ECDSA_P384_SHA384: u32 = 1;
+
+/// Bit of the guest policy allowing the hypervisor to debug the guest.
+const POLICY_DEBUG_BIT: u64 = 1 << 19;
+
+/// Security version numbers of the SNP TCB components.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnpTcbVersion(pub u64);
+
+impl SnpTcbVersion {
+    /// Returns the security version of the boot loader.
+    #[must_use]
+    pub const fn boot_loader(self) -> u8 {
+        self.0.to_le_bytes()[0]
+    }
+
+    /// Returns the security version of the PSP operating system.
+    #[must_use]
+    pub const fn tee(self) -> u8 {
+        self.0.to_le_bytes()[1]
+    }
+
+    /// Returns the security version of the SNP firmware.
+    #[must_use]
+    pub const fn snp(self) -> u8 {
+        self.0.to_le_bytes()[6]
+    }
+
+    /// Returns the security version of the CPU microcode.
+    #[must_use]
+    pub const fn microcode(self) -> u8 {
+        self.0.to_le_bytes()[7]
+    }
+}
+
+/// A parsed SNP attestation report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnpReport {
+    /// Report format version
+    pub version: u32,
+    /// Security version number of the guest
+    pub guest_svn: u32,
+    /// Guest policy, see [`SnpReport::is_debug_allowed`]
+    pub policy: u64,
+    /// Family identifier of the guest, provided at launch
+    pub family_id: [u8; 16],
+    /// Image identifier of the guest, provided at launch
+    pub image_id: [u8; 16],
+    /// Virtual machine privilege level that requested the report
+    pub vmpl: u32,
+    /// Algorithm of the signature, [`SNP_SIGNATURE_ALGORITHM_ECDSA_P384_SHA384`]
+    pub signature_algorithm: u32,
+    /// Current TCB of the platform
+    pub current_tcb: SnpTcbVersion,
+    /// Information about the platform, e.g. whether SMT is enabled
+    pub platform_info: u64,
+    /// Flags about the signing key
+    pub flags: u32,
+    /// Data chosen by the guest when requesting the report
+    pub report_data: [u8; REPORT_DATA_LENGTH],
+    /// Measurement of the initial guest memory
+    pub measurement: [u8; 48],
+    /// Data provided by the hypervisor at launch
+    pub host_data: [u8; 32],
+    /// Digest of the ID key that signed the ID block, if any
+    pub id_key_digest: [u8; 48],
+    /// Digest of the author key that certified the ID key, if any
+    pub author_key_digest: [u8; 48],
+    /// Identifier of the guest, assigned by the firmware
+    pub report_id: [u8; 32],
+    /// Identifier of the guest, assigned by the migration agent
+    pub report_id_ma: [u8; 32],
+    /// TCB used to derive the key that signed the report
+    pub reported_tcb: SnpTcbVersion,
+    /// Identifier of the chip, used to fetch its VCEK certificate
+    pub chip_id: [u8; 64],
+    /// Committed TCB of the platform
+    pub committed_tcb: SnpTcbVersion,
+    /// TCB of the platform when the guest was launched
+    pub launch_tcb: SnpTcbVersion,
+    /// `R` component of the signature, little-endian and zero-padded
+    pub signature_r: [u8; 72],
+    /// `S` component of the signature, little-endian and zero-padded
+    pub signature_s: [u8; 72],
+}
+
+impl SnpReport {
+    /// Parses an SNP attestation report.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidCpuQuote` - If the report does not have the expected
+    ///   length, or has a version older than [`SNP_MIN_REPORT_VERSION`]
+    pub fn parse(report: &[u8]) -> Result<Self> {
+        if report.len() != SNP_REPORT_LENGTH {
+            return Err(AttestError::InvalidCpuQuote(format!(
+                "SNP reports are {SNP_REPORT_LENGTH} bytes long, got {} bytes",
+                report.len()
+            )));
+        }
+        let mut reader = Reader::new(report);
+        let version = reader.u32_le("version")?;
+        if version < SNP_MIN_REPORT_VERSION {
+            return Err(AttestError::InvalidCpuQuote(format!(
+                "Unsupported SNP report version {version}, expected {SNP_MIN_REPORT_VERSION} or later"
+            )));
+        }
+        let guest_svn = reader.u32_le("guest SVN")?;
+        let policy = reader.u64_le("policy")?;
+        let family_id = reader.array("family ID")?;
+        let image_id = reader.array("image ID")?;
+        let vmpl = reader.u32_le("VMPL")?;
+        let signature_algorithm = reader.u32_le("signature algorithm")?;
+        let current_tcb = SnpTcbVersion(reader.u64_le("current TCB")?);
+        let platform_info = reader.u64_le("platform info")?;
+        let flags = reader.u32_le("flags")?;
+        reader.skip(4, "reserved")?;
+        let report_data = reader.array("report data")?;
+        let measurement = reader.array("measurement")?;
+        let host_data = reader.array("host data")?;
+        let id_key_digest = reader.array("ID key digest")?;
+        let author_key_digest = reader.array("author key digest")?;
+        let report_id = reader.array("report ID")?;
+        let report_id_ma = reader.array("report ID MA")?;
+        let reported_tcb = SnpTcbVersion(reader.u64_le("reported TCB")?);
+        reader.skip(0x18, "reserved")?;
+        let chip_id = reader.array("chip ID")?;
+        let committed_tcb = SnpTcbVersion(reader.u64_le("committed TCB")?);
+        reader.skip(8, "firmware versions")?;
+        let launch_tcb = SnpTcbVersion(reader.u64_le("launch TCB")?);
+        reader.skip(SNP_SIGNED_LENGTH - reader.position, "reserved")?;
+        let signature_r = reader.array("signature R")?;
+        let signature_s = reader.array("signature S")?;
+        Ok(Self {
+            version,
+            guest_svn,
+            policy,
+            family_id,
+            image_id,
+            vmpl,
+            signature_algorithm,
+            current_tcb,
+            platform_info,
+            flags,
+            report_data,
+            measurement,
+            host_data,
+            id_key_digest,
+            author_key_digest,
+            report_id,
+            report_id_ma,
+            reported_tcb,
+            chip_id,
+            committed_tcb,
+            launch_tcb,
+            signature_r,
+            signature_s,
+        })
+    }
+
+    /// Returns `true` if the guest policy allows the hypervisor to debug the guest, so
+    /// its memory is not protected from the host.
+    #[must_use]
+    pub const fn is_debug_allowed(&self) -> bool {
+        self.policy & POLICY_DEBUG_BIT != 0
+    }
+
+    /// Returns the part of `report` covered by its signature.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidCpuQuote` - If `report` is too short
+    pub fn signed_bytes(report: &[u8]) -> Result<&[u8]> {
+        report.get(..SNP_SIGNED_LENGTH).ok_or_else(|| {
+            AttestError::InvalidCpuQuote(format!(
+                "SNP reports are {SNP_REPORT_LENGTH} bytes long, got {} bytes",
+                report.len()
+            ))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REPORT: &[u8] = include_bytes!("../../evidence/snp_report.bin");
+
+    #[test]
+    fn test_parse_snp_report() {
+        let report = SnpReport::parse(REPORT).unwrap();
+        assert_eq!(report.version, 3);
+        assert_eq!(report.vmpl, 0);
+        assert_eq!(
+            report.signature_algorithm,
+            SNP_SIGNATURE_ALGORITHM_ECDSA_P384_SHA384
+        );
+        assert!(!report.is_debug_allowed());
+        assert_eq!(report.measurement, [0x5a; 48]);
+        assert_eq!(report.chip_id, [0xc1; 64]);
+        assert_eq!(report.reported_tcb.boot_loader(), 4);
+        assert_eq!(report.reported_tcb.tee(), 0);
+        assert_eq!(report.reported_tcb.snp(), 22);
+        assert_eq!(report.reported_tcb.microcode(), 213);
+        assert_eq!(
+            hex::encode(&report.report_data[..32]),
+            "931d8dd0add203ac3d8b4fbde75e115278eefcdceac5b87671a748f32364dfcb"
+        );
+        assert_eq!(report.signature_r, [0x72; 72]);
+        assert_eq!(report.signature_s, [0x73; 72]);
+        assert_eq!(SnpReport::signed_bytes(REPORT).unwrap().len(), 0x2A0);
+    }
+
+    #[test]
+    fn test_parse_invalid_snp_reports() {
+        assert!(matches!(
+            SnpReport::parse(&REPORT[..SNP_REPORT_LENGTH - 1]),
+            Err(AttestError::InvalidCpuQuote(_))
+        ));
+        let mut v1_report = REPORT.to_vec();
+        v1_report[..4].copy_from_slice(&1u32.to_le_bytes());
+        assert!(SnpReport::parse(&v1_report).is_err());
+        let mut debug_report = REPORT.to_vec();
+        debug_report[0x08..0x10].copy_from_slice(&(0x3_0000 | POLICY_DEBUG_BIT).to_le_bytes());
+        assert!(SnpReport::parse(&debug_report).unwrap().is_debug_allowed());
+        assert!(SnpReport::signed_bytes(&REPORT[..16]).is_err());
+    }
+}
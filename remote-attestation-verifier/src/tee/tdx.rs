@@ -0,0 +1,183 @@
+//! Intel TDX quotes (version 4, as produced by the DCAP quoting enclave).
+
+use super::{Reader, REPORT_DATA_LENGTH};
+use crate::errors::{AttestError, Result};
+
+/// Version of the supported quote format.
+pub const TDX_QUOTE_VERSION: u16 = 4;
+
+/// TEE type of TDX quotes.
+pub const TDX_TEE_TYPE: u32 = 0x81;
+
+/// Length of a SHA-384 measurement register, in bytes.
+const MEASUREMENT_LENGTH: usize = 48;
+
+/// Header of a TDX quote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TdxQuoteHeader {
+    /// Quote format version, [`TDX_QUOTE_VERSION`]
+    pub version: u16,
+    /// Type of the attestation key, `2` for ECDSA P-256
+    pub attestation_key_type: u16,
+    /// TEE type, [`TDX_TEE_TYPE`]
+    pub tee_type: u32,
+    /// Vendor of the quoting enclave
+    pub qe_vendor_id: [u8; 16],
+    /// Custom data of the quoting enclave
+    pub user_data: [u8; 20],
+}
+
+/// Body of a TDX quote: the TD report of the trust domain (`TDREPORT`, TDX 1.0).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TdReport {
+    /// Security version numbers of the TCB components
+    pub tee_tcb_svn: [u8; 16],
+    /// Measurement of the TDX module
+    pub mr_seam: [u8; MEASUREMENT_LENGTH],
+    /// Measurement of the signer of the TDX module
+    pub mr_signer_seam: [u8; MEASUREMENT_LENGTH],
+    /// Attributes of the TDX module
+    pub seam_attributes: u64,
+    /// Attributes of the trust domain, bit 0 set for debug trust domains
+    pub td_attributes: u64,
+    /// Extended features available to the trust domain
+    pub xfam: u64,
+    /// Measurement of the initial contents of the trust domain
+    pub mr_td: [u8; MEASUREMENT_LENGTH],
+    /// Identifier of the software-defined configuration of the trust domain
+    pub mr_config_id: [u8; MEASUREMENT_LENGTH],
+    /// Identifier of the owner of the trust domain
+    pub mr_owner: [u8; MEASUREMENT_LENGTH],
+    /// Identifier of the owner-defined configuration of the trust domain
+    pub mr_owner_config: [u8; MEASUREMENT_LENGTH],
+    /// Runtime measurement registers
+    pub rtmrs: [[u8; MEASUREMENT_LENGTH]; 4],
+    /// Data chosen by the trust domain when requesting the report
+    pub report_data: [u8; REPORT_DATA_LENGTH],
+}
+
+impl TdReport {
+    /// Returns `true` if the trust domain runs in debug mode, so its memory is not
+    /// protected from the host.
+    #[must_use]
+    pub const fn is_debug(&self) -> bool {
+        self.td_attributes & 1 != 0
+    }
+}
+
+/// A parsed TDX quote.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TdxQuote {
+    /// The quote header
+    pub header: TdxQuoteHeader,
+    /// The report of the trust domain, holding its measurements and report data
+    pub td_report: TdReport,
+    /// The signature data: quote signature, attestation key and certification data
+    pub signature_data: Vec<u8>,
+}
+
+impl TdxQuote {
+    /// Parses a version 4 TDX quote.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::InvalidCpuQuote` - If the quote is truncated, is not a version 4
+    ///   quote, or is not a TDX quote
+    pub fn parse(quote: &[u8]) -> Result<Self> {
+        let mut reader = Reader::new(quote);
+        let version = reader.u16_le("version")?;
+        if version != TDX_QUOTE_VERSION {
+            return Err(AttestError::InvalidCpuQuote(format!(
+                "Unsupported TDX quote version {version}, expected {TDX_QUOTE_VERSION}"
+            )));
+        }
+        let attestation_key_type = reader.u16_le("attestation key type")?;
+        let tee_type = reader.u32_le("TEE type")?;
+        if tee_type != TDX_TEE_TYPE {
+            return Err(AttestError::InvalidCpuQuote(format!(
+                "TEE type {tee_type:#x} is not TDX ({TDX_TEE_TYPE:#x})"
+            )));
+        }
+        reader.skip(4, "reserved")?;
+        let header = TdxQuoteHeader {
+            version,
+            attestation_key_type,
+            tee_type,
+            qe_vendor_id: reader.array("QE vendor ID")?,
+            user_data: reader.array("user data")?,
+        };
+        let td_report = TdReport {
+            tee_tcb_svn: reader.array("TEE TCB SVN")?,
+            mr_seam: reader.array("MRSEAM")?,
+            mr_signer_seam: reader.array("MRSIGNERSEAM")?,
+            seam_attributes: reader.u64_le("SEAM attributes")?,
+            td_attributes: reader.u64_le("TD attributes")?,
+            xfam: reader.u64_le("XFAM")?,
+            mr_td: reader.array("MRTD")?,
+            mr_config_id: reader.array("MRCONFIGID")?,
+            mr_owner: reader.array("MROWNER")?,
+            mr_owner_config: reader.array("MROWNERCONFIG")?,
+            rtmrs: [
+                reader.array("RTMR0")?,
+                reader.array("RTMR1")?,
+                reader.array("RTMR2")?,
+                reader.array("RTMR3")?,
+            ],
+            report_data: reader.array("report data")?,
+        };
+        let signature_data_length = reader.u32_le("signature data length")? as usize;
+        let signature_data = reader
+            .take(signature_data_length, "signature data")?
+            .to_vec();
+        Ok(Self {
+            header,
+            td_report,
+            signature_data,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUOTE: &[u8] = include_bytes!("../../evidence/tdx_quote.bin");
+
+    #[test]
+    fn test_parse_tdx_quote() {
+        let quote = TdxQuote::parse(QUOTE).unwrap();
+        assert_eq!(quote.header.version, TDX_QUOTE_VERSION);
+        assert_eq!(quote.header.attestation_key_type, 2);
+        assert_eq!(
+            hex::encode(quote.header.qe_vendor_id),
+            "939a7233f79c4ca9940a0db3957f0607"
+        );
+        assert!(!quote.td_report.is_debug());
+        assert_eq!(quote.td_report.mr_td, [0x11; MEASUREMENT_LENGTH]);
+        assert_eq!(quote.td_report.rtmrs[3], [0x23; MEASUREMENT_LENGTH]);
+        assert_eq!(
+            hex::encode(&quote.td_report.report_data[..32]),
+            "931d8dd0add203ac3d8b4fbde75e115278eefcdceac5b87671a748f32364dfcb"
+        );
+        assert_eq!(quote.signature_data.len(), 128);
+    }
+
+    #[test]
+    fn test_parse_invalid_tdx_quotes() {
+        for length in [0, 47, 48 + 583, QUOTE.len() - 1] {
+            assert!(
+                matches!(
+                    TdxQuote::parse(&QUOTE[..length]),
+                    Err(AttestError::InvalidCpuQuote(_))
+                ),
+                "a quote truncated to {length} bytes is rejected"
+            );
+        }
+        let mut sgx_quote = QUOTE.to_vec();
+        sgx_quote[4..8].copy_from_slice(&0u32.to_le_bytes());
+        assert!(TdxQuote::parse(&sgx_quote).is_err());
+        let mut v3_quote = QUOTE.to_vec();
+        v3_quote[..2].copy_from_slice(&3u16.to_le_bytes());
+        assert!(TdxQuote::parse(&v3_quote).is_err());
+    }
+}
@@ -0,0 +1,72 @@
+//! Signed evidence of a fake HGX host, shared by the end-to-end tests of the workspace.
+//!
+//! ```rust,ignore
+//! let bundle = synthetic_bundle([0x42; 32]);
+//! assert_eq!(bundle.gpu_evidence.len(), SYNTHETIC_GPUS);
+//! ```
+
+use topology::test_support::SyntheticTopology;
+
+use super::{FakeDeviceKind, TestPki};
+use crate::{
+    bundle::{EvidenceBundle, HostMetadata},
+    types::{DeviceEvidence, NvSwitchEvidence},
+};
+
+/// Number of GPUs of the fake host.
+pub const SYNTHETIC_GPUS: usize = 8;
+
+/// Number of switches of the fake host.
+pub const SYNTHETIC_SWITCHES: usize = 4;
+
+/// Seed of the [`SyntheticTopology`] of the fake host.
+pub const SYNTHETIC_TOPOLOGY_SEED: u8 = 7;
+
+/// Returns the evidence of a fake host with 8 GPUs and 4 switches, forming the
+/// [`SyntheticTopology::hgx_h100`] topology, collected with `nonce`.
+///
+/// The devices are issued by a fresh [`TestPki`], device `i` having the UEID `[i; 8]`
+/// and the FWID `[i; 48]`.
+///
+/// # Panics
+///
+/// Panics if the test PKI cannot be generated.
+#[must_use]
+pub fn synthetic_evidence(nonce: [u8; 32]) -> (Vec<DeviceEvidence>, Vec<NvSwitchEvidence>) {
+    let pki = TestPki::generate().expect("Failed to generate test PKI");
+    let mut topology = SyntheticTopology::hgx_h100(SYNTHETIC_TOPOLOGY_SEED);
+    topology.nonce = nonce;
+    let issue = |kind, index: u8| {
+        pki.issue_device(kind, [index; 8], [index; 48])
+            .expect("Failed to issue fake device")
+    };
+    let gpu_evidence = (0..)
+        .take(SYNTHETIC_GPUS)
+        .map(|index| issue(FakeDeviceKind::Gpu, index).gpu_evidence(&topology.gpu_report_builder()))
+        .collect();
+    let switch_evidence = (0..)
+        .take(SYNTHETIC_SWITCHES)
+        .map(|index| {
+            issue(FakeDeviceKind::NvSwitch, index)
+                .switch_evidence(&topology.switch_report_builder(index.into()))
+        })
+        .collect();
+    (gpu_evidence, switch_evidence)
+}
+
+/// Returns a bundle holding the [`synthetic_evidence`] collected with `nonce`, and
+/// default host metadata.
+///
+/// # Panics
+///
+/// Panics if the test PKI cannot be generated.
+#[must_use]
+pub fn synthetic_bundle(nonce: [u8; 32]) -> EvidenceBundle {
+    let (gpu_evidence, switch_evidence) = synthetic_evidence(nonce);
+    EvidenceBundle::new(
+        &nonce,
+        gpu_evidence,
+        switch_evidence,
+        HostMetadata::default(),
+    )
+}
@@ -0,0 +1,16 @@
+//! Test support for offline end-to-end tests, enabled by the `test-support` feature.
+//!
+//! * [`MockNras`] is a local mock of the NVIDIA Remote Attestation Service.
+//! * [`TestPki`] issues NVIDIA-shaped device certificate hierarchies, and signs
+//!   synthetic SPDM reports into [`DeviceEvidence`](crate::DeviceEvidence) and
+//!   [`NvSwitchEvidence`](crate::NvSwitchEvidence) values.
+//! * [`synthetic_bundle`] returns the signed evidence of a fake HGX host with 8 GPUs
+//!   and 4 switches.
+
+mod bundle;
+mod nras;
+mod pki;
+
+pub use bundle::*;
+pub use nras::*;
+pub use pki::*;
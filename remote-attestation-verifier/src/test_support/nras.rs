@@ -0,0 +1,527 @@
+//! Local mock of the NVIDIA Remote Attestation Service (NRAS), for offline tests.
+//!
+//! [`MockNras`] serves `/v3/attest/gpu`, `/v3/attest/switch` and
+//! `/.well-known/jwks.json` on a random local port. Attestation requests are
+//! answered with detached EAT responses, signed with ES384 by a key generated
+//! when the server starts, and failures can be scripted per request:
+//!
+//! ```rust,ignore
+//! let nras = MockNras::start().await?;
+//! nras.push_failure(MockFailure::Status { status: 503, body: String::new() });
+//! let result = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options()).await;
+//! assert!(result.unwrap_err().is_retryable());
+//! ```
+//!
+//! Every response carries a request identifier header, and the `traceparent` header
+//! of every request is recorded. Tokens are JWTs, or COSE-signed CWTs after
+//! [`MockNras::set_token_format`].
+
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    extract::State,
+    http::{HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use rcgen::{CertificateParams, KeyPair, PKCS_ECDSA_P384_SHA384};
+use ring::{
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, ECDSA_P384_SHA384_FIXED_SIGNING},
+};
+use serde_json::{json, Value};
+use tokio::{net::TcpListener, sync::oneshot, task::JoinHandle};
+
+use crate::{
+    constants::{
+        ARCH_KEY, EVIDENCE_LIST_KEY, HOPPER_ARCH, LS10_ARCH, NONCE_KEY, NRAS_REQUEST_ID_HEADER,
+    },
+    cose::{encode_cwt, TokenFormat},
+    options::AttestRemoteOptions,
+    utils::unix_now,
+};
+
+/// Path of the GPU attestation endpoint.
+pub const GPU_ATTEST_PATH: &str = "/v3/attest/gpu";
+
+/// Path of the `NVSwitch` attestation endpoint.
+pub const SWITCH_ATTEST_PATH: &str = "/v3/attest/switch";
+
+/// Path of the JWKS endpoint.
+pub const JWKS_PATH: &str = "/.well-known/jwks.json";
+
+/// W3C trace context header recorded for every request.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+
+/// Key identifier of the mock NRAS signing key.
+pub const MOCK_NRAS_KID: &str = "mock-nras-signing-key";
+
+/// Key identifier absent from the mock JWKS, used by [`MockFailure::WrongKid`].
+pub const UNKNOWN_KID: &str = "mock-nras-unknown-key";
+
+/// Lifetime of the tokens issued by the mock, in seconds.
+const TOKEN_LIFETIME_SECS: u64 = 3600;
+
+/// A scripted failure, consumed by the next attestation request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MockFailure {
+    /// Answer with the given HTTP status and body
+    Status { status: u16, body: String },
+    /// Answer with a body that is not valid JSON
+    MalformedBody,
+    /// Answer with a JSON body that is not a detached EAT
+    UnexpectedStructure,
+    /// Sign the overall token with a key identifier absent from the JWKS
+    WrongKid,
+    /// Sign the overall token with a key that does not match the JWKS certificate
+    WrongKey,
+    /// Issue an overall token that has already expired
+    ExpiredToken,
+    /// Answer the next JWKS request with the given HTTP status
+    JwksStatus(u16),
+}
+
+/// A request received by the mock.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedRequest {
+    /// Path of the request
+    pub path: String,
+    /// JSON body of the request, `null` for JWKS requests
+    pub body: Value,
+    /// W3C `traceparent` header of the request, if any
+    pub traceparent: Option<String>,
+    /// Identifier assigned by the mock, returned in the [`NRAS_REQUEST_ID_HEADER`]
+    /// response header
+    pub request_id: String,
+}
+
+/// An ES384 key, in the forms used to sign JWTs and CWTs.
+struct SigningKey {
+    jwt: EncodingKey,
+    cose: EcdsaKeyPair,
+}
+
+struct MockState {
+    base_url: String,
+    signing_key: SigningKey,
+    rogue_key: SigningKey,
+    certificate_der: Vec<u8>,
+    attestation_result: Mutex<bool>,
+    token_format: Mutex<TokenFormat>,
+    failures: Mutex<VecDeque<MockFailure>>,
+    jwks_failure: Mutex<Option<u16>>,
+    requests: Mutex<Vec<ReceivedRequest>>,
+    jwks_requests: Mutex<Vec<ReceivedRequest>>,
+}
+
+/// A running mock NRAS server, shut down when dropped.
+pub struct MockNras {
+    address: SocketAddr,
+    state: Arc<MockState>,
+    shutdown: Option<oneshot::Sender<()>>,
+    task: JoinHandle<()>,
+}
+
+impl MockNras {
+    /// Generates a signing key and starts the server on a random local port.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the key cannot be generated or the port cannot be bound.
+    pub async fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let address = listener.local_addr()?;
+        let (signing_key, certificate_der) = generate_signing_key()?;
+        let (rogue_key, _) = generate_signing_key()?;
+        let state = Arc::new(MockState {
+            base_url: format!("http://{address}"),
+            signing_key,
+            rogue_key,
+            certificate_der,
+            attestation_result: Mutex::new(true),
+            token_format: Mutex::new(TokenFormat::Jwt),
+            failures: Mutex::new(VecDeque::new()),
+            jwks_failure: Mutex::new(None),
+            requests: Mutex::new(Vec::new()),
+            jwks_requests: Mutex::new(Vec::new()),
+        });
+        let router = Router::new()
+            .route(GPU_ATTEST_PATH, post(attest_gpu))
+            .route(SWITCH_ATTEST_PATH, post(attest_switch))
+            .route(JWKS_PATH, get(jwks))
+            .with_state(Arc::clone(&state));
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+        let task = tokio::spawn(async move {
+            axum::serve(listener, router)
+                .with_graceful_shutdown(async {
+                    shutdown_signal.await.ok();
+                })
+                .await
+                .ok();
+        });
+        Ok(Self {
+            address,
+            state,
+            shutdown: Some(shutdown),
+            task,
+        })
+    }
+
+    /// Returns the address the server listens on.
+    #[must_use]
+    pub const fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    /// Returns the base URL of the server, e.g. `http://127.0.0.1:41234`.
+    #[must_use]
+    pub fn url(&self) -> &str {
+        &self.state.base_url
+    }
+
+    /// Returns the URL of the GPU attestation endpoint.
+    #[must_use]
+    pub fn gpu_verifier_url(&self) -> String {
+        format!("{}{GPU_ATTEST_PATH}", self.url())
+    }
+
+    /// Returns the URL of the `NVSwitch` attestation endpoint.
+    #[must_use]
+    pub fn switch_verifier_url(&self) -> String {
+        format!("{}{SWITCH_ATTEST_PATH}", self.url())
+    }
+
+    /// Returns remote attestation options pointing at the GPU attestation endpoint.
+    #[must_use]
+    pub fn gpu_options(&self) -> AttestRemoteOptions {
+        AttestRemoteOptions {
+            verifier_url: Some(self.gpu_verifier_url()),
+            allow_hold_cert: Some(false),
+            ..AttestRemoteOptions::default()
+        }
+    }
+
+    /// Returns remote attestation options pointing at the `NVSwitch` attestation endpoint.
+    #[must_use]
+    pub fn switch_options(&self) -> AttestRemoteOptions {
+        AttestRemoteOptions {
+            verifier_url: Some(self.switch_verifier_url()),
+            allow_hold_cert: Some(false),
+            ..AttestRemoteOptions::default()
+        }
+    }
+
+    /// Returns the DER encoded certificate of the signing key, as published in the JWKS.
+    #[must_use]
+    pub fn certificate_der(&self) -> &[u8] {
+        &self.state.certificate_der
+    }
+
+    /// Sets the overall attestation result of the following successful responses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock state mutex is poisoned.
+    pub fn set_attestation_result(&self, attestation_result: bool) {
+        *self.state.attestation_result.lock().unwrap() = attestation_result;
+    }
+
+    /// Sets the format of the tokens issued by the following successful responses.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock state mutex is poisoned.
+    pub fn set_token_format(&self, token_format: TokenFormat) {
+        *self.state.token_format.lock().unwrap() = token_format;
+    }
+
+    /// Scripts a failure for the next attestation request without a scripted failure.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock state mutex is poisoned.
+    pub fn push_failure(&self, failure: MockFailure) {
+        self.state.failures.lock().unwrap().push_back(failure);
+    }
+
+    /// Returns the attestation requests received so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock state mutex is poisoned.
+    #[must_use]
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Returns the JWKS requests received so far.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the mock state mutex is poisoned.
+    #[must_use]
+    pub fn jwks_requests(&self) -> Vec<ReceivedRequest> {
+        self.state.jwks_requests.lock().unwrap().clone()
+    }
+}
+
+impl Drop for MockNras {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            shutdown.send(()).ok();
+        }
+        self.task.abort();
+    }
+}
+
+/// Generates an ES384 signing key and a self-signed certificate for it.
+fn generate_signing_key() -> std::io::Result<(SigningKey, Vec<u8>)> {
+    let key_pair = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384).map_err(std::io::Error::other)?;
+    let certificate = CertificateParams::new(vec!["mock-nras".to_string()])
+        .and_then(|params| params.self_signed(&key_pair))
+        .map_err(std::io::Error::other)?;
+    let cose = EcdsaKeyPair::from_pkcs8(
+        &ECDSA_P384_SHA384_FIXED_SIGNING,
+        &key_pair.serialize_der(),
+        &SystemRandom::new(),
+    )
+    .map_err(|e| std::io::Error::other(e.to_string()))?;
+    let signing_key = SigningKey {
+        jwt: EncodingKey::from_ec_der(&key_pair.serialize_der()),
+        cose,
+    };
+    Ok((signing_key, certificate.der().to_vec()))
+}
+
+async fn attest_gpu(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let received = received_request(GPU_ATTEST_PATH, &headers);
+    let request_id = received.request_id.clone();
+    with_request_id(
+        attest(&state, received, HOPPER_ARCH, "GPU", &body),
+        &request_id,
+    )
+}
+
+async fn attest_switch(
+    State(state): State<Arc<MockState>>,
+    headers: HeaderMap,
+    body: String,
+) -> Response {
+    let received = received_request(SWITCH_ATTEST_PATH, &headers);
+    let request_id = received.request_id.clone();
+    with_request_id(
+        attest(&state, received, LS10_ARCH, "SWITCH", &body),
+        &request_id,
+    )
+}
+
+async fn jwks(State(state): State<Arc<MockState>>, headers: HeaderMap) -> Response {
+    let received = received_request(JWKS_PATH, &headers);
+    let request_id = received.request_id.clone();
+    state.jwks_requests.lock().unwrap().push(received);
+    let jwks_failure = state.jwks_failure.lock().unwrap().take();
+    if let Some(status) = jwks_failure {
+        let response = (status_code(status), "JWKS unavailable").into_response();
+        return with_request_id(response, &request_id);
+    }
+    let response = Json(json!({
+        "keys": [{
+            "kty": "EC",
+            "crv": "P-384",
+            "use": "sig",
+            "alg": "ES384",
+            "kid": MOCK_NRAS_KID,
+            "x5c": [STANDARD.encode(&state.certificate_der)],
+        }]
+    }))
+    .into_response();
+    with_request_id(response, &request_id)
+}
+
+/// Records the headers of a request, and assigns it an identifier.
+fn received_request(path: &str, headers: &HeaderMap) -> ReceivedRequest {
+    ReceivedRequest {
+        path: path.to_string(),
+        body: Value::Null,
+        traceparent: headers
+            .get(TRACEPARENT_HEADER)
+            .and_then(|traceparent| traceparent.to_str().ok())
+            .map(str::to_string),
+        request_id: hex::encode(rand::random::<[u8; 8]>()),
+    }
+}
+
+/// Returns `response` with the request identifier header.
+fn with_request_id(mut response: Response, request_id: &str) -> Response {
+    if let Ok(request_id) = HeaderValue::from_str(request_id) {
+        response
+            .headers_mut()
+            .insert(NRAS_REQUEST_ID_HEADER, request_id);
+    }
+    response
+}
+
+/// Handles an attestation request, following the scripted failures if any.
+fn attest(
+    state: &MockState,
+    mut received: ReceivedRequest,
+    arch: &str,
+    device: &str,
+    body: &str,
+) -> Response {
+    let request_id = received.request_id.clone();
+    let bad_request = |message: &str| bad_request(message, &request_id);
+    let request: Value = match serde_json::from_str(body) {
+        Ok(request) => request,
+        Err(e) => return bad_request(&format!("Request body is not valid JSON: {e}")),
+    };
+    received.body = request.clone();
+    state.requests.lock().unwrap().push(received);
+    let Some(nonce) = request.get(NONCE_KEY).and_then(Value::as_str) else {
+        return bad_request("Missing nonce");
+    };
+    let evidence_count = match request.get(EVIDENCE_LIST_KEY).and_then(Value::as_array) {
+        Some(evidence_list) if !evidence_list.is_empty() => evidence_list.len(),
+        _ => return bad_request("Missing evidence list"),
+    };
+    if request.get(ARCH_KEY).and_then(Value::as_str) != Some(arch) {
+        return bad_request(&format!("Architecture must be {arch}"));
+    }
+
+    let failure = state.failures.lock().unwrap().pop_front();
+    let (kid, key, lifetime) = match failure {
+        Some(MockFailure::Status { status, body }) => {
+            return (status_code(status), body).into_response()
+        }
+        Some(MockFailure::MalformedBody) => {
+            return (StatusCode::OK, "not a detached EAT").into_response()
+        }
+        Some(MockFailure::UnexpectedStructure) => {
+            return Json(json!({"detached_eat": []})).into_response()
+        }
+        Some(MockFailure::JwksStatus(status)) => {
+            *state.jwks_failure.lock().unwrap() = Some(status);
+            (MOCK_NRAS_KID, &state.signing_key, Some(TOKEN_LIFETIME_SECS))
+        }
+        Some(MockFailure::WrongKid) => (UNKNOWN_KID, &state.signing_key, Some(TOKEN_LIFETIME_SECS)),
+        Some(MockFailure::WrongKey) => (MOCK_NRAS_KID, &state.rogue_key, Some(TOKEN_LIFETIME_SECS)),
+        Some(MockFailure::ExpiredToken) => (MOCK_NRAS_KID, &state.signing_key, None),
+        None => (MOCK_NRAS_KID, &state.signing_key, Some(TOKEN_LIFETIME_SECS)),
+    };
+
+    let now = unix_now();
+    let (issued_at, expires_at) = lifetime.map_or(
+        (now - 2 * TOKEN_LIFETIME_SECS, now - TOKEN_LIFETIME_SECS),
+        |lifetime| (now, now + lifetime),
+    );
+    let attestation_result = *state.attestation_result.lock().unwrap();
+    let token_format = *state.token_format.lock().unwrap();
+    let claims = |subject: &str| {
+        json!({
+            "iss": state.base_url,
+            "sub": subject,
+            "iat": issued_at,
+            "nbf": issued_at,
+            "exp": expires_at,
+            "jti": hex::encode(rand::random::<[u8; 16]>()),
+            "eat_nonce": nonce,
+            "x-nvidia-ver": "2.0",
+        })
+    };
+
+    let mut device_tokens = serde_json::Map::new();
+    for index in 0..evidence_count {
+        let mut device_claims = claims(&format!("NVIDIA-{device}-ATTESTATION"));
+        add_device_claims(&mut device_claims, device, index, attestation_result);
+        let Ok(token) = sign(
+            &device_claims,
+            MOCK_NRAS_KID,
+            &state.signing_key,
+            token_format,
+        ) else {
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        };
+        device_tokens.insert(format!("{device}-{index}"), Value::String(token));
+    }
+    let mut overall_claims = claims("NVIDIA-PLATFORM-ATTESTATION");
+    overall_claims["x-nvidia-overall-att-result"] = json!(attestation_result);
+    overall_claims["submods"] = device_tokens
+        .keys()
+        .map(|submod| (submod.clone(), json!(["DETACHED", submod])))
+        .collect();
+    let Ok(overall_token) = sign(&overall_claims, kid, key, token_format) else {
+        return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+    };
+    let token_type = match token_format {
+        TokenFormat::Jwt => "JWT",
+        TokenFormat::Cwt => "CWT",
+    };
+    Json(json!([[token_type, overall_token], device_tokens])).into_response()
+}
+
+/// Adds the claims NRAS reports for a device to `claims`: the measurement result, and
+/// passed signature, nonce, certificate chain and architecture checks.
+fn add_device_claims(claims: &mut Value, device: &str, index: usize, attestation_result: bool) {
+    claims["measres"] = json!(if attestation_result {
+        "success"
+    } else {
+        "fail"
+    });
+    claims["secboot"] = json!(true);
+    claims["dbgstat"] = json!("disabled");
+    let prefix = format!("x-nvidia-{}", device.to_ascii_lowercase());
+    for check in [
+        "arch-check",
+        "attestation-report-signature-verified",
+        "attestation-report-nonce-match",
+        "attestation-report-cert-chain-validated",
+    ] {
+        claims[format!("{prefix}-{check}")] = json!(true);
+    }
+    claims["x-nvidia-device-index"] = json!(index);
+}
+
+/// Signs `claims` with ES384, as a JWT or a CWT.
+fn sign(
+    claims: &Value,
+    kid: &str,
+    key: &SigningKey,
+    token_format: TokenFormat,
+) -> Result<String, String> {
+    match token_format {
+        TokenFormat::Jwt => {
+            let mut header = Header::new(Algorithm::ES384);
+            header.kid = Some(kid.to_string());
+            jsonwebtoken::encode(&header, claims, &key.jwt).map_err(|e| e.to_string())
+        }
+        TokenFormat::Cwt => encode_cwt(claims, kid, &key.cose),
+    }
+}
+
+/// Returns an NRAS-style error response with status 400.
+fn bad_request(message: &str, request_id: &str) -> Response {
+    (
+        StatusCode::BAD_REQUEST,
+        Json(json!({
+            "errorCode": 400,
+            "message": message,
+            "requestId": request_id,
+        })),
+    )
+        .into_response()
+}
+
+fn status_code(status: u16) -> StatusCode {
+    StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
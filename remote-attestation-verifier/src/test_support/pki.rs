@@ -0,0 +1,465 @@
+//! Fake NVIDIA device certificate hierarchies and signed synthetic evidence.
+//!
+//! [`TestPki`] mirrors the shape of the NVIDIA device identity PKI: a root CA, a
+//! per-architecture identity CA and provisioner CA, a per-device identity
+//! certificate carrying a TCG DICE UEID extension, and an alias leaf carrying a TCG
+//! DICE `TcbInfo` extension with the firmware digest (FWID). All keys are P-384, and
+//! the leaf key signs SPDM reports built with `topology::test_support`:
+//!
+//! ```rust,ignore
+//! let pki = TestPki::generate()?;
+//! let gpu = pki.issue_device(FakeDeviceKind::Gpu, [1; 8], [2; 48])?;
+//! let evidence = gpu.gpu_evidence(&SpdmReportBuilder::new().nonce(nonce));
+//! assert!(verify_certificate_chain(&evidence.certificate_bytes()?, pki.root_certificate_der()));
+//! ```
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, CustomExtension, DistinguishedName, DnType,
+    IsCa, KeyPair, KeyUsagePurpose, PKCS_ECDSA_P384_SHA384,
+};
+use ring::{
+    rand::SystemRandom,
+    signature::{
+        EcdsaKeyPair, UnparsedPublicKey, ECDSA_P384_SHA384_FIXED, ECDSA_P384_SHA384_FIXED_SIGNING,
+    },
+};
+use topology::test_support::{SpdmReportBuilder, P384_SIGNATURE_SIZE, SHA384_DIGEST_SIZE};
+use x509_parser::{
+    pem::Pem,
+    prelude::{FromDer, X509Certificate},
+};
+
+use crate::types::{DeviceEvidence, NvSwitchEvidence};
+
+/// OID of the TCG DICE `TcbInfo` extension, holding firmware digests (FWIDs).
+pub const TCG_DICE_TCB_INFO_OID: &[u64] = &[2, 23, 133, 5, 4, 1];
+
+/// OID of the TCG DICE UEID extension, holding the unique device identifier.
+pub const TCG_DICE_UEID_OID: &[u64] = &[2, 23, 133, 5, 4, 4];
+
+/// DER encoding of the SHA-384 algorithm OID (2.16.840.1.101.3.4.2.2).
+const SHA384_OID_DER: &[u8] = &[
+    0x06, 0x09, 0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x02, 0x02,
+];
+
+/// Common name of the fake root CA.
+pub const ROOT_CA_COMMON_NAME: &str = "NVIDIA Device Identity CA";
+
+/// Kind of fake device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FakeDeviceKind {
+    /// A Hopper GPU
+    Gpu,
+    /// An LS10 `NVSwitch`
+    NvSwitch,
+}
+
+impl FakeDeviceKind {
+    /// Returns the model name used in certificate subjects.
+    #[must_use]
+    pub const fn model(self) -> &'static str {
+        match self {
+            Self::Gpu => "GH100",
+            Self::NvSwitch => "LS10",
+        }
+    }
+}
+
+/// A certificate and the key pair it certifies.
+struct Issuer {
+    certificate: Certificate,
+    key_pair: KeyPair,
+}
+
+/// A fake NVIDIA device identity PKI.
+pub struct TestPki {
+    root: Issuer,
+    gpu_provisioner: Issuer,
+    gpu_identity: Issuer,
+    switch_provisioner: Issuer,
+    switch_identity: Issuer,
+}
+
+impl TestPki {
+    /// Generates the root CA, and the identity and provisioner CAs of each device kind.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key or a certificate cannot be generated.
+    pub fn generate() -> Result<Self, rcgen::Error> {
+        let root = self_signed_ca(ROOT_CA_COMMON_NAME)?;
+        let (gpu_identity, gpu_provisioner) = architecture_cas(&root, FakeDeviceKind::Gpu)?;
+        let (switch_identity, switch_provisioner) =
+            architecture_cas(&root, FakeDeviceKind::NvSwitch)?;
+        Ok(Self {
+            root,
+            gpu_provisioner,
+            gpu_identity,
+            switch_provisioner,
+            switch_identity,
+        })
+    }
+
+    /// Returns the DER encoded root CA certificate, the trust anchor of all chains.
+    #[must_use]
+    pub fn root_certificate_der(&self) -> &[u8] {
+        self.root.certificate.der()
+    }
+
+    /// Issues a device identity certificate and an alias leaf certificate.
+    ///
+    /// # Arguments
+    ///
+    /// * `kind` - The kind of device
+    /// * `ueid` - The unique device identifier, recorded in the UEID extension
+    /// * `fwid` - The SHA-384 firmware digest, recorded in the `TcbInfo` extension
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a key or a certificate cannot be generated.
+    pub fn issue_device(
+        &self,
+        kind: FakeDeviceKind,
+        ueid: [u8; 8],
+        fwid: [u8; SHA384_DIGEST_SIZE],
+    ) -> Result<FakeDevice, rcgen::Error> {
+        let (identity, provisioner) = match kind {
+            FakeDeviceKind::Gpu => (&self.gpu_identity, &self.gpu_provisioner),
+            FakeDeviceKind::NvSwitch => (&self.switch_identity, &self.switch_provisioner),
+        };
+        let model = kind.model();
+
+        let mut params = ca_params(&format!("{model} A01 FSP BROM"))?;
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign];
+        params
+            .custom_extensions
+            .push(CustomExtension::from_oid_content(
+                TCG_DICE_UEID_OID,
+                der_sequence(&der_tlv(0x04, &ueid)),
+            ));
+        let device_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
+        let device_certificate =
+            params.signed_by(&device_key, &provisioner.certificate, &provisioner.key_pair)?;
+
+        let mut params = CertificateParams::new(Vec::new())?;
+        params.distinguished_name = distinguished_name(&format!("{model} A01 GSP FMC LF"));
+        params.key_usages = vec![KeyUsagePurpose::DigitalSignature];
+        params.use_authority_key_identifier_extension = true;
+        params
+            .custom_extensions
+            .push(CustomExtension::from_oid_content(
+                TCG_DICE_TCB_INFO_OID,
+                tcb_info(model, &fwid),
+            ));
+        let leaf_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
+        let leaf_certificate = params.signed_by(&leaf_key, &device_certificate, &device_key)?;
+
+        let signing_key = EcdsaKeyPair::from_pkcs8(
+            &ECDSA_P384_SHA384_FIXED_SIGNING,
+            &leaf_key.serialize_der(),
+            &SystemRandom::new(),
+        )
+        .map_err(|_| rcgen::Error::CouldNotParseKeyPair)?;
+        let certificate_chain_pem = [
+            &leaf_certificate,
+            &device_certificate,
+            &provisioner.certificate,
+            &identity.certificate,
+            &self.root.certificate,
+        ]
+        .iter()
+        .map(|certificate| certificate.pem())
+        .collect();
+        Ok(FakeDevice {
+            kind,
+            ueid,
+            fwid,
+            leaf_certificate_der: leaf_certificate.der().to_vec(),
+            certificate_chain_pem,
+            signing_key,
+        })
+    }
+}
+
+/// A fake device, able to sign SPDM reports with its leaf key.
+pub struct FakeDevice {
+    kind: FakeDeviceKind,
+    ueid: [u8; 8],
+    fwid: [u8; SHA384_DIGEST_SIZE],
+    leaf_certificate_der: Vec<u8>,
+    certificate_chain_pem: String,
+    signing_key: EcdsaKeyPair,
+}
+
+impl FakeDevice {
+    /// Returns the kind of device.
+    #[must_use]
+    pub const fn kind(&self) -> FakeDeviceKind {
+        self.kind
+    }
+
+    /// Returns the unique device identifier.
+    #[must_use]
+    pub const fn ueid(&self) -> [u8; 8] {
+        self.ueid
+    }
+
+    /// Returns the firmware digest.
+    #[must_use]
+    pub const fn fwid(&self) -> [u8; SHA384_DIGEST_SIZE] {
+        self.fwid
+    }
+
+    /// Returns the DER encoded leaf certificate.
+    #[must_use]
+    pub fn leaf_certificate_der(&self) -> &[u8] {
+        &self.leaf_certificate_der
+    }
+
+    /// Returns the PEM encoded certificate chain, from the leaf to the root.
+    #[must_use]
+    pub fn certificate_chain_pem(&self) -> &str {
+        &self.certificate_chain_pem
+    }
+
+    /// Builds the report and signs the request and response with the leaf key.
+    ///
+    /// The signature configured on the builder is replaced by an ECDSA P-384 SHA-384
+    /// signature over every preceding byte of the report, as produced by the device.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the signature cannot be computed, which only happens if the system
+    /// random number generator fails.
+    #[must_use]
+    pub fn sign_report(&self, builder: &SpdmReportBuilder) -> Vec<u8> {
+        let mut report = builder.clone().signature(Vec::new()).build();
+        let signature = self
+            .signing_key
+            .sign(&SystemRandom::new(), &report)
+            .expect("Failed to sign SPDM report");
+        report.extend_from_slice(signature.as_ref());
+        report
+    }
+
+    /// Returns GPU evidence holding the signed report and the certificate chain.
+    #[must_use]
+    pub fn gpu_evidence(&self, builder: &SpdmReportBuilder) -> DeviceEvidence {
+        DeviceEvidence {
+            certificate: STANDARD.encode(&self.certificate_chain_pem),
+            evidence: STANDARD.encode(self.sign_report(builder)),
+        }
+    }
+
+    /// Returns `NVSwitch` evidence holding the signed report and the certificate chain.
+    #[must_use]
+    pub fn switch_evidence(&self, builder: &SpdmReportBuilder) -> NvSwitchEvidence {
+        NvSwitchEvidence {
+            certificate: STANDARD.encode(&self.certificate_chain_pem),
+            evidence: STANDARD.encode(self.sign_report(builder)),
+        }
+    }
+}
+
+/// Returns `true` if the trailing P-384 signature of `report` was produced by the key
+/// of the DER encoded `leaf_certificate`.
+#[must_use]
+pub fn verify_report_signature(report: &[u8], leaf_certificate: &[u8]) -> bool {
+    let Some(signed_length) = report.len().checked_sub(P384_SIGNATURE_SIZE) else {
+        return false;
+    };
+    let Ok((_, certificate)) = X509Certificate::from_der(leaf_certificate) else {
+        return false;
+    };
+    let public_key = &certificate.public_key().subject_public_key.data;
+    UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, public_key)
+        .verify(&report[..signed_length], &report[signed_length..])
+        .is_ok()
+}
+
+/// Returns `true` if `chain_pem` is a chain of certificates, from a leaf to the self-signed
+/// `root_certificate`, in which every certificate is signed by the next one.
+#[must_use]
+pub fn verify_certificate_chain(chain_pem: &[u8], root_certificate: &[u8]) -> bool {
+    let Ok(pems) = Pem::iter_from_buffer(chain_pem).collect::<Result<Vec<_>, _>>() else {
+        return false;
+    };
+    let Ok(certificates) = pems
+        .iter()
+        .map(Pem::parse_x509)
+        .collect::<Result<Vec<_>, _>>()
+    else {
+        return false;
+    };
+    let Some((root, _)) = certificates.split_last() else {
+        return false;
+    };
+    let is_linked = certificates.windows(2).all(|pair| {
+        pair[0].issuer() == pair[1].subject()
+            && pair[0].verify_signature(Some(pair[1].public_key())).is_ok()
+    });
+    is_linked
+        && pems
+            .last()
+            .is_some_and(|pem| pem.contents == root_certificate)
+        && root.verify_signature(None).is_ok()
+}
+
+/// Generates a self-signed root CA.
+fn self_signed_ca(common_name: &str) -> Result<Issuer, rcgen::Error> {
+    let key_pair = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
+    let certificate = ca_params(common_name)?.self_signed(&key_pair)?;
+    Ok(Issuer {
+        certificate,
+        key_pair,
+    })
+}
+
+/// Generates the identity CA of an architecture and its provisioner CA.
+fn architecture_cas(root: &Issuer, kind: FakeDeviceKind) -> Result<(Issuer, Issuer), rcgen::Error> {
+    let model = kind.model();
+    let identity_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
+    let identity = ca_params(&format!("NVIDIA {model} Identity"))?.signed_by(
+        &identity_key,
+        &root.certificate,
+        &root.key_pair,
+    )?;
+    let provisioner_key = KeyPair::generate_for(&PKCS_ECDSA_P384_SHA384)?;
+    let provisioner = ca_params(&format!("NVIDIA {model} Provisioner ICA 1"))?.signed_by(
+        &provisioner_key,
+        &identity,
+        &identity_key,
+    )?;
+    Ok((
+        Issuer {
+            certificate: identity,
+            key_pair: identity_key,
+        },
+        Issuer {
+            certificate: provisioner,
+            key_pair: provisioner_key,
+        },
+    ))
+}
+
+/// Returns the parameters of a CA certificate.
+fn ca_params(common_name: &str) -> Result<CertificateParams, rcgen::Error> {
+    let mut params = CertificateParams::new(Vec::new())?;
+    params.distinguished_name = distinguished_name(common_name);
+    params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+    params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+    params.use_authority_key_identifier_extension = true;
+    Ok(params)
+}
+
+/// Returns an NVIDIA-shaped distinguished name.
+fn distinguished_name(common_name: &str) -> DistinguishedName {
+    let mut distinguished_name = DistinguishedName::new();
+    distinguished_name.push(DnType::CommonName, common_name);
+    distinguished_name.push(DnType::OrganizationName, "NVIDIA Corporation");
+    distinguished_name.push(DnType::CountryName, "US");
+    distinguished_name
+}
+
+/// Encodes a TCG DICE `TcbInfo` with a vendor, a model and a single SHA-384 FWID.
+fn tcb_info(model: &str, fwid: &[u8]) -> Vec<u8> {
+    let fwid = der_sequence(&[SHA384_OID_DER, &der_tlv(0x04, fwid)].concat());
+    der_sequence(
+        &[
+            der_tlv(0x80, b"NVIDIA"),
+            der_tlv(0x81, model.as_bytes()),
+            der_tlv(0xA6, &fwid),
+        ]
+        .concat(),
+    )
+}
+
+fn der_sequence(content: &[u8]) -> Vec<u8> {
+    der_tlv(0x30, content)
+}
+
+/// Encodes a DER type-length-value, for contents shorter than 64 KiB.
+fn der_tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+    let mut tlv = vec![tag];
+    match u8::try_from(content.len()) {
+        Ok(length) if length < 0x80 => tlv.push(length),
+        Ok(length) => tlv.extend_from_slice(&[0x81, length]),
+        Err(_) => {
+            let length = u16::try_from(content.len()).expect("DER content too large");
+            tlv.push(0x82);
+            tlv.extend_from_slice(&length.to_be_bytes());
+        }
+    }
+    tlv.extend_from_slice(content);
+    tlv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fake_device_evidence() {
+        let pki = TestPki::generate().expect("Failed to generate test PKI");
+        let gpu = pki
+            .issue_device(FakeDeviceKind::Gpu, [1; 8], [2; SHA384_DIGEST_SIZE])
+            .expect("Failed to issue device");
+        let evidence = gpu.gpu_evidence(&SpdmReportBuilder::new().nonce([3; 32]));
+
+        let chain = evidence.certificate_bytes().unwrap();
+        assert_eq!(
+            String::from_utf8_lossy(&chain)
+                .matches("BEGIN CERTIFICATE")
+                .count(),
+            5
+        );
+        assert!(verify_certificate_chain(&chain, pki.root_certificate_der()));
+        let other_pki = TestPki::generate().unwrap();
+        assert!(!verify_certificate_chain(
+            &chain,
+            other_pki.root_certificate_der()
+        ));
+
+        let mut report = evidence.evidence_bytes().unwrap();
+        assert_eq!(report[..4], [0x11, 0xE0, 0x01, 0xFF]);
+        assert!(verify_report_signature(&report, gpu.leaf_certificate_der()));
+        report[10] ^= 1;
+        assert!(!verify_report_signature(
+            &report,
+            gpu.leaf_certificate_der()
+        ));
+    }
+
+    #[test]
+    fn test_fake_device_extensions() {
+        let pki = TestPki::generate().unwrap();
+        let switch = pki
+            .issue_device(FakeDeviceKind::NvSwitch, [4; 8], [5; SHA384_DIGEST_SIZE])
+            .unwrap();
+        let (_, leaf) = X509Certificate::from_der(switch.leaf_certificate_der()).unwrap();
+        assert!(leaf
+            .subject()
+            .iter_common_name()
+            .any(|name| name.as_str() == Ok("LS10 A01 GSP FMC LF")));
+        let tcb_info = leaf
+            .extensions()
+            .iter()
+            .find(|extension| extension.oid.to_id_string() == "2.23.133.5.4.1")
+            .expect("Missing TcbInfo extension");
+        assert!(tcb_info
+            .value
+            .windows(SHA384_DIGEST_SIZE)
+            .any(|window| window == [5; SHA384_DIGEST_SIZE]));
+
+        let chain = switch.certificate_chain_pem().as_bytes();
+        let device = Pem::iter_from_buffer(chain).nth(1).unwrap().unwrap();
+        let device = device.parse_x509().unwrap();
+        let ueid = device
+            .extensions()
+            .iter()
+            .find(|extension| extension.oid.to_id_string() == "2.23.133.5.4.4")
+            .expect("Missing UEID extension");
+        assert_eq!(ueid.value, [0x30, 0x0A, 0x04, 0x08, 4, 4, 4, 4, 4, 4, 4, 4]);
+    }
+}
@@ -1,13 +1,29 @@
-use std::{fs, path::Path};
+use std::{fs, path::Path, sync::Arc};
 
-use base64::{engine::general_purpose::STANDARD, Engine};
+use base64::{
+    engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD},
+    Engine,
+};
 use nscq::NscqHandler;
 use nvml_wrapper::Nvml;
 use rand::Rng;
+use ring::{rand::SystemRandom, signature::Ed25519KeyPair};
 
 use crate::{
+    cache::{FileAttestationCache, InMemoryAttestationCache},
+    cose::TokenFormat,
+    credential::{CredentialIssuer, CredentialVerifier},
+    ear::{Ear, TrustTier, GENUINE_HARDWARE},
+    errors::{AttestError, JwksError},
     remote_gpu_attestation::AttestRemoteOptions,
-    remote_nvswitch_attestation::collect_nvswitch_evidence, types::NvSwitchEvidence,
+    remote_nvswitch_attestation::collect_nvswitch_evidence,
+    test_support::{
+        synthetic_bundle, verify_certificate_chain, verify_report_signature, FakeDeviceKind,
+        MockFailure, MockNras, TestPki, GPU_ATTEST_PATH, SWITCH_ATTEST_PATH, SYNTHETIC_GPUS,
+        SYNTHETIC_SWITCHES,
+    },
+    types::NvSwitchEvidence,
+    utils::{get_overall_claims_token, nras_token::decode_nras_token},
     verify_gpu_attestation, verify_nvswitch_attestation, DeviceEvidence,
 };
 
@@ -66,6 +82,7 @@ async fn test_attest_working_evidence() {
 }
 
 #[tokio::test]
+#[ignore = "requires NVIDIA GPU"]
 async fn test_attest_new_gpu_evidence() {
     let (evidence, nonce) = generate_new_gpu_evidence();
     match verify_gpu_attestation(&evidence, &nonce, AttestRemoteOptions::default()).await {
@@ -80,6 +97,7 @@ async fn test_attest_new_gpu_evidence() {
 }
 
 #[tokio::test]
+#[ignore = "requires NVIDIA GPU"]
 async fn test_attest_new_nvswitch_evidence() {
     let (evidence, nonce) = generate_new_nvswitch_evidence();
     match verify_nvswitch_attestation(&evidence, &nonce, AttestRemoteOptions::default()).await {
@@ -92,3 +110,602 @@ async fn test_attest_new_nvswitch_evidence() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_mock_nras_gpu_attestation() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    let (attestation_passed, response) =
+        verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+            .await
+            .expect("Failed to attest against mock NRAS");
+    assert!(attestation_passed);
+    assert_eq!(response[1].as_object().unwrap().len(), evidence.len());
+    let requests = nras.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, GPU_ATTEST_PATH);
+    assert_eq!(requests[0].body["nonce"], nonce);
+
+    nras.set_attestation_result(false);
+    let (attestation_passed, _) = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert!(!attestation_passed);
+}
+
+#[tokio::test]
+async fn test_mock_nras_nvswitch_attestation() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let evidence = vec![NvSwitchEvidence {
+        certificate: STANDARD.encode("certificate"),
+        evidence: STANDARD.encode("evidence"),
+    }];
+    let nonce = hex::encode([0u8; 32]);
+    let (attestation_passed, _) =
+        verify_nvswitch_attestation(&evidence, &nonce, nras.switch_options())
+            .await
+            .expect("Failed to attest against mock NRAS");
+    assert!(attestation_passed);
+    assert_eq!(nras.requests()[0].path, SWITCH_ATTEST_PATH);
+
+    // GPU evidence sent to the switch endpoint is rejected with an NRAS error body
+    let (gpu_evidence, _) = read_working_evidence();
+    let error = verify_gpu_attestation(&gpu_evidence, &nonce, nras.switch_options())
+        .await
+        .unwrap_err();
+    match error {
+        AttestError::HttpStatus {
+            status: 400,
+            body: Some(body),
+            ..
+        } => assert_eq!(body.message(), "Architecture must be LS10"),
+        e => panic!("Unexpected error: {e}"),
+    }
+}
+
+#[tokio::test]
+async fn test_mock_nras_scripted_failures() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    nras.push_failure(MockFailure::Status {
+        status: 503,
+        body: r#"{"errorCode": 503, "message": "Service unavailable"}"#.to_string(),
+    });
+    nras.push_failure(MockFailure::MalformedBody);
+    nras.push_failure(MockFailure::UnexpectedStructure);
+    nras.push_failure(MockFailure::WrongKid);
+    nras.push_failure(MockFailure::WrongKey);
+    nras.push_failure(MockFailure::ExpiredToken);
+    nras.push_failure(MockFailure::JwksStatus(502));
+
+    let mut errors = Vec::new();
+    for _ in 0..7 {
+        errors.push(
+            verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+                .await
+                .unwrap_err(),
+        );
+    }
+    assert!(matches!(
+        errors[0],
+        AttestError::HttpStatus { status: 503, .. }
+    ));
+    assert!(errors[0].is_retryable());
+    assert!(matches!(errors[1], AttestError::MalformedResponse(_)));
+    assert!(matches!(errors[2], AttestError::MalformedResponse(_)));
+    assert!(matches!(
+        errors[3],
+        AttestError::Jwks {
+            source: JwksError::KeyNotFound(_),
+            ..
+        }
+    ));
+    assert!(matches!(errors[4], AttestError::TokenSignature(_)));
+    assert!(matches!(errors[5], AttestError::ClaimValidation(_)));
+    assert!(matches!(
+        errors[6],
+        AttestError::Jwks {
+            source: JwksError::Status(502),
+            ..
+        }
+    ));
+    assert!(errors[6].is_retryable());
+
+    // Failures are consumed, so the next request succeeds
+    assert!(
+        verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+            .await
+            .expect("Failed to attest against mock NRAS")
+            .0
+    );
+}
+
+#[tokio::test]
+async fn test_mock_nras_cwt_tokens() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    nras.set_token_format(TokenFormat::Cwt);
+    let (evidence, nonce) = read_working_evidence();
+    let (attestation_passed, response) =
+        verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+            .await
+            .expect("Failed to attest against mock NRAS");
+    assert!(attestation_passed);
+    assert_eq!(response[0][0], "CWT");
+
+    let token = get_overall_claims_token(&response).unwrap();
+    assert_eq!(TokenFormat::detect(&token), TokenFormat::Cwt);
+    let claims = decode_nras_token(&nras.gpu_verifier_url(), &token)
+        .await
+        .expect("Failed to decode CWT");
+    assert_eq!(claims.additional_claims["eat_nonce"], nonce);
+    assert_eq!(
+        claims.additional_claims["sub"],
+        "NVIDIA-PLATFORM-ATTESTATION"
+    );
+
+    nras.push_failure(MockFailure::WrongKey);
+    nras.push_failure(MockFailure::ExpiredToken);
+    let wrong_key = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .unwrap_err();
+    assert!(matches!(wrong_key, AttestError::InvalidCoseToken(_)));
+    let expired = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .unwrap_err();
+    assert!(matches!(expired, AttestError::ClaimValidation(_)));
+}
+
+#[tokio::test]
+async fn test_mock_nras_ear() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    let (_, response) = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    let jwks_requests = nras.jwks_requests().len();
+    let mut ear = Ear::new().with_nonce(&nonce);
+    ear.add_nras_response(&nras.gpu_verifier_url(), &response)
+        .await
+        .expect("Failed to appraise the device tokens");
+    // The JWKS is fetched once for all the device tokens
+    assert_eq!(nras.jwks_requests().len(), jwks_requests + 1);
+    ear.add_check("topology", true);
+    assert_eq!(ear.submods.len(), evidence.len() + 1);
+    assert_eq!(ear.status(), TrustTier::Affirming);
+    assert_eq!(ear.submods["GPU-0"].trust_vector.hardware, GENUINE_HARDWARE);
+
+    nras.set_attestation_result(false);
+    nras.set_token_format(TokenFormat::Cwt);
+    let (_, response) = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    let mut ear = Ear::new();
+    ear.add_nras_response(&nras.gpu_verifier_url(), &response)
+        .await
+        .expect("Failed to appraise the device tokens");
+    assert_eq!(ear.status(), TrustTier::Contraindicated);
+
+    assert!(matches!(
+        ear.add_nras_response(&nras.gpu_verifier_url(), &serde_json::json!([]))
+            .await,
+        Err(AttestError::MalformedResponse(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_mock_nras_credential() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+    let issuer = CredentialIssuer::new("https://proxy.example", "key-1", pkcs8.as_ref()).unwrap();
+    let (_, response) = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    let credential = issuer
+        .issue("node-42", &nras.gpu_verifier_url(), &response)
+        .await
+        .expect("Failed to issue a credential");
+    let claims = CredentialVerifier::new("https://proxy.example", &issuer.jwks())
+        .unwrap()
+        .verify(&credential)
+        .unwrap();
+    let overall_token = get_overall_claims_token(&response).unwrap();
+    assert_eq!(
+        claims.nras_token_digest,
+        blake3::hash(overall_token.as_bytes()).to_hex().to_string()
+    );
+
+    // The result is read from the overall token of the response, so the device tokens
+    // of a passed attestation cannot vouch for a failed one
+    nras.set_attestation_result(false);
+    let (_, failed_response) = verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    let mut mixed_response = response.clone();
+    mixed_response[0] = failed_response[0].clone();
+    assert!(matches!(
+        issuer
+            .issue("node-42", &nras.gpu_verifier_url(), &mixed_response)
+            .await,
+        Err(AttestError::RemoteAttestationFailed)
+    ));
+    mixed_response[0][1] = format!("{overall_token}x").into();
+    assert!(issuer
+        .issue("node-42", &nras.gpu_verifier_url(), &mixed_response)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_mock_nras_cached_attestation() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    let options = AttestRemoteOptions {
+        cache: Some(Arc::new(InMemoryAttestationCache::default())),
+        ..nras.gpu_options()
+    };
+    let first = verify_gpu_attestation(&evidence, &nonce, options.clone())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    let second = verify_gpu_attestation(&evidence, &nonce, options)
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert_eq!(first, second);
+    assert_eq!(nras.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn test_mock_nras_file_cache_entries_are_verified() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    let directory =
+        std::env::temp_dir().join(format!("nvrust-tampered-cache-{}", std::process::id()));
+    let options = AttestRemoteOptions {
+        cache: Some(Arc::new(FileAttestationCache::new(&directory).unwrap())),
+        ..nras.gpu_options()
+    };
+    nras.set_attestation_result(false);
+    let (passed, _) = verify_gpu_attestation(&evidence, &nonce, options.clone())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert!(!passed);
+
+    // A forged token in the entry is not trusted, and NRAS is queried again
+    let entry = fs::read_dir(&directory)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let mut contents: serde_json::Value =
+        serde_json::from_slice(&fs::read(&entry).unwrap()).unwrap();
+    let token = contents["response"][0][1].as_str().unwrap().to_string();
+    let (header, rest) = token.split_once('.').unwrap();
+    let (payload, signature) = rest.split_once('.').unwrap();
+    let mut claims: serde_json::Value =
+        serde_json::from_slice(&URL_SAFE_NO_PAD.decode(payload).unwrap()).unwrap();
+    claims["x-nvidia-overall-att-result"] = true.into();
+    let payload = URL_SAFE_NO_PAD.encode(claims.to_string().as_bytes());
+    contents["response"][0][1] = format!("{header}.{payload}.{signature}").into();
+    fs::write(&entry, contents.to_string()).unwrap();
+    let (passed, _) = verify_gpu_attestation(&evidence, &nonce, options.clone())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert!(!passed);
+    assert_eq!(nras.requests().len(), 2);
+
+    // The entry written back by the second verification is served from the cache
+    verify_gpu_attestation(&evidence, &nonce, options)
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert_eq!(nras.requests().len(), 2);
+    fs::remove_dir_all(directory).ok();
+}
+
+#[tokio::test]
+async fn test_mock_nras_file_cache_entries_are_bound_to_the_request() {
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    let directory =
+        std::env::temp_dir().join(format!("nvrust-replayed-cache-{}", std::process::id()));
+    let options = AttestRemoteOptions {
+        cache: Some(Arc::new(FileAttestationCache::new(&directory).unwrap())),
+        ..nras.gpu_options()
+    };
+    nras.set_attestation_result(false);
+    let (passed, _) = verify_gpu_attestation(&evidence, &nonce, options.clone())
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert!(!passed);
+
+    // A valid passing response issued for another nonce is not trusted, and NRAS is
+    // queried again
+    nras.set_attestation_result(true);
+    let other_nonce = "00".repeat(32);
+    let (passed, other_response) =
+        verify_gpu_attestation(&evidence, &other_nonce, nras.gpu_options())
+            .await
+            .expect("Failed to attest against mock NRAS");
+    assert!(passed);
+    nras.set_attestation_result(false);
+    let entry = fs::read_dir(&directory)
+        .unwrap()
+        .next()
+        .unwrap()
+        .unwrap()
+        .path();
+    let mut contents: serde_json::Value =
+        serde_json::from_slice(&fs::read(&entry).unwrap()).unwrap();
+    contents["response"] = other_response;
+    fs::write(&entry, contents.to_string()).unwrap();
+    let (passed, _) = verify_gpu_attestation(&evidence, &nonce, options)
+        .await
+        .expect("Failed to attest against mock NRAS");
+    assert!(!passed);
+    assert_eq!(nras.requests().len(), 3);
+    fs::remove_dir_all(directory).ok();
+}
+
+#[test]
+fn test_synthetic_bundle() {
+    let bundle = synthetic_bundle([0x42; 32]);
+    bundle.validate().expect("Synthetic bundle should be valid");
+    assert_eq!(bundle.nonce_bytes().unwrap(), [0x42; 32]);
+    assert_eq!(bundle.gpu_evidence.len(), SYNTHETIC_GPUS);
+    assert_eq!(bundle.switch_evidence.len(), SYNTHETIC_SWITCHES);
+}
+
+#[tokio::test]
+async fn test_fake_devices_attestation_pipeline() {
+    let pki = TestPki::generate().expect("Failed to generate test PKI");
+    let topology = topology::test_support::SyntheticTopology::hgx_h100(7);
+    let fwid = [0x42; 48];
+    let gpus = (0..8u8)
+        .map(|index| pki.issue_device(FakeDeviceKind::Gpu, [index; 8], fwid))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to issue GPUs");
+    let switches = (0..4u8)
+        .map(|index| pki.issue_device(FakeDeviceKind::NvSwitch, [0x80 | index; 8], fwid))
+        .collect::<Result<Vec<_>, _>>()
+        .expect("Failed to issue switches");
+    let gpu_evidence: Vec<DeviceEvidence> = gpus
+        .iter()
+        .map(|gpu| gpu.gpu_evidence(&topology.gpu_report_builder()))
+        .collect();
+    let switch_evidence: Vec<NvSwitchEvidence> = switches
+        .iter()
+        .enumerate()
+        .map(|(index, switch)| switch.switch_evidence(&topology.switch_report_builder(index)))
+        .collect();
+
+    let gpu_reports: Vec<Vec<u8>> = gpu_evidence
+        .iter()
+        .map(|evidence| evidence.evidence_bytes().unwrap())
+        .collect();
+    let switch_reports: Vec<Vec<u8>> = switch_evidence
+        .iter()
+        .map(|evidence| evidence.evidence_bytes().unwrap())
+        .collect();
+    for (gpu, (evidence, report)) in gpus.iter().zip(gpu_evidence.iter().zip(&gpu_reports)) {
+        assert!(verify_report_signature(report, gpu.leaf_certificate_der()));
+        let chain = evidence.certificate_bytes().unwrap();
+        assert!(verify_certificate_chain(&chain, pki.root_certificate_der()));
+    }
+    for (switch, (evidence, report)) in switches
+        .iter()
+        .zip(switch_evidence.iter().zip(&switch_reports))
+    {
+        assert!(verify_report_signature(
+            report,
+            switch.leaf_certificate_der()
+        ));
+        let chain = evidence.certificate_bytes().unwrap();
+        assert!(verify_certificate_chain(&chain, pki.root_certificate_der()));
+    }
+    // A report signed by another device does not verify against this device's leaf
+    assert!(!verify_report_signature(
+        &gpu_reports[0],
+        gpus[1].leaf_certificate_der()
+    ));
+
+    let gpu_report_slices: Vec<&[u8]> = gpu_reports.iter().map(Vec::as_slice).collect();
+    let switch_report_slices: Vec<&[u8]> = switch_reports.iter().map(Vec::as_slice).collect();
+    let unique_switch_pdis_set = topology::topology::gpu_topology_check(&gpu_report_slices)
+        .expect("Failed to check GPU topology");
+    topology::topology::switch_topology_check(
+        &switch_report_slices,
+        gpus.len(),
+        unique_switch_pdis_set,
+    )
+    .expect("Failed to check switch topology");
+
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let nonce = hex::encode(topology.nonce);
+    let (attestation_passed, _) = verify_gpu_attestation(&gpu_evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest GPUs against mock NRAS");
+    assert!(attestation_passed);
+    let (attestation_passed, _) =
+        verify_nvswitch_attestation(&switch_evidence, &nonce, nras.switch_options())
+            .await
+            .expect("Failed to attest switches against mock NRAS");
+    assert!(attestation_passed);
+}
+
+#[cfg(feature = "metrics")]
+#[tokio::test]
+async fn test_attestation_metrics() {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use crate::metrics::{
+        ATTESTATIONS_TOTAL, JWKS_FETCHES_TOTAL, NRAS_REQUESTS_TOTAL, NRAS_REQUEST_DURATION_SECONDS,
+        TOKEN_VERIFICATION_FAILURES_TOTAL,
+    };
+
+    // The test runtime is single-threaded, so the mock server and the client record
+    // through the same thread-local recorder
+    let recorder = DebuggingRecorder::new();
+    let snapshotter = recorder.snapshotter();
+    let _guard = ::metrics::set_default_local_recorder(&recorder);
+
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    nras.push_failure(MockFailure::Status {
+        status: 503,
+        body: r#"{"errorCode": 503, "message": "Service unavailable"}"#.to_string(),
+    });
+    nras.push_failure(MockFailure::WrongKey);
+    for _ in 0..2 {
+        verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+            .await
+            .unwrap_err();
+    }
+    verify_gpu_attestation(&evidence, &nonce, nras.gpu_options())
+        .await
+        .expect("Failed to attest against mock NRAS");
+
+    let metrics: Vec<_> = snapshotter
+        .snapshot()
+        .into_vec()
+        .into_iter()
+        .map(|(key, _, _, value)| {
+            let key = key.key();
+            let mut labels: Vec<String> = key
+                .labels()
+                .map(|label| format!("{}={}", label.key(), label.value()))
+                .collect();
+            labels.sort();
+            (key.name().to_string(), labels.join(","), value)
+        })
+        .collect();
+    let counter = |name: &str, labels: &str| {
+        metrics
+            .iter()
+            .find(|(metric, metric_labels, _)| metric == name && metric_labels == labels)
+            .map(|(_, _, value)| value)
+    };
+    assert_eq!(
+        counter(NRAS_REQUESTS_TOTAL, "arch=HOPPER,status=503"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        counter(NRAS_REQUESTS_TOTAL, "arch=HOPPER,status=200"),
+        Some(&DebugValue::Counter(2))
+    );
+    assert_eq!(
+        counter(ATTESTATIONS_TOTAL, "arch=HOPPER,result=error"),
+        Some(&DebugValue::Counter(2))
+    );
+    assert_eq!(
+        counter(ATTESTATIONS_TOTAL, "arch=HOPPER,result=passed"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        counter(TOKEN_VERIFICATION_FAILURES_TOTAL, "reason=token_signature"),
+        Some(&DebugValue::Counter(1))
+    );
+    assert_eq!(
+        counter(JWKS_FETCHES_TOTAL, "outcome=success"),
+        Some(&DebugValue::Counter(2))
+    );
+    match counter(NRAS_REQUEST_DURATION_SECONDS, "arch=HOPPER") {
+        Some(DebugValue::Histogram(durations)) => assert_eq!(durations.len(), 3),
+        value => panic!("Unexpected NRAS latency histogram: {value:?}"),
+    }
+}
+
+#[cfg(feature = "otel")]
+#[tokio::test]
+async fn test_trace_context_propagation() {
+    use std::collections::HashSet;
+
+    use opentelemetry::trace::{TraceContextExt, TracerProvider};
+    use opentelemetry_sdk::{
+        propagation::TraceContextPropagator,
+        trace::{InMemorySpanExporter, SdkTracerProvider},
+    };
+    use tracing::Instrument;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use crate::test_support::ReceivedRequest;
+
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let subscriber = tracing_subscriber::registry()
+        .with(tracing_opentelemetry::layer().with_tracer(provider.tracer("nvrust-tests")));
+    // The test runtime is single-threaded, so every span is recorded by this subscriber
+    let _guard = tracing::subscriber::set_default(subscriber);
+    opentelemetry::global::set_text_map_propagator(TraceContextPropagator::new());
+
+    let nras = MockNras::start().await.expect("Failed to start mock NRAS");
+    let (evidence, nonce) = read_working_evidence();
+    // Two verifications run concurrently, each in the trace of its caller
+    let first = tracing::info_span!("caller", index = 0);
+    let second = tracing::info_span!("caller", index = 1);
+    let trace_id = |span: &tracing::Span| span.context().span().span_context().trace_id();
+    let trace_ids = HashSet::from([trace_id(&first).to_string(), trace_id(&second).to_string()]);
+    assert_eq!(trace_ids.len(), 2);
+    let (first, second) = tokio::join!(
+        verify_gpu_attestation(&evidence, &nonce, nras.gpu_options()).instrument(first),
+        verify_gpu_attestation(&evidence, &nonce, nras.gpu_options()).instrument(second),
+    );
+    assert!(first.unwrap().0);
+    assert!(second.unwrap().0);
+
+    let finished_spans = exporter.get_finished_spans().unwrap();
+    let span_ids = |name: &str| -> HashSet<(String, String)> {
+        finished_spans
+            .iter()
+            .filter(|span| span.name == name)
+            .map(|span| {
+                (
+                    span.span_context.trace_id().to_string(),
+                    span.span_context.span_id().to_string(),
+                )
+            })
+            .collect()
+    };
+    // Each request carries the context of its own request span, in its caller's trace
+    let propagated = |requests: Vec<ReceivedRequest>| -> HashSet<(String, String)> {
+        requests
+            .iter()
+            .map(|request| {
+                let traceparent = request.traceparent.as_deref().expect("Missing traceparent");
+                let fields: Vec<&str> = traceparent.split('-').collect();
+                assert_eq!(fields.len(), 4, "{traceparent}");
+                (fields[1].to_string(), fields[2].to_string())
+            })
+            .collect()
+    };
+    let nras_spans = span_ids("nras_request");
+    assert_eq!(nras_spans.len(), 2);
+    assert_eq!(propagated(nras.requests()), nras_spans);
+    assert_eq!(
+        nras_spans
+            .iter()
+            .map(|(trace_id, _)| trace_id.clone())
+            .collect::<HashSet<_>>(),
+        trace_ids
+    );
+    let jwks_spans = span_ids("jwks_request");
+    assert_eq!(jwks_spans.len(), 2);
+    assert_eq!(propagated(nras.jwks_requests()), jwks_spans);
+
+    // The request identifiers returned by NRAS are recorded on the request spans
+    let recorded_request_ids: HashSet<String> = finished_spans
+        .iter()
+        .filter(|span| span.name == "nras_request")
+        .flat_map(|span| &span.attributes)
+        .filter(|attribute| attribute.key.as_str() == "request_id")
+        .map(|attribute| attribute.value.to_string())
+        .collect();
+    let request_ids: HashSet<String> = nras
+        .requests()
+        .into_iter()
+        .map(|request| request.request_id)
+        .collect();
+    assert_eq!(recorded_request_ids, request_ids);
+}
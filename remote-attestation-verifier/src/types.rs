@@ -1,11 +1,14 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
 use serde::{Deserialize, Serialize};
 
+use crate::errors::{AttestError, Result};
+
 /// Represents attestation evidence for a hardware device (GPU or `NVSwitch`)
 ///
 /// This structure contains the certificate chain and attestation evidence
 /// required to verify the authenticity and integrity of a hardware device.
 /// Both fields are stored as base64 encoded strings.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct DeviceEvidence {
     /// The certificate chain for the device (either GPU or `NVSwitch`),
     /// in base64 encoded format
@@ -21,7 +24,7 @@ pub struct DeviceEvidence {
 /// This structure contains the certificate chain and attestation evidence
 /// required to verify the authenticity and integrity of an `NVSwitch` device.
 /// Both fields are stored as base64 encoded strings.
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 pub struct NvSwitchEvidence {
     /// The certificate chain for the `NVSwitch` device, in base64 encoded format
     pub certificate: String,
@@ -29,3 +32,50 @@ pub struct NvSwitchEvidence {
     /// The remote attestation evidence for the `NVSwitch` device, in base64 encoded format
     pub evidence: String,
 }
+
+impl DeviceEvidence {
+    /// Decodes the base64 encoded attestation report.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::EvidenceDecode` - If the evidence is not valid base64
+    pub fn evidence_bytes(&self) -> Result<Vec<u8>> {
+        decode_base64_field(&self.evidence, "evidence")
+    }
+
+    /// Decodes the base64 encoded certificate chain.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::EvidenceDecode` - If the certificate chain is not valid base64
+    pub fn certificate_bytes(&self) -> Result<Vec<u8>> {
+        decode_base64_field(&self.certificate, "certificate")
+    }
+}
+
+impl NvSwitchEvidence {
+    /// Decodes the base64 encoded attestation report.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::EvidenceDecode` - If the evidence is not valid base64
+    pub fn evidence_bytes(&self) -> Result<Vec<u8>> {
+        decode_base64_field(&self.evidence, "evidence")
+    }
+
+    /// Decodes the base64 encoded certificate chain.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::EvidenceDecode` - If the certificate chain is not valid base64
+    pub fn certificate_bytes(&self) -> Result<Vec<u8>> {
+        decode_base64_field(&self.certificate, "certificate")
+    }
+}
+
+/// Decodes a base64 encoded evidence field.
+pub(crate) fn decode_base64_field(value: &str, field: &str) -> Result<Vec<u8>> {
+    STANDARD
+        .decode(value)
+        .map_err(|e| AttestError::EvidenceDecode(format!("Invalid base64 in `{field}`: {e}")))
+}
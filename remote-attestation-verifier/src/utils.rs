@@ -3,15 +3,19 @@ use crate::{
     errors::{AttestError, Result},
 };
 use serde_json::Value;
-use std::sync::{LazyLock, Mutex};
+use std::{
+    sync::{LazyLock, Mutex},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 /// Global state to control certificate hold status.
 /// This is initialized as `None` and can be set at runtime.
 static CERT_HOLD_STATUS: LazyLock<Mutex<Option<bool>>> = LazyLock::new(|| Mutex::new(None));
 
-// Sets whether certificates should be allowed to be held.
+/// Sets whether certificates should be allowed to be held.
 ///
-/// This function updates the global certificate hold status.
+/// This function updates the global certificate hold status, which remote attestation
+/// falls back to when [`crate::AttestRemoteOptions::allow_hold_cert`] is unset.
 ///
 /// # Arguments
 ///
@@ -20,6 +24,7 @@ static CERT_HOLD_STATUS: LazyLock<Mutex<Option<bool>>> = LazyLock::new(|| Mutex:
 /// # Panics
 ///
 /// * `AttestError::JsonError` - If there is an error parsing the JSON token
+#[deprecated(note = "set `AttestRemoteOptions::allow_hold_cert` instead")]
 pub fn set_allow_hold_cert(value: bool) {
     let mut status = CERT_HOLD_STATUS.lock().unwrap();
     *status = Some(value);
@@ -29,6 +34,9 @@ pub fn set_allow_hold_cert(value: bool) {
 ///
 /// This function first checks the global certificate hold status.
 /// If not set, it falls back to checking the `NV_ALLOW_HOLD_CERT` environment variable.
+/// Remote attestation only falls back to it when
+/// [`crate::AttestRemoteOptions::allow_hold_cert`] is unset, which
+/// [`crate::AttestRemoteOptions::builder`] loads from the same environment variable.
 ///
 /// # Returns
 ///
@@ -38,6 +46,7 @@ pub fn set_allow_hold_cert(value: bool) {
 /// # Panics
 ///
 /// * `AttestError::JsonError` - If there is an error parsing the JSON token
+#[deprecated(note = "read `AttestRemoteOptions::allow_hold_cert` instead")]
 pub fn get_allow_hold_cert() -> bool {
     CERT_HOLD_STATUS.lock().unwrap().map_or_else(
         || std::env::var(NV_ALLOW_HOLD_CERT_KEY).unwrap_or_default() == "true",
@@ -45,6 +54,36 @@ pub fn get_allow_hold_cert() -> bool {
     )
 }
 
+/// Returns the current time, in seconds since the Unix epoch.
+#[must_use]
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+/// Returns `true` if one of the values of an `Accept` header accepts `media_type`.
+///
+/// Media ranges are compared to `media_type` without their parameters and ignoring
+/// case, and ranges with a zero quality (`q=0`) are not acceptable. Wildcards are not
+/// matched, so clients opt in to `media_type` explicitly.
+pub fn accepts_media_type<'a>(accept: impl IntoIterator<Item = &'a str>, media_type: &str) -> bool {
+    accept
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .any(|media_range| {
+            let mut parameters = media_range.split(';');
+            let range = parameters.next().unwrap_or_default().trim();
+            range.eq_ignore_ascii_case(media_type)
+                && !parameters.any(|parameter| {
+                    parameter.split_once('=').is_some_and(|(name, value)| {
+                        name.trim().eq_ignore_ascii_case("q")
+                            && value.trim().parse::<f32>().is_ok_and(|q| q <= 0.0)
+                    })
+                })
+        })
+}
+
 /// Gets the overall claims token from a JSON token structure.
 ///
 /// # Arguments
@@ -53,14 +92,18 @@ pub fn get_allow_hold_cert() -> bool {
 ///
 /// # Returns
 ///
-/// * `Result<String, JsonError>` - The extracted token or an error
+/// * `Result<String>` - The extracted token or an error
+///
+/// # Errors
+///
+/// * `AttestError::MalformedResponse` - If the token structure is not the expected one
 #[tracing::instrument(level = "debug", skip(token))]
 pub fn get_overall_claims_token(token: &Value) -> Result<String> {
     // 1. Extract the first array element
     let overall_token_arr = token.get(0).and_then(|arr| arr.as_array()).ok_or_else(|| {
-        AttestError::JsonError(serde::ser::Error::custom(
-            "Token structure invalid: first element is not an array",
-        ))
+        AttestError::MalformedResponse(
+            "Token structure invalid: first element is not an array".to_string(),
+        )
     })?;
 
     // 2. Extract the second element from the first array
@@ -68,31 +111,38 @@ pub fn get_overall_claims_token(token: &Value) -> Result<String> {
         .get(1)
         .and_then(|token| token.as_str())
         .ok_or_else(|| {
-            AttestError::JsonError(serde::ser::Error::custom(
-                "Token structure invalid: second element is not a string",
-            ))
+            AttestError::MalformedResponse(
+                "Token structure invalid: second element is not a string".to_string(),
+            )
         })?;
 
     Ok(overall_token.to_string())
 }
 
 pub mod nras_token {
+    pub use crate::cose::TokenFormat;
     use crate::{
         constants::{DEFAULT_TIMEOUT, KEYS_KEY, KID_KEY},
-        errors::{AttestError, Result},
+        cose::CoseSign1Token,
+        errors::{AttestError, JwksError, Result},
+        propagation::inject_trace_context,
     };
     use base64::{engine::general_purpose::STANDARD, Engine};
     use jsonwebtoken::{decode, decode_header, Algorithm, DecodingKey, Validation};
-    use reqwest::Client;
-    use serde::{Deserialize, Serialize};
+    use reqwest::{header::HeaderMap, Client};
+    use serde::{de::DeserializeOwned, Deserialize, Serialize};
     use serde_json::Value;
-    use std::collections::HashMap;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        time::Instant,
+    };
+    use tracing::Instrument;
     use url::Url;
     use x509_parser::prelude::{FromDer, X509Certificate};
 
     /// Custom claims structure for NVIDIA attestation tokens.
     ///
-    /// This struct represents the claims contained in a JWT token issued by NVIDIA's
+    /// This struct represents the claims contained in a JWT or CWT token issued by NVIDIA's
     /// Remote Attestation Service (NRAS). It includes standard JWT fields like issuer,
     /// subject, and expiration time, as well as NVIDIA-specific attestation results.   
     #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -106,7 +156,53 @@ pub mod nras_token {
         pub additional_claims: HashMap<String, Value>,
     }
 
-    /// Decodes and verifies an NVIDIA Remote Attestation Service (NRAS) JWT token.
+    impl NvidiaAttestationClaims {
+        /// Checks that the token was issued for `nonce` (`eat_nonce` claim), ignoring the
+        /// case of the hex digits.
+        ///
+        /// # Errors
+        ///
+        /// * `AttestError::ClaimValidation` - If the token has no nonce, or another one
+        pub(crate) fn check_nonce(&self, nonce: &str) -> Result<()> {
+            let token_nonce = self
+                .additional_claims
+                .get("eat_nonce")
+                .and_then(Value::as_str);
+            if token_nonce.is_some_and(|token_nonce| token_nonce.eq_ignore_ascii_case(nonce)) {
+                Ok(())
+            } else {
+                Err(AttestError::ClaimValidation(format!(
+                    "NRAS token was issued for nonce {token_nonce:?}, expected {nonce}"
+                )))
+            }
+        }
+
+        /// Returns the names of the devices the token covers (keys of the `submods` claim).
+        pub(crate) fn submod_names(&self) -> impl Iterator<Item = &str> {
+            self.additional_claims
+                .get("submods")
+                .and_then(Value::as_object)
+                .into_iter()
+                .flat_map(|submods| submods.keys().map(String::as_str))
+        }
+    }
+
+    /// Claims of an NRAS device token.
+    ///
+    /// Device tokens hold the claims of a single GPU or switch (e.g.
+    /// `x-nvidia-gpu-measurements-match`), and no overall attestation result.
+    #[derive(Clone, Debug, Serialize, Deserialize)]
+    pub struct NvidiaDeviceClaims {
+        /// Map containing the claims present in the token
+        #[serde(flatten)]
+        pub claims: HashMap<String, Value>,
+    }
+
+    /// Decodes and verifies an NVIDIA Remote Attestation Service (NRAS) token.
+    ///
+    /// The token may be a JWT, or a COSE-signed CWT (see [`crate::cose`]); the format is
+    /// detected with [`TokenFormat::detect`], and both are verified with ES384 and decoded
+    /// to the same claims.
     ///
     /// This function performs the following steps:
     /// 1. Constructs a JWKS URL from the provided verifier URL
@@ -114,12 +210,12 @@ pub mod nras_token {
     /// 3. Extracts the key ID (kid) from the token header
     /// 4. Finds the matching key in the JWKS data
     /// 5. Extracts the certificate from the key
-    /// 6. Uses the certificate to decode and verify the JWT token
+    /// 6. Uses the certificate to decode and verify the token
     ///
     /// # Arguments
     ///
     /// * `verifier_url` - Base URL of the NVIDIA attestation verifier service
-    /// * `token` - The JWT token string to decode and verify
+    /// * `token` - The JWT or base64url encoded CWT to decode and verify
     ///
     /// # Returns
     ///
@@ -128,12 +224,12 @@ pub mod nras_token {
     /// # Errors
     ///
     /// Returns various `AttestError` variants if:
-    /// * The JWKS URL cannot be constructed
-    /// * The JWKS data cannot be fetched
-    /// * The token header is invalid
-    /// * The matching key cannot be found
-    /// * The certificate is invalid
-    /// * The token signature verification fails
+    /// * The JWKS URL cannot be constructed (`AttestError::UrlParseError`)
+    /// * The JWKS data cannot be fetched, or has no key matching the token's kid (`AttestError::Jwks`)
+    /// * The token header is invalid (`AttestError::HeaderDecodeError`, `AttestError::InvalidCoseToken`)
+    /// * The certificate is invalid (`AttestError::CertificateDecodeError`, `AttestError::CertificateParseError`)
+    /// * The token signature verification fails (`AttestError::TokenSignature`, `AttestError::InvalidCoseToken`)
+    /// * The token claims are not valid, e.g. expired (`AttestError::ClaimValidation`)
     #[tracing::instrument(
         level = "debug",
         name = "decode_nras_token",
@@ -148,26 +244,157 @@ pub mod nras_token {
         token: &str,
     ) -> Result<NvidiaAttestationClaims> {
         let jwks_url = create_jwks_url(verifier_url)?;
-        let client = Client::builder().timeout(DEFAULT_TIMEOUT).build()?;
-        let jwks_data: Value = client.get(&jwks_url).send().await?.json().await?;
-        let header = decode_header(token)?;
-        let kid = header.kid.ok_or_else(|| {
-            AttestError::InvalidJwtToken("Kid not found in token header".to_string())
-        })?;
-        let matching_key = get_matching_key(&jwks_data, &kid).ok_or_else(|| {
-            AttestError::InvalidJwtToken("Matching key not found in JWKS data".to_string())
-        })?;
-        let x5c = matching_key
-            .get("x5c")
-            .and_then(|x| x.as_array())
+        let header = TokenHeader::decode(token)?;
+        let keys = fetch_jwks_keys(&jwks_url, &[header.kid.as_str()]).await?;
+        header.verify(token, &keys[0])
+    }
+
+    /// Decodes and verifies the device tokens of an NRAS response, as returned by
+    /// [`crate::verify_gpu_attestation`] or [`crate::verify_nvswitch_attestation`].
+    ///
+    /// Returns the claims of each device, keyed by the device name NRAS assigned (e.g.
+    /// `GPU-0`). The JWKS is fetched once for all the tokens.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::MalformedResponse` - If the response holds no device tokens
+    /// * The errors of [`decode_nras_token`] if a device token cannot be verified
+    pub async fn decode_device_tokens(
+        verifier_url: &str,
+        nras_response: &Value,
+    ) -> Result<BTreeMap<String, NvidiaDeviceClaims>> {
+        let device_tokens = nras_response
+            .get(1)
+            .and_then(Value::as_object)
             .ok_or_else(|| {
+                AttestError::MalformedResponse(
+                    "Token structure invalid: second element is not an object".to_string(),
+                )
+            })?;
+        let jwks_url = create_jwks_url(verifier_url)?;
+        let mut tokens = Vec::with_capacity(device_tokens.len());
+        for (device, token) in device_tokens {
+            let token = token.as_str().ok_or_else(|| {
+                AttestError::MalformedResponse(format!("Token of `{device}` is not a string"))
+            })?;
+            tokens.push((device, token, TokenHeader::decode(token)?));
+        }
+        let kids: Vec<&str> = tokens
+            .iter()
+            .map(|(_, _, header)| header.kid.as_str())
+            .collect();
+        let keys = fetch_jwks_keys(&jwks_url, &kids).await?;
+        tokens
+            .into_iter()
+            .zip(&keys)
+            .map(|((device, token, header), key)| Ok((device.clone(), header.verify(token, key)?)))
+            .collect()
+    }
+
+    /// Key id of an NRAS token, and the decoded token if it is a CWT.
+    struct TokenHeader {
+        kid: String,
+        cose_token: Option<CoseSign1Token>,
+    }
+
+    impl TokenHeader {
+        /// Decodes the header of a JWT or CWT, detected with [`TokenFormat::detect`].
+        fn decode(token: &str) -> Result<Self> {
+            let format = TokenFormat::detect(token);
+            let header = match format {
+                TokenFormat::Jwt => {
+                    let kid = decode_header(token)?.kid.ok_or_else(|| {
+                        AttestError::InvalidJwtToken("Kid not found in token header".to_string())
+                    })?;
+                    Self {
+                        kid,
+                        cose_token: None,
+                    }
+                }
+                TokenFormat::Cwt => {
+                    let cose_token = CoseSign1Token::decode(token)?;
+                    let kid = cose_token.kid().map(str::to_string).ok_or_else(|| {
+                        AttestError::InvalidCoseToken("Kid not found in token header".to_string())
+                    })?;
+                    Self {
+                        kid,
+                        cose_token: Some(cose_token),
+                    }
+                }
+            };
+            tracing::debug!(level = "decode_nras_token", ?format, kid = %header.kid, "Decoding NRAS token");
+            Ok(header)
+        }
+
+        /// Verifies `token` with the certificate of its JWKS key `key`, and returns its
+        /// claims.
+        fn verify<T: DeserializeOwned>(self, token: &str, key: &Value) -> Result<T> {
+            let x5c = key.get("x5c").and_then(|x| x.as_array()).ok_or_else(|| {
                 AttestError::InvalidJwtToken("No x5c field in the matching key".to_string())
             })?;
-        let cert_b64 = x5c.first().and_then(|c| c.as_str()).ok_or_else(|| {
-            AttestError::InvalidJwtToken("No certificate found in x5c field".to_string())
-        })?;
-        let cert_der = STANDARD.decode(cert_b64)?;
-        decode_jwt_token(token, &cert_der)
+            let cert_b64 = x5c.first().and_then(|c| c.as_str()).ok_or_else(|| {
+                AttestError::InvalidJwtToken("No certificate found in x5c field".to_string())
+            })?;
+            let cert_der = STANDARD.decode(cert_b64)?;
+            self.cose_token.map_or_else(
+                || decode_jwt_token(token, &cert_der),
+                |cose_token| cose_token.verify(&cert_der),
+            )
+        }
+    }
+
+    /// Fetches the JWKS at `jwks_url` once, and returns the keys identified by `kids`, in
+    /// order.
+    ///
+    /// # Errors
+    ///
+    /// * `AttestError::Jwks` - If the JWKS cannot be fetched or parsed, or has no key
+    ///   identified by one of `kids`
+    async fn fetch_jwks_keys(jwks_url: &str, kids: &[&str]) -> Result<Vec<Value>> {
+        let fetch_started = Instant::now();
+        let keys = fetch_jwks(jwks_url).await.and_then(|jwks_data| {
+            kids.iter()
+                .map(|kid| {
+                    get_matching_key(&jwks_data, kid)
+                        .cloned()
+                        .ok_or_else(|| JwksError::KeyNotFound((*kid).to_string()))
+                })
+                .collect()
+        });
+        crate::metrics::record_jwks_fetch(keys.as_ref().err(), fetch_started.elapsed());
+        keys.map_err(|source| AttestError::Jwks {
+            url: jwks_url.to_string(),
+            source,
+        })
+    }
+
+    /// Fetches and parses the JWKS at `jwks_url`.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `JwksError` if the JWKS cannot be fetched or parsed.
+    async fn fetch_jwks(jwks_url: &str) -> std::result::Result<Value, JwksError> {
+        let client = Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .build()
+            .map_err(JwksError::Transport)?;
+        let request_span = tracing::info_span!("jwks_request", url = %jwks_url);
+        let mut headers = HeaderMap::new();
+        inject_trace_context(&request_span, &mut headers);
+        let response = client
+            .get(jwks_url)
+            .headers(headers)
+            .send()
+            .instrument(request_span)
+            .await
+            .map_err(JwksError::Transport)?;
+        if !response.status().is_success() {
+            return Err(JwksError::Status(response.status().as_u16()));
+        }
+        response
+            .json()
+            .await
+            .map_err(|e| JwksError::Malformed(e.to_string()))
     }
 
     /// Generate JWKS URL using the verifier URL
@@ -217,7 +444,7 @@ pub mod nras_token {
                 // Iterate through the keys to find a matching kid
                 keys_array
                     .iter()
-                    .find(|key| (key.get(KID_KEY).and_then(|k| k.as_str()) == Some(kid)))
+                    .find(|key| key.get(KID_KEY).and_then(|k| k.as_str()) == Some(kid))
             })
     }
 
@@ -230,14 +457,15 @@ pub mod nras_token {
     ///
     /// # Returns
     ///
-    /// * `Result<NvidiaAttestationClaims>` - The decoded token claims or an error
+    /// * `Result<T>` - The decoded token claims or an error
     #[tracing::instrument(skip(token, cert_der))]
-    fn decode_jwt_token(token: &str, cert_der: &[u8]) -> Result<NvidiaAttestationClaims> {
+    fn decode_jwt_token<T: DeserializeOwned>(token: &str, cert_der: &[u8]) -> Result<T> {
         let (_, cert) = X509Certificate::from_der(cert_der)?;
         let sec1_der = cert.public_key().subject_public_key.data.as_ref();
         let decoding_key = DecodingKey::from_ec_der(sec1_der);
         let validation = Validation::new(Algorithm::ES384);
-        let token_data = decode::<NvidiaAttestationClaims>(token, &decoding_key, &validation)?;
+        let token_data =
+            decode::<T>(token, &decoding_key, &validation).map_err(AttestError::from_jwt_error)?;
         Ok(token_data.claims)
     }
 }
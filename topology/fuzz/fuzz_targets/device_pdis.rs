@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use topology::device_pdis::extract_device_pdis_in_gpu_attestation_report_data;
+
+fuzz_target!(|report: &[u8]| {
+    let _ = extract_device_pdis_in_gpu_attestation_report_data(report);
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use topology::spdm::SpdmMeasurementReport;
+
+fuzz_target!(|report: &[u8]| {
+    let _ = SpdmMeasurementReport::parse(report);
+});
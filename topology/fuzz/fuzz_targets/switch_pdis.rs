@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use topology::switch_pdis::extract_switch_pdis_in_gpu_attestation_report_data;
+
+fuzz_target!(|report: &[u8]| {
+    let _ = extract_switch_pdis_in_gpu_attestation_report_data(report);
+});
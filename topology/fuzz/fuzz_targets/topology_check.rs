@@ -0,0 +1,13 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use topology::topology::{gpu_topology_check, switch_topology_check};
+
+// Every GPU and switch reports the same input, so that a report that parses
+// drives both checks past the report count validation.
+fuzz_target!(|report: &[u8]| {
+    if let Ok(unique_switch_pdis_set) = gpu_topology_check(&[report; 8]) {
+        let _ = switch_topology_check(&[report; 4], 8, unique_switch_pdis_set);
+    }
+    let _ = switch_topology_check(&[report; 4], 8, std::iter::once([0; 8]).collect());
+});
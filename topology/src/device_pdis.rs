@@ -91,8 +91,9 @@ fn extract_switch_device_gpu_pdis(
 ///
 /// # Returns
 ///
-/// * `Ok((opaque_data_start, opaque_data_length))` - If the position of the opaque data is valid.
-/// * `Err(NvidiaRemoteAttestationError::InvalidSpdmMeasurementLength)` - If the position of the opaque data is invalid.
+/// * `Ok((opaque_data_start, opaque_data_length))` - If the opaque data lies within the SPDM measurement.
+/// * `Err(NvidiaRemoteAttestationError::InvalidSpdmMeasurementLength)` - If the SPDM measurement is too
+///   short to contain the measurement record length, the opaque data length, or the opaque data.
 fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize)> {
     let mut opaque_data_start = 0;
 
@@ -102,7 +103,11 @@ fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize
     opaque_data_start += spdm_response_field_size::PARAM2;
     opaque_data_start += spdm_response_field_size::NUMBER_OF_BLOCKS;
 
-    check_spdm_measurement_length(spdm_measurement, opaque_data_start, "Measurement Record")?;
+    check_spdm_measurement_length(
+        spdm_measurement,
+        opaque_data_start + spdm_response_field_size::MEASUREMENT_RECORD_LENGTH,
+        "Measurement Record",
+    )?;
 
     let measurement_record_length = u32::from_le_bytes([
         spdm_measurement[opaque_data_start],
@@ -115,7 +120,11 @@ fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize
         spdm_response_field_size::MEASUREMENT_RECORD_LENGTH + measurement_record_length;
     opaque_data_start += spdm_response_field_size::NONCE;
 
-    check_spdm_measurement_length(spdm_measurement, opaque_data_start, "Opaque Data")?;
+    check_spdm_measurement_length(
+        spdm_measurement,
+        opaque_data_start + spdm_response_field_size::OPAQUE_DATA,
+        "Opaque Data Length",
+    )?;
 
     let opaque_data_length = u16::from_le_bytes([
         spdm_measurement[opaque_data_start],
@@ -123,6 +132,12 @@ fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize
     ]) as usize;
     opaque_data_start += spdm_response_field_size::OPAQUE_DATA;
 
+    check_spdm_measurement_length(
+        spdm_measurement,
+        opaque_data_start + opaque_data_length,
+        "Opaque Data",
+    )?;
+
     Ok((opaque_data_start, opaque_data_length))
 }
 
@@ -230,10 +245,10 @@ fn parse_opaque_data_for_pdis(
                     })?);
                 }
             }
-            opaque_data_types::OPAQUE_FIELD_ID_SWITCH_GPU_PDIS => {
-                if found_gpu_pdis_bytes.is_none() {
-                    found_gpu_pdis_bytes = Some(current_data_slice.to_vec());
-                }
+            opaque_data_types::OPAQUE_FIELD_ID_SWITCH_GPU_PDIS
+                if found_gpu_pdis_bytes.is_none() =>
+            {
+                found_gpu_pdis_bytes = Some(current_data_slice.to_vec());
             }
             _ => {}
         }
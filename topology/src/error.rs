@@ -23,6 +23,12 @@ pub enum NvidiaRemoteAttestationError {
         length_of_field: usize,
         report_length: usize,
     },
+    #[error("Invalid SPDM report: {message}, field: {field}, position: {position}")]
+    InvalidSpdmReport {
+        message: String,
+        field: String,
+        position: usize,
+    },
     #[error("Invalid opaque data type: {message}, opaque data length: {opaque_data_length} bytes, current position: {current_position} bytes")]
     InvalidOpaqueDataType {
         message: String,
@@ -35,6 +41,13 @@ pub enum NvidiaRemoteAttestationError {
         current_position: usize,
         opaque_data_length: usize,
     },
+    #[error("Invalid opaque data value: {message}, data size: {data_size} bytes, opaque data length: {opaque_data_length} bytes, current position: {current_position} bytes")]
+    InvalidOpaqueDataValue {
+        message: String,
+        current_position: usize,
+        data_size: usize,
+        opaque_data_length: usize,
+    },
     #[error("NV switch PDIS not found")]
     NvSwitchPdisNotFound,
     #[error("Invalid switch PDIS length: {message}, length: {length}")]
@@ -1,5 +1,9 @@
 pub mod device_pdis;
 pub mod error;
+pub mod metrics;
 pub mod nvml;
+pub mod spdm;
 pub mod switch_pdis;
+#[cfg(any(test, feature = "test-support"))]
+pub mod test_support;
 pub mod topology;
@@ -0,0 +1,103 @@
+//! Metrics recorded by the topology checks.
+//!
+//! With the `metrics` feature, every topology check increments
+//! [`TOPOLOGY_CHECKS_TOTAL`] through the [`metrics`](https://docs.rs/metrics) facade,
+//! labelled with the `check` (`gpu` or `switch`) and its `result` (`passed` or
+//! `failed`). Without the feature, recording compiles to nothing.
+
+/// Topology checks, by check and result.
+pub const TOPOLOGY_CHECKS_TOTAL: &str = "nvidia_attestation_topology_checks_total";
+
+/// A topology check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TopologyCheck {
+    /// The `NVSwitch` PDIs reported by the GPUs
+    Gpu,
+    /// The PDIs reported by the `NVSwitch` devices
+    Switch,
+}
+
+/// Registers the description of the metrics with the installed recorder.
+#[cfg(feature = "metrics")]
+pub fn describe_metrics() {
+    metrics::describe_counter!(
+        TOPOLOGY_CHECKS_TOTAL,
+        "Topology checks, by check and result"
+    );
+}
+
+/// Records the outcome of a topology check.
+#[cfg_attr(not(feature = "metrics"), allow(unused_variables))]
+pub(crate) fn record_topology_check(check: TopologyCheck, passed: bool) {
+    #[cfg(feature = "metrics")]
+    {
+        let check = match check {
+            TopologyCheck::Gpu => "gpu",
+            TopologyCheck::Switch => "switch",
+        };
+        let result = if passed { "passed" } else { "failed" };
+        metrics::counter!(TOPOLOGY_CHECKS_TOTAL, "check" => check, "result" => result).increment(1);
+    }
+}
+
+#[cfg(all(test, feature = "metrics"))]
+mod tests {
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+
+    use super::*;
+    use crate::{
+        test_support::SyntheticTopology,
+        topology::{gpu_topology_check, switch_topology_check},
+    };
+
+    #[test]
+    fn test_topology_check_metrics() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let topology = SyntheticTopology::hgx_h100(3);
+        let gpu_reports = topology.gpu_reports();
+        let switch_reports = topology.switch_reports();
+        let gpu_reports: Vec<&[u8]> = gpu_reports.iter().map(Vec::as_slice).collect();
+        let switch_reports: Vec<&[u8]> = switch_reports.iter().map(Vec::as_slice).collect();
+
+        metrics::with_local_recorder(&recorder, || {
+            let unique_switch_pdis_set = gpu_topology_check(&gpu_reports).unwrap();
+            switch_topology_check(&switch_reports, gpu_reports.len(), unique_switch_pdis_set)
+                .unwrap();
+            gpu_topology_check(&gpu_reports[..7]).unwrap_err();
+        });
+
+        let mut checks: Vec<_> = snapshotter
+            .snapshot()
+            .into_vec()
+            .into_iter()
+            .map(|(key, _, _, value)| {
+                let key = key.key();
+                assert_eq!(key.name(), TOPOLOGY_CHECKS_TOTAL);
+                let labels: Vec<_> = key
+                    .labels()
+                    .map(|label| label.value().to_string())
+                    .collect();
+                (labels, value)
+            })
+            .collect();
+        checks.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            checks,
+            [
+                (
+                    vec!["gpu".to_string(), "failed".to_string()],
+                    DebugValue::Counter(1)
+                ),
+                (
+                    vec!["gpu".to_string(), "passed".to_string()],
+                    DebugValue::Counter(1)
+                ),
+                (
+                    vec!["switch".to_string(), "passed".to_string()],
+                    DebugValue::Counter(1)
+                ),
+            ]
+        );
+    }
+}
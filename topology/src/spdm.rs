@@ -0,0 +1,405 @@
+use crate::error::{NvidiaRemoteAttestationError, Result};
+
+/// The expected length of the SPDM `GET_MEASUREMENT` request message part.
+pub const LENGTH_OF_SPDM_GET_MEASUREMENT_REQUEST_MESSAGE: usize = 37;
+
+/// The size of the SPDM nonce.
+pub const NONCE_SIZE: usize = 32;
+
+/// Measurement specification bit of DMTF-formatted measurement blocks.
+const DMTF_MEASUREMENT_SPECIFICATION: u8 = 0x01;
+
+/// The SPDM `GET_MEASUREMENTS` request message of an attestation report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpdmMeasurementRequest {
+    /// The SPDM version, e.g. `0x11` for SPDM 1.1
+    pub spdm_version: u8,
+    /// The request code, `0xE0` for `GET_MEASUREMENTS`
+    pub request_code: u8,
+    /// The request attributes (`0x01` if a signature is requested)
+    pub param1: u8,
+    /// The measurement operation (`0xFF` for all measurement blocks)
+    pub param2: u8,
+    /// The nonce chosen by the requester
+    pub nonce: [u8; NONCE_SIZE],
+    /// The certificate slot used to sign the response
+    pub slot_id: u8,
+}
+
+/// A DMTF-formatted measurement block of an SPDM `MEASUREMENTS` response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpdmMeasurementBlock {
+    /// The index of the measurement
+    pub index: u8,
+    /// The measurement specification, `0x01` for DMTF
+    pub measurement_specification: u8,
+    /// The DMTF measurement value type
+    pub value_type: u8,
+    /// The measurement value, usually a digest
+    pub value: Vec<u8>,
+}
+
+/// A TLV-encoded field of the opaque data of an SPDM `MEASUREMENTS` response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpaqueField {
+    /// The field type, one of the `OPAQUE_FIELD_ID_*` values
+    pub data_type: u16,
+    /// The raw field value
+    pub value: Vec<u8>,
+}
+
+impl OpaqueField {
+    /// Returns the NVIDIA name of the field type, if known.
+    #[must_use]
+    pub const fn name(&self) -> Option<&'static str> {
+        opaque_field_name(self.data_type)
+    }
+}
+
+/// The SPDM `MEASUREMENTS` response message of an attestation report.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpdmMeasurementResponse {
+    /// The SPDM version, e.g. `0x11` for SPDM 1.1
+    pub spdm_version: u8,
+    /// The response code, `0x60` for `MEASUREMENTS`
+    pub response_code: u8,
+    /// The first response parameter
+    pub param1: u8,
+    /// The second response parameter, holding the certificate slot
+    pub param2: u8,
+    /// The measurement blocks
+    pub measurement_blocks: Vec<SpdmMeasurementBlock>,
+    /// The nonce chosen by the responder
+    pub nonce: [u8; NONCE_SIZE],
+    /// The TLV-encoded opaque data fields
+    pub opaque_fields: Vec<OpaqueField>,
+    /// The signature over the request and the response, empty if unsigned
+    pub signature: Vec<u8>,
+}
+
+/// A parsed attestation report: an SPDM `GET_MEASUREMENTS` request followed by the
+/// `MEASUREMENTS` response, as returned by NVML for GPUs and by NSCQ for `NVSwitch` devices.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SpdmMeasurementReport {
+    /// The request message
+    pub request: SpdmMeasurementRequest,
+    /// The response message
+    pub response: SpdmMeasurementResponse,
+}
+
+impl SpdmMeasurementReport {
+    /// Parses an attestation report.
+    ///
+    /// # Arguments
+    ///
+    /// * `report` - The raw attestation report
+    ///
+    /// # Returns
+    ///
+    /// * `Ok(report)` - The parsed request and response messages.
+    ///
+    /// # Errors
+    ///
+    /// * `NvidiaRemoteAttestationError::InvalidReportLength` - If the report is too short to contain a SPDM `GET_MEASUREMENT` request message.
+    /// * `NvidiaRemoteAttestationError::InvalidSpdmReport` - If a field of the response is truncated, or if the
+    ///   measurement record does not hold the announced number of DMTF-formatted measurement blocks.
+    pub fn parse(report: &[u8]) -> Result<Self> {
+        if report.len() < LENGTH_OF_SPDM_GET_MEASUREMENT_REQUEST_MESSAGE {
+            return Err(NvidiaRemoteAttestationError::InvalidReportLength {
+                message: "Report is too short to contain a SPDM GET_MEASUREMENT request message"
+                    .to_string(),
+                length_of_spdm_get_measurement_request_message:
+                    LENGTH_OF_SPDM_GET_MEASUREMENT_REQUEST_MESSAGE,
+                report_length: report.len(),
+            });
+        }
+        let mut reader = Reader::new(report);
+        let request = SpdmMeasurementRequest {
+            spdm_version: reader.u8("request SPDM version")?,
+            request_code: reader.u8("request code")?,
+            param1: reader.u8("request param1")?,
+            param2: reader.u8("request param2")?,
+            nonce: reader.array("request nonce")?,
+            slot_id: reader.u8("slot ID")?,
+        };
+
+        let spdm_version = reader.u8("response SPDM version")?;
+        let response_code = reader.u8("response code")?;
+        let param1 = reader.u8("response param1")?;
+        let param2 = reader.u8("response param2")?;
+        let number_of_blocks = reader.u8("number of blocks")?;
+        let measurement_record_length = reader.u24_le("measurement record length")?;
+        let measurement_record_position = reader.position;
+        let measurement_record = reader.take(measurement_record_length, "measurement record")?;
+        let measurement_blocks =
+            parse_measurement_blocks(measurement_record, measurement_record_position)?;
+        if measurement_blocks.len() != usize::from(number_of_blocks) {
+            return Err(NvidiaRemoteAttestationError::InvalidSpdmReport {
+                message: format!(
+                    "expected {number_of_blocks} measurement blocks, found {}",
+                    measurement_blocks.len()
+                ),
+                field: "measurement record".to_string(),
+                position: measurement_record_position,
+            });
+        }
+        let nonce = reader.array("response nonce")?;
+        let opaque_data_length = usize::from(reader.u16_le("opaque data length")?);
+        let opaque_data_position = reader.position;
+        let opaque_data = reader.take(opaque_data_length, "opaque data")?;
+        let opaque_fields = parse_opaque_fields(opaque_data, opaque_data_position)?;
+        let signature = reader.rest().to_vec();
+
+        Ok(Self {
+            request,
+            response: SpdmMeasurementResponse {
+                spdm_version,
+                response_code,
+                param1,
+                param2,
+                measurement_blocks,
+                nonce,
+                opaque_fields,
+                signature,
+            },
+        })
+    }
+
+    /// Returns the value of the first opaque data field of type `data_type`, if any.
+    #[must_use]
+    pub fn opaque_field(&self, data_type: u16) -> Option<&[u8]> {
+        self.response
+            .opaque_fields
+            .iter()
+            .find(|field| field.data_type == data_type)
+            .map(|field| field.value.as_slice())
+    }
+}
+
+/// Returns the NVIDIA name of an opaque data field type, if known.
+///
+/// The names follow the `OPAQUE_FIELD_ID_*` constants of NVIDIA's `nvtrust` verifier.
+#[must_use]
+pub const fn opaque_field_name(data_type: u16) -> Option<&'static str> {
+    Some(match data_type {
+        1 => "CERT_ISSUER_NAME",
+        2 => "CERT_AUTHORITY_KEY_IDENTIFIER",
+        3 => "DRIVER_VERSION",
+        4 => "GPU_INFO",
+        5 => "SKU",
+        6 => "VBIOS_VERSION",
+        7 => "MANUFACTURER_ID",
+        8 => "TAMPER_DETECTION",
+        9 => "SMC",
+        10 => "VPR",
+        11 => "NVDEC0_STATUS",
+        12 => "MSRSCNT",
+        13 => "CPRINFO",
+        14 => "BOARD_ID",
+        15 => "CHIP_SKU",
+        16 => "CHIP_SKU_MOD",
+        17 => "PROJECT",
+        18 => "PROJECT_SKU",
+        19 => "PROJECT_SKU_MOD",
+        20 => "FWID",
+        21 => "PROTECTED_PCIE_STATUS",
+        22 => "SWITCH_PDI",
+        23 => "FLOORSWEPT_PORTS",
+        24 => "POSITION_ID",
+        25 => "LOCK_SWITCH_STATUS",
+        26 => "GPU_LINK_CONN",
+        27 => "SYS_ENABLE_STATUS",
+        28 => "OPAQUE_DATA_VERSION",
+        29 => "CHIP_INFO",
+        _ => return None,
+    })
+}
+
+/// Parses the DMTF-formatted measurement blocks of a measurement record.
+///
+/// `offset` is the position of the record within the report, for error reporting.
+fn parse_measurement_blocks(record: &[u8], offset: usize) -> Result<Vec<SpdmMeasurementBlock>> {
+    let mut reader = Reader::with_offset(record, offset);
+    let mut blocks = Vec::new();
+    while !reader.is_empty() {
+        let index = reader.u8("measurement block index")?;
+        let measurement_specification = reader.u8("measurement specification")?;
+        let measurement_size = usize::from(reader.u16_le("measurement size")?);
+        let measurement_position = reader.position;
+        let mut measurement = Reader::with_offset(
+            reader.take(measurement_size, "measurement")?,
+            measurement_position,
+        );
+        if measurement_specification & DMTF_MEASUREMENT_SPECIFICATION == 0 {
+            return Err(NvidiaRemoteAttestationError::InvalidSpdmReport {
+                message: format!(
+                    "unsupported measurement specification {measurement_specification:#04x}"
+                ),
+                field: "measurement specification".to_string(),
+                position: measurement_position,
+            });
+        }
+        let value_type = measurement.u8("measurement value type")?;
+        let value_size = usize::from(measurement.u16_le("measurement value size")?);
+        let value = measurement.take(value_size, "measurement value")?.to_vec();
+        if !measurement.is_empty() {
+            return Err(NvidiaRemoteAttestationError::InvalidSpdmReport {
+                message: "measurement size does not match the measurement value size".to_string(),
+                field: "measurement".to_string(),
+                position: measurement_position,
+            });
+        }
+        blocks.push(SpdmMeasurementBlock {
+            index,
+            measurement_specification,
+            value_type,
+            value,
+        });
+    }
+    Ok(blocks)
+}
+
+/// Parses the TLV-encoded fields of the opaque data.
+///
+/// `offset` is the position of the opaque data within the report, for error reporting.
+fn parse_opaque_fields(opaque_data: &[u8], offset: usize) -> Result<Vec<OpaqueField>> {
+    let mut reader = Reader::with_offset(opaque_data, offset);
+    let mut fields = Vec::new();
+    while !reader.is_empty() {
+        let data_type = reader.u16_le("opaque data type")?;
+        let data_size = usize::from(reader.u16_le("opaque data size")?);
+        let value = reader.take(data_size, "opaque data value")?.to_vec();
+        fields.push(OpaqueField { data_type, value });
+    }
+    Ok(fields)
+}
+
+/// A bounds-checked little-endian reader.
+struct Reader<'a> {
+    data: &'a [u8],
+    /// Position of `data` within the report
+    offset: usize,
+    /// Position of the next byte within the report
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    const fn new(data: &'a [u8]) -> Self {
+        Self::with_offset(data, 0)
+    }
+
+    const fn with_offset(data: &'a [u8], offset: usize) -> Self {
+        Self {
+            data,
+            offset,
+            position: offset,
+        }
+    }
+
+    const fn is_empty(&self) -> bool {
+        self.position - self.offset >= self.data.len()
+    }
+
+    fn take(&mut self, length: usize, field: &str) -> Result<&'a [u8]> {
+        let start = self.position - self.offset;
+        let bytes = start
+            .checked_add(length)
+            .and_then(|end| self.data.get(start..end))
+            .ok_or_else(|| NvidiaRemoteAttestationError::InvalidSpdmReport {
+                message: format!(
+                    "expected {length} bytes, {} remaining",
+                    self.data.len().saturating_sub(start)
+                ),
+                field: field.to_string(),
+                position: self.position,
+            })?;
+        self.position += length;
+        Ok(bytes)
+    }
+
+    fn rest(&mut self) -> &'a [u8] {
+        let start = self.position - self.offset;
+        self.position = self.offset + self.data.len();
+        &self.data[start..]
+    }
+
+    fn array<const N: usize>(&mut self, field: &str) -> Result<[u8; N]> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N, field)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self, field: &str) -> Result<u8> {
+        Ok(self.take(1, field)?[0])
+    }
+
+    fn u16_le(&mut self, field: &str) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.array(field)?))
+    }
+
+    fn u24_le(&mut self, field: &str) -> Result<usize> {
+        let [b0, b1, b2] = self.array(field)?;
+        Ok(u32::from_le_bytes([b0, b1, b2, 0]) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::test_support::{
+        MeasurementBlock, SpdmReportBuilder, SyntheticTopology, DMTF_IMMUTABLE_ROM_DIGEST,
+        GET_MEASUREMENTS_REQUEST_CODE, MEASUREMENTS_RESPONSE_CODE, P384_SIGNATURE_SIZE,
+        SHA384_DIGEST_SIZE,
+    };
+
+    #[test]
+    fn test_parse_synthetic_report() {
+        let builder = SpdmReportBuilder::new()
+            .nonce([1; NONCE_SIZE])
+            .response_nonce([2; NONCE_SIZE])
+            .slot_id(3)
+            .measurement_block(MeasurementBlock {
+                index: 1,
+                value_type: DMTF_IMMUTABLE_ROM_DIGEST,
+                value: vec![4; SHA384_DIGEST_SIZE],
+            })
+            .measurement_digests(2)
+            .opaque_field(3, b"550.54.15\0".to_vec())
+            .signature(vec![5; P384_SIGNATURE_SIZE]);
+        let report = SpdmMeasurementReport::parse(&builder.build()).unwrap();
+
+        assert_eq!(report.request.request_code, GET_MEASUREMENTS_REQUEST_CODE);
+        assert_eq!(report.request.nonce, [1; NONCE_SIZE]);
+        assert_eq!(report.request.slot_id, 3);
+        assert_eq!(report.response.response_code, MEASUREMENTS_RESPONSE_CODE);
+        assert_eq!(report.response.nonce, [2; NONCE_SIZE]);
+        assert_eq!(report.response.measurement_blocks.len(), 3);
+        assert_eq!(
+            report.response.measurement_blocks[0].value,
+            [4; SHA384_DIGEST_SIZE]
+        );
+        assert_eq!(report.opaque_field(3), Some(b"550.54.15\0".as_slice()));
+        assert_eq!(
+            report.response.opaque_fields[0].name(),
+            Some("DRIVER_VERSION")
+        );
+        assert_eq!(report.response.signature, [5; P384_SIGNATURE_SIZE]);
+    }
+
+    #[test]
+    fn test_parse_truncated_report() {
+        let report = SyntheticTopology::hgx_h100(1)
+            .switch_report_builder(0)
+            .build();
+        let signature_start = report.len() - P384_SIGNATURE_SIZE;
+        assert!(SpdmMeasurementReport::parse(&report).is_ok());
+        for length in 0..signature_start {
+            assert!(SpdmMeasurementReport::parse(&report[..length]).is_err());
+        }
+        assert!(matches!(
+            SpdmMeasurementReport::parse(&report[..50]),
+            Err(NvidiaRemoteAttestationError::InvalidSpdmReport { .. })
+        ));
+    }
+}
@@ -79,7 +79,10 @@ pub fn extract_switch_pdis_in_gpu_attestation_report_data(
 fn extract_switch_pdis(
     switch_gpu_pdis: &[u8],
 ) -> Result<Vec<[u8; opaque_data_field_size::PDI_DATA_FIELD_SIZE]>> {
-    if switch_gpu_pdis.len() % opaque_data_field_size::PDI_DATA_FIELD_SIZE != 0 {
+    if !switch_gpu_pdis
+        .len()
+        .is_multiple_of(opaque_data_field_size::PDI_DATA_FIELD_SIZE)
+    {
         return Err(NvidiaRemoteAttestationError::InvalidSwitchPdisLength {
             message: format!(
                 "Switch PDIS length is not a multiple of {}",
@@ -122,34 +125,40 @@ fn extract_switch_pdis(
 ///   for a TLV entry fails (e.g., due to insufficient data).
 /// * `NvidiaRemoteAttestationError::InvalidOpaqueDataSize`: If reading the size field
 ///   for a TLV entry fails (e.g., due to insufficient data).
+/// * `NvidiaRemoteAttestationError::InvalidOpaqueDataValue`: If the value of the
+///   `OPAQUE_FIELD_ID_SWITCH_GPU_PDIS` entry extends past the end of the opaque data.
 /// * `NvidiaRemoteAttestationError::NvSwitchPdisNotFound`: If the loop completes without
 ///   finding a TLV entry with the type `OPAQUE_FIELD_ID_SWITCH_GPU_PDIS`.
 fn extract_switch_gpu_pdis_in_opaque_data(opaque_data: &[u8]) -> Result<Vec<u8>> {
     let mut current_position = 0;
     while current_position < opaque_data.len() {
-        let data_type = opaque_data
-            [current_position..current_position + opaque_data_field_size::OPAQUE_DATA_FIELD_TYPE]
-            .try_into()
-            .map_err(|_| NvidiaRemoteAttestationError::InvalidOpaqueDataType {
+        let data_type = read_u16_le(opaque_data, current_position).ok_or_else(|| {
+            NvidiaRemoteAttestationError::InvalidOpaqueDataType {
                 message: "Invalid opaque data type".to_string(),
                 current_position,
                 opaque_data_length: opaque_data.len(),
-            })?;
-        let data_type = u16::from_le_bytes(data_type);
+            }
+        })?;
         current_position += opaque_data_field_size::OPAQUE_DATA_FIELD_TYPE;
-        let data_size = opaque_data
-            [current_position..current_position + opaque_data_field_size::OPAQUE_DATA_FIELD_SIZE]
-            .try_into()
-            .map_err(|_| NvidiaRemoteAttestationError::InvalidOpaqueDataSize {
+        let data_size = read_u16_le(opaque_data, current_position).ok_or_else(|| {
+            NvidiaRemoteAttestationError::InvalidOpaqueDataSize {
                 message: "Invalid opaque data size".to_string(),
                 current_position,
                 opaque_data_length: opaque_data.len(),
-            })?;
-        let data_size = u16::from_le_bytes(data_size);
+            }
+        })?;
         let data_size = data_size as usize;
         current_position += opaque_data_field_size::OPAQUE_DATA_FIELD_SIZE;
         if data_type == opaque_data_types::OPAQUE_FIELD_ID_SWITCH_PDI {
-            return Ok(opaque_data[current_position..current_position + data_size].to_vec());
+            return opaque_data
+                .get(current_position..current_position + data_size)
+                .map(<[u8]>::to_vec)
+                .ok_or_else(|| NvidiaRemoteAttestationError::InvalidOpaqueDataValue {
+                    message: "Opaque data too short for the declared data size".to_string(),
+                    current_position,
+                    data_size,
+                    opaque_data_length: opaque_data.len(),
+                });
         }
         current_position += data_size;
     }
@@ -176,9 +185,10 @@ fn extract_switch_gpu_pdis_in_opaque_data(opaque_data: &[u8]) -> Result<Vec<u8>>
 /// # Errors
 ///
 /// Returns `NvidiaRemoteAttestationError::InvalidSpdmMeasurementLength` if the
-/// `spdm_measurement` slice is too short to contain the necessary fields leading up to
-/// or including the opaque data length field. This check is performed by internal calls to
-/// `check_spdm_measurement_length`.
+/// `spdm_measurement` slice is too short to contain the measurement record length, the
+/// opaque data length, or the opaque data itself. This check is performed by internal
+/// calls to `check_spdm_measurement_length`, so that the returned range is always within
+/// `spdm_measurement`.
 fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize)> {
     let mut opaque_data_start = 0;
 
@@ -188,7 +198,11 @@ fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize
     opaque_data_start += spdm_response_field_size::PARAM2;
     opaque_data_start += spdm_response_field_size::NUMBER_OF_BLOCKS;
 
-    check_spdm_measurement_length(spdm_measurement, opaque_data_start, "Measurement Record")?;
+    check_spdm_measurement_length(
+        spdm_measurement,
+        opaque_data_start + spdm_response_field_size::MEASUREMENT_RECORD_LENGTH,
+        "Measurement Record",
+    )?;
 
     let measurement_record_length = u32::from_le_bytes([
         spdm_measurement[opaque_data_start],
@@ -201,7 +215,11 @@ fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize
         spdm_response_field_size::MEASUREMENT_RECORD_LENGTH + measurement_record_length;
     opaque_data_start += spdm_response_field_size::NONCE;
 
-    check_spdm_measurement_length(spdm_measurement, opaque_data_start, "Opaque Data")?;
+    check_spdm_measurement_length(
+        spdm_measurement,
+        opaque_data_start + spdm_response_field_size::OPAQUE_DATA,
+        "Opaque Data Length",
+    )?;
 
     let opaque_data_length = u16::from_le_bytes([
         spdm_measurement[opaque_data_start],
@@ -209,9 +227,21 @@ fn compute_opaque_data_position(spdm_measurement: &[u8]) -> Result<(usize, usize
     ]) as usize;
     opaque_data_start += spdm_response_field_size::OPAQUE_DATA;
 
+    check_spdm_measurement_length(
+        spdm_measurement,
+        opaque_data_start + opaque_data_length,
+        "Opaque Data",
+    )?;
+
     Ok((opaque_data_start, opaque_data_length))
 }
 
+/// Reads a little-endian `u16` at `position`, or returns `None` if `data` is too short.
+fn read_u16_le(data: &[u8], position: usize) -> Option<u16> {
+    let bytes = data.get(position..position.checked_add(2)?)?;
+    Some(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
 /// Checks if the provided SPDM measurement byte slice is long enough to contain
 /// a specific field, considering the cumulative size of all preceding fields.
 ///
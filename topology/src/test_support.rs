@@ -0,0 +1,401 @@
+//! Synthetic SPDM attestation reports, for tests that do not run on GPU or `NVSwitch` hardware.
+//!
+//! [`SpdmReportBuilder`] emits byte-exact SPDM `GET_MEASUREMENTS` request and response
+//! messages, as returned by NVML for GPUs and by NSCQ for `NVSwitch` devices, and
+//! [`SyntheticTopology`] builds consistent sets of GPU and switch reports for
+//! `gpu_topology_check` and `switch_topology_check`.
+
+use crate::{
+    device_pdis::opaque_data_types::{OPAQUE_FIELD_ID_DEVICE_PDI, OPAQUE_FIELD_ID_SWITCH_GPU_PDIS},
+    switch_pdis::{
+        opaque_data_field_size::PDI_DATA_FIELD_SIZE, opaque_data_types::OPAQUE_FIELD_ID_SWITCH_PDI,
+    },
+};
+
+/// A Platform Data Information (PDI) entry.
+pub type Pdi = [u8; PDI_DATA_FIELD_SIZE];
+
+/// SPDM version 1.1, as used by Hopper GPUs and LS10 switches.
+pub const SPDM_VERSION_1_1: u8 = 0x11;
+
+/// Request code of the SPDM `GET_MEASUREMENTS` request.
+pub const GET_MEASUREMENTS_REQUEST_CODE: u8 = 0xE0;
+
+/// Response code of the SPDM `MEASUREMENTS` response.
+pub const MEASUREMENTS_RESPONSE_CODE: u8 = 0x60;
+
+/// `GET_MEASUREMENTS` request attribute asking for a signed response.
+pub const SIGNATURE_REQUESTED: u8 = 0x01;
+
+/// `GET_MEASUREMENTS` operation requesting all measurement blocks.
+pub const ALL_MEASUREMENT_BLOCKS: u8 = 0xFF;
+
+/// Measurement specification of DMTF-formatted measurement blocks.
+pub const DMTF_MEASUREMENT_SPECIFICATION: u8 = 0x01;
+
+/// DMTF measurement value type of an immutable ROM digest.
+pub const DMTF_IMMUTABLE_ROM_DIGEST: u8 = 0x00;
+
+/// Size of an ECDSA P-384 signature, as appended to NVIDIA reports.
+pub const P384_SIGNATURE_SIZE: usize = 96;
+
+/// Size of a SHA-384 digest, the measurement digest size of NVIDIA reports.
+pub const SHA384_DIGEST_SIZE: usize = 48;
+
+/// Size of the SPDM nonce.
+const NONCE_SIZE: usize = 32;
+
+/// A DMTF-formatted SPDM measurement block.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeasurementBlock {
+    /// Index of the measurement
+    pub index: u8,
+    /// DMTF measurement value type
+    pub value_type: u8,
+    /// Measurement value, usually a digest
+    pub value: Vec<u8>,
+}
+
+impl MeasurementBlock {
+    /// Encodes the block: index, specification, measurement size and DMTF measurement.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value does not fit a 16-bit size field.
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let value_size = u16::try_from(self.value.len()).expect("Measurement value too large");
+        let mut bytes = Vec::with_capacity(7 + self.value.len());
+        bytes.push(self.index);
+        bytes.push(DMTF_MEASUREMENT_SPECIFICATION);
+        bytes.extend_from_slice(&(value_size + 3).to_le_bytes());
+        bytes.push(self.value_type);
+        bytes.extend_from_slice(&value_size.to_le_bytes());
+        bytes.extend_from_slice(&self.value);
+        bytes
+    }
+}
+
+/// Builder of synthetic SPDM `GET_MEASUREMENTS` request and response messages.
+///
+/// The emitted report is the 37-byte request followed by the response: version,
+/// response code, param1, param2, number of blocks, 3-byte measurement record
+/// length, measurement record, nonce, 2-byte opaque data length, opaque data TLVs
+/// and signature. All multi-byte integers are little endian.
+#[derive(Debug, Clone)]
+pub struct SpdmReportBuilder {
+    spdm_version: u8,
+    request_nonce: [u8; NONCE_SIZE],
+    response_nonce: [u8; NONCE_SIZE],
+    slot_id: u8,
+    measurement_blocks: Vec<MeasurementBlock>,
+    opaque_fields: Vec<(u16, Vec<u8>)>,
+    signature: Vec<u8>,
+}
+
+impl Default for SpdmReportBuilder {
+    fn default() -> Self {
+        Self {
+            spdm_version: SPDM_VERSION_1_1,
+            request_nonce: [0; NONCE_SIZE],
+            response_nonce: [0; NONCE_SIZE],
+            slot_id: 0,
+            measurement_blocks: Vec::new(),
+            opaque_fields: Vec::new(),
+            signature: vec![0; P384_SIGNATURE_SIZE],
+        }
+    }
+}
+
+impl SpdmReportBuilder {
+    /// Creates a builder for an SPDM 1.1 report without measurements nor opaque data.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the SPDM version of both messages.
+    #[must_use]
+    pub const fn spdm_version(mut self, spdm_version: u8) -> Self {
+        self.spdm_version = spdm_version;
+        self
+    }
+
+    /// Sets the nonce of the request.
+    #[must_use]
+    pub const fn nonce(mut self, nonce: [u8; NONCE_SIZE]) -> Self {
+        self.request_nonce = nonce;
+        self
+    }
+
+    /// Sets the nonce of the response, generated by the responder.
+    #[must_use]
+    pub const fn response_nonce(mut self, nonce: [u8; NONCE_SIZE]) -> Self {
+        self.response_nonce = nonce;
+        self
+    }
+
+    /// Sets the certificate slot used to sign the response.
+    #[must_use]
+    pub const fn slot_id(mut self, slot_id: u8) -> Self {
+        self.slot_id = slot_id;
+        self
+    }
+
+    /// Appends a measurement block.
+    #[must_use]
+    pub fn measurement_block(mut self, block: MeasurementBlock) -> Self {
+        self.measurement_blocks.push(block);
+        self
+    }
+
+    /// Appends `count` immutable ROM digest blocks, indexed from 1, each filled with its index.
+    #[must_use]
+    pub fn measurement_digests(mut self, count: u8) -> Self {
+        for index in 1..=count {
+            self.measurement_blocks.push(MeasurementBlock {
+                index,
+                value_type: DMTF_IMMUTABLE_ROM_DIGEST,
+                value: vec![index; SHA384_DIGEST_SIZE],
+            });
+        }
+        self
+    }
+
+    /// Appends an opaque data TLV.
+    #[must_use]
+    pub fn opaque_field(mut self, data_type: u16, value: impl Into<Vec<u8>>) -> Self {
+        self.opaque_fields.push((data_type, value.into()));
+        self
+    }
+
+    /// Appends the `OPAQUE_FIELD_ID_SWITCH_PDI` TLV (22) of a GPU report, listing the
+    /// PDIs of the switches the GPU is connected to.
+    #[must_use]
+    pub fn switch_pdis(self, pdis: &[Pdi]) -> Self {
+        self.opaque_field(OPAQUE_FIELD_ID_SWITCH_PDI, pdis.concat())
+    }
+
+    /// Appends the `OPAQUE_FIELD_ID_DEVICE_PDI` TLV (22) of a switch report, holding the
+    /// PDI of the switch itself.
+    #[must_use]
+    pub fn device_pdi(self, pdi: Pdi) -> Self {
+        self.opaque_field(OPAQUE_FIELD_ID_DEVICE_PDI, pdi)
+    }
+
+    /// Appends the `OPAQUE_FIELD_ID_SWITCH_GPU_PDIS` TLV (26) of a switch report, listing
+    /// the PDIs of the GPUs connected to the switch.
+    #[must_use]
+    pub fn switch_gpu_pdis(self, pdis: &[Pdi]) -> Self {
+        self.opaque_field(OPAQUE_FIELD_ID_SWITCH_GPU_PDIS, pdis.concat())
+    }
+
+    /// Sets the signature appended to the response, 96 zero bytes by default.
+    #[must_use]
+    pub fn signature(mut self, signature: impl Into<Vec<u8>>) -> Self {
+        self.signature = signature.into();
+        self
+    }
+
+    /// Encodes the 37-byte `GET_MEASUREMENTS` request.
+    #[must_use]
+    pub fn build_request(&self) -> Vec<u8> {
+        let mut request = Vec::with_capacity(5 + NONCE_SIZE);
+        request.push(self.spdm_version);
+        request.push(GET_MEASUREMENTS_REQUEST_CODE);
+        request.push(SIGNATURE_REQUESTED);
+        request.push(ALL_MEASUREMENT_BLOCKS);
+        request.extend_from_slice(&self.request_nonce);
+        request.push(self.slot_id);
+        request
+    }
+
+    /// Encodes the `MEASUREMENTS` response.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are more than 255 measurement blocks, or if the measurement
+    /// record, the opaque data or an opaque field does not fit its size field.
+    #[must_use]
+    pub fn build_response(&self) -> Vec<u8> {
+        let record: Vec<u8> = self
+            .measurement_blocks
+            .iter()
+            .flat_map(MeasurementBlock::to_bytes)
+            .collect();
+        let record_length = u32::try_from(record.len())
+            .ok()
+            .filter(|length| *length < 1 << 24)
+            .expect("Measurement record too large");
+        let mut opaque_data = Vec::new();
+        for (data_type, value) in &self.opaque_fields {
+            let size = u16::try_from(value.len()).expect("Opaque field too large");
+            opaque_data.extend_from_slice(&data_type.to_le_bytes());
+            opaque_data.extend_from_slice(&size.to_le_bytes());
+            opaque_data.extend_from_slice(value);
+        }
+        let opaque_data_length = u16::try_from(opaque_data.len()).expect("Opaque data too large");
+
+        let mut response = vec![
+            self.spdm_version,
+            MEASUREMENTS_RESPONSE_CODE,
+            0,
+            self.slot_id,
+            u8::try_from(self.measurement_blocks.len()).expect("Too many blocks"),
+        ];
+        response.extend_from_slice(&record_length.to_le_bytes()[..3]);
+        response.extend_from_slice(&record);
+        response.extend_from_slice(&self.response_nonce);
+        response.extend_from_slice(&opaque_data_length.to_le_bytes());
+        response.extend_from_slice(&opaque_data);
+        response.extend_from_slice(&self.signature);
+        response
+    }
+
+    /// Encodes the full report: the request followed by the response.
+    #[must_use]
+    pub fn build(&self) -> Vec<u8> {
+        let mut report = self.build_request();
+        report.extend_from_slice(&self.build_response());
+        report
+    }
+}
+
+/// A synthetic HGX topology of GPUs fully connected to `NVSwitch` devices.
+///
+/// GPU reports list the PDIs of all switches, padded with disabled (zero) PDIs, in
+/// the reversed byte order used by GPUs. Switch reports hold the PDI of the switch
+/// and the PDIs of all GPUs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntheticTopology {
+    /// PDIs of the GPUs
+    pub gpu_pdis: Vec<Pdi>,
+    /// PDIs of the switches, in the byte order reported by the switches themselves
+    pub switch_pdis: Vec<Pdi>,
+    /// Nonce used for all reports
+    pub nonce: [u8; NONCE_SIZE],
+}
+
+impl SyntheticTopology {
+    /// Number of switch PDI entries listed in GPU reports, including disabled ones.
+    pub const GPU_REPORT_SWITCH_PDI_ENTRIES: usize = 8;
+
+    /// Creates the topology of an 8-GPU, 4-switch HGX H100 board.
+    ///
+    /// PDIs are derived from `seed`, so that distinct seeds give distinct topologies.
+    #[must_use]
+    pub fn hgx_h100(seed: u8) -> Self {
+        let pdi = |kind: u8, index: u8| -> Pdi { [seed, kind, index, 0xA5, 0x5A, index, kind, 1] };
+        Self {
+            gpu_pdis: (0..8).map(|index| pdi(0x47, index)).collect(),
+            switch_pdis: (0..4).map(|index| pdi(0x53, index)).collect(),
+            nonce: [seed; NONCE_SIZE],
+        }
+    }
+
+    /// Returns the switch PDIs as listed in GPU reports.
+    #[must_use]
+    pub fn gpu_view_of_switch_pdis(&self) -> Vec<Pdi> {
+        let mut pdis: Vec<Pdi> = self
+            .switch_pdis
+            .iter()
+            .map(|pdi| {
+                let mut pdi = *pdi;
+                pdi.reverse();
+                pdi
+            })
+            .collect();
+        pdis.resize(
+            pdis.len().max(Self::GPU_REPORT_SWITCH_PDI_ENTRIES),
+            [0; PDI_DATA_FIELD_SIZE],
+        );
+        pdis
+    }
+
+    /// Returns a GPU report builder, to be customized before building.
+    #[must_use]
+    pub fn gpu_report_builder(&self) -> SpdmReportBuilder {
+        SpdmReportBuilder::new()
+            .nonce(self.nonce)
+            .measurement_digests(4)
+            .switch_pdis(&self.gpu_view_of_switch_pdis())
+    }
+
+    /// Returns a report builder for the switch at `index`, to be customized before building.
+    #[must_use]
+    pub fn switch_report_builder(&self, index: usize) -> SpdmReportBuilder {
+        SpdmReportBuilder::new()
+            .nonce(self.nonce)
+            .measurement_digests(2)
+            .device_pdi(self.switch_pdis[index])
+            .switch_gpu_pdis(&self.gpu_pdis)
+    }
+
+    /// Returns the attestation reports of all GPUs.
+    #[must_use]
+    pub fn gpu_reports(&self) -> Vec<Vec<u8>> {
+        let report = self.gpu_report_builder().build();
+        vec![report; self.gpu_pdis.len()]
+    }
+
+    /// Returns the attestation reports of all switches.
+    #[must_use]
+    pub fn switch_reports(&self) -> Vec<Vec<u8>> {
+        (0..self.switch_pdis.len())
+            .map(|index| self.switch_report_builder(index).build())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        device_pdis::extract_device_pdis_in_gpu_attestation_report_data,
+        switch_pdis::extract_switch_pdis_in_gpu_attestation_report_data,
+    };
+
+    #[test]
+    fn test_report_layout() {
+        let builder = SpdmReportBuilder::new()
+            .nonce([7; 32])
+            .response_nonce([9; 32])
+            .measurement_digests(2)
+            .opaque_field(1, vec![0xAB, 0xCD]);
+        let request = builder.build_request();
+        assert_eq!(request.len(), 37);
+        assert_eq!(request[..4], [0x11, 0xE0, 0x01, 0xFF]);
+        assert_eq!(request[4..36], [7; 32]);
+
+        let response = builder.build_response();
+        let block_size = 7 + SHA384_DIGEST_SIZE;
+        assert_eq!(response[..5], [0x11, 0x60, 0, 0, 2]);
+        assert_eq!(
+            response[5..8],
+            u32::try_from(2 * block_size).unwrap().to_le_bytes()[..3]
+        );
+        assert_eq!(response[8..12], [1, 1, 51, 0]);
+        let nonce_start = 8 + 2 * block_size;
+        assert_eq!(response[nonce_start..nonce_start + 32], [9; 32]);
+        assert_eq!(
+            response[nonce_start + 32..nonce_start + 40],
+            [6, 0, 1, 0, 2, 0, 0xAB, 0xCD]
+        );
+        assert_eq!(response.len(), nonce_start + 40 + P384_SIGNATURE_SIZE);
+        assert_eq!(builder.build(), [request, response].concat());
+    }
+
+    #[test]
+    fn test_reports_parse() {
+        let topology = SyntheticTopology::hgx_h100(1);
+        let gpu_report = &topology.gpu_reports()[0];
+        assert_eq!(
+            extract_switch_pdis_in_gpu_attestation_report_data(gpu_report).unwrap(),
+            topology.gpu_view_of_switch_pdis()
+        );
+        let switch_report = &topology.switch_reports()[2];
+        let pdis = extract_device_pdis_in_gpu_attestation_report_data(switch_report).unwrap();
+        assert_eq!(pdis.switch_pdis, topology.switch_pdis[2]);
+        assert_eq!(pdis.switch_device_gpu_pdis, topology.gpu_pdis);
+    }
+}
@@ -2,6 +2,7 @@ use std::collections::HashSet;
 
 use crate::device_pdis::{extract_device_pdis_in_gpu_attestation_report_data, SwitchDevicePdis};
 use crate::error::{NvidiaRemoteAttestationError, Result};
+use crate::metrics::{record_topology_check, TopologyCheck};
 use crate::switch_pdis::extract_switch_pdis_in_gpu_attestation_report_data;
 use crate::switch_pdis::opaque_data_field_size::PDI_DATA_FIELD_SIZE;
 
@@ -58,6 +59,15 @@ const DISABLED_PDI: &[u8] = &[0u8; PDI_DATA_FIELD_SIZE];
 #[tracing::instrument(name = "gpu_topology_check", skip_all)]
 pub fn gpu_topology_check(
     gpu_attestation_reports: &[&[u8]],
+) -> Result<HashSet<[u8; PDI_DATA_FIELD_SIZE]>> {
+    let result = gpu_topology_check_inner(gpu_attestation_reports);
+    record_topology_check(TopologyCheck::Gpu, result.is_ok());
+    result
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn gpu_topology_check_inner(
+    gpu_attestation_reports: &[&[u8]],
 ) -> Result<HashSet<[u8; PDI_DATA_FIELD_SIZE]>> {
     if gpu_attestation_reports.len() != NUMBER_OF_GPU_TOPOLOGY_CHECK_REPORTS {
         tracing::error!(
@@ -122,11 +132,43 @@ pub fn gpu_topology_check(
     Ok(unique_switch_pdis_set.expect("Unique switch PDIS set should be Some at this point"))
 }
 
+/// Performs a switch topology check, verifying that every `NVSwitch` attestation report
+/// belongs to the set of switch PDIs returned by [`gpu_topology_check`], and that all the
+/// switches report the same set of `num_gpus` GPU PDIs.
+///
+/// # Errors
+///
+/// Returns an `Err(NvidiaRemoteAttestationError)` if any check fails:
+/// * `NvidiaRemoteAttestationError::InvalidSwitchAttestationReportsLength`: If the number of
+///   reports does not match `NUMBER_OF_SWITCH_ATTESTATION_REPORTS`.
+/// * Errors propagated from `extract_device_pdis_in_gpu_attestation_report_data`: If PDI
+///   extraction fails for any report.
+/// * `NvidiaRemoteAttestationError::SwitchPdisNotFound`: If the PDI of a switch is not in
+///   `unique_switch_pdis_set`.
+/// * `NvidiaRemoteAttestationError::InvalidSwitchDeviceGpuPdisLength`: If a switch does not
+///   report `num_gpus` unique GPU PDIs.
+/// * `NvidiaRemoteAttestationError::InvalidSwitchDeviceGpuPdisTopology`: If the GPU PDIs
+///   reported by a switch differ from those reported by the first switch.
 #[tracing::instrument(name = "switch_topology_check", skip_all, fields(num_gpus))]
 pub fn switch_topology_check(
     switch_attestation_reports: &[&[u8]],
     num_gpus: usize,
     unique_switch_pdis_set: HashSet<[u8; PDI_DATA_FIELD_SIZE]>,
+) -> Result<()> {
+    let result = switch_topology_check_inner(
+        switch_attestation_reports,
+        num_gpus,
+        &unique_switch_pdis_set,
+    );
+    record_topology_check(TopologyCheck::Switch, result.is_ok());
+    result
+}
+
+#[allow(clippy::cognitive_complexity)]
+fn switch_topology_check_inner(
+    switch_attestation_reports: &[&[u8]],
+    num_gpus: usize,
+    unique_switch_pdis_set: &HashSet<[u8; PDI_DATA_FIELD_SIZE]>,
 ) -> Result<()> {
     if switch_attestation_reports.len() != NUMBER_OF_SWITCH_ATTESTATION_REPORTS {
         tracing::error!(
@@ -211,6 +253,139 @@ mod tests {
     use nvml_wrapper::Nvml;
     use rand::Rng;
 
+    use crate::test_support::{SpdmReportBuilder, SyntheticTopology};
+
+    fn as_slices(reports: &[Vec<u8>]) -> Vec<&[u8]> {
+        reports.iter().map(Vec::as_slice).collect()
+    }
+
+    #[test]
+    fn test_synthetic_topology_checks() {
+        let topology = SyntheticTopology::hgx_h100(1);
+        let unique_switch_pdis_set = gpu_topology_check(&as_slices(&topology.gpu_reports()))
+            .expect("Failed to check GPU topology");
+        assert_eq!(unique_switch_pdis_set.len(), 4);
+        switch_topology_check(
+            &as_slices(&topology.switch_reports()),
+            topology.gpu_pdis.len(),
+            unique_switch_pdis_set,
+        )
+        .expect("Failed to check switch topology");
+    }
+
+    #[test]
+    fn test_synthetic_gpu_topology_tampering() {
+        let topology = SyntheticTopology::hgx_h100(1);
+
+        // A GPU connected to a foreign switch
+        let mut reports = topology.gpu_reports();
+        let mut foreign_switch_pdis = topology.gpu_view_of_switch_pdis();
+        foreign_switch_pdis[3] = SyntheticTopology::hgx_h100(2).gpu_view_of_switch_pdis()[3];
+        reports[5] = SpdmReportBuilder::new()
+            .nonce(topology.nonce)
+            .switch_pdis(&foreign_switch_pdis)
+            .build();
+        assert!(matches!(
+            gpu_topology_check(&as_slices(&reports)),
+            Err(NvidiaRemoteAttestationError::InvalidSwitchPdisTopology { .. })
+        ));
+
+        // A GPU that does not see one of the switches
+        let mut missing_switch_pdis = topology.gpu_view_of_switch_pdis();
+        missing_switch_pdis[0] = [0; PDI_DATA_FIELD_SIZE];
+        let mut reports = topology.gpu_reports();
+        reports[0] = SpdmReportBuilder::new()
+            .switch_pdis(&missing_switch_pdis)
+            .build();
+        assert!(matches!(
+            gpu_topology_check(&as_slices(&reports)),
+            Err(NvidiaRemoteAttestationError::InvalidSwitchPdisLength { length: 3, .. })
+        ));
+
+        // A missing GPU report
+        let reports = topology.gpu_reports();
+        assert!(matches!(
+            gpu_topology_check(&as_slices(&reports[..7])),
+            Err(NvidiaRemoteAttestationError::InvalidGpuAttestationReportsLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_synthetic_switch_topology_tampering() {
+        let topology = SyntheticTopology::hgx_h100(1);
+        let unique_switch_pdis_set =
+            gpu_topology_check(&as_slices(&topology.gpu_reports())).unwrap();
+
+        // A switch that is not seen by the GPUs
+        let mut reports = topology.switch_reports();
+        reports[1] = SyntheticTopology::hgx_h100(2).switch_reports()[1].clone();
+        assert!(matches!(
+            switch_topology_check(&as_slices(&reports), 8, unique_switch_pdis_set.clone()),
+            Err(NvidiaRemoteAttestationError::SwitchPdisNotFound)
+        ));
+
+        // A switch connected to a foreign GPU
+        let mut gpu_pdis = topology.gpu_pdis.clone();
+        gpu_pdis[7] = SyntheticTopology::hgx_h100(2).gpu_pdis[7];
+        let mut reports = topology.switch_reports();
+        reports[3] = SpdmReportBuilder::new()
+            .device_pdi(topology.switch_pdis[3])
+            .switch_gpu_pdis(&gpu_pdis)
+            .build();
+        assert!(matches!(
+            switch_topology_check(&as_slices(&reports), 8, unique_switch_pdis_set.clone()),
+            Err(NvidiaRemoteAttestationError::InvalidSwitchDeviceGpuPdisTopology { .. })
+        ));
+
+        // Fewer GPUs than connected to the switches
+        assert!(matches!(
+            switch_topology_check(
+                &as_slices(&topology.switch_reports()),
+                4,
+                unique_switch_pdis_set
+            ),
+            Err(NvidiaRemoteAttestationError::InvalidSwitchDeviceGpuPdisLength { .. })
+        ));
+    }
+
+    #[test]
+    fn test_malformed_reports_do_not_panic() {
+        let topology = SyntheticTopology::hgx_h100(1);
+        let gpu_report = topology.gpu_report_builder().build();
+        let switch_report = topology.switch_report_builder(0).build();
+        for report in [&gpu_report, &switch_report] {
+            for length in 0..report.len() {
+                let truncated = &report[..length];
+                let _ = extract_switch_pdis_in_gpu_attestation_report_data(truncated);
+                let _ = extract_device_pdis_in_gpu_attestation_report_data(truncated);
+            }
+            // Corrupt every byte, including the length fields of the measurement record,
+            // the opaque data and each opaque data entry
+            for position in 0..report.len() {
+                for value in [0xFF, report[position].wrapping_sub(1)] {
+                    let mut corrupted = report.clone();
+                    corrupted[position] = value;
+                    let _ = extract_switch_pdis_in_gpu_attestation_report_data(&corrupted);
+                    let _ = extract_device_pdis_in_gpu_attestation_report_data(&corrupted);
+                }
+            }
+        }
+
+        // An opaque data entry whose value extends past the end of the opaque data
+        let mut report = SpdmReportBuilder::new()
+            .opaque_field(22, [0xAB; PDI_DATA_FIELD_SIZE])
+            .build();
+        let position = report
+            .windows(4)
+            .rposition(|window| window == [22, 0, 8, 0])
+            .unwrap();
+        report[position + 2] = 0xFF;
+        assert!(matches!(
+            extract_switch_pdis_in_gpu_attestation_report_data(&report),
+            Err(NvidiaRemoteAttestationError::InvalidOpaqueDataValue { .. })
+        ));
+    }
+
     #[test]
     fn test_gpu_topology_check() {
         let nvml = Nvml::init().unwrap();